@@ -41,6 +41,35 @@ mod kube {
     }
 }
 
+#[divan::bench_group(sample_count = 400, sample_size = 5)]
+mod indented {
+    use divan::{black_box, black_box_drop, Bencher};
+    use sonny_jim::Arena;
+
+    const KUBE: &str = include_str!("../testdata/kubernetes-oapi.json");
+
+    /// The kubernetes document with its indentation quadrupled.
+    fn indented() -> String {
+        KUBE.replace("  ", "        ")
+    }
+
+    #[divan::bench]
+    fn sonny_jim(bencher: Bencher) {
+        let src = indented();
+        bencher.bench_local(|| black_box_drop(sonny_jim::parse(black_box(&mut Arena::new(&src)))));
+    }
+
+    #[divan::bench]
+    fn serde_raw(bencher: Bencher) {
+        let src = indented();
+        bencher.bench_local(|| {
+            black_box_drop(serde_json::from_str::<&serde_json::value::RawValue>(
+                black_box(&src),
+            ))
+        });
+    }
+}
+
 #[divan::bench_group(sample_count = 4000, sample_size = 500)]
 mod small {
     use divan::{black_box, black_box_drop};
@@ -53,6 +82,12 @@ mod small {
         black_box_drop(sonny_jim::parse(black_box(&mut Arena::new(SMALL))));
     }
 
+    #[divan::bench]
+    fn sonny_jim_pooled(bencher: divan::Bencher) {
+        let pool = sonny_jim::ArenaPool::new();
+        bencher.bench_local(|| black_box_drop(pool.parse_next(black_box(SMALL))));
+    }
+
     #[divan::bench]
     fn serde_raw() {
         black_box_drop(serde_json::from_str::<&serde_json::value::RawValue>(
@@ -0,0 +1,319 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::ops::Range;
+
+use crate::{Arena, Array, LeafValue, Object, StringKey, Value, ValueKind};
+
+/// The result of [`Object::get_entry`].
+#[derive(Debug, Clone, Copy)]
+pub enum Entry<'a> {
+    /// The key is not in the object.
+    Missing,
+    /// The key is explicitly `null`.
+    Null(&'a Value),
+    Present(&'a Value),
+}
+
+impl<'a> Entry<'a> {
+    /// The value, if it is present and not `null`.
+    pub fn value(self) -> Option<&'a Value> {
+        match self {
+            Entry::Present(value) => Some(value),
+            Entry::Missing | Entry::Null(_) => None,
+        }
+    }
+}
+
+impl Array {
+    /// The number of values in the array.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Object {
+    /// Look up `key`, distinguishing a missing key from an explicit `null`.
+    ///
+    /// If the key is duplicated, the last value wins.
+    pub fn get_entry<'a>(&self, arena: &'a Arena<'_>, key: &str) -> Entry<'a> {
+        match arena.get(self, key) {
+            None => Entry::Missing,
+            Some(value) if value.is_null() => Entry::Null(value),
+            Some(value) => Entry::Present(value),
+        }
+    }
+
+    /// Every value for `key`, in source order.
+    ///
+    /// Duplicate keys are all kept by the parser, so header-like or query-like
+    /// objects can be read as a multimap.
+    pub fn get_all<'a>(
+        &self,
+        arena: &'a Arena<'_>,
+        key: &'a str,
+    ) -> impl DoubleEndedIterator<Item = &'a Value> + 'a {
+        arena
+            .entries(self)
+            .filter(move |(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+impl Arena<'_> {
+    /// The keys and values of `object`, in source order.
+    ///
+    /// Duplicate keys are all kept, each with its own value.
+    pub fn entries<'a>(
+        &'a self,
+        object: &Object,
+    ) -> impl DoubleEndedIterator<Item = (&'a str, &'a Value)> + 'a {
+        let values = &self.values[object.values().start as usize..object.values().end as usize];
+        core::iter::zip(self.keys_of(object), values).map(|(k, v)| (&self[k], v))
+    }
+
+    /// The keys of `object`, in source order.
+    pub(crate) fn keys_of(&self, object: &Object) -> &[StringKey] {
+        let keys = object.keys(&self.values);
+        &self.keys[keys.start as usize..keys.end as usize]
+    }
+
+    /// Look up `key` in `object`. If the key is duplicated, the last value wins.
+    pub fn get(&self, object: &Object, key: &str) -> Option<&Value> {
+        self.entries(object)
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// The values of `array`.
+    pub(crate) fn array_values(&self, array: &Array) -> &[Value] {
+        &self.values[array.values().start as usize..array.values().end as usize]
+    }
+
+    /// The value at `index` in `array`.
+    pub fn array_get(&self, array: &Array, index: usize) -> Option<&Value> {
+        self.array_values(array).get(index)
+    }
+
+    /// The values of `array`, in order.
+    pub fn array_iter(&self, array: &Array) -> core::slice::Iter<'_, Value> {
+        self.array_values(array).iter()
+    }
+
+    /// Where `value` is in the source, in bytes.
+    ///
+    /// Values copied in from another arena are not in the source,
+    /// and have a reversed range, `end < start`.
+    pub fn span(&self, value: &Value) -> Range<u32> {
+        self.spans[value.slot() as usize].clone()
+    }
+
+    /// The span of the container whose values are `values`, kept in the header before them.
+    pub(crate) fn container_span(&self, values: Range<u32>) -> Range<u32> {
+        self.spans[values.start as usize - 1].clone()
+    }
+
+    /// The source text of `value`.
+    pub(crate) fn raw(&self, value: &Value) -> &str {
+        self.raw_at(&self.span(value))
+    }
+
+    /// The source text at `span`, which is reversed if it was copied from another arena.
+    pub(crate) fn raw_at(&self, span: &Range<u32>) -> &str {
+        let Range { start, end } = *span;
+        if end < start {
+            // copied from another arena
+            &self.scratch.text[end as usize..start as usize]
+        } else {
+            &self.scratch.src[start as usize..end as usize]
+        }
+    }
+
+    /// The contents of a string value, with escapes decoded.
+    pub fn as_str(&self, value: &Value) -> Option<Cow<'_, str>> {
+        let ValueKind::Leaf(LeafValue::String) = value.kind() else {
+            return None;
+        };
+
+        let raw = self.raw(value);
+        let quote = raw.as_bytes()[0];
+        let raw = &raw[1..raw.len() - 1];
+        if memchr::memchr(b'\\', raw.as_bytes()).is_none() {
+            return Some(Cow::Borrowed(raw));
+        }
+
+        let mut out = String::new();
+        crate::unescape_into(raw, quote, &mut out).ok()?;
+        Some(Cow::Owned(out))
+    }
+
+    /// The value of a number, as an `f64`.
+    pub fn as_f64(&self, value: &Value) -> Option<f64> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Number | LeafValue::BigInt) => self.raw(value).parse().ok(),
+            ValueKind::Leaf(LeafValue::RadixInt(_)) => self.as_i128(value).map(|n| n as f64),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer, if it fits in an `i128`.
+    pub fn as_i128(&self, value: &Value) -> Option<i128> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Number | LeafValue::BigInt) => self.raw(value).parse().ok(),
+            ValueKind::Leaf(LeafValue::RadixInt(radix)) => {
+                let raw = self.raw(value);
+                let (neg, raw) = match raw.strip_prefix('-') {
+                    Some(raw) => (true, raw),
+                    None => (false, raw),
+                };
+                let n = i128::from_str_radix(&raw[2..], radix.base()).ok()?;
+                Some(if neg { -n } else { n })
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of an integer, if it fits in an `i64`.
+    pub fn as_i64(&self, value: &Value) -> Option<i64> {
+        self.as_i128(value)?.try_into().ok()
+    }
+
+    /// The value of an integer, if it fits in a `u64`.
+    pub fn as_u64(&self, value: &Value) -> Option<u64> {
+        self.as_i128(value)?.try_into().ok()
+    }
+
+    pub fn as_bool(&self, value: &Value) -> Option<bool> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Bool(b)) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// The digits of a number exactly as written, such as those of a
+    /// [`LeafValue::BigInt`] that is too large for [`Arena::as_i128`].
+    pub fn raw_number(&self, value: &Value) -> Option<&str> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt) => {
+                Some(self.raw(value))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Entry, ValueKind};
+
+    #[test]
+    #[cfg(feature = "unicode-escapes")]
+    fn get_all() {
+        use alloc::vec::Vec;
+
+        let src =
+            r#"{"set-cookie": "a=1", "host": "x", "set-cookie": "b=2", "set\u002dcookie": "c=3"}"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+
+        let cookies: Vec<_> = object
+            .get_all(&arena, "set-cookie")
+            .map(|v| arena.as_str(v).unwrap())
+            .collect();
+        assert_eq!(cookies, ["a=1", "b=2", "c=3"]);
+        assert_eq!(object.get_all(&arena, "host").count(), 1);
+        assert_eq!(object.get_all(&arena, "missing").count(), 0);
+    }
+
+    #[test]
+    fn get_and_entries() {
+        use alloc::vec::Vec;
+
+        let mut arena = Arena::new(r#"{"a": 1, "b": {"c": true}, "a": 2}"#);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+
+        assert_eq!(arena.to_json(arena.get(&object, "a").unwrap()), "2");
+        assert_eq!(
+            arena.to_json(arena.get(&object, "b").unwrap()),
+            r#"{"c":true}"#
+        );
+        assert!(arena.get(&object, "c").is_none());
+
+        let keys: Vec<_> = arena.entries(&object).map(|(k, _)| k).collect();
+        assert_eq!(keys, ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn arrays() {
+        let mut arena = Arena::new("[1, [], 3]");
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+
+        assert_eq!(array.len(), 3);
+        assert_eq!(arena.to_json(arena.array_get(&array, 2).unwrap()), "3");
+        assert!(arena.array_get(&array, 3).is_none());
+
+        let ValueKind::Array(empty) = &arena.array_iter(&array).nth(1).unwrap().kind() else {
+            panic!("expected array")
+        };
+        assert!(empty.is_empty());
+        assert_eq!(arena.array_iter(empty).count(), 0);
+    }
+
+    #[test]
+    fn leaves() {
+        let mut arena = Arena::new(r#"["a\tb", -1, 1.5, 18446744073709551615, true, null]"#);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+        let [s, neg, float, big, b, null] = arena.array_values(&array) else {
+            panic!("expected 6 values")
+        };
+
+        assert_eq!(arena.as_str(s).unwrap(), "a\tb");
+        assert_eq!(arena.as_i64(neg), Some(-1));
+        assert_eq!(arena.as_u64(neg), None);
+        assert_eq!(arena.as_f64(float), Some(1.5));
+        assert_eq!(arena.as_i64(float), None);
+        assert_eq!(arena.as_i64(big), None);
+        assert_eq!(arena.as_u64(big), Some(u64::MAX));
+        assert_eq!(arena.as_bool(b), Some(true));
+        assert_eq!(arena.as_bool(null), None);
+        assert!(null.is_null());
+        assert!(arena.as_str(b).is_none());
+    }
+
+    #[test]
+    fn get_entry() {
+        let mut arena = Arena::new(r#"{"name": "x", "email": null, "age": 1, "age": null}"#);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+
+        assert!(matches!(
+            object.get_entry(&arena, "name"),
+            Entry::Present(_)
+        ));
+        assert!(matches!(object.get_entry(&arena, "email"), Entry::Null(_)));
+        assert!(matches!(object.get_entry(&arena, "phone"), Entry::Missing));
+        assert!(matches!(object.get_entry(&arena, "age"), Entry::Null(_)));
+
+        let name = object.get_entry(&arena, "name").value().unwrap();
+        assert_eq!(arena.as_str(name).unwrap(), "x");
+        assert!(object.get_entry(&arena, "email").value().is_none());
+    }
+}
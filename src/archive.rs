@@ -0,0 +1,411 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{
+    Arena, Array, Document, LeafValue, Object, Radix, Repr, StringKey, Value, ValueKind, MAX_LEN,
+};
+
+/// The first bytes of every archive.
+const MAGIC: [u8; 4] = *b"sjar";
+/// Written in the archive's byte order, which is always little-endian.
+const BYTE_ORDER: u32 = 0x0102_0304;
+
+/// The magic, the byte order, the version, five sections and the root value.
+const HEADER_LEN: usize = 12 + 5 * 8 + VALUE_LEN;
+/// A span, a tag and two fields, all `u32`.
+const VALUE_LEN: usize = 5 * 4;
+const KEY_LEN: usize = 2 * 4;
+
+/// [`Document::from_archive`] was given bytes that it cannot load.
+#[derive(Debug, PartialEq)]
+pub enum InvalidArchive {
+    /// The bytes do not start with an archive header.
+    Magic,
+    /// The archive was not written in little-endian byte order.
+    ByteOrder,
+    /// The archive was written with another version of the format.
+    Version(u32),
+    /// A section or value in the archive is out of bounds.
+    Corrupt,
+}
+
+impl fmt::Display for InvalidArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidArchive::Magic => f.write_str("not a document archive"),
+            InvalidArchive::ByteOrder => f.write_str("document archive is not little-endian"),
+            InvalidArchive::Version(v) => write!(
+                f,
+                "document archive has version {v}, expected {}",
+                Document::ARCHIVE_VERSION
+            ),
+            InvalidArchive::Corrupt => f.write_str("document archive is corrupt"),
+        }
+    }
+}
+
+impl core::error::Error for InvalidArchive {}
+
+impl Document {
+    /// The version of the archive format written by [`to_archive`](Self::to_archive).
+    ///
+    /// The layout of a version never changes, and only archives of this version can be loaded,
+    /// so this can be part of the key when sharing archives in a build cache.
+    pub const ARCHIVE_VERSION: u32 = 4;
+
+    /// Write the document out, so that it can be loaded again with
+    /// [`from_archive`](Self::from_archive) without parsing the source again.
+    ///
+    /// All numbers are little-endian `u32`s, whatever the byte order of the machine,
+    /// so an archive can be loaded on any machine with the same [`ARCHIVE_VERSION`](Self::ARCHIVE_VERSION).
+    /// The archive starts with a header of the magic `sjar`, `0x01020304`, the version, then the byte offset and length of each section
+    /// (the source, the decoded keys, the copied text, the keys and the values), then the root value.
+    /// Each section starts on a multiple of 4 bytes.
+    ///
+    /// A key is its span. A value is its span, a tag, then two fields that depend on the tag:
+    /// the slot of a leaf, or the values range of an object or array. The values of a container
+    /// come right after a header, with its span and, for an object, where its keys start.
+    pub fn to_archive(&self) -> Vec<u8> {
+        let arena = &self.arena;
+        let mut w = Vec::with_capacity(
+            HEADER_LEN
+                + arena.scratch.src.len()
+                + arena.scratch.scratch.len()
+                + arena.scratch.text.len()
+                + arena.keys.len() * KEY_LEN
+                + arena.values.len() * VALUE_LEN
+                + 12,
+        );
+        w.resize(HEADER_LEN, 0);
+
+        let mut sections = [(0, 0); 5];
+        for (i, section) in sections.iter_mut().enumerate() {
+            w.resize(w.len().next_multiple_of(4), 0);
+            let start = w.len();
+            match i {
+                0 => w.extend_from_slice(arena.scratch.src.as_bytes()),
+                1 => w.extend_from_slice(arena.scratch.scratch.as_bytes()),
+                2 => w.extend_from_slice(arena.scratch.text.as_bytes()),
+                3 => arena.keys.iter().for_each(|key| put_key(&mut w, key)),
+                _ => core::iter::zip(&arena.values, &arena.spans)
+                    .for_each(|(value, span)| put_value(&mut w, value, span)),
+            }
+            *section = (start as u32, (w.len() - start) as u32);
+        }
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&BYTE_ORDER.to_le_bytes());
+        header.extend_from_slice(&Self::ARCHIVE_VERSION.to_le_bytes());
+        for (start, len) in sections {
+            header.extend_from_slice(&start.to_le_bytes());
+            header.extend_from_slice(&len.to_le_bytes());
+        }
+        put_value(&mut header, &self.root, &arena.span(&self.root));
+        w[..HEADER_LEN].copy_from_slice(&header);
+        w
+    }
+
+    /// Load a document written by [`to_archive`](Self::to_archive).
+    ///
+    /// This copies each section out of `bytes`, checks that every range in them is in bounds
+    /// and that each leaf spans a token of its kind, but does not parse the source, so it is much faster than [`Document::parse`].
+    /// Any bytes that are not a valid archive are rejected with an error, never a panic.
+    pub fn from_archive(bytes: &[u8]) -> Result<Self, InvalidArchive> {
+        if bytes.get(..4) != Some(&MAGIC) {
+            return Err(InvalidArchive::Magic);
+        }
+        let header = bytes.get(..HEADER_LEN).ok_or(InvalidArchive::Corrupt)?;
+        if u32_at(header, 4) != BYTE_ORDER {
+            return Err(InvalidArchive::ByteOrder);
+        }
+        match u32_at(header, 8) {
+            Self::ARCHIVE_VERSION => {}
+            version => return Err(InvalidArchive::Version(version)),
+        }
+        let section = |i: usize| {
+            let start = u32_at(header, 12 + i * 8) as usize;
+            let len = u32_at(header, 16 + i * 8) as usize;
+            let end = start.checked_add(len).ok_or(InvalidArchive::Corrupt)?;
+            bytes.get(start..end).ok_or(InvalidArchive::Corrupt)
+        };
+        let text =
+            |i: usize| core::str::from_utf8(section(i)?).map_err(|_| InvalidArchive::Corrupt);
+
+        let src: Arc<str> = text(0)?.into();
+        let scratch = String::from(text(1)?);
+        let copied = String::from(text(2)?);
+        let keys = section(3)?;
+        if keys.len() % KEY_LEN != 0 {
+            return Err(InvalidArchive::Corrupt);
+        }
+        let keys = keys.chunks_exact(KEY_LEN).map(get_key).collect();
+        let values = section(4)?;
+        if values.len() % VALUE_LEN != 0 {
+            return Err(InvalidArchive::Corrupt);
+        }
+        let (values, spans) = values
+            .chunks_exact(VALUE_LEN)
+            .map(get_value)
+            .collect::<Result<_, _>>()?;
+        let (root, _) = get_value(&header[HEADER_LEN - VALUE_LEN..])?;
+
+        let mut arena = Arena::new_shared(src);
+        arena.scratch.scratch = scratch;
+        arena.scratch.text = copied;
+        arena.keys = keys;
+        arena.values = values;
+        arena.spans = spans;
+        if !arena.is_valid_archive(&root) {
+            return Err(InvalidArchive::Corrupt);
+        }
+        Ok(Self { arena, root })
+    }
+}
+
+impl Arena<'_> {
+    /// Check that every range in the arena is in bounds, and that every leaf spans a token
+    /// of its kind, so that reading it cannot panic.
+    fn is_valid_archive(&self, root: &Value) -> bool {
+        let text = |range: &Range<u32>, reversed: &str, forward: &str| {
+            let Range { start, end } = *range;
+            if end < start {
+                reversed.get(end as usize..start as usize).is_some()
+            } else {
+                forward.get(start as usize..end as usize).is_some()
+            }
+        };
+        let key = |k: &StringKey| text(&k.0, &self.scratch.scratch, &self.scratch.src);
+        let span = |s: &Range<u32>| text(s, &self.scratch.text, &self.scratch.src);
+        // the headers before each slot, so a container can be checked to hold none.
+        let mut headers = vec![0];
+        for v in &self.values {
+            headers.push(headers[headers.len() - 1] + v.is_header() as u32);
+        }
+        let children = |values: Range<u32>| {
+            values.start >= 1
+                && values.end as usize <= self.values.len()
+                && self.values[values.start as usize - 1].is_header()
+                && headers[values.start as usize] == headers[values.end as usize]
+        };
+        // checked after `children`, so that the header is there.
+        let keys = |object: Object| match self.values[object.values().start as usize - 1].repr() {
+            Repr::Header { keys } => (keys as usize)
+                .checked_add(object.values().len())
+                .is_some_and(|end| end <= self.keys.len()),
+            _ => false,
+        };
+        let value = |v: &Value| {
+            v.is_header()
+                || match v.kind() {
+                    ValueKind::Leaf(leaf) => self
+                        .spans
+                        .get(v.slot() as usize)
+                        .is_some_and(|span| crate::is_leaf_token(leaf, self.raw_at(span))),
+                    ValueKind::Object(object) => children(object.values()) && keys(object),
+                    ValueKind::Array(array) => children(array.values()),
+                }
+        };
+        self.keys.iter().all(key)
+            && self.spans.iter().all(span)
+            && self.values.iter().all(value)
+            && !root.is_header()
+            && value(root)
+            && self.is_acyclic(root)
+    }
+
+    /// Check that no container reachable from `root` contains itself, so that walking it ends.
+    fn is_acyclic(&self, root: &Value) -> bool {
+        let children = |value: &Value| match value.kind() {
+            ValueKind::Leaf(_) => 0..0,
+            ValueKind::Object(object) => object.values(),
+            ValueKind::Array(array) => array.values(),
+        };
+        // whether each value is on the current path, or has been checked already
+        let (mut open, mut done) = (
+            vec![false; self.values.len()],
+            vec![false; self.values.len()],
+        );
+        let mut stack = vec![(None, children(root))];
+        while let Some((parent, range)) = stack.last_mut() {
+            match range.next() {
+                Some(i) if open[i as usize] => return false,
+                Some(i) if !done[i as usize] => {
+                    open[i as usize] = true;
+                    stack.push((Some(i as usize), children(&self.values[i as usize])));
+                }
+                Some(_) => {}
+                None => {
+                    if let Some(parent) = *parent {
+                        open[parent] = false;
+                        done[parent] = true;
+                    }
+                    stack.pop();
+                }
+            }
+        }
+        true
+    }
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn put_key(w: &mut Vec<u8>, key: &StringKey) {
+    w.extend_from_slice(&key.0.start.to_le_bytes());
+    w.extend_from_slice(&key.0.end.to_le_bytes());
+}
+
+fn get_key(bytes: &[u8]) -> StringKey {
+    StringKey(u32_at(bytes, 0)..u32_at(bytes, 4))
+}
+
+fn put_value(w: &mut Vec<u8>, value: &Value, span: &Range<u32>) {
+    if let Repr::Header { keys } = value.repr() {
+        put_fields(w, span, 11, [keys, 0]);
+        return;
+    }
+    let leaf = [value.slot(), 0];
+    let (tag, fields) = match value.kind() {
+        ValueKind::Leaf(LeafValue::Bool(false)) => (0, leaf),
+        ValueKind::Leaf(LeafValue::Bool(true)) => (1, leaf),
+        ValueKind::Leaf(LeafValue::Null) => (2, leaf),
+        ValueKind::Leaf(LeafValue::Number) => (3, leaf),
+        ValueKind::Leaf(LeafValue::String) => (4, leaf),
+        ValueKind::Object(object) => (5, [object.values().start, object.values().end]),
+        ValueKind::Array(array) => (6, [array.values().start, array.values().end]),
+        ValueKind::Leaf(LeafValue::RadixInt(Radix::Binary)) => (7, leaf),
+        ValueKind::Leaf(LeafValue::RadixInt(Radix::Octal)) => (8, leaf),
+        ValueKind::Leaf(LeafValue::RadixInt(Radix::Hex)) => (9, leaf),
+        ValueKind::Leaf(LeafValue::BigInt) => (10, leaf),
+    };
+    put_fields(w, span, tag, fields);
+}
+
+fn put_fields(w: &mut Vec<u8>, span: &Range<u32>, tag: u32, fields: [u32; 2]) {
+    for n in [span.start, span.end, tag].into_iter().chain(fields) {
+        w.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+fn get_value(bytes: &[u8]) -> Result<(Value, Range<u32>), InvalidArchive> {
+    let [start, end, tag, a, b] = core::array::from_fn(|i| u32_at(bytes, i * 4));
+    let container = a <= b && b - a <= MAX_LEN;
+    let kind = match tag {
+        0 => ValueKind::Leaf(LeafValue::Bool(false)),
+        1 => ValueKind::Leaf(LeafValue::Bool(true)),
+        2 => ValueKind::Leaf(LeafValue::Null),
+        3 => ValueKind::Leaf(LeafValue::Number),
+        4 => ValueKind::Leaf(LeafValue::String),
+        5 if container => ValueKind::Object(Object::new(a..b)),
+        6 if container => ValueKind::Array(Array::new(a..b)),
+        7 => ValueKind::Leaf(LeafValue::RadixInt(Radix::Binary)),
+        8 => ValueKind::Leaf(LeafValue::RadixInt(Radix::Octal)),
+        9 => ValueKind::Leaf(LeafValue::RadixInt(Radix::Hex)),
+        10 => ValueKind::Leaf(LeafValue::BigInt),
+        11 => return Ok((Value::header(a), start..end)),
+        _ => return Err(InvalidArchive::Corrupt),
+    };
+    Ok((Value::new(kind, a), start..end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VALUE_LEN;
+    use crate::{Arena, Document, InvalidArchive, Value, ValueKind};
+
+    #[test]
+    fn archive() {
+        let src = r#"{"a\n": [1, 0.5, "b\"c", true, null], "d": {}, "e": [], "a\n": "f"}"#;
+        let doc = Document::parse(src).unwrap();
+        let bytes = doc.to_archive();
+
+        let loaded = Document::from_archive(&bytes).unwrap();
+        assert_eq!(
+            loaded.arena().to_json(loaded.root()),
+            doc.arena().to_json(doc.root())
+        );
+        assert_eq!(loaded.to_archive(), bytes);
+
+        assert_eq!(
+            Document::from_archive(&[]).unwrap_err(),
+            InvalidArchive::Magic
+        );
+        assert_eq!(
+            Document::from_archive(&bytes[..bytes.len() - 1]).unwrap_err(),
+            InvalidArchive::Corrupt
+        );
+        let mut bad = bytes.clone();
+        bad[4..8].reverse();
+        assert_eq!(
+            Document::from_archive(&bad).unwrap_err(),
+            InvalidArchive::ByteOrder
+        );
+        let mut bad = bytes.clone();
+        bad[8] = 5;
+        assert_eq!(
+            Document::from_archive(&bad).unwrap_err(),
+            InvalidArchive::Version(5)
+        );
+        // point the root past the end of the values
+        let mut bad = bytes.clone();
+        bad[68..72].copy_from_slice(&100u32.to_le_bytes());
+        assert_eq!(
+            Document::from_archive(&bad).unwrap_err(),
+            InvalidArchive::Corrupt
+        );
+
+        // an array that contains itself
+        let doc = Document::parse("[[]]").unwrap();
+        let mut bad = doc.to_archive();
+        let inner = bad.len() - VALUE_LEN + 12;
+        bad[inner..inner + 8].copy_from_slice(&[2, 0, 0, 0, 3, 0, 0, 0]);
+        assert_eq!(
+            Document::from_archive(&bad).unwrap_err(),
+            InvalidArchive::Corrupt
+        );
+
+        // a string whose span is empty, rather than a quoted token
+        let doc = Document::parse(r#"["ab"]"#).unwrap();
+        let mut bad = doc.to_archive();
+        let leaf = bad.len() - VALUE_LEN;
+        bad.copy_within(leaf..leaf + 4, leaf + 4);
+        assert_eq!(
+            Document::from_archive(&bad).unwrap_err(),
+            InvalidArchive::Corrupt
+        );
+
+        // no corruption of a single byte can panic
+        for i in 0..bytes.len() {
+            for b in [0, 1, 0x80, 0xff] {
+                let mut bad = bytes.clone();
+                bad[i] = b;
+                if let Ok(doc) = Document::from_archive(&bad) {
+                    doc.arena().to_json(doc.root());
+                    read(doc.arena(), doc.root());
+                }
+            }
+        }
+    }
+
+    /// Read every value below `value`, as a user of the archive would.
+    fn read(arena: &Arena<'_>, value: &Value) {
+        match value.kind() {
+            ValueKind::Leaf(_) => {
+                arena.as_str(value);
+                arena.as_f64(value);
+                arena.as_i128(value);
+            }
+            ValueKind::Object(object) => {
+                for (_, value) in arena.entries(&object) {
+                    read(arena, value);
+                }
+            }
+            ValueKind::Array(array) => arena.array_iter(&array).for_each(|v| read(arena, v)),
+        }
+    }
+}
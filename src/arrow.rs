@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use arrow_array::builder::{
+    ArrayBuilder, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder, UInt64Builder,
+};
+use arrow_array::RecordBatch;
+use arrow_schema::{ArrowError, DataType, SchemaRef};
+
+use crate::columns::{extract_into, ColumnSink};
+use crate::{Arena, ColumnError, LeafValue, Value, ValueKind};
+
+#[derive(Debug)]
+pub enum RecordBatchError {
+    /// The field at this index of the schema has a type that can't be read from JSON.
+    UnsupportedType {
+        field: usize,
+    },
+    Columns(ColumnError),
+    /// The columns did not fit the schema, such as a `null` in a non-nullable field.
+    Arrow(ArrowError),
+}
+
+impl fmt::Display for RecordBatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordBatchError::UnsupportedType { field } => {
+                write!(f, "field {field} has an unsupported type")
+            }
+            RecordBatchError::Columns(e) => e.fmt(f),
+            RecordBatchError::Arrow(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RecordBatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecordBatchError::UnsupportedType { .. } => None,
+            RecordBatchError::Columns(e) => Some(e),
+            RecordBatchError::Arrow(e) => Some(e),
+        }
+    }
+}
+
+enum Builder {
+    F64(Float64Builder),
+    I64(Int64Builder),
+    U64(UInt64Builder),
+    Bool(BooleanBuilder),
+    String(StringBuilder),
+}
+
+impl Builder {
+    fn new(ty: &DataType) -> Option<Self> {
+        Some(match ty {
+            DataType::Float64 => Builder::F64(Float64Builder::new()),
+            DataType::Int64 => Builder::I64(Int64Builder::new()),
+            DataType::UInt64 => Builder::U64(UInt64Builder::new()),
+            DataType::Boolean => Builder::Bool(BooleanBuilder::new()),
+            DataType::Utf8 => Builder::String(StringBuilder::new()),
+            _ => return None,
+        })
+    }
+
+    fn builder(&mut self) -> &mut dyn ArrayBuilder {
+        match self {
+            Builder::F64(b) => b,
+            Builder::I64(b) => b,
+            Builder::U64(b) => b,
+            Builder::Bool(b) => b,
+            Builder::String(b) => b,
+        }
+    }
+}
+
+impl ColumnSink for Builder {
+    fn push(&mut self, arena: &Arena<'_>, value: Option<&Value>) -> bool {
+        macro_rules! push {
+            ($builder:expr, $convert:expr) => {
+                match value {
+                    None => $builder.append_null(),
+                    Some(value) => match $convert(value) {
+                        Some(v) => $builder.append_value(v),
+                        None => return false,
+                    },
+                }
+            };
+        }
+
+        match self {
+            Builder::F64(b) => push!(b, |v| arena.as_f64(v)),
+            Builder::I64(b) => push!(b, |v| arena.as_i128(v).and_then(|n| i64::try_from(n).ok())),
+            Builder::U64(b) => push!(b, |v| arena.as_i128(v).and_then(|n| u64::try_from(n).ok())),
+            Builder::Bool(b) => push!(b, |v: &Value| match v.kind() {
+                ValueKind::Leaf(LeafValue::Bool(b)) => Some(b),
+                _ => None,
+            }),
+            Builder::String(b) => push!(b, |v| arena.as_str(v)),
+        }
+        true
+    }
+}
+
+/// Read a top-level array of objects into a [`RecordBatch`] with `schema`,
+/// taking each column from the key with the same name as its field.
+///
+/// Values are appended straight into Arrow array builders as each element is parsed.
+/// Supported field types are `Float64`, `Int64`, `UInt64`, `Boolean` and `Utf8`.
+pub fn to_record_batch(src: &str, schema: SchemaRef) -> Result<RecordBatch, RecordBatchError> {
+    let mut builders = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(field, f)| {
+            Builder::new(f.data_type()).ok_or(RecordBatchError::UnsupportedType { field })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let keys = schema.fields().iter().map(|f| f.name().as_str());
+    extract_into(src, keys, &mut builders).map_err(RecordBatchError::Columns)?;
+
+    let columns = builders.iter_mut().map(|b| b.builder().finish()).collect();
+    RecordBatch::try_new(schema, columns).map_err(RecordBatchError::Arrow)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, UInt64Type};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use crate::{to_record_batch, ColumnError, RecordBatchError};
+
+    #[test]
+    fn record_batch() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("price", DataType::Float64, false),
+            Field::new("qty", DataType::UInt64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let src = r#"[{"price": 1.5, "qty": 2, "name": "a\nb"}, {"price": 3, "qty": null}]"#;
+        let batch = to_record_batch(src, schema.clone()).unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        let price = batch.column(0).as_primitive::<Float64Type>();
+        assert_eq!(price.values().to_vec(), [1.5, 3.0]);
+        let qty = batch.column(1).as_primitive::<UInt64Type>();
+        assert_eq!(qty.iter().collect::<Vec<_>>(), [Some(2), None]);
+        let name = batch.column(2).as_string::<i32>();
+        assert_eq!(name.iter().collect::<Vec<_>>(), [Some("a\nb"), None]);
+
+        // price is not nullable
+        let err = to_record_batch(r#"[{"qty": 1}]"#, schema.clone()).unwrap_err();
+        assert!(matches!(err, RecordBatchError::Arrow(_)));
+
+        let err = to_record_batch(r#"[{"price": "1"}]"#, schema).unwrap_err();
+        assert!(matches!(
+            err,
+            RecordBatchError::Columns(ColumnError::Type { column: 0, .. })
+        ));
+
+        let schema = Arc::new(Schema::new(vec![Field::new("d", DataType::Date32, true)]));
+        let err = to_record_batch("[]", schema).unwrap_err();
+        assert!(matches!(
+            err,
+            RecordBatchError::UnsupportedType { field: 0 }
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+use core::ops::Range;
+
+use crate::{Arena, EqOptions};
+
+/// Assert that two JSON documents are semantically equal, as with [`Arena::eq_semantic`].
+///
+/// Both sides are anything that is `AsRef<str>`. An [`EqOptions`] can be given as a third argument.
+/// On failure, the panic message has the path to the first difference,
+/// and where it is in each document.
+///
+/// ```
+/// sonny_jim::assert_json_eq!(r#"{"a": 1, "b": [1.0]}"#, r#"{"b": [1], "a": 1}"#);
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_json_eq!($left, $right, $crate::EqOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {
+        if let ::core::result::Result::Err(message) = $crate::__json_eq_message(
+            ::core::convert::AsRef::<str>::as_ref(&$left),
+            ::core::convert::AsRef::<str>::as_ref(&$right),
+            $options,
+        ) {
+            ::core::panic!("{}", message)
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn __json_eq_message(left: &str, right: &str, options: EqOptions) -> Result<(), String> {
+    let mut l = Arena::new(left);
+    let a = crate::parse(&mut l).map_err(|e| format!("left is not valid JSON: {e}"))?;
+    let mut r = Arena::new(right);
+    let b = crate::parse(&mut r).map_err(|e| format!("right is not valid JSON: {e}"))?;
+
+    let Err(mismatch) = l.compare_semantic(&a, &r, &b, options) else {
+        return Ok(());
+    };
+
+    let mut message = format!("JSON values are not equal: {mismatch}\n");
+    for (side, src, span) in [
+        ("left", left, mismatch.left),
+        ("right", right, mismatch.right),
+    ] {
+        let _ = write!(message, "{side:>6}: ");
+        match span {
+            Some(span) => location(src, span, &mut message),
+            None => message.push_str("(missing)"),
+        }
+        message.push('\n');
+    }
+    Err(message)
+}
+
+/// Write `line:column: snippet` for the value at `span`.
+fn location(src: &str, span: Range<u32>, out: &mut String) {
+    const MAX: usize = 60;
+
+    let (start, end) = (span.start as usize, span.end as usize);
+    let line_start = src[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line = src[..start].matches('\n').count() + 1;
+    let column = src[line_start..start].chars().count() + 1;
+
+    let snippet = &src[start..end];
+    let _ = write!(out, "{line}:{column}: ");
+    match snippet.char_indices().nth(MAX) {
+        Some((i, _)) => {
+            out.push_str(&snippet[..i]);
+            out.push_str("...");
+        }
+        None => out.push_str(snippet),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::EqOptions;
+
+    fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let err = std::panic::catch_unwind(f).unwrap_err();
+        err.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn assert_json_eq() {
+        crate::assert_json_eq!(r#"{"a": 1, "b": [1.0]}"#, r#"{"b": [1], "a": 1}"#);
+        crate::assert_json_eq!(
+            "[1, 2]",
+            String::from("[2, 1]"),
+            EqOptions {
+                ignore_array_order: true,
+                ..EqOptions::default()
+            },
+        );
+
+        let message = panic_message(|| {
+            crate::assert_json_eq!(
+                "{\n  \"a\": {\"b\": [1, 2]}\n}",
+                r#"{"a": {"b": [1, "two"]}}"#
+            )
+        });
+        assert_eq!(
+            message,
+            "JSON values are not equal: values differ at \"/a/b/1\"\n  \
+             left: 2:18: 2\n \
+             right: 1:17: \"two\"\n"
+        );
+
+        let message = panic_message(|| crate::assert_json_eq!(r#"{"a": 1}"#, "{}"));
+        assert_eq!(
+            message,
+            "JSON values are not equal: \"/a\" is missing on the right\n  \
+             left: 1:7: 1\n \
+             right: (missing)\n"
+        );
+
+        let message = panic_message(|| crate::assert_json_eq!("[", "[]"));
+        assert!(message.starts_with("left is not valid JSON"));
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+const URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Append the unpadded base64url encoding of `bytes` to `out`.
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub(crate) fn encode_url(bytes: &[u8], out: &mut String) {
+    let mut chunks = bytes.chunks_exact(3);
+    for chunk in &mut chunks {
+        let n = u32::from_be_bytes([0, chunk[0], chunk[1], chunk[2]]);
+        for shift in [18, 12, 6, 0] {
+            out.push(URL_ALPHABET[(n >> shift) as usize & 0x3f] as char);
+        }
+    }
+
+    match *chunks.remainder() {
+        [a] => {
+            let n = u32::from_be_bytes([0, a, 0, 0]);
+            for shift in [18, 12] {
+                out.push(URL_ALPHABET[(n >> shift) as usize & 0x3f] as char);
+            }
+        }
+        [a, b] => {
+            let n = u32::from_be_bytes([0, a, b, 0]);
+            for shift in [18, 12, 6] {
+                out.push(URL_ALPHABET[(n >> shift) as usize & 0x3f] as char);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Decode unpadded base64url, as used by JOSE.
+pub(crate) fn decode_url(s: &str) -> Result<Vec<u8>, ()> {
+    decode_with(s, |c| match c {
+        b'A'..=b'Z' => Ok((c - b'A') as u32),
+        b'a'..=b'z' => Ok((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Ok((c - b'0' + 52) as u32),
+        b'-' => Ok(62),
+        b'_' => Ok(63),
+        _ => Err(()),
+    })
+}
+
+/// Decode standard base64, with or without padding.
+#[cfg(feature = "base64")]
+pub(crate) fn decode_standard(s: &str) -> Result<Vec<u8>, ()> {
+    let unpadded = s.trim_end_matches('=');
+    let padding = s.len() - unpadded.len();
+    if padding > 2 || (padding > 0 && !s.len().is_multiple_of(4)) {
+        return Err(());
+    }
+    decode_with(unpadded, |c| match c {
+        b'A'..=b'Z' => Ok((c - b'A') as u32),
+        b'a'..=b'z' => Ok((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Ok((c - b'0' + 52) as u32),
+        b'+' => Ok(62),
+        b'/' => Ok(63),
+        _ => Err(()),
+    })
+}
+
+fn decode_with(s: &str, sextet: impl Fn(u8) -> Result<u32, ()>) -> Result<Vec<u8>, ()> {
+    let mut out = Vec::with_capacity(s.len() / 4 * 3 + 2);
+    let mut chunks = s.as_bytes().chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut n = 0;
+        for &c in chunk {
+            n = n << 6 | sextet(c)?;
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..]);
+    }
+
+    match *chunks.remainder() {
+        [] => {}
+        [a, b] => {
+            let n = sextet(a)? << 18 | sextet(b)? << 12;
+            out.push((n >> 16) as u8);
+        }
+        [a, b, c] => {
+            let n = sextet(a)? << 18 | sextet(b)? << 12 | sextet(c)? << 6;
+            out.extend_from_slice(&n.to_be_bytes()[1..3]);
+        }
+        _ => return Err(()),
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "cbor", feature = "msgpack"))]
+    #[test]
+    fn encode_url() {
+        use alloc::string::String;
+
+        let cases: [(&[u8], &str); 5] = [
+            (b"", ""),
+            (b"f", "Zg"),
+            (b"fo", "Zm8"),
+            (b"foo", "Zm9v"),
+            (&[0xfb, 0xff, 0xbf], "-_-_"),
+        ];
+        for (input, expected) in cases {
+            let mut out = String::new();
+            super::encode_url(input, &mut out);
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn decode_url() {
+        let cases: [(&str, &[u8]); 5] = [
+            ("", b""),
+            ("Zg", b"f"),
+            ("Zm8", b"fo"),
+            ("Zm9v", b"foo"),
+            ("-_-_", &[0xfb, 0xff, 0xbf]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(super::decode_url(input).unwrap(), expected);
+        }
+        assert!(super::decode_url("Z").is_err());
+        assert!(super::decode_url("Zm9v+g").is_err());
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn decode_standard() {
+        let cases: [(&str, &[u8]); 5] = [
+            ("", b""),
+            ("Zg==", b"f"),
+            ("Zm8", b"fo"),
+            ("Zm9v", b"foo"),
+            ("+/+/", &[0xfb, 0xff, 0xbf]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(super::decode_standard(input).unwrap(), expected);
+        }
+        for input in ["Zg=", "Zm9v====", "-_-_"] {
+            assert!(super::decode_standard(input).is_err(), "{input}");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Entry points for each phase of parsing, so benchmarks can tell which one got slower.
+//!
+//! Comparing [`lex`], [`parse_no_intern`] and [`parse`] on the same input splits the time
+//! between lexing, building values, and interning keys. Arena growth can be separated out
+//! by parsing into an arena made with [`Arena::from_buffers`](crate::Arena::from_buffers),
+//! whose buffers were kept from a previous parse.
+
+use core::ops::Range;
+use logos::Logos;
+
+use crate::{Arena, Error, Token, Value};
+
+/// Lex `src` the way the parser does, without checking the grammar or building values.
+///
+/// Returns the number of tokens, or the span of the first one that is invalid.
+pub fn lex(src: &str) -> Result<usize, Range<u32>> {
+    let mut lexer = Token::lexer(src);
+    let mut tokens = 0;
+    loop {
+        lexer.bump(crate::skip_whitespace(lexer.remainder().as_bytes()));
+        match lexer.remainder().as_bytes().first() {
+            None => return Ok(tokens),
+            Some(&b) if crate::STRUCTURAL[b as usize].is_some() => lexer.bump(1),
+            Some(_) => match lexer.next() {
+                Some(Ok(_)) => {}
+                Some(Err(())) => {
+                    let span = lexer.span();
+                    return Err(span.start as u32..span.end as u32);
+                }
+                None => return Ok(tokens),
+            },
+        }
+        tokens += 1;
+    }
+}
+
+/// Parse like [`parse`], but without interning keys.
+///
+/// Every key is stored as it is found, so repeated keys are not shared.
+pub fn parse_no_intern(arena: &mut Arena<'_>) -> Result<Value, Error> {
+    arena.intern = false;
+    let value = crate::parse(arena);
+    arena.intern = true;
+    value
+}
+
+/// The whole parse, the same as [`crate::parse`].
+pub fn parse(arena: &mut Arena<'_>) -> Result<Value, Error> {
+    crate::parse(arena)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Arena;
+
+    #[test]
+    fn phases() {
+        let src = r#"[{"id": 1, "tags": ["a"]}, {"id": 2, "tags": []}]"#;
+        assert_eq!(super::lex(src), Ok(24));
+        assert_eq!(super::lex("[1, @]"), Err(4..5));
+
+        let mut interned = Arena::new(src);
+        let value = super::parse(&mut interned).unwrap();
+        let mut arena = Arena::new(src);
+        let no_intern = super::parse_no_intern(&mut arena).unwrap();
+        assert_eq!(arena.to_json(&no_intern), interned.to_json(&value));
+        assert_eq!(interned.table.len() + interned.small.len(), 2);
+        assert!(arena.table.is_empty() && arena.small.is_empty());
+    }
+}
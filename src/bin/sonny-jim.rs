@@ -0,0 +1,154 @@
+//! A command line tool for checking, formatting and querying JSON files.
+
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+use sonny_jim::refs::Refs;
+use sonny_jim::{Document, EqOptions, Query};
+
+const USAGE: &str = "\
+usage: sonny-jim <command> [options] [file]
+
+Reads standard input when no file, or `-`, is given.
+
+commands:
+    validate [file...]                  check that each file is valid JSON
+    fmt [--indent=<n>] [--sort-keys] [file]
+                                        pretty-print
+    minify [file]                       print as compact JSON
+    get <pointer> [file]                print the value at a JSON pointer, like /a/0
+    query <jsonpath> [file]             print the path and value of each match, like $..id
+    diff [--ignore-array-order] <left> <right>
+                                        report where two files first differ, ignoring
+                                        key order and number formatting
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("sonny-jim: {message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Run a command, returning whether it succeeded.
+fn run(args: &[String]) -> Result<bool, String> {
+    let Some((command, args)) = args.split_first() else {
+        return Err(USAGE.into());
+    };
+    let (flags, args): (Vec<&str>, Vec<&str>) = args
+        .iter()
+        .map(String::as_str)
+        .partition(|arg| arg.starts_with("--"));
+    let mut out = std::io::stdout().lock();
+
+    match (command.as_str(), args.as_slice()) {
+        ("validate", files) => {
+            let files = if files.is_empty() { &["-"][..] } else { files };
+            let mut valid = true;
+            for file in files {
+                if let Err(message) = load(file) {
+                    eprintln!("{message}");
+                    valid = false;
+                }
+            }
+            Ok(valid)
+        }
+        ("fmt", args) => {
+            let indent = match flags.iter().find_map(|flag| flag.strip_prefix("--indent=")) {
+                Some(n) => n.parse().map_err(|_| format!("invalid indent {n:?}"))?,
+                None => 2,
+            };
+            let file = file_arg(args)?;
+            let doc = load(file)?;
+            let snapshot = doc
+                .arena()
+                .snapshot(doc.root())
+                .indent(indent)
+                .sort_keys(flags.contains(&"--sort-keys"));
+            print(&mut out, &snapshot)
+        }
+        ("minify", args) => {
+            let file = file_arg(args)?;
+            let doc = load(file)?;
+            print(&mut out, &doc.arena().value_ref(doc.root()))
+        }
+        ("get", [pointer, args @ ..]) => {
+            let file = file_arg(args)?;
+            let doc = load(file)?;
+            let refs = Refs::new(doc.arena(), doc.root());
+            match refs.pointer(pointer) {
+                Some(value) => print(&mut out, &doc.arena().value_ref(value)),
+                None => {
+                    eprintln!("{pointer:?} not found");
+                    Ok(false)
+                }
+            }
+        }
+        ("query", [query, args @ ..]) => {
+            let query = Query::parse(query).map_err(|e| e.to_string())?;
+            let file = file_arg(args)?;
+            let doc = load(file)?;
+            let matches = doc.arena().query(doc.root(), &query);
+            for (path, value) in &matches {
+                let line = format!("{}\t{}", path.to_jsonpath(), doc.arena().value_ref(value));
+                print(&mut out, &line)?;
+            }
+            Ok(!matches.is_empty())
+        }
+        ("diff", [left, right]) => {
+            let (left, right) = (load(left)?, load(right)?);
+            let options = EqOptions {
+                ignore_array_order: flags.contains(&"--ignore-array-order"),
+                ..EqOptions::default()
+            };
+            let result =
+                left.arena()
+                    .compare_semantic(left.root(), right.arena(), right.root(), options);
+            match result {
+                Ok(()) => Ok(true),
+                Err(mismatch) => {
+                    print(&mut out, &mismatch)?;
+                    Ok(false)
+                }
+            }
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+/// The one optional file argument of a command.
+fn file_arg<'a>(args: &[&'a str]) -> Result<&'a str, String> {
+    match args {
+        [] => Ok("-"),
+        [file] => Ok(file),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn load(file: &str) -> Result<Document, String> {
+    let src = if file == "-" {
+        let mut src = String::new();
+        std::io::stdin()
+            .read_to_string(&mut src)
+            .map_err(|e| format!("<stdin>: {e}"))?;
+        src
+    } else {
+        std::fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))?
+    };
+    Document::parse(src.clone()).map_err(|e| {
+        let before = &src[..(e.span().start as usize).min(src.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        format!("{file}:{line}:{column}: {e}")
+    })
+}
+
+fn print(out: &mut impl Write, value: &impl std::fmt::Display) -> Result<bool, String> {
+    writeln!(out, "{value}").map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -0,0 +1,136 @@
+//! Values and ids branded with the arena they came from, so mixing arenas is a compile error.
+//!
+//! Each call to [`Arena::scope`] gets a fresh, unnameable lifetime `'id`, and everything the
+//! [`Scope`] gives out carries it. Handing a [`Branded`] to another scope does not type check:
+//!
+//! ```compile_fail
+//! let mut a = sonny_jim::Arena::new("[1]");
+//! let mut b = sonny_jim::Arena::new("[2]");
+//! a.scope(|mut a| {
+//!     let root = a.parse().unwrap();
+//!     b.scope(|b| {
+//!         b.children(&root).count();
+//!     });
+//! });
+//! ```
+
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use crate::{Arena, Error, Value, ValueId, ValueKind};
+
+/// An invariant lifetime, so that two brands can never be unified.
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// A `T` that belongs to the arena of the [`Scope`] with the same `'id`.
+#[derive(Clone, Copy)]
+pub struct Branded<'id, T> {
+    inner: T,
+    brand: Brand<'id>,
+}
+
+impl<T> Branded<'_, T> {
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> Deref for Branded<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Branded<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// An arena borrowed by [`Arena::scope`].
+pub struct Scope<'id, 'a, 's> {
+    arena: &'a mut Arena<'s>,
+    brand: Brand<'id>,
+}
+
+impl<'s> Arena<'s> {
+    /// Run `f` with this arena, where the values and ids it gives out cannot be used with any other.
+    pub fn scope<R>(&mut self, f: impl for<'id> FnOnce(Scope<'id, '_, 's>) -> R) -> R {
+        f(Scope {
+            arena: self,
+            brand: PhantomData,
+        })
+    }
+}
+
+impl<'id, 's> Scope<'id, '_, 's> {
+    fn brand<T>(&self, inner: T) -> Branded<'id, T> {
+        Branded {
+            inner,
+            brand: self.brand,
+        }
+    }
+
+    /// The arena, for reading branded values with the rest of the API.
+    pub fn arena(&self) -> &Arena<'s> {
+        self.arena
+    }
+
+    /// Parse the source of the arena, like [`parse`](crate::parse).
+    pub fn parse(&mut self) -> Result<Branded<'id, Value>, Error> {
+        crate::parse(self.arena).map(|value| self.brand(value))
+    }
+
+    /// The ids of the children of `value`, with their keys if it is an object.
+    pub fn children<'v>(
+        &'v self,
+        value: &Branded<'id, Value>,
+    ) -> impl Iterator<Item = (Option<&'v str>, Branded<'id, ValueId>)> + 'v {
+        // only borrow the arena, which unlike the scope is covariant in `'s`.
+        let arena: &'v Arena<'v> = self.arena;
+        let brand = self.brand;
+        let (array, object) = match value.kind() {
+            ValueKind::Array(array) => (Some(arena.array_ids(&array)), None),
+            ValueKind::Object(object) => (None, Some(arena.object_ids(&object))),
+            ValueKind::Leaf(_) => (None, None),
+        };
+        let array = array.into_iter().flatten().map(|id| (None, id));
+        let object = object
+            .into_iter()
+            .flatten()
+            .map(|(key, id)| (Some(arena.key(key)), id));
+        array
+            .chain(object)
+            .map(move |(key, inner)| (key, Branded { inner, brand }))
+    }
+
+    /// The value with the id `id`.
+    pub fn value(&self, id: Branded<'id, ValueId>) -> Branded<'id, Value> {
+        self.brand(*self.arena.value(id.inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::Arena;
+
+    #[test]
+    fn scope() {
+        let mut arena = Arena::new(r#"{"a": [1, 2], "b": null}"#);
+        let json = arena.scope(|mut scope| {
+            let root = scope.parse().unwrap();
+            let (key, a) = scope.children(&root).next().unwrap();
+            assert_eq!(key, Some("a"));
+            let a = scope.value(a);
+            scope
+                .children(&a)
+                .map(|(_, id)| scope.arena().to_json(&scope.value(id)))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(json, ["1", "2"]);
+    }
+}
@@ -0,0 +1,142 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use foldhash::quality::RandomState;
+use hashbrown::HashTable;
+
+#[cfg(feature = "alloc-stats")]
+use crate::ParseStats;
+use crate::{Arena, Reserve, Scratch, Source, Stacks, StringKey, Value};
+
+/// The allocations behind an [`Arena`]: its values and their spans, keys, key interner, scratch space and
+/// parser stacks.
+///
+/// None of them depend on the source string, so they can be moved from one arena to the next
+/// with [`Arena::into_buffers`] and [`Arena::from_buffers`], without reallocating.
+#[derive(Default)]
+pub struct Buffers {
+    hasher: RandomState,
+    table: HashTable<StringKey>,
+    small: Vec<StringKey>,
+    keys: Vec<StringKey>,
+    values: Vec<Value>,
+    spans: Vec<Range<u32>>,
+    scratch: String,
+    text: String,
+    stacks: Stacks,
+}
+
+impl Buffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'s> Arena<'s> {
+    /// Create an empty arena for `src`, backed by `buffers`.
+    pub fn from_buffers(src: &'s str, buffers: Buffers) -> Self {
+        Self::with_buffers(Source::Borrowed(src), buffers)
+    }
+
+    pub(crate) fn with_buffers(src: Source<'s>, buffers: Buffers) -> Self {
+        let Buffers {
+            hasher,
+            table,
+            small,
+            keys,
+            values,
+            spans,
+            scratch,
+            text,
+            stacks,
+        } = buffers;
+
+        Self {
+            scratch: Scratch {
+                src,
+                scratch,
+                max_scratch: usize::MAX,
+                text,
+            },
+            hasher,
+            table,
+            small,
+            keys,
+            values,
+            spans,
+            reserve: Reserve::DEFAULT,
+            known: None,
+            numbers: None,
+            stacks,
+            #[cfg(debug_assertions)]
+            brand: crate::id::next_brand(),
+            #[cfg(feature = "bench")]
+            intern: true,
+            #[cfg(feature = "alloc-stats")]
+            stats: ParseStats::default(),
+            #[cfg(feature = "trace")]
+            trace: crate::ParseTrace::default(),
+        }
+    }
+
+    /// Clear the arena, and give back its allocations for the next one.
+    pub fn into_buffers(self) -> Buffers {
+        let Self {
+            scratch:
+                Scratch {
+                    mut scratch,
+                    mut text,
+                    ..
+                },
+            hasher,
+            mut table,
+            mut small,
+            mut keys,
+            mut values,
+            mut spans,
+            mut stacks,
+            ..
+        } = self;
+
+        scratch.clear();
+        text.clear();
+        table.clear();
+        small.clear();
+        keys.clear();
+        values.clear();
+        spans.clear();
+        stacks.clear();
+
+        Buffers {
+            hasher,
+            table,
+            small,
+            keys,
+            values,
+            spans,
+            scratch,
+            text,
+            stacks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Buffers};
+
+    #[test]
+    fn reuse_buffers() {
+        let mut buffers = Buffers::new();
+        let mut ptr = None;
+        for src in [r#"{"a\n": [1, 2]}"#, r#"{"b\n": [3]}"#] {
+            let mut arena = Arena::from_buffers(src, buffers);
+            let value = crate::parse(&mut arena).unwrap();
+            assert_eq!(arena.to_json(&value), src.replace(' ', ""));
+
+            let ptrs = (arena.values.as_ptr(), arena.stacks.stack.as_ptr());
+            assert_eq!(*ptr.get_or_insert(ptrs), ptrs);
+            buffers = arena.into_buffers();
+        }
+    }
+}
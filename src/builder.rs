@@ -0,0 +1,201 @@
+use alloc::vec::Vec;
+
+use crate::flatten::array_index;
+use crate::{Arena, Array, Object, Value, ValueKind};
+
+/// Composes a document out of subtrees of other documents.
+pub struct Builder<'s> {
+    arena: Arena<'s>,
+    root: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpliceError {
+    /// The pointer is not empty and does not start with `/`.
+    InvalidPointer,
+    /// The pointer does not point to a value, or to a place a value can be added.
+    NotFound,
+}
+
+/// Where a value lives.
+#[derive(Clone, Copy)]
+enum Slot {
+    Root,
+    Value(usize),
+}
+
+impl<'s> Builder<'s> {
+    pub fn new(arena: Arena<'s>, root: Value) -> Self {
+        Self { arena, root }
+    }
+
+    pub fn arena(&self) -> &Arena<'s> {
+        &self.arena
+    }
+
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    pub fn into_parts(self) -> (Arena<'s>, Value) {
+        (self.arena, self.root)
+    }
+
+    /// Graft a copy of `value` from `src` into the document at the
+    /// [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) `pointer`.
+    ///
+    /// The value at `pointer` is replaced. If the pointer names a missing
+    /// object member, or the end of an array (`-` or its length), the value is added.
+    pub fn splice(
+        &mut self,
+        pointer: &str,
+        src: &Arena<'_>,
+        value: &Value,
+    ) -> Result<(), SpliceError> {
+        if pointer.is_empty() {
+            self.root = src.deep_copy_into(value, &mut self.arena);
+            return Ok(());
+        }
+
+        let mut segments: Vec<_> = pointer
+            .strip_prefix('/')
+            .ok_or(SpliceError::InvalidPointer)?
+            .split('/')
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        let last = segments.pop().unwrap_or_default();
+
+        let mut parent = Slot::Root;
+        for segment in &segments {
+            parent = self.child(parent, segment).ok_or(SpliceError::NotFound)?;
+        }
+
+        if let Some(slot) = self.child(parent, &last) {
+            let value = src.deep_copy_into(value, &mut self.arena);
+            self.set(slot, value);
+            return Ok(());
+        }
+
+        // not there yet, so it's added by moving the container's entries to the end.
+        let span = self.arena.span(self.slot(parent));
+        let kind = match self.slot(parent).kind() {
+            ValueKind::Object(object) => {
+                let value = src.deep_copy_into(value, &mut self.arena);
+                let key = self.arena.intern_copy(&last);
+                let keys = object.keys(&self.arena.values);
+                let values = self
+                    .arena
+                    .push_header(span.clone(), self.arena.keys.len() as u32);
+                self.arena
+                    .keys
+                    .extend_from_within(keys.start as usize..keys.end as usize);
+                self.arena.keys.push(key);
+                for i in object.values() {
+                    self.arena.push_value(self.arena.values[i as usize]);
+                }
+                self.arena.push_value(value);
+
+                ValueKind::Object(Object::new(values..self.arena.values.len() as u32))
+            }
+            ValueKind::Array(array) if last == "-" || array_index(&last) == Some(array.len()) => {
+                let value = src.deep_copy_into(value, &mut self.arena);
+                let values = self.arena.push_header(span.clone(), 0);
+                for i in array.values() {
+                    self.arena.push_value(self.arena.values[i as usize]);
+                }
+                self.arena.push_value(value);
+
+                ValueKind::Array(Array::new(values..self.arena.values.len() as u32))
+            }
+            _ => return Err(SpliceError::NotFound),
+        };
+        let value = self.arena.root(kind, span);
+        self.set(parent, value);
+        Ok(())
+    }
+
+    fn slot(&self, slot: Slot) -> &Value {
+        match slot {
+            Slot::Root => &self.root,
+            Slot::Value(i) => &self.arena.values[i],
+        }
+    }
+
+    fn set(&mut self, slot: Slot, value: Value) {
+        match slot {
+            Slot::Root => self.root = value,
+            Slot::Value(i) => {
+                let span = self.arena.span(&value);
+                self.arena.set(i as u32, value.kind(), span);
+            }
+        }
+    }
+
+    fn child(&self, slot: Slot, segment: &str) -> Option<Slot> {
+        let index = match self.slot(slot).kind() {
+            ValueKind::Leaf(_) => return None,
+            ValueKind::Object(object) => {
+                // the last duplicate wins, as with `get`.
+                let keys = self.arena.keys_of(&object);
+                let i = keys.iter().rposition(|key| &self.arena[key] == segment)?;
+                object.values().start as usize + i
+            }
+            ValueKind::Array(array) => {
+                let i = array_index(segment)?;
+                (i < array.len()).then_some(array.values().start as usize + i)?
+            }
+        };
+        Some(Slot::Value(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Builder, SpliceError};
+
+    #[test]
+    fn splice() {
+        let mut arena = Arena::new(r#"{"spec": {"containers": [{"name": "app"}]}, "a/b": 1}"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let mut builder = Builder::new(arena, root);
+
+        let mut src = Arena::new(r#"{"image": "app:v2", "sidecar": {"name": "proxy"}}"#);
+        let src_root = crate::parse(&mut src).unwrap();
+        let crate::ValueKind::Object(object) = src_root.kind() else {
+            panic!("expected object")
+        };
+        let image = src.get(&object, "image").unwrap();
+        let sidecar = src.get(&object, "sidecar").unwrap();
+
+        builder
+            .splice("/spec/containers/0/image", &src, image)
+            .unwrap();
+        builder.splice("/spec/containers/-", &src, sidecar).unwrap();
+        builder.splice("/a~1b", &src, image).unwrap();
+        drop(src);
+
+        assert_eq!(
+            builder.arena().to_json(builder.root()),
+            r#"{"spec":{"containers":[{"name":"app","image":"app:v2"},{"name":"proxy"}]},"a/b":"app:v2"}"#
+        );
+
+        let mut src = Arena::new("[true]");
+        let value = crate::parse(&mut src).unwrap();
+        assert_eq!(
+            builder.splice("spec", &src, &value),
+            Err(SpliceError::InvalidPointer)
+        );
+        assert_eq!(
+            builder.splice("/spec/containers/3", &src, &value),
+            Err(SpliceError::NotFound)
+        );
+        assert_eq!(
+            builder.splice("/a~1b/c", &src, &value),
+            Err(SpliceError::NotFound)
+        );
+
+        builder.splice("", &src, &value).unwrap();
+        let (arena, root) = builder.into_parts();
+        assert_eq!(arena.to_json(&root), "[true]");
+    }
+}
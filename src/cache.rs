@@ -0,0 +1,127 @@
+use core::fmt;
+use core::hash::BuildHasher;
+use foldhash::quality::FixedState;
+use std::borrow::ToOwned;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{Document, Error};
+
+/// Parses files, and keeps an [archive](Document::to_archive) of each one
+/// so that the next parse of the same contents can skip the parser.
+///
+/// Archives are named after the source file and a hash of its contents, like `spec.json.1f2e3d4c5b6a7988.sjar`,
+/// and are stored next to it unless a directory is given with [`CachedParser::in_dir`].
+/// A missing, stale or unreadable archive falls back to parsing, and failing to write one is ignored.
+#[derive(Debug, Default)]
+pub struct CachedParser {
+    dir: Option<PathBuf>,
+}
+
+/// The error from [`CachedParser::parse`] or [`watch`](crate::watch()), when reading or parsing a file.
+#[derive(Debug)]
+pub enum CacheError {
+    /// The source file could not be read.
+    Io(io::Error),
+    Json(Error),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(e) => e.fmt(f),
+            CacheError::Json(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for CacheError {}
+
+impl CachedParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store archives in `dir`, rather than next to each source file.
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    /// Parse the file at `path`, or load it from its archive if the contents have been parsed before.
+    pub fn parse(&self, path: impl AsRef<Path>) -> Result<Document, CacheError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path).map_err(CacheError::Io)?;
+        let archive = self.archive_path(path, &src);
+
+        if let Some(doc) = std::fs::read(&archive)
+            .ok()
+            .and_then(|bytes| Document::from_archive(&bytes).ok())
+        {
+            // the hash only picks the file, so check it really is this source.
+            if *doc.arena.scratch.src == *src {
+                return Ok(doc);
+            }
+        }
+
+        let doc = Document::parse(src).map_err(CacheError::Json)?;
+        // write to a temporary file first, so a concurrent parse never reads half an archive.
+        let tmp = archive.with_extension("sjar.tmp");
+        if std::fs::write(&tmp, doc.to_archive()).is_ok()
+            && std::fs::rename(&tmp, &archive).is_err()
+        {
+            let _ = std::fs::remove_file(&tmp);
+        }
+        Ok(doc)
+    }
+
+    /// Where the archive of `src`, read from `path`, is stored.
+    fn archive_path(&self, path: &Path, src: &str) -> PathBuf {
+        let hash = FixedState::default().hash_one(src);
+        let mut name = path.file_name().unwrap_or_default().to_owned();
+        name.push(std::format!(".{hash:016x}.sjar"));
+        match &self.dir {
+            Some(dir) => dir.join(name),
+            None => path.with_file_name(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CachedParser;
+
+    #[test]
+    fn cached() {
+        let dir = std::env::temp_dir().join(std::format!("sonny-jim-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("doc.json");
+        let archives = || {
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .filter(|entry| entry.as_ref().unwrap().path().extension().unwrap() == "sjar")
+                .count()
+        };
+
+        let parser = CachedParser::new();
+        std::fs::write(&path, r#"{"a": [1, "b\nc"]}"#).unwrap();
+        for _ in 0..2 {
+            let doc = parser.parse(&path).unwrap();
+            assert_eq!(doc.arena().to_json(doc.root()), r#"{"a":[1,"b\nc"]}"#);
+            assert_eq!(archives(), 1);
+        }
+
+        // new contents get a new archive
+        std::fs::write(&path, r#"[true]"#).unwrap();
+        let doc = parser.parse(&path).unwrap();
+        assert_eq!(doc.arena().to_json(doc.root()), "[true]");
+        assert_eq!(archives(), 2);
+
+        std::fs::write(&path, r#"[true"#).unwrap();
+        assert!(parser.parse(&path).is_err());
+        assert!(parser.parse(dir.join("missing.json")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
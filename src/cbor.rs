@@ -0,0 +1,190 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::transcode::{JsonWriter, Position};
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: u64) -> Result<&'a [u8], usize> {
+        let end = usize::try_from(n)
+            .ok()
+            .and_then(|n| self.pos.checked_add(n))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(self.pos)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, usize> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint<const N: usize>(&mut self) -> Result<u64, usize> {
+        let mut buf = [0; 8];
+        buf[8 - N..].copy_from_slice(self.take(N as u64)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Read the argument of a header. `None` means indefinite length.
+    fn argument(&mut self, info: u8) -> Result<Option<u64>, usize> {
+        match info {
+            0..24 => Ok(Some(info as u64)),
+            24 => self.uint::<1>().map(Some),
+            25 => self.uint::<2>().map(Some),
+            26 => self.uint::<4>().map(Some),
+            27 => self.uint::<8>().map(Some),
+            31 => Ok(None),
+            _ => Err(self.pos - 1),
+        }
+    }
+
+    fn definite(&mut self, info: u8) -> Result<u64, usize> {
+        let start = self.pos - 1;
+        self.argument(info)?.ok_or(start)
+    }
+}
+
+/// Transcode a single CBOR data item into JSON text.
+///
+/// Byte strings become unpadded base64url strings, as recommended by RFC 8949.
+/// On failure, returns the byte offset of the error.
+pub(crate) fn to_json(bytes: &[u8]) -> Result<String, usize> {
+    let mut r = Reader { bytes, pos: 0 };
+    let mut w = JsonWriter::new();
+
+    while !w.is_done() {
+        let start = r.pos;
+        let mut initial = r.u8()?;
+
+        // the "break" stop code for indefinite length containers
+        if initial == 0xff {
+            if !w.end() {
+                return Err(start);
+            }
+            continue;
+        }
+
+        let position = w.item();
+
+        // tags carry no meaning in JSON
+        while initial >> 5 == 6 {
+            r.definite(initial & 0x1f)?;
+            initial = r.u8()?;
+        }
+
+        let start = r.pos - 1;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        match major {
+            0 => {
+                let n = r.definite(info)?;
+                w.scalar(&position, format_args!("{n}"));
+            }
+            1 => {
+                let n = r.definite(info)?;
+                w.scalar(&position, format_args!("{}", -1 - n as i128));
+            }
+            2 => {
+                let bytes = read_string(&mut r, major, info)?;
+                w.bytes(&bytes);
+            }
+            3 => {
+                let bytes = read_string(&mut r, major, info)?;
+                let s = core::str::from_utf8(&bytes).map_err(|_| start)?;
+                w.string(s);
+            }
+            4 | 5 if position == Position::Value => {
+                let len = r.argument(info)?;
+                w.begin(major == 5, len);
+            }
+            7 => match info {
+                20 => w.scalar(&position, format_args!("false")),
+                21 => w.scalar(&position, format_args!("true")),
+                // undefined has no JSON equivalent
+                22 | 23 => w.scalar(&position, format_args!("null")),
+                25 => w.float(&position, f16_to_f64(r.uint::<2>()? as u16)),
+                26 => w.float(&position, f32::from_bits(r.uint::<4>()? as u32) as f64),
+                27 => w.float(&position, f64::from_bits(r.uint::<8>()?)),
+                _ => return Err(start),
+            },
+            _ => return Err(start),
+        }
+    }
+
+    if r.pos != bytes.len() {
+        return Err(r.pos);
+    }
+    Ok(w.out)
+}
+
+/// Read a definite or indefinite length byte/text string.
+fn read_string(r: &mut Reader<'_>, major: u8, info: u8) -> Result<Vec<u8>, usize> {
+    match r.argument(info)? {
+        Some(len) => Ok(r.take(len)?.to_vec()),
+        None => {
+            let mut buf = Vec::new();
+            loop {
+                let start = r.pos;
+                let initial = r.u8()?;
+                if initial == 0xff {
+                    break Ok(buf);
+                }
+                // chunks must be definite length strings of the same type
+                if initial >> 5 != major || initial & 0x1f == 31 {
+                    break Err(start);
+                }
+                let len = r.definite(initial & 0x1f)?;
+                buf.extend_from_slice(r.take(len)?);
+            }
+        }
+    }
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half >> 15 == 1 { -1.0 } else { 1.0 };
+    let exp = (half >> 10) & 0x1f;
+    let mant = (half & 0x3ff) as f64;
+    let value = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mant / 1024.0) * 2f64.powi(exp as i32 - 15),
+    };
+    sign * value
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn transcode() {
+        // {"a": [1, -2, 1.5], "b": h'0102', 3: [_ "x", true, null], "c": {_ }}
+        let cbor = [
+            0xa4, 0x61, b'a', 0x83, 0x01, 0x21, 0xf9, 0x3e, 0x00, 0x61, b'b', 0x42, 0x01, 0x02,
+            0x03, 0x9f, 0x61, b'x', 0xf5, 0xf6, 0xff, 0x61, b'c', 0xbf, 0xff,
+        ];
+        assert_eq!(
+            super::to_json(&cbor).unwrap(),
+            r#"{"a":[1,-2,1.5],"b":"AQI","3":["x",true,null],"c":{}}"#
+        );
+
+        // self-described, tagged, chunked text
+        let cbor = [0xd9, 0xd9, 0xf7, 0x7f, 0x62, b'a', b'b', 0x61, b'"', 0xff];
+        assert_eq!(super::to_json(&cbor).unwrap(), r#""ab\"""#);
+    }
+
+    #[test]
+    fn invalid() {
+        // truncated array
+        assert_eq!(super::to_json(&[0x82, 0x01]), Err(2));
+        // trailing bytes
+        assert_eq!(super::to_json(&[0x01, 0x01]), Err(1));
+        // stray break
+        assert_eq!(super::to_json(&[0x82, 0x01, 0xff]), Err(2));
+        // array as key
+        assert_eq!(super::to_json(&[0xa1, 0x80, 0x01]), Err(1));
+    }
+}
@@ -0,0 +1,283 @@
+//! Generating Rust code that reads typed structs out of documents shaped like a sample.
+//!
+//! The generated code interns every key of the sample with [`KnownKeys`](crate::KnownKeys),
+//! and matches members by their key id, so reading a struct does no string comparisons.
+//!
+//! ```
+//! let sample = r#"{"id": 1, "user": {"name": "a"}}"#;
+//! let mut arena = sonny_jim::Arena::new(sample);
+//! let value = sonny_jim::parse(&mut arena).unwrap();
+//! let code = sonny_jim::codegen::generate(&arena, &value, "Event").unwrap();
+//! assert!(code.contains("pub struct EventUser {"));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+
+use crate::{Arena, Case, LeafValue, Value, ValueKind};
+
+/// The sample given to [`generate`] is not an object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotAnObject {
+    pub span: Range<u32>,
+}
+
+impl fmt::Display for NotAnObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected an object at {:?}", self.span)
+    }
+}
+
+impl core::error::Error for NotAnObject {}
+
+/// Generate a struct called `name` with a field for each member of `sample`, and the code to read it.
+///
+/// Nested objects become structs of their own. The type of each field is guessed from its value
+/// in the sample: arrays, `null`s and integers too large for an `i64` are kept as JSON text.
+/// Every field is optional, since a member can be missing or of another type.
+///
+/// The generated `from_value` only finds members when the arena was given the generated
+/// `known_keys()` with [`Arena::set_known_keys`] before parsing.
+pub fn generate(arena: &Arena<'_>, sample: &Value, name: &str) -> Result<String, NotAnObject> {
+    if !matches!(sample.kind(), ValueKind::Object(_)) {
+        return Err(NotAnObject {
+            span: arena.span(sample),
+        });
+    }
+
+    let mut codegen = Codegen {
+        arena,
+        keys: BTreeMap::new(),
+        structs: Vec::new(),
+    };
+    codegen.structs(sample, pascal_case(name));
+
+    let mut keys: Vec<_> = codegen.keys.iter().collect();
+    keys.sort_by_key(|(_, id)| **id);
+
+    let mut out = String::new();
+    out.push_str("use std::sync::Arc;\n\n");
+    out.push_str("use sonny_jim::{Arena, Coercion, KnownKeys, Value, ValueKind};\n\n");
+    out.push_str("/// Every key of the structs below, by id.\n");
+    let _ = write!(out, "pub const KEYS: [&str; {}] = [", keys.len());
+    for (i, (key, _)) in keys.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "{key:?}");
+    }
+    out.push_str("];\n\n");
+    out.push_str("/// The keys to give to [`Arena::set_known_keys`] before parsing.\n");
+    out.push_str("pub fn known_keys() -> Arc<KnownKeys> {\n");
+    out.push_str("    Arc::new(KnownKeys::new(KEYS).expect(\"the keys are distinct\"))\n");
+    out.push_str("}\n");
+    for s in &codegen.structs {
+        s.write(&mut out);
+    }
+    Ok(out)
+}
+
+struct Codegen<'a, 's> {
+    arena: &'a Arena<'s>,
+    /// The id of every key, numbered in the order they were found.
+    keys: BTreeMap<&'a str, usize>,
+    structs: Vec<Struct>,
+}
+
+struct Struct {
+    name: String,
+    fields: Vec<Field>,
+}
+
+struct Field {
+    name: String,
+    id: usize,
+    ty: String,
+    read: Read,
+}
+
+enum Read {
+    Coerce,
+    Struct,
+    Json,
+}
+
+impl<'a> Codegen<'a, '_> {
+    /// Generate the structs of `value` and the objects inside it.
+    fn structs(&mut self, value: &'a Value, name: String) {
+        let ValueKind::Object(object) = value.kind() else {
+            return;
+        };
+        let index = self.structs.len();
+        self.structs.push(Struct {
+            name: name.clone(),
+            fields: Vec::new(),
+        });
+
+        let mut fields = Vec::new();
+        let mut seen = Vec::new();
+        for (key, value) in self.arena.entries(&object) {
+            // with duplicate keys, the first value decides the type.
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+
+            let next = self.keys.len();
+            let id = *self.keys.entry(key).or_insert(next);
+            let mut field = identifier(key);
+            while fields.iter().any(|f: &Field| f.name == field) {
+                field.push('_');
+            }
+
+            let (ty, read) = match value.kind() {
+                ValueKind::Leaf(LeafValue::Bool(_)) => ("bool".into(), Read::Coerce),
+                ValueKind::Leaf(LeafValue::String) => ("String".into(), Read::Coerce),
+                ValueKind::Leaf(LeafValue::Number) => match self.arena.raw(value).parse::<i64>() {
+                    Ok(_) => ("i64".into(), Read::Coerce),
+                    Err(_) => ("f64".into(), Read::Coerce),
+                },
+                ValueKind::Leaf(LeafValue::RadixInt(_)) => ("i64".into(), Read::Coerce),
+                ValueKind::Object(_) => {
+                    let ty = name.clone() + &pascal_case(&field);
+                    self.structs(value, ty.clone());
+                    (ty, Read::Struct)
+                }
+                ValueKind::Leaf(LeafValue::Null | LeafValue::BigInt) | ValueKind::Array(_) => {
+                    ("String".into(), Read::Json)
+                }
+            };
+            fields.push(Field {
+                name: field,
+                id,
+                ty,
+                read,
+            });
+        }
+        self.structs[index].fields = fields;
+    }
+}
+
+impl Struct {
+    fn write(&self, out: &mut String) {
+        let name = &self.name;
+        out.push_str("\n#[derive(Debug, Clone, Default, PartialEq)]\n");
+        if self.fields.is_empty() {
+            let _ = writeln!(out, "pub struct {name} {{}}\n");
+        } else {
+            let _ = writeln!(out, "pub struct {name} {{");
+        }
+        for field in &self.fields {
+            if let Read::Json = field.read {
+                out.push_str("    /// As JSON text.\n");
+            }
+            let _ = writeln!(out, "    pub {}: Option<{}>,", field.name, field.ty);
+        }
+        if !self.fields.is_empty() {
+            out.push_str("}\n\n");
+        }
+
+        let _ = writeln!(out, "impl {name} {{");
+        out.push_str("    /// Read the members of `value`, if it is an object.\n");
+        if self.fields.is_empty() {
+            out.push_str("    pub fn from_value(_: &Arena<'_>, value: &Value) -> Option<Self> {\n");
+            out.push_str("        let ValueKind::Object(_) = &value.kind else {\n");
+            out.push_str("            return None;\n");
+            out.push_str("        };\n");
+            out.push_str("        Some(Self::default())\n");
+            out.push_str("    }\n}\n");
+            return;
+        }
+        out.push_str("    pub fn from_value(arena: &Arena<'_>, value: &Value) -> Option<Self> {\n");
+        out.push_str("        let ValueKind::Object(object) = &value.kind else {\n");
+        out.push_str("            return None;\n");
+        out.push_str("        };\n");
+        out.push_str("        let mut out = Self::default();\n");
+        out.push_str("        for (id, value) in arena.known_entries(object) {\n");
+        out.push_str("            match id {\n");
+        for field in &self.fields {
+            let read = match field.read {
+                Read::Coerce => "arena.coerce(value, Coercion::STRICT)".into(),
+                Read::Struct => alloc::format!("{}::from_value(arena, value)", field.ty),
+                Read::Json => "Some(arena.to_json(value))".into(),
+            };
+            let _ = writeln!(
+                out,
+                "                Some({}) => out.{} = {read},",
+                field.id, field.name
+            );
+        }
+        out.push_str("                _ => {}\n");
+        out.push_str("            }\n");
+        out.push_str("        }\n");
+        out.push_str("        Some(out)\n");
+        out.push_str("    }\n}\n");
+    }
+}
+
+/// A snake case field name for `key`.
+fn identifier(key: &str) -> String {
+    let mut name: String = Case::Snake
+        .convert(key)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) || name == "_" {
+        name.push('_');
+    }
+    name
+}
+
+/// `user_id` as `UserId`.
+fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.push(first.to_ascii_uppercase());
+            out.extend(chars);
+        }
+    }
+    out
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::Arena;
+
+    #[test]
+    fn generate() {
+        let src = r#"{
+            "id": 1,
+            "userName": "a",
+            "score": 1.5,
+            "ok": true,
+            "tags": ["x"],
+            "type": null,
+            "meta": {"id": "m", "2fa": false, "empty": {}}
+        }"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        insta::assert_snapshot!(super::generate(&arena, &value, "event").unwrap());
+
+        let mut arena = Arena::new("[1]");
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(
+            super::generate(&arena, &value, "Event").unwrap_err().span,
+            0..3
+        );
+    }
+}
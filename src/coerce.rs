@@ -0,0 +1,275 @@
+use alloc::string::{String, ToString};
+
+use crate::ser::write_decimal;
+use crate::{Arena, LeafValue, Object, Value, ValueKind};
+
+/// Which values are read as another type, by [`Object::get_as`] and the lenient accessors.
+///
+/// The default is strict, and only reads values of the requested type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coercion {
+    /// Which strings are read as numbers.
+    pub number_strings: NumberStrings,
+    /// Read `"true"` and `"false"` as booleans.
+    pub bool_strings: bool,
+    /// Read `0` and `1` as booleans.
+    pub int_bools: bool,
+    /// Read numbers and booleans as strings, like `42` as `"42"`.
+    pub scalar_strings: bool,
+}
+
+impl Coercion {
+    /// Only read values of the requested type.
+    pub const STRICT: Self = Self {
+        number_strings: NumberStrings::Never,
+        bool_strings: false,
+        int_bools: false,
+        scalar_strings: false,
+    };
+
+    /// Read every value that is unambiguously of the requested type.
+    pub const LENIENT: Self = Self {
+        number_strings: NumberStrings::Json,
+        bool_strings: true,
+        int_bools: true,
+        scalar_strings: true,
+    };
+}
+
+impl Default for Coercion {
+    fn default() -> Self {
+        Self::STRICT
+    }
+}
+
+/// Which strings [`Coercion`] reads as numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberStrings {
+    /// None, only numbers.
+    Never,
+    /// Strings that hold exactly a JSON number, like `"42"` or `"-1.5e3"`.
+    #[default]
+    Json,
+    /// Also with surrounding whitespace or a leading `+`, like `" +42 "`.
+    Loose,
+}
+
+impl NumberStrings {
+    /// The number in `s`, if this policy accepts it.
+    fn number(self, s: &str) -> Option<&str> {
+        match self {
+            NumberStrings::Never => None,
+            NumberStrings::Json => is_json_number(s).then_some(s),
+            NumberStrings::Loose => {
+                let s = s.trim();
+                let s = s.strip_prefix('+').unwrap_or(s);
+                is_json_number(s).then_some(s)
+            }
+        }
+    }
+}
+
+/// Whether `s` is a number in the JSON grammar.
+fn is_json_number(s: &str) -> bool {
+    matches!(crate::scan_number(s.as_bytes()), Ok((len, _)) if len == s.len())
+}
+
+impl Arena<'_> {
+    /// The value of a number, or of a string holding a number that `coercion` accepts, as an `f64`.
+    pub fn as_f64_lenient(&self, value: &Value, coercion: Coercion) -> Option<f64> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::String) => coercion
+                .number_strings
+                .number(&self.as_str(value)?)?
+                .parse()
+                .ok(),
+            _ => self.as_f64(value),
+        }
+    }
+
+    /// The value of an integer, or of a string holding an integer that `coercion` accepts,
+    /// if it fits in an `i64`.
+    pub fn as_i64_lenient(&self, value: &Value, coercion: Coercion) -> Option<i64> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::String) => coercion
+                .number_strings
+                .number(&self.as_str(value)?)?
+                .parse()
+                .ok(),
+            _ => self.as_i128(value)?.try_into().ok(),
+        }
+    }
+
+    /// A boolean, or a string or integer that `coercion` accepts as one.
+    pub fn as_bool_lenient(&self, value: &Value, coercion: Coercion) -> Option<bool> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Bool(b)) => Some(b),
+            ValueKind::Leaf(LeafValue::String) if coercion.bool_strings => {
+                match &*self.as_str(value)? {
+                    "true" => Some(true),
+                    "false" => Some(false),
+                    _ => None,
+                }
+            }
+            _ if coercion.int_bools => match self.as_i128(value)? {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The contents of a string, or a number or boolean that `coercion` accepts as one.
+    pub fn as_string_lenient(&self, value: &Value, coercion: Coercion) -> Option<String> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::String) => Some(self.as_str(value)?.into_owned()),
+            _ if !coercion.scalar_strings => None,
+            ValueKind::Leaf(LeafValue::Bool(b)) => Some(b.to_string()),
+            ValueKind::Leaf(LeafValue::Number | LeafValue::BigInt) => {
+                Some(self.raw(value).to_string())
+            }
+            ValueKind::Leaf(LeafValue::RadixInt(radix)) => {
+                let mut out = String::new();
+                write_decimal(self.raw(value), radix, &mut out);
+                Some(out)
+            }
+            _ => None,
+        }
+    }
+
+    /// Read `value` as a `T`, coercing it as `coercion` allows.
+    pub fn coerce<T: Coerce>(&self, value: &Value, coercion: Coercion) -> Option<T> {
+        T::coerce(self, value, coercion)
+    }
+}
+
+/// Types that a leaf value can be read as, by [`Arena::coerce`] and [`Object::get_as`].
+pub trait Coerce: Sized {
+    fn coerce(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Option<Self>;
+}
+
+impl Coerce for f64 {
+    fn coerce(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Option<Self> {
+        arena.as_f64_lenient(value, coercion)
+    }
+}
+
+impl Coerce for i64 {
+    fn coerce(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Option<Self> {
+        arena.as_i64_lenient(value, coercion)
+    }
+}
+
+impl Coerce for bool {
+    fn coerce(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Option<Self> {
+        arena.as_bool_lenient(value, coercion)
+    }
+}
+
+impl Coerce for String {
+    fn coerce(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Option<Self> {
+        arena.as_string_lenient(value, coercion)
+    }
+}
+
+impl Object {
+    /// Look up `key`, and read its value as a `T`, coercing it as `coercion` allows.
+    ///
+    /// If the key is duplicated, the last value wins.
+    pub fn get_as<T: Coerce>(&self, arena: &Arena<'_>, key: &str, coercion: Coercion) -> Option<T> {
+        arena.coerce(arena.get(self, key)?, coercion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use alloc::string::String;
+
+    use crate::{Arena, Coercion, NumberStrings, ValueKind};
+
+    #[test]
+    fn lenient_numbers() {
+        let src =
+            r#"[42, "42", "-1.5e3", " +7 ", "007", "1.", "0x10", "", 1.5, "9223372036854775808"]"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+        let values = arena.array_values(&array);
+
+        let f64s = |number_strings| {
+            let coercion = Coercion {
+                number_strings,
+                ..Coercion::STRICT
+            };
+            values
+                .iter()
+                .map(|v| arena.as_f64_lenient(v, coercion))
+                .collect::<Vec<_>>()
+        };
+        let n = None;
+        assert_eq!(
+            f64s(NumberStrings::Never),
+            [Some(42.0), n, n, n, n, n, n, n, Some(1.5), n]
+        );
+        assert_eq!(
+            f64s(NumberStrings::Json),
+            [
+                Some(42.0),
+                Some(42.0),
+                Some(-1500.0),
+                n,
+                n,
+                n,
+                n,
+                n,
+                Some(1.5),
+                Some(9223372036854775808.0)
+            ]
+        );
+        assert_eq!(f64s(NumberStrings::Loose)[3], Some(7.0));
+
+        let i64s = values
+            .iter()
+            .map(|v| arena.as_i64_lenient(v, Coercion::LENIENT))
+            .collect::<Vec<_>>();
+        assert_eq!(i64s[..2], [Some(42), Some(42)]);
+        assert!(i64s[2..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn get_as() {
+        let src = r#"{"a": "true", "b": 1, "c": 2, "d": 42, "e": "x", "f": false, "g": "1.5"}"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+
+        let strict = Coercion::STRICT;
+        assert_eq!(object.get_as::<bool>(&arena, "a", strict), None);
+        assert_eq!(object.get_as::<bool>(&arena, "f", strict), Some(false));
+        assert_eq!(object.get_as::<String>(&arena, "d", strict), None);
+        assert_eq!(object.get_as::<f64>(&arena, "g", strict), None);
+
+        let lenient = Coercion::LENIENT;
+        assert_eq!(object.get_as::<bool>(&arena, "a", lenient), Some(true));
+        assert_eq!(object.get_as::<bool>(&arena, "b", lenient), Some(true));
+        assert_eq!(object.get_as::<bool>(&arena, "c", lenient), None);
+        assert_eq!(object.get_as::<bool>(&arena, "e", lenient), None);
+        assert_eq!(
+            object.get_as::<String>(&arena, "d", lenient).as_deref(),
+            Some("42")
+        );
+        assert_eq!(
+            object.get_as::<String>(&arena, "f", lenient).as_deref(),
+            Some("false")
+        );
+        assert_eq!(object.get_as::<f64>(&arena, "g", lenient), Some(1.5));
+        assert_eq!(object.get_as::<i64>(&arena, "missing", lenient), None);
+    }
+}
@@ -0,0 +1,261 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::rows::Elements;
+use crate::{Arena, Error, LeafValue, Stacks, Value, ValueKind};
+
+/// The type to extract a column as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    F64,
+    I64,
+    U64,
+    Bool,
+    String,
+}
+
+/// The values of one key across every row. Missing keys and `null` are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    F64(Vec<Option<f64>>),
+    I64(Vec<Option<i64>>),
+    U64(Vec<Option<u64>>),
+    Bool(Vec<Option<bool>>),
+    String(Vec<Option<String>>),
+}
+
+#[derive(Debug)]
+pub enum ColumnError {
+    /// The input is not a single top-level array.
+    NotAnArray {
+        span: Range<u32>,
+    },
+    /// An element of the array is not an object.
+    NotAnObject {
+        span: Range<u32>,
+    },
+    /// A value does not have the type of its column.
+    Type {
+        column: usize,
+        span: Range<u32>,
+    },
+    Json(Error),
+}
+
+impl fmt::Display for ColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnError::NotAnArray { span } => write!(f, "expected an array at {span:?}"),
+            ColumnError::NotAnObject { span } => write!(f, "expected an object at {span:?}"),
+            ColumnError::Type { column, span } => {
+                write!(f, "value at {span:?} does not fit column {column}")
+            }
+            ColumnError::Json(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for ColumnError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ColumnError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Column {
+    fn new(ty: ColumnType) -> Self {
+        match ty {
+            ColumnType::F64 => Column::F64(Vec::new()),
+            ColumnType::I64 => Column::I64(Vec::new()),
+            ColumnType::U64 => Column::U64(Vec::new()),
+            ColumnType::Bool => Column::Bool(Vec::new()),
+            ColumnType::String => Column::String(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Column::F64(v) => v.len(),
+            Column::I64(v) => v.len(),
+            Column::U64(v) => v.len(),
+            Column::Bool(v) => v.len(),
+            Column::String(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Somewhere to put the values of a column.
+pub(crate) trait ColumnSink {
+    /// Append `value`, or `None` for a missing key or `null`.
+    /// Returns `false` if it has the wrong type.
+    fn push(&mut self, arena: &Arena<'_>, value: Option<&Value>) -> bool;
+}
+
+impl ColumnSink for Column {
+    fn push(&mut self, arena: &Arena<'_>, value: Option<&Value>) -> bool {
+        macro_rules! push {
+            ($column:expr, $convert:expr) => {
+                match value {
+                    None => $column.push(None),
+                    Some(value) => match $convert(value) {
+                        Some(v) => $column.push(Some(v)),
+                        None => return false,
+                    },
+                }
+            };
+        }
+
+        match self {
+            Column::F64(column) => push!(column, |v| arena.as_f64(v)),
+            Column::I64(column) => push!(column, |v| arena
+                .as_i128(v)
+                .and_then(|n| i64::try_from(n).ok())),
+            Column::U64(column) => push!(column, |v| arena
+                .as_i128(v)
+                .and_then(|n| u64::try_from(n).ok())),
+            Column::Bool(column) => push!(column, |v: &Value| match v.kind() {
+                ValueKind::Leaf(LeafValue::Bool(b)) => Some(b),
+                _ => None,
+            }),
+            Column::String(column) => {
+                push!(column, |v| arena.as_str(v).map(|s| s.into_owned()))
+            }
+        }
+        true
+    }
+}
+
+/// Extracts typed columns from a top-level array of objects.
+///
+/// Only one element is parsed at a time, so no document is built for the whole array.
+#[derive(Default)]
+pub struct ExtractColumns<'k> {
+    columns: Vec<(&'k str, ColumnType)>,
+}
+
+impl<'k> ExtractColumns<'k> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a column for `key`.
+    pub fn column(mut self, key: &'k str, ty: ColumnType) -> Self {
+        self.columns.push((key, ty));
+        self
+    }
+
+    /// Extract the columns from `src`, in the order they were added.
+    pub fn extract(&self, src: &str) -> Result<Vec<Column>, ColumnError> {
+        let mut columns: Vec<Column> = self
+            .columns
+            .iter()
+            .map(|(_, ty)| Column::new(*ty))
+            .collect();
+        let keys = self.columns.iter().map(|(key, _)| *key);
+        extract_into(src, keys, &mut columns)?;
+        Ok(columns)
+    }
+}
+
+/// Push the value of each key in each row of `src` into the matching sink.
+pub(crate) fn extract_into<'k>(
+    src: &str,
+    keys: impl Iterator<Item = &'k str> + Clone,
+    sinks: &mut [impl ColumnSink],
+) -> Result<(), ColumnError> {
+    let mut arena = Arena::new(src);
+    let mut stacks = Stacks::default();
+
+    for range in Elements::new(src) {
+        let range = range.map_err(|span| ColumnError::NotAnArray { span })?;
+        let row = arena
+            .parse_element(&mut stacks, range)
+            .map_err(ColumnError::Json)?;
+        let ValueKind::Object(object) = row.kind() else {
+            return Err(ColumnError::NotAnObject {
+                span: arena.span(&row),
+            });
+        };
+
+        for (i, (key, sink)) in core::iter::zip(keys.clone(), &mut *sinks).enumerate() {
+            let value = arena
+                .get(&object, key)
+                .filter(|v| !matches!(v.kind(), ValueKind::Leaf(LeafValue::Null)));
+            if !sink.push(&arena, value) {
+                let span = value.map_or(arena.span(&row), |v| arena.span(v));
+                return Err(ColumnError::Type { column: i, span });
+            }
+        }
+        arena.clear();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+
+    use crate::{Column, ColumnError, ColumnType, ExtractColumns};
+
+    #[test]
+    fn extract() {
+        let src = r#"[
+            {"price": 1.5, "qty": 2, "name": "a", "ok": true},
+            {"qty": 0x10, "price": 3, "name": null},
+            {"price": -2e1, "qty": 7, "name": "c\n", "ok": false}
+        ]"#;
+        let columns = ExtractColumns::new()
+            .column("price", ColumnType::F64)
+            .column("qty", ColumnType::U64)
+            .column("name", ColumnType::String)
+            .column("ok", ColumnType::Bool)
+            .extract(src);
+
+        // radix integers are not standard JSON
+        assert!(matches!(columns, Err(ColumnError::Json(_))));
+
+        let src = src.replace("0x10", "16");
+        let columns = ExtractColumns::new()
+            .column("price", ColumnType::F64)
+            .column("qty", ColumnType::U64)
+            .column("name", ColumnType::String)
+            .column("ok", ColumnType::Bool)
+            .extract(&src)
+            .unwrap();
+        assert_eq!(
+            columns,
+            [
+                Column::F64(vec![Some(1.5), Some(3.0), Some(-20.0)]),
+                Column::U64(vec![Some(2), Some(16), Some(7)]),
+                Column::String(vec![
+                    Some(String::from("a")),
+                    None,
+                    Some(String::from("c\n"))
+                ]),
+                Column::Bool(vec![Some(true), None, Some(false)]),
+            ]
+        );
+
+        let err = ExtractColumns::new()
+            .column("price", ColumnType::I64)
+            .extract(&src)
+            .unwrap_err();
+        assert!(matches!(err, ColumnError::Type { column: 0, .. }));
+
+        let columns = ExtractColumns::new()
+            .column("qty", ColumnType::I64)
+            .extract("[]")
+            .unwrap();
+        assert!(columns[0].is_empty());
+    }
+}
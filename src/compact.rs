@@ -0,0 +1,169 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::hash::BuildHasher;
+use core::ops::Range;
+use hashbrown::hash_table::Entry;
+use hashbrown::HashTable;
+
+use crate::{Arena, Array, Object, Scratch, StringKey, Value, ValueKind};
+
+impl Arena<'_> {
+    /// Drop every value, key and scratch byte that is not reachable from `roots`.
+    ///
+    /// The roots are rewritten in place to point into the compacted arena.
+    /// Values that are not in `roots` must not be used with this arena afterwards.
+    ///
+    /// The text of values copied in from other arenas is not compacted.
+    pub fn compact(&mut self, roots: &mut [Value]) {
+        let mut values = Vec::new();
+        let mut keys = Vec::new();
+        let mut scratch = String::new();
+        let mut table = HashTable::new();
+
+        let mut intern = |old: &Scratch<'_>, key: &StringKey| -> StringKey {
+            let str = &old[key];
+            let hash = self.hasher.hash_one(str);
+            let lookup = |key: &StringKey| {
+                let Range { start, end } = key.0;
+                if end < start {
+                    &scratch[end as usize..start as usize]
+                } else {
+                    &old.src[start as usize..end as usize]
+                }
+            };
+            match table.entry(
+                hash,
+                |key| lookup(key) == str,
+                |key| self.hasher.hash_one(lookup(key)),
+            ) {
+                Entry::Occupied(occupied_entry) => occupied_entry.get().clone(),
+                Entry::Vacant(vacant_entry) => {
+                    let Range { start, end } = key.0;
+                    let key = if end < start {
+                        let scratch_start = scratch.len();
+                        scratch.push_str(str);
+                        StringKey(scratch.len() as u32..scratch_start as u32)
+                    } else {
+                        key.clone()
+                    };
+                    vacant_entry.insert(key).get().clone()
+                }
+            }
+        };
+
+        // A copying collector: roots copy their children into the new buffers,
+        // and then the new values buffer doubles as the work queue for the
+        // children's children. No recursion needed.
+        let mut copy = |kind: ValueKind,
+                        span: Range<u32>,
+                        values: &mut Vec<Value>,
+                        spans: &mut Vec<Range<u32>>| {
+            let children = match kind {
+                ValueKind::Leaf(_) => return kind,
+                ValueKind::Object(object) => object.values(),
+                ValueKind::Array(array) => array.values(),
+            };
+            values.push(Value::header(keys.len() as u32));
+            spans.push(span);
+            let vi = values.len();
+            for i in children {
+                values.push(Value::new(
+                    self.values[i as usize].kind(),
+                    values.len() as u32,
+                ));
+                spans.push(self.spans[i as usize].clone());
+            }
+            let vj = values.len();
+
+            match kind {
+                ValueKind::Object(object) => {
+                    for key in self.keys_of(&object) {
+                        keys.push(intern(&self.scratch, key));
+                    }
+                    ValueKind::Object(Object::new(vi as u32..vj as u32))
+                }
+                _ => ValueKind::Array(Array::new(vi as u32..vj as u32)),
+            }
+        };
+
+        let mut spans = Vec::new();
+        for root in roots {
+            let span = self.span(root);
+            let kind = copy(root.kind(), span.clone(), &mut values, &mut spans);
+            *root = match kind {
+                ValueKind::Leaf(_) => {
+                    let value = Value::new(kind, values.len() as u32);
+                    values.push(value);
+                    spans.push(span);
+                    value
+                }
+                _ => Value::new(kind, 0),
+            };
+        }
+
+        let mut i = 0;
+        while i < values.len() {
+            if !values[i].is_header() {
+                let kind = copy(values[i].kind(), spans[i].clone(), &mut values, &mut spans);
+                values[i] = Value::new(kind, i as u32);
+            }
+            i += 1;
+        }
+
+        self.values = values;
+        self.spans = spans;
+        self.keys = keys;
+        self.scratch.scratch = scratch;
+        self.table = table;
+        self.small.clear();
+        crate::known::Known::clear(&mut self.known);
+        #[cfg(debug_assertions)]
+        {
+            self.brand = crate::id::next_brand();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, ValueKind};
+
+    #[test]
+    fn compact_subtree() {
+        let data = r#"{
+            "drop": {"big": [1, 2, 3, 4, 5], "esc\naped": true},
+            "keep": {"esc\naped": [1, {"a": null}], "b": "c"}
+        }"#;
+
+        let mut arena = Arena::new(data);
+        let root = crate::parse(&mut arena).unwrap();
+
+        let ValueKind::Object(object) = root.kind() else {
+            panic!("expected object")
+        };
+        let keep = arena.values[object.values().end as usize - 1];
+        let before = std::format!(
+            "{:?}",
+            crate::fmt::FmtValue {
+                arena: &arena,
+                value: &keep
+            }
+        );
+
+        let mut roots = [keep];
+        arena.compact(&mut roots);
+
+        let after = std::format!(
+            "{:?}",
+            crate::fmt::FmtValue {
+                arena: &arena,
+                value: &roots[0]
+            }
+        );
+        assert_eq!(before, after);
+        // five values, and a header for each of the three containers
+        assert_eq!(arena.values.len(), 8);
+        assert_eq!(arena.keys.len(), 3);
+        assert_eq!(arena.scratch.scratch, "esc\naped");
+    }
+}
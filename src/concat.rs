@@ -0,0 +1,95 @@
+//! Parsing a source of several top-level values, one after another.
+
+use alloc::vec::Vec;
+use logos::Logos;
+
+use crate::{Arena, ContextItem, Error, PollParse, RawParser, Token, Value};
+
+/// Parse every top-level value in the source, like `{"a": 1} {"a": 2}` or a
+/// concatenated export with one document per line, sharing the arena and its interned keys.
+///
+/// The values only need whitespace between them. An empty source has no values.
+/// If parsing fails, the arena is left as it was before, as with [`parse`](crate::parse).
+pub fn parse_all(arena: &mut Arena<'_>) -> Result<Vec<Value>, Error> {
+    let src = arena.scratch.src.clone();
+    arena.reserve_for(src.len());
+    let mark = arena.mark();
+
+    let mut parser = RawParser {
+        arena,
+        lexer: Token::lexer(&src),
+        stack: Vec::new(),
+        value_stack: Vec::new(),
+        key_stack: Vec::new(),
+        context: ContextItem::WaitingValue,
+    };
+
+    let mut roots = Vec::new();
+    let res = loop {
+        let rest = parser.lexer.remainder().as_bytes();
+        parser.lexer.bump(crate::skip_whitespace(rest));
+        if parser.lexer.remainder().is_empty()
+            && parser.stack.is_empty()
+            && matches!(parser.context, ContextItem::WaitingValue)
+        {
+            break Ok(roots);
+        }
+
+        match parser.step() {
+            Ok(PollParse::Ready(value)) => {
+                roots.push(value);
+                break Ok(roots);
+            }
+            Ok(PollParse::Pending) => {
+                // a value at the top level is complete as soon as it is read,
+                // since the lexer reads all of a number at once.
+                if parser.stack.is_empty() {
+                    if let ContextItem::Value { span, value } =
+                        core::mem::replace(&mut parser.context, ContextItem::WaitingValue)
+                    {
+                        roots.push(parser.arena.root(value, span));
+                    }
+                }
+            }
+            Err(e) => break Err(e),
+        }
+    };
+
+    if res.is_err() {
+        parser.arena.rollback(mark);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::parse_all;
+    use crate::Arena;
+
+    #[test]
+    fn parse_all_values() {
+        let src = "{\"a\": 1}\n{\"a\": [2, 3]} 4 \"five\"[]\n";
+        let mut arena = Arena::new(src);
+        let roots = parse_all(&mut arena).unwrap();
+        let json: Vec<_> = roots.iter().map(|root| arena.to_json(root)).collect();
+        assert_eq!(
+            json,
+            [r#"{"a":1}"#, r#"{"a":[2,3]}"#, "4", r#""five""#, "[]"]
+        );
+        assert_eq!(arena.span(&roots[1]), 9..22);
+        // the key is interned once for both documents.
+        assert_eq!(arena.keys[0], arena.keys[1]);
+
+        for src in ["", "  \n "] {
+            let mut arena = Arena::new(src);
+            assert!(parse_all(&mut arena).unwrap().is_empty());
+        }
+        for src in ["1, 2", "{} {", "[1] ]", "{} x"] {
+            let mut arena = Arena::new(src);
+            assert!(parse_all(&mut arena).is_err(), "{src}");
+            assert!(arena.values.is_empty(), "{src}");
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use alloc::vec::Vec;
+use core::hash::BuildHasher;
+use core::ops::Range;
+use foldhash::quality::RandomState;
+use hashbrown::hash_table::Entry;
+use hashbrown::HashMap;
+
+use crate::refs::{RefError, RefErrorKind, Refs};
+use crate::{Arena, Array, Object, PathSegment, Repr, StringKey, Value, ValueKind};
+
+impl Arena<'_> {
+    /// Copy `value`, and everything below it, into `dest`.
+    ///
+    /// The source text of `value` is copied along with it,
+    /// so the copy does not borrow from this arena.
+    pub fn deep_copy_into(&self, value: &Value, dest: &mut Arena<'_>) -> Value {
+        if let Some((values, keys)) = self.subtree(value) {
+            return self.bulk_copy_into(value, values, keys, dest);
+        }
+        match Copier::new(self, dest, None).copy(value) {
+            Ok(value) => value,
+            Err(_) => unreachable!("copies without refs cannot fail"),
+        }
+    }
+
+    /// The value and key ranges holding the descendants of `value` and their headers,
+    /// if they are contiguous, as they are straight out of the parser.
+    fn subtree(&self, value: &Value) -> Option<(Range<u32>, Range<u32>)> {
+        let mut values = (u32::MAX, 0, 0);
+        let mut keys = (u32::MAX, 0, 0);
+        let extend = |(lo, hi, count): &mut (u32, u32, u32), range: &Range<u32>| {
+            if !range.is_empty() {
+                *lo = (*lo).min(range.start);
+                *hi = (*hi).max(range.end);
+                *count += range.len() as u32;
+            }
+        };
+
+        let mut stack = Vec::from([value]);
+        while let Some(value) = stack.pop() {
+            let children = match value.kind() {
+                ValueKind::Leaf(_) => continue,
+                ValueKind::Object(object) => {
+                    extend(&mut keys, &object.keys(&self.values));
+                    object.values()
+                }
+                ValueKind::Array(array) => array.values(),
+            };
+            // along with the header, just before the children.
+            extend(&mut values, &(children.start - 1..children.end));
+            stack.extend(&self.values[children.start as usize..children.end as usize]);
+        }
+
+        let range = |(lo, hi, count): (u32, u32, u32)| match count {
+            0 => Some(0..0),
+            _ if hi - lo == count => Some(lo..hi),
+            _ => None,
+        };
+        Some((range(values)?, range(keys)?))
+    }
+
+    /// Copy a subtree whose descendants are in `values` and `keys`,
+    /// by copying the ranges and offsetting them.
+    fn bulk_copy_into(
+        &self,
+        value: &Value,
+        values: Range<u32>,
+        keys: Range<u32>,
+        dest: &mut Arena<'_>,
+    ) -> Value {
+        let (lo, _) = bounds(&self.span(value));
+        let new = dest.scratch.text.len() as u32;
+        dest.scratch.text.push_str(self.raw(value));
+
+        // offsets can be negative, so they wrap.
+        let voffset = (dest.values.len() as u32).wrapping_sub(values.start);
+        let koffset = (dest.keys.len() as u32).wrapping_sub(keys.start);
+        let shift = |range: &Range<u32>, offset: u32| {
+            range.start.wrapping_add(offset)..range.end.wrapping_add(offset)
+        };
+        let copy_span = |span: &Range<u32>| {
+            let (start, end) = bounds(span);
+            end - lo + new..start - lo + new
+        };
+        let copy = |value: &Value, slot: u32| {
+            let kind = match value.repr() {
+                // array headers have no keys to read, so shifting theirs too is harmless.
+                Repr::Header { keys } => return Value::header(keys.wrapping_add(koffset)),
+                Repr::Leaf { leaf, .. } => ValueKind::Leaf(leaf),
+                Repr::Object(object) => {
+                    ValueKind::Object(Object::new(shift(&object.values(), voffset)))
+                }
+                Repr::Array(array) => ValueKind::Array(Array::new(shift(&array.values(), voffset))),
+            };
+            Value::new(kind, slot)
+        };
+
+        let range = values.start as usize..values.end as usize;
+        dest.values.extend(
+            core::iter::zip(values.clone(), &self.values[range.clone()])
+                .map(|(slot, value)| copy(value, slot.wrapping_add(voffset))),
+        );
+        dest.spans.extend(self.spans[range].iter().map(copy_span));
+        for key in &self.keys[keys.start as usize..keys.end as usize] {
+            let key = dest.intern_copy(&self[key]);
+            dest.keys.push(key);
+        }
+        let span = copy_span(&self.span(value));
+        dest.root(copy(value, 0).kind(), span)
+    }
+
+    /// Intern a key copied from another arena.
+    pub(crate) fn intern_copy(&mut self, key: &str) -> StringKey {
+        let Self {
+            scratch,
+            hasher,
+            table,
+            small,
+            ..
+        } = self;
+
+        match scratch.find_small(small, hasher, table, key) {
+            Ok(Some(key)) => return key,
+            Ok(None) => {
+                let start = scratch.scratch.len();
+                scratch.scratch.push_str(key);
+                let key = StringKey(scratch.scratch.len() as u32..start as u32);
+                small.push(key.clone());
+                return key;
+            }
+            Err(()) => {}
+        }
+
+        let hash = hasher.hash_one(key);
+        match table.entry(
+            hash,
+            |k| &scratch[k] == key,
+            |k| hasher.hash_one(&scratch[k]),
+        ) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let start = scratch.scratch.len();
+                scratch.scratch.push_str(key);
+                let key = StringKey(scratch.scratch.len() as u32..start as u32);
+                entry.insert(key).get().clone()
+            }
+        }
+    }
+}
+
+/// Maps positions in the source arena's text to positions in the destination's copied text.
+#[derive(Clone, Copy)]
+struct Shift {
+    old: u32,
+    new: u32,
+}
+
+/// Children that have slots reserved in the destination, but are not copied yet.
+struct Work<'a> {
+    values: Range<u32>,
+    /// The keys of the children, if they are object members.
+    keys: Option<Range<u32>>,
+    dest: u32,
+    shift: Shift,
+    expansion: Option<usize>,
+    /// The path to the container, only kept when filtering.
+    path: Vec<PathSegment<'a>>,
+    /// The children that the filter dropped, in order.
+    dropped: Vec<u32>,
+}
+
+/// Decides whether a value, by its path, is copied.
+type Keep<'a> = &'a mut dyn FnMut(&[PathSegment<'_>], &Value) -> bool;
+
+/// Copies values between arenas, optionally inlining `$ref`s.
+pub(crate) struct Copier<'a, 's, 'd, 'ds> {
+    src: &'a Arena<'s>,
+    dest: &'d mut Arena<'ds>,
+    refs: Option<&'a Refs<'a, 's>>,
+    /// ref targets that have been inlined, and the expansion they were inlined within.
+    /// Following the parents gives the chain of refs that lead to a value.
+    expansions: Vec<(&'a Value, Option<usize>)>,
+    /// where the text of each copied value starts in the destination, by source span,
+    /// so that refs to the same target share their text.
+    text: HashMap<(u32, u32), u32, RandomState>,
+    work: Vec<Work<'a>>,
+    keep: Option<Keep<'a>>,
+}
+
+/// The span of `value`, ignoring whether it is reversed.
+fn bounds(span: &Range<u32>) -> (u32, u32) {
+    (span.start.min(span.end), span.start.max(span.end))
+}
+
+impl<'a, 's, 'd, 'ds> Copier<'a, 's, 'd, 'ds> {
+    pub(crate) fn new(
+        src: &'a Arena<'s>,
+        dest: &'d mut Arena<'ds>,
+        refs: Option<&'a Refs<'a, 's>>,
+    ) -> Self {
+        Self {
+            src,
+            dest,
+            refs,
+            expansions: Vec::new(),
+            text: HashMap::default(),
+            work: Vec::new(),
+            keep: None,
+        }
+    }
+
+    /// Only copy the values below the root for which `keep` returns `true`,
+    /// leaving out their keys too.
+    pub(crate) fn with_filter(mut self, keep: Keep<'a>) -> Self {
+        self.keep = Some(keep);
+        self
+    }
+
+    pub(crate) fn copy(mut self, value: &'a Value) -> Result<Value, RefError> {
+        let (kind, span) = self.place(value, None, None, &mut Vec::new())?;
+        let root = self.dest.root(kind, span);
+
+        while let Some(mut work) = self.work.pop() {
+            let mut dropped = work.dropped.iter().peekable();
+            let mut dest = work.dest;
+            for (i, v) in work.values.enumerate() {
+                if dropped.next_if_eq(&&v).is_some() {
+                    continue;
+                }
+                let value = &self.src.values[v as usize];
+                if self.keep.is_some() {
+                    work.path.push(self.segment(work.keys.as_ref(), i));
+                }
+                let (kind, span) =
+                    self.place(value, Some(work.shift), work.expansion, &mut work.path)?;
+                work.path.pop();
+                self.dest.set(dest, kind, span);
+                dest += 1;
+            }
+        }
+
+        Ok(root)
+    }
+
+    /// The path segment of the child at `index`, in an object if it has `keys`.
+    fn segment(&self, keys: Option<&Range<u32>>, index: usize) -> PathSegment<'a> {
+        let src = self.src;
+        match keys {
+            Some(keys) => PathSegment::Key(&src[&src.keys[keys.start as usize + index]]),
+            None => PathSegment::Index(index),
+        }
+    }
+
+    /// The children in `values` that the filter drops, given the path to their container.
+    fn dropped(
+        &mut self,
+        keys: Option<&Range<u32>>,
+        values: Range<u32>,
+        path: &mut Vec<PathSegment<'a>>,
+    ) -> Vec<u32> {
+        let mut dropped = Vec::new();
+        if self.keep.is_none() {
+            return dropped;
+        }
+        for (i, v) in values.enumerate() {
+            path.push(self.segment(keys, i));
+            let keep = self.keep.as_mut().unwrap();
+            if !keep(path, &self.src.values[v as usize]) {
+                dropped.push(v);
+            }
+            path.pop();
+        }
+        dropped
+    }
+
+    /// Copy `value` itself, and reserve slots for its children.
+    ///
+    /// Returns its kind and span, for the caller to put in a slot.
+    fn place(
+        &mut self,
+        mut value: &'a Value,
+        mut shift: Option<Shift>,
+        mut expansion: Option<usize>,
+        path: &mut Vec<PathSegment<'a>>,
+    ) -> Result<(ValueKind, Range<u32>), RefError> {
+        if let Some(refs) = self.refs {
+            let target = refs.resolve(value)?;
+            if !core::ptr::eq(target, value) {
+                let mut parent = expansion;
+                while let Some(i) = parent {
+                    let (inlined, grandparent) = self.expansions[i];
+                    if core::ptr::eq(inlined, target) {
+                        let r = refs.ref_of(value).unwrap_or(value);
+                        return Err(RefError {
+                            span: self.src.span(r),
+                            kind: RefErrorKind::Cycle,
+                        });
+                    }
+                    parent = grandparent;
+                }
+
+                self.expansions.push((target, expansion));
+                expansion = Some(self.expansions.len() - 1);
+                value = target;
+                shift = None;
+            }
+        }
+
+        let shift = match shift {
+            Some(shift) => shift,
+            None => self.copy_text(value),
+        };
+        let (lo, hi) = bounds(&self.src.span(value));
+        // reversed, as the text is in the destination's copied text.
+        let span = hi - shift.old + shift.new..lo - shift.old + shift.new;
+
+        let kind = match value.kind() {
+            ValueKind::Leaf(leaf) => ValueKind::Leaf(leaf),
+            ValueKind::Object(object) => {
+                let keys = object.keys(&self.src.values);
+                let dropped = self.dropped(Some(&keys), object.values(), path);
+                let ki = self.dest.keys.len();
+                let mut skip = dropped.iter().peekable();
+                for (key, v) in core::iter::zip(keys.clone(), object.values()) {
+                    if skip.next_if_eq(&&v).is_some() {
+                        continue;
+                    }
+                    let key = self
+                        .dest
+                        .intern_copy(&self.src[&self.src.keys[key as usize]]);
+                    self.dest.keys.push(key);
+                }
+
+                let work = self.work(object.values(), Some(keys), shift, expansion, path, dropped);
+                let values = self.reserve(work, span.clone(), ki as u32);
+                ValueKind::Object(Object::new(values))
+            }
+            ValueKind::Array(array) => {
+                let dropped = self.dropped(None, array.values(), path);
+                let work = self.work(array.values(), None, shift, expansion, path, dropped);
+                ValueKind::Array(Array::new(self.reserve(work, span.clone(), 0)))
+            }
+        };
+
+        Ok((kind, span))
+    }
+
+    fn work(
+        &self,
+        values: Range<u32>,
+        keys: Option<Range<u32>>,
+        shift: Shift,
+        expansion: Option<usize>,
+        path: &[PathSegment<'a>],
+        dropped: Vec<u32>,
+    ) -> Work<'a> {
+        Work {
+            values,
+            keys,
+            dest: 0,
+            shift,
+            expansion,
+            path: match self.keep {
+                Some(_) => path.to_vec(),
+                None => Vec::new(),
+            },
+            dropped,
+        }
+    }
+
+    /// Reserve slots for the children of `work` that are kept, to be copied later,
+    /// after a header holding `span`, the span of their container, and `keys`, where its keys start.
+    fn reserve(&mut self, mut work: Work<'a>, span: Range<u32>, keys: u32) -> Range<u32> {
+        let start = self.dest.push_header(span, keys);
+        let len = work.values.len() - work.dropped.len();
+        // placeholders, until the children are copied.
+        let end = start as usize + len;
+        self.dest.values.resize(end, Value::HEADER);
+        self.dest.spans.resize(end, 0..0);
+        let end = end as u32;
+
+        work.dest = start;
+        self.work.push(work);
+        start..end
+    }
+
+    fn copy_text(&mut self, value: &Value) -> Shift {
+        let Self {
+            src, dest, text, ..
+        } = self;
+        let span = src.span(value);
+        let new = *text.entry((span.start, span.end)).or_insert_with(|| {
+            let new = dest.scratch.text.len() as u32;
+            dest.scratch.text.push_str(src.raw(value));
+            new
+        });
+        Shift {
+            old: bounds(&span).0,
+            new,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, ValueKind};
+
+    #[test]
+    fn deep_copy() {
+        let a_src = r#"{"keep": {"a\nb": [1, 'x', {"c": null}], "d": "e"}, "drop": 0}"#;
+        let mut a = Arena::new(a_src);
+        let root = crate::parse_with_dialect(
+            &mut a,
+            crate::Dialect {
+                single_quotes: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let ValueKind::Object(object) = root.kind() else {
+            panic!("expected object")
+        };
+        let keep = a.get(&object, "keep").unwrap();
+
+        let mut b = Arena::new(r#"{"d": 1}"#);
+        let b_root = crate::parse(&mut b).unwrap();
+        let copy = a.deep_copy_into(keep, &mut b);
+        drop(a);
+
+        assert_eq!(b.to_json(&copy), r#"{"a\nb":[1,"x",{"c":null}],"d":"e"}"#);
+        assert_eq!(b.raw(&copy), r#"{"a\nb": [1, 'x', {"c": null}], "d": "e"}"#);
+        assert_eq!(b.to_json(&b_root), r#"{"d":1}"#);
+        // "d" is interned once
+        assert_eq!(b.keys.iter().filter(|&k| *k == b.keys[0]).count(), 2);
+
+        // copying a copy
+        let mut c = Arena::new("");
+        let copy = b.deep_copy_into(&copy, &mut c);
+        assert_eq!(c.to_json(&copy), r#"{"a\nb":[1,"x",{"c":null}],"d":"e"}"#);
+    }
+
+    #[test]
+    fn non_contiguous() {
+        let src = r#"{"a": [[1, 2], {"b": [3]}], "c": {"d": [4, 5]}}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        assert!(arena.subtree(&root).is_some());
+
+        // compacting lays values out breadth first, so subtrees are no longer contiguous.
+        let mut roots = [root];
+        arena.compact(&mut roots);
+        let ValueKind::Object(object) = roots[0].kind() else {
+            panic!("expected object")
+        };
+        let a = arena.get(&object, "a").unwrap();
+        assert!(arena.subtree(a).is_none());
+
+        let mut dest = Arena::new("");
+        let copy = arena.deep_copy_into(a, &mut dest);
+        assert_eq!(dest.to_json(&copy), r#"[[1,2],{"b":[3]}]"#);
+        let copy = arena.deep_copy_into(&roots[0], &mut dest);
+        assert_eq!(
+            dest.to_json(&copy),
+            r#"{"a":[[1,2],{"b":[3]}],"c":{"d":[4,5]}}"#
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+use foldhash::quality::RandomState;
+use hashbrown::HashSet;
+
+use crate::rows::Elements;
+use crate::{Arena, Error, LeafValue, Object, Stacks, ValueKind};
+
+#[derive(Debug)]
+pub enum CsvError {
+    /// The input is not a single top-level array.
+    NotAnArray {
+        span: Range<u32>,
+    },
+    /// An element of the array is not an object.
+    NotAnObject {
+        span: Range<u32>,
+    },
+    Json(Error),
+    /// The output could not be written to.
+    Write,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::NotAnArray { span } => write!(f, "expected an array at {span:?}"),
+            CsvError::NotAnObject { span } => write!(f, "expected an object at {span:?}"),
+            CsvError::Json(e) => e.fmt(f),
+            CsvError::Write => f.write_str("failed to write CSV output"),
+        }
+    }
+}
+
+impl core::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            CsvError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<fmt::Error> for CsvError {
+    fn from(_: fmt::Error) -> Self {
+        CsvError::Write
+    }
+}
+
+/// Convert a top-level JSON array of objects into CSV.
+///
+/// The header is the union of all keys, in the order they are first seen.
+/// Strings are written without their JSON quotes, `null` and missing keys
+/// as empty cells, and nested objects and arrays as compact JSON.
+/// Rows end with `\r\n`, as in RFC 4180.
+///
+/// The input is read twice, once for the header and once for the rows,
+/// and only one element is parsed at a time, so memory use does not grow
+/// with the length of the array.
+pub fn to_csv(src: &str, out: &mut impl Write) -> Result<(), CsvError> {
+    let mut arena = Arena::new(src);
+    let mut stacks = Stacks::default();
+
+    let mut header = Vec::new();
+    let mut seen = HashSet::with_hasher(RandomState::default());
+    for range in Elements::new(src) {
+        let range = range.map_err(|span| CsvError::NotAnArray { span })?;
+        let row = parse_row(&mut arena, &mut stacks, range)?;
+        for (key, _) in arena.entries(&row) {
+            if !seen.contains(key) {
+                seen.insert(String::from(key));
+                header.push(String::from(key));
+            }
+        }
+        arena.clear();
+    }
+    drop(seen);
+
+    for (i, key) in header.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write_cell(out, key)?;
+    }
+    out.write_str("\r\n")?;
+
+    let mut cell = String::new();
+    for range in Elements::new(src) {
+        let range = range.map_err(|span| CsvError::NotAnArray { span })?;
+        let row = parse_row(&mut arena, &mut stacks, range)?;
+        for (i, key) in header.iter().enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            match arena.get(&row, key).map(|value| (value, value.kind())) {
+                None | Some((_, ValueKind::Leaf(LeafValue::Null))) => {}
+                Some((value, ValueKind::Leaf(LeafValue::String))) => {
+                    write_cell(out, &arena.as_str(value).unwrap_or_default())?
+                }
+                Some((value, _)) => {
+                    cell.clear();
+                    arena.write_json(value, &mut cell);
+                    write_cell(out, &cell)?;
+                }
+            }
+        }
+        out.write_str("\r\n")?;
+        arena.clear();
+    }
+
+    Ok(())
+}
+
+fn parse_row(
+    arena: &mut Arena<'_>,
+    stacks: &mut Stacks,
+    range: Range<usize>,
+) -> Result<Object, CsvError> {
+    let value = arena.parse_element(stacks, range).map_err(CsvError::Json)?;
+    match value.kind() {
+        ValueKind::Object(object) => Ok(object),
+        _ => Err(CsvError::NotAnObject {
+            span: arena.span(&value),
+        }),
+    }
+}
+
+fn write_cell(out: &mut impl Write, cell: &str) -> fmt::Result {
+    if !cell.contains([',', '"', '\r', '\n']) {
+        return out.write_str(cell);
+    }
+    out.write_char('"')?;
+    for (i, part) in cell.split('"').enumerate() {
+        if i > 0 {
+            out.write_str("\"\"")?;
+        }
+        out.write_str(part)?;
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{to_csv, CsvError};
+
+    #[test]
+    fn csv() {
+        let src = r#"[
+            {"name": "a", "price": 1.5, "tags": ["x", "y"]},
+            {"name": "b,c", "qty": 2, "price": null},
+            {"note": "say \"hi\"\n", "name": "d"}
+        ]"#;
+        let mut out = String::new();
+        to_csv(src, &mut out).unwrap();
+        assert_eq!(
+            out,
+            "name,price,tags,qty,note\r\n\
+             a,1.5,\"[\"\"x\"\",\"\"y\"\"]\",,\r\n\
+             \"b,c\",,,2,\r\n\
+             d,,,,\"say \"\"hi\"\"\n\"\r\n"
+        );
+
+        let mut out = String::new();
+        to_csv("[]", &mut out).unwrap();
+        assert_eq!(out, "\r\n");
+
+        let errors = ["{}", "[1]", "[{},]", "[{}", "[{}] []", r#"[{"a" 1}]"#];
+        for src in errors {
+            let err = to_csv(src, &mut String::new()).unwrap_err();
+            assert!(
+                matches!(
+                    (src, err),
+                    (
+                        "{}" | "[{},]" | "[{}" | "[{}] []",
+                        CsvError::NotAnArray { .. }
+                    ) | ("[1]", CsvError::NotAnObject { .. })
+                        | (r#"[{"a" 1}]"#, CsvError::Json(_))
+                ),
+                "{src}"
+            );
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! A serde [`Deserializer`](serde::Deserializer) over a parsed value.
+
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use serde::de::value::{BorrowedStrDeserializer, CowStrDeserializer};
+use serde::de::{self, DeserializeSeed, Visitor};
+
+use crate::{Arena, Coercion, LeafValue, Number, Value, ValueKind};
+
+/// An error from deserializing a value with [`Arena::deserializer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeError {
+    pub message: String,
+    /// The span of the innermost value that failed, if the error came from a value.
+    pub span: Option<Range<u32>>,
+}
+
+impl DeError {
+    fn at(mut self, span: &Range<u32>) -> Self {
+        self.span.get_or_insert_with(|| span.clone());
+        self
+    }
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "{} at bytes {span:?}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl core::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError {
+            message: msg.to_string(),
+            span: None,
+        }
+    }
+}
+
+/// Deserializes one value of an arena, borrowing keys and unescaped strings from it.
+#[derive(Clone, Copy)]
+pub struct Deserializer<'a, 's> {
+    arena: &'a Arena<'s>,
+    value: &'a Value,
+    coercion: Coercion,
+}
+
+impl<'s> Arena<'s> {
+    /// A deserializer for `value`, like `T::deserialize(arena.deserializer(&value))`.
+    pub fn deserializer<'a>(&'a self, value: &'a Value) -> Deserializer<'a, 's> {
+        Deserializer {
+            arena: self,
+            value,
+            coercion: Coercion::STRICT,
+        }
+    }
+}
+
+impl<'a, 's> Deserializer<'a, 's> {
+    /// Read values as another type when `coercion` allows, such as `"42"` into a `u32` field.
+    pub fn with_coercion(mut self, coercion: Coercion) -> Self {
+        self.coercion = coercion;
+        self
+    }
+
+    fn child(self, value: &'a Value) -> Self {
+        Deserializer { value, ..self }
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(self.value.kind(), ValueKind::Leaf(LeafValue::String))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de, '_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let arena = self.arena;
+        let value = self.value;
+        let res = match value.kind() {
+            ValueKind::Leaf(LeafValue::Null) => visitor.visit_unit(),
+            ValueKind::Leaf(LeafValue::Bool(b)) => visitor.visit_bool(b),
+            ValueKind::Leaf(LeafValue::String) => match arena.as_str(value) {
+                Some(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+                Some(Cow::Owned(s)) => visitor.visit_string(s),
+                None => Err(de::Error::custom("invalid escape in string")),
+            },
+            ValueKind::Leaf(_) => match arena.as_number(value) {
+                Some(Number::I64(n)) => visitor.visit_i64(n),
+                Some(Number::U64(n)) => visitor.visit_u64(n),
+                Some(Number::F64(n)) => match arena.as_i128(value) {
+                    Some(n) => visitor.visit_i128(n),
+                    None => visitor.visit_f64(n),
+                },
+                None => Err(de::Error::custom("invalid number")),
+            },
+            ValueKind::Array(array) => {
+                let mut seq = Seq {
+                    de: self,
+                    iter: arena.array_iter(&array),
+                };
+                let out = visitor.visit_seq(&mut seq)?;
+                match seq.iter.len() {
+                    0 => Ok(out),
+                    _ => Err(de::Error::invalid_length(
+                        array.len(),
+                        &"fewer elements in array",
+                    )),
+                }
+            }
+            ValueKind::Object(object) => visitor.visit_map(Map {
+                de: self,
+                iter: arena.entries(&object),
+                value: None,
+            }),
+        };
+        res.map_err(|e| e.at(&arena.span(value)))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.arena.as_bool_lenient(self.value, self.coercion) {
+            Some(b) => visitor.visit_bool(b),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let n = self
+            .is_string()
+            .then(|| self.arena.as_i64_lenient(self.value, self.coercion));
+        match n.flatten() {
+            Some(n) => visitor.visit_i64(n),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_i64(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let n = self
+            .is_string()
+            .then(|| self.arena.as_f64_lenient(self.value, self.coercion));
+        match n.flatten() {
+            Some(n) => visitor.visit_f64(n),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.is_string() {
+            return self.deserialize_any(visitor);
+        }
+        match self.arena.as_string_lenient(self.value, self.coercion) {
+            Some(s) => visitor.visit_string(s),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.value.kind() {
+            ValueKind::Leaf(LeafValue::Null) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let arena = self.arena;
+        let (variant, value) = match self.value.kind() {
+            ValueKind::Leaf(LeafValue::String) => (arena.as_str(self.value), None),
+            ValueKind::Object(object) => {
+                let mut entries = arena.entries(&object);
+                match (entries.next(), entries.next()) {
+                    (Some((key, value)), None) => (Some(Cow::Borrowed(key)), Some(value)),
+                    _ => (None, None),
+                }
+            }
+            _ => (None, None),
+        };
+        let Some(variant) = variant else {
+            let unexpected = de::Unexpected::Other(self.value.json_type().type_name());
+            let err: DeError =
+                de::Error::invalid_type(unexpected, &"a string or an object with one key");
+            return Err(err.at(&arena.span(self.value)));
+        };
+        visitor
+            .visit_enum(Enum {
+                de: self,
+                variant,
+                value,
+            })
+            .map_err(|e| e.at(&arena.span(self.value)))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct Seq<'a, 's, I> {
+    de: Deserializer<'a, 's>,
+    iter: I,
+}
+
+impl<'de, I: ExactSizeIterator<Item = &'de Value>> de::SeqAccess<'de> for Seq<'de, '_, I> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(self.de.child(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializes the matches of a [`Query`](crate::Query) as a sequence.
+pub(crate) struct Matches<'a, 's> {
+    pub(crate) de: Deserializer<'a, 's>,
+    pub(crate) values: Vec<&'a Value>,
+}
+
+impl<'de> de::Deserializer<'de> for Matches<'de, '_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let len = self.values.len();
+        let mut seq = Seq {
+            de: self.de,
+            iter: self.values.into_iter(),
+        };
+        let out = visitor.visit_seq(&mut seq)?;
+        match seq.iter.len() {
+            0 => Ok(out),
+            _ => Err(de::Error::invalid_length(len, &"fewer matches")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+struct Map<'a, 's, I> {
+    de: Deserializer<'a, 's>,
+    iter: I,
+    value: Option<&'a Value>,
+}
+
+impl<'de, I: Iterator<Item = (&'de str, &'de Value)>> de::MapAccess<'de> for Map<'de, '_, I> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value called before next_key");
+        seed.deserialize(self.de.child(value))
+    }
+}
+
+struct Enum<'a, 's> {
+    de: Deserializer<'a, 's>,
+    variant: Cow<'a, str>,
+    value: Option<&'a Value>,
+}
+
+impl<'de, 's> de::EnumAccess<'de> for Enum<'de, 's> {
+    type Error = DeError;
+    type Variant = Variant<'de, 's>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Variant<'de, 's>), DeError> {
+        let variant = seed.deserialize(CowStrDeserializer::new(self.variant))?;
+        Ok((
+            variant,
+            Variant {
+                de: self.de,
+                value: self.value,
+            },
+        ))
+    }
+}
+
+struct Variant<'a, 's> {
+    de: Deserializer<'a, 's>,
+    value: Option<&'a Value>,
+}
+
+impl<'de, 's> Variant<'de, 's> {
+    fn value(self) -> Result<Deserializer<'de, 's>, DeError> {
+        match self.value {
+            Some(value) => Ok(self.de.child(value)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"a variant with a value",
+            )),
+        }
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Variant<'de, '_> {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => de::Deserialize::deserialize(self.de.child(value)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, DeError> {
+        seed.deserialize(self.value()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_seq(self.value()?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_map(self.value()?, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use serde::Deserialize;
+
+    use crate::{Arena, Coercion};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct User<'a> {
+        id: u32,
+        #[serde(borrow)]
+        name: Cow<'a, str>,
+        tags: Vec<String>,
+        role: Role,
+        manager: Option<u32>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        Admin,
+        Guest { until: i64 },
+    }
+
+    #[test]
+    fn deserialize() {
+        let src = r#"{"id": 7, "name": "ada", "tags": ["a\n", "b"], "role": {"guest": {"until": -1}}, "manager": null}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+
+        let user = User::deserialize(arena.deserializer(&root)).unwrap();
+        assert!(matches!(user.name, Cow::Borrowed("ada")));
+        assert_eq!(user.tags, ["a\n", "b"]);
+        assert_eq!(user.role, Role::Guest { until: -1 });
+        assert_eq!((user.id, user.manager), (7, None));
+
+        let src = r#"{"id": "7", "name": "ada", "tags": [], "role": "admin", "manager": 3}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let err = User::deserialize(arena.deserializer(&root)).unwrap_err();
+        assert_eq!(err.span, Some(7..10));
+
+        let de = arena.deserializer(&root).with_coercion(Coercion::LENIENT);
+        let user = User::deserialize(de).unwrap();
+        assert_eq!(
+            (user.id, user.role, user.manager),
+            (7, Role::Admin, Some(3))
+        );
+    }
+}
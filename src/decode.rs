@@ -0,0 +1,137 @@
+use alloc::borrow::Cow;
+#[cfg(feature = "base64")]
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Arena, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    /// The span of the offending value.
+    pub span: Range<u32>,
+    pub kind: DecodeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    ExpectedString,
+    /// The string is not standard base64.
+    InvalidBase64,
+    /// The string is not a UUID like `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    InvalidUuid,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::ExpectedString => write!(f, "expected a string at {:?}", self.span),
+            DecodeErrorKind::InvalidBase64 => write!(f, "invalid base64 at {:?}", self.span),
+            DecodeErrorKind::InvalidUuid => write!(f, "invalid UUID at {:?}", self.span),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+impl Arena<'_> {
+    /// Decode a string of standard base64, with or without padding.
+    #[cfg(feature = "base64")]
+    pub fn as_base64_bytes(&self, value: &Value) -> Result<Vec<u8>, DecodeError> {
+        let s = self.decode_str(value)?;
+        crate::base64::decode_standard(&s).map_err(|()| DecodeError {
+            span: self.span(value),
+            kind: DecodeErrorKind::InvalidBase64,
+        })
+    }
+
+    /// Decode a hyphenated UUID string, in either case, into its 16 bytes.
+    #[cfg(feature = "uuid")]
+    pub fn as_uuid(&self, value: &Value) -> Result<[u8; 16], DecodeError> {
+        let invalid = || DecodeError {
+            span: self.span(value),
+            kind: DecodeErrorKind::InvalidUuid,
+        };
+        let s = self.decode_str(value)?;
+
+        let mut groups = s.split('-');
+        let mut bytes = [0; 16];
+        let mut start = 0;
+        for len in [4, 2, 2, 2, 6] {
+            let group = groups.next().ok_or_else(invalid)?;
+            if group.len() != len * 2 {
+                return Err(invalid());
+            }
+            for (byte, pair) in bytes[start..start + len]
+                .iter_mut()
+                .zip(group.as_bytes().chunks(2))
+            {
+                let digit = |i| crate::hex_digit(pair[i]).ok_or_else(invalid);
+                *byte = digit(0)? << 4 | digit(1)?;
+            }
+            start += len;
+        }
+        match groups.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(bytes),
+        }
+    }
+
+    fn decode_str(&self, value: &Value) -> Result<Cow<'_, str>, DecodeError> {
+        self.as_str(value).ok_or_else(|| DecodeError {
+            span: self.span(value),
+            kind: DecodeErrorKind::ExpectedString,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, DecodeErrorKind, ValueKind};
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn as_base64_bytes() {
+        let src = r#"["aGVsbG8=", "aGVsbG8", "a$==", 1]"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+        let values = arena.array_values(&array);
+
+        assert_eq!(arena.as_base64_bytes(&values[0]).unwrap(), b"hello");
+        assert_eq!(arena.as_base64_bytes(&values[1]).unwrap(), b"hello");
+        let err = arena.as_base64_bytes(&values[2]).unwrap_err();
+        assert_eq!(
+            (err.span, err.kind),
+            (24..30, DecodeErrorKind::InvalidBase64)
+        );
+        let err = arena.as_base64_bytes(&values[3]).unwrap_err();
+        assert_eq!(err.kind, DecodeErrorKind::ExpectedString);
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn as_uuid() {
+        let src = r#"["67e55044-10b1-426F-9247-bb680e5fe0c8", "67e5504410b1426f9247bb680e5fe0c8", "67e55044-10b1-426f-9247-bb680e5fe0c8-00"]"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+        let values = arena.array_values(&array);
+
+        assert_eq!(
+            arena.as_uuid(&values[0]).unwrap(),
+            [
+                0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+                0xe0, 0xc8
+            ]
+        );
+        for value in &values[1..] {
+            let err = arena.as_uuid(value).unwrap_err();
+            assert_eq!(err.kind, DecodeErrorKind::InvalidUuid);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ops::Range;
+use hashbrown::HashTable;
+
+use crate::{Arena, Array, Object, Pending, StringKey, Value, ValueKind};
+
+/// A step of the walk over a tree, children first.
+enum Visit {
+    Enter(Value),
+    /// Every child of the container has been visited.
+    Exit(Value),
+}
+
+impl Arena<'_> {
+    /// Store each distinct subtree reachable from `roots` only once.
+    ///
+    /// Containers with the same keys and the same children, as written in the source,
+    /// share their storage. The roots are rewritten in place, and like [`Arena::compact`],
+    /// values that are not in `roots` must not be used with this arena afterwards.
+    ///
+    /// A shared container, and the values inside it, keep the spans of wherever it
+    /// appeared first. [`Arena::span_of`] still gives each place it appears its own span.
+    pub fn dedup(&mut self, roots: &mut [Value]) {
+        let mut values = Vec::new();
+        let mut spans = Vec::new();
+        let mut keys = Vec::new();
+        let mut table = HashTable::new();
+
+        for root in roots {
+            let mut work = vec![Visit::Enter(*root)];
+            // the deduplicated values, children before their parent
+            let mut done = Vec::new();
+            while let Some(visit) = work.pop() {
+                match visit {
+                    Visit::Enter(value) => {
+                        let children = match value.kind() {
+                            ValueKind::Leaf(_) => {
+                                done.push(Pending {
+                                    span: self.span(&value),
+                                    kind: value.kind(),
+                                });
+                                continue;
+                            }
+                            ValueKind::Object(object) => object.values(),
+                            ValueKind::Array(array) => array.values(),
+                        };
+                        let children = &self.values[children.start as usize..children.end as usize];
+                        let children = children.iter().rev().copied().map(Visit::Enter);
+                        work.push(Visit::Exit(value));
+                        work.extend(children);
+                    }
+                    Visit::Exit(value) => {
+                        let len = match value.kind() {
+                            ValueKind::Leaf(_) => unreachable!(),
+                            ValueKind::Object(object) => object.values().len(),
+                            ValueKind::Array(array) => array.len(),
+                        };
+                        let children = done.split_off(done.len() - len);
+                        let span = self.span(&value);
+                        let kind = self.intern_container(
+                            value.kind(),
+                            span.clone(),
+                            children,
+                            (&mut values, &mut spans),
+                            &mut keys,
+                            &mut table,
+                        );
+                        done.push(Pending { span, kind });
+                    }
+                }
+            }
+            let Pending { span, kind } = done.pop().unwrap();
+            *root = Value::new(kind, values.len() as u32);
+            if let ValueKind::Leaf(_) = kind {
+                values.push(*root);
+                spans.push(span);
+            }
+        }
+
+        self.values = values;
+        self.spans = spans;
+        self.keys = keys;
+    }
+
+    /// The container in `values` with these keys and `children`, added if it is new,
+    /// with `span` in its header.
+    fn intern_container(
+        &self,
+        kind: ValueKind,
+        span: Range<u32>,
+        children: Vec<Pending>,
+        (values, spans): (&mut Vec<Value>, &mut Vec<Range<u32>>),
+        keys: &mut Vec<StringKey>,
+        table: &mut HashTable<(u64, ValueKind)>,
+    ) -> ValueKind {
+        let object_keys = match kind {
+            ValueKind::Object(object) => Some(self.keys_of(&object)),
+            _ => None,
+        };
+
+        let mut hasher = self.hasher.build_hasher();
+        object_keys.is_some().hash(&mut hasher);
+        for key in object_keys.into_iter().flatten() {
+            self[key].hash(&mut hasher);
+        }
+        for child in &children {
+            self.hash_shallow(child, &mut hasher);
+        }
+        let hash = hasher.finish();
+
+        let same = |kind: &ValueKind| {
+            let (existing_keys, existing) = match kind {
+                ValueKind::Object(object) => {
+                    let range = object.keys(values);
+                    (
+                        Some(&keys[range.start as usize..range.end as usize]),
+                        object.values(),
+                    )
+                }
+                ValueKind::Array(array) => (None, array.values()),
+                ValueKind::Leaf(_) => unreachable!(),
+            };
+            let existing = existing.start as usize..existing.end as usize;
+            let existing = core::iter::zip(&values[existing.clone()], &spans[existing]);
+            let same_keys = match (object_keys, existing_keys) {
+                (Some(a), Some(b)) => {
+                    a.len() == b.len() && core::iter::zip(a, b).all(|(a, b)| self[a] == self[b])
+                }
+                (None, None) => true,
+                _ => false,
+            };
+            same_keys
+                && existing.len() == children.len()
+                && core::iter::zip(existing, &children).all(|((value, span), b)| {
+                    let a = Pending {
+                        span: span.clone(),
+                        kind: value.kind(),
+                    };
+                    self.same_shallow(&a, b)
+                })
+        };
+        if let Some((_, kind)) = table.find(hash, |(_, kind)| same(kind)) {
+            return *kind;
+        }
+
+        values.push(Value::header(keys.len() as u32));
+        spans.push(span);
+        let vi = values.len() as u32;
+        for child in children {
+            values.push(Value::new(child.kind, values.len() as u32));
+            spans.push(child.span);
+        }
+        let vj = values.len() as u32;
+        let kind = match object_keys {
+            Some(object_keys) => {
+                keys.extend_from_slice(object_keys);
+                ValueKind::Object(Object::new(vi..vj))
+            }
+            None => ValueKind::Array(Array::new(vi..vj)),
+        };
+        table.insert_unique(hash, (hash, kind), |(hash, _)| *hash);
+        kind
+    }
+
+    /// Hash a value whose children have already been deduplicated.
+    fn hash_shallow(&self, value: &Pending, hasher: &mut impl Hasher) {
+        match value.kind {
+            ValueKind::Leaf(leaf) => {
+                0u8.hash(hasher);
+                core::mem::discriminant(&leaf).hash(hasher);
+                self.raw_at(&value.span).hash(hasher);
+            }
+            ValueKind::Object(object) => {
+                // each object has its own header, so its values tell it apart.
+                1u8.hash(hasher);
+                object.values().hash(hasher);
+            }
+            ValueKind::Array(array) => {
+                2u8.hash(hasher);
+                array.values().hash(hasher);
+            }
+        }
+    }
+
+    /// Compare two values whose children have already been deduplicated.
+    fn same_shallow(&self, a: &Pending, b: &Pending) -> bool {
+        match (a.kind, b.kind) {
+            (ValueKind::Leaf(x), ValueKind::Leaf(y)) => {
+                x == y && self.raw_at(&a.span) == self.raw_at(&b.span)
+            }
+            (ValueKind::Object(x), ValueKind::Object(y)) => x.values() == y.values(),
+            (ValueKind::Array(x), ValueKind::Array(y)) => x.values() == y.values(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{Arena, ValueKind};
+
+    #[test]
+    fn dedup() {
+        let src = r#"[{"type": "string"}, {"type": "string"}, {"a": [1, 2]}, {"a": [1, 2]}, {"a": [1, 2.0]}, [], []]"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let before = arena.values.len();
+
+        let mut roots = [root];
+        arena.dedup(&mut roots);
+        let [root] = roots;
+        assert_eq!(
+            arena.to_json(&root),
+            src.replace(", ", ",").replace(": ", ":")
+        );
+        assert!(arena.values.len() < before);
+
+        let ValueKind::Array(array) = root.kind() else {
+            panic!("expected array")
+        };
+        let items = arena.array_values(&array);
+        let range = |i: usize| match items[i].kind() {
+            ValueKind::Object(object) => object.values(),
+            ValueKind::Array(array) => array.values(),
+            ValueKind::Leaf(_) => panic!("expected container"),
+        };
+        assert_eq!(range(0), range(1));
+        assert_eq!(range(2), range(3));
+        assert_ne!(range(3), range(4));
+        assert_eq!(range(5), range(6));
+        // the outer spans are still their own
+        let ids: Vec<_> = arena.array_ids(&array).collect();
+        assert_ne!(arena.span_of(ids[0]), arena.span_of(ids[1]));
+    }
+}
@@ -0,0 +1,331 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    Arena, ContextItem, Error, LeafValue, Pending, StackItem, StackItemKind, StringKey, Token,
+    Value,
+};
+
+/// Extensions to, and restrictions of, the JSON grammar. Everything is disabled by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dialect {
+    /// Tolerate missing commas between values and object members that are on separate lines,
+    /// and missing colons after keys.
+    ///
+    /// For recovering what you can from corrupted logs, eg
+    /// `{"a": 1\n"b": [2\n3]}`.
+    pub salvage: bool,
+    /// Accept `'single quoted'` strings and keys.
+    ///
+    /// Within them, `"` needs no escape, and `\'` is a valid escape.
+    pub single_quotes: bool,
+    /// Accept bare identifiers as object keys, eg `{foo: 1}`.
+    pub unquoted_keys: bool,
+    /// Accept hex, octal and binary integers, eg `0xff`, `0o17` and `0b101`.
+    ///
+    /// They are parsed as [`LeafValue::RadixInt`](crate::LeafValue::RadixInt),
+    /// and serialized as decimal.
+    pub radix_numbers: bool,
+    /// Parse integers beyond ±2<sup>53</sup>, which an `f64` cannot hold exactly,
+    /// as [`LeafValue::BigInt`](crate::LeafValue::BigInt), eg snowflake IDs.
+    ///
+    /// They are always serialized exactly as written.
+    pub big_ints: bool,
+    /// Reject strings with invalid escapes, or unpaired surrogates like `"\ud83e"`, while lexing.
+    ///
+    /// Otherwise they are only found when the string is decoded, which never happens
+    /// for strings that are not read, such as values when only validating.
+    pub validate_escapes: bool,
+    /// Reject a document that is not an object or array, like `"hello"` or `42`.
+    pub require_container: bool,
+}
+
+/// Parse the arena's source using the extensions enabled in `dialect`.
+pub fn parse_with_dialect(arena: &mut Arena<'_>, dialect: Dialect) -> Result<Value, Error> {
+    let len = arena.scratch.src.len();
+    let mut stacks = core::mem::take(&mut arena.stacks);
+    let res = crate::parse_range(arena, &mut stacks, 0..len, dialect);
+    arena.stacks = stacks;
+    res
+}
+
+/// Whether `token` can start a value.
+fn starts_value(token: &Token) -> bool {
+    matches!(token, Token::Leaf(_) | Token::OpenObject | Token::OpenArray)
+}
+
+/// Insert the comma or colon that is missing before `token`, if any.
+///
+/// A comma is only inserted where a line break separates the value before it from `token`,
+/// which starts at `start` in `src`.
+pub(crate) fn recover(
+    token: &Token,
+    src: &str,
+    start: u32,
+    context: ContextItem,
+    stack: &[StackItem],
+    value_stack: &mut Vec<Pending>,
+    key_stack: &mut Vec<StringKey>,
+) -> ContextItem {
+    let Some(top) = stack.last() else {
+        return context;
+    };
+    let newline = |span: &Range<u32>| src[span.end as usize..start as usize].contains('\n');
+    match (context, &top.kind) {
+        // `[1\n2]`
+        (ContextItem::Value { span, value }, StackItemKind::Array(_))
+            if starts_value(token) && newline(&span) =>
+        {
+            value_stack.push(Pending { span, kind: value });
+            ContextItem::WaitingValue
+        }
+        // `{"a": 1\n"b": 2}`
+        (ContextItem::Value { span, value }, StackItemKind::Object(..))
+            if matches!(token, Token::Leaf(LeafValue::String) | Token::Ident) && newline(&span) =>
+        {
+            value_stack.push(Pending { span, kind: value });
+            ContextItem::WaitingKey
+        }
+        // `{"a" 1}`
+        (ContextItem::Key { key, .. }, StackItemKind::Object(..)) if starts_value(token) => {
+            key_stack.push(key);
+            ContextItem::WaitingValue
+        }
+        (context, _) => context,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_with_dialect, Dialect};
+    use crate::{Arena, LeafValue, Radix};
+
+    #[test]
+    fn salvage() {
+        let src = r#"{"level": "info"
+            "msg" "started"
+            "tags": ["a", "b"
+                "c", {"d": null}]
+            "n": 1,
+            "nested": [[1]
+                [2]]}"#;
+
+        let mut arena = Arena::new(src);
+        assert!(crate::parse(&mut arena).is_err());
+
+        let salvage = Dialect {
+            salvage: true,
+            ..Dialect::default()
+        };
+        let mut arena = Arena::new(src);
+        let value = parse_with_dialect(&mut arena, salvage).unwrap();
+        assert_eq!(
+            arena.to_json(&value),
+            r#"{"level":"info","msg":"started","tags":["a","b","c",{"d":null}],"n":1,"nested":[[1],[2]]}"#
+        );
+
+        // still not everything goes
+        for src in [
+            "[1,,2]",
+            r#"{"a": 1 2}"#,
+            "1 2",
+            "{1: 2}",
+            // commas are only inserted at line breaks
+            "[1 2]",
+            r#"{"a":1 "b":2}"#,
+            "[[1] [2]]",
+        ] {
+            let mut arena = Arena::new(src);
+            assert!(parse_with_dialect(&mut arena, salvage).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    fn single_quotes() {
+        let src = r#"{'a': 'it\'s', "b": ['say "hi"', 'x\ny'], 'say "hi"': 1}"#;
+
+        let mut arena = Arena::new(src);
+        assert!(crate::parse(&mut arena).is_err());
+
+        let dialect = Dialect {
+            single_quotes: true,
+            ..Dialect::default()
+        };
+        let mut arena = Arena::new(src);
+        let value = parse_with_dialect(&mut arena, dialect).unwrap();
+        assert_eq!(
+            arena.to_json(&value),
+            r#"{"a":"it's","b":["say \"hi\"","x\ny"],"say \"hi\"":1}"#
+        );
+
+        // spans still point at the original quotes
+        let crate::ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+        let a = arena.get(&object, "a").unwrap();
+        let span = arena.span(a);
+        assert_eq!(&src[span.start as usize..span.end as usize], r"'it\'s'");
+
+        // `\'` is still not valid in double quoted strings
+        let mut arena = Arena::new(r#"{"a\'": 1}"#);
+        assert!(parse_with_dialect(&mut arena, dialect).is_err());
+    }
+
+    #[test]
+    fn unquoted_keys() {
+        let src = r#"{foo: 1, $bar_2: {"baz": true}, foo: null}"#;
+
+        let mut arena = Arena::new(src);
+        assert!(crate::parse(&mut arena).is_err());
+
+        let dialect = Dialect {
+            unquoted_keys: true,
+            ..Dialect::default()
+        };
+        let mut arena = Arena::new(src);
+        let value = parse_with_dialect(&mut arena, dialect).unwrap();
+        assert_eq!(
+            arena.to_json(&value),
+            r#"{"foo":1,"$bar_2":{"baz":true},"foo":null}"#
+        );
+        // bare keys are interned like quoted keys. the nested object is stored first.
+        assert_eq!(arena.keys[1], arena.keys[3]);
+
+        for src in ["[foo]", "{foo: bar}", "{1foo: 1}", "{true: 1}"] {
+            let mut arena = Arena::new(src);
+            assert!(parse_with_dialect(&mut arena, dialect).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    fn radix_numbers() {
+        let src = "[0xFF, -0o17, 0b101, 0x0, 0xffffffffffffffffffffffffffffffffff, 1.5]";
+
+        let mut arena = Arena::new(src);
+        assert!(crate::parse(&mut arena).is_err());
+
+        let dialect = Dialect {
+            radix_numbers: true,
+            ..Dialect::default()
+        };
+        let mut arena = Arena::new(src);
+        let value = parse_with_dialect(&mut arena, dialect).unwrap();
+        assert_eq!(
+            arena.to_json(&value),
+            "[255,-15,5,0,87112285931760246646623899502532662132735,1.5]"
+        );
+
+        let crate::ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+        let values = arena.array_values(&array);
+        assert!(matches!(
+            values[0].kind(),
+            crate::ValueKind::Leaf(LeafValue::RadixInt(Radix::Hex))
+        ));
+        assert_eq!(arena.as_f64(&values[1]), Some(-15.0));
+
+        for src in ["0x", "0o8", "0b2", "0xg"] {
+            let mut arena = Arena::new(src);
+            assert!(parse_with_dialect(&mut arena, dialect).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    fn big_ints() {
+        let src = r#"{"id": 1152921504606846977, "safe": 9007199254740992, "neg": -9007199254740993, "huge": 123456789012345678901234567890, "float": 1152921504606846977.0}"#;
+        let dialect = Dialect {
+            big_ints: true,
+            ..Dialect::default()
+        };
+        let mut arena = Arena::new(src);
+        let value = parse_with_dialect(&mut arena, dialect).unwrap();
+        let crate::ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+
+        let kind = |key| match arena.get(&object, key).unwrap().kind() {
+            crate::ValueKind::Leaf(leaf) => leaf,
+            _ => panic!("expected leaf"),
+        };
+        assert_eq!(kind("id"), LeafValue::BigInt);
+        assert_eq!(kind("safe"), LeafValue::Number);
+        assert_eq!(kind("neg"), LeafValue::BigInt);
+        assert_eq!(kind("huge"), LeafValue::BigInt);
+        assert_eq!(kind("float"), LeafValue::Number);
+
+        let id = arena.get(&object, "id").unwrap();
+        assert_eq!(arena.as_i128(id), Some(1152921504606846977));
+        let huge = arena.get(&object, "huge").unwrap();
+        assert_eq!(arena.as_i128(huge), Some(123456789012345678901234567890));
+        assert_eq!(
+            arena.raw_number(huge),
+            Some("123456789012345678901234567890")
+        );
+
+        // even when other numbers are reformatted, big integers are written as they are.
+        let numbers = crate::NumberFormat {
+            floats: crate::FloatFormat::Shortest,
+            integer_passthrough: false,
+            ..crate::NumberFormat::default()
+        };
+        assert_eq!(
+            arena.to_json_with(&value, numbers),
+            r#"{"id":1152921504606846977,"safe":9007199254740992,"neg":-9007199254740993,"huge":123456789012345678901234567890,"float":1152921504606847000}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-escapes")]
+    fn validate_escapes() {
+        let dialect = Dialect {
+            validate_escapes: true,
+            ..Dialect::default()
+        };
+        let src = r#"{"a\n": ["\ud83e\udd80", "\u00e9\"\/"]}"#;
+        let mut arena = Arena::new(src);
+        let value = parse_with_dialect(&mut arena, dialect).unwrap();
+        assert_eq!(arena.to_json(&value), src.replace(' ', ""));
+
+        for (src, span) in [
+            (r#"["ok", "\ud83e"]"#, 7..10),
+            (r#"["\udd80\ud83e"]"#, 1..4),
+            (r#"["\ud83e\u0041"]"#, 1..4),
+            (r#"["\u12"]"#, 1..4),
+            (r#"["\x"]"#, 1..4),
+            // the span ends after the whole char that was escaped
+            (r#"["\é"]"#, 1..5),
+            (r#"["\日"]"#, 1..6),
+        ] {
+            // the strings are never decoded, so only the lexer can find these.
+            let mut arena = Arena::new(src);
+            assert!(crate::parse(&mut arena).is_ok(), "{src}");
+            let mut arena = Arena::new(src);
+            let err = parse_with_dialect(&mut arena, dialect).unwrap_err();
+            assert_eq!(err.span(), span, "{src}");
+        }
+    }
+
+    #[test]
+    fn require_container() {
+        let dialect = Dialect {
+            require_container: true,
+            ..Dialect::default()
+        };
+        for src in ["{}", r#"[1, "a"]"#, r#" {"a": 1}"#] {
+            let mut arena = Arena::new(src);
+            assert!(parse_with_dialect(&mut arena, dialect).is_ok(), "{src}");
+        }
+        for (src, span) in [(r#""hello""#, 0..7), ("42", 0..2), (" null", 1..5)] {
+            let mut arena = Arena::new(src);
+            assert!(crate::parse(&mut arena).is_ok(), "{src}");
+            let mut arena = Arena::new(src);
+            let err = parse_with_dialect(&mut arena, dialect).unwrap_err();
+            assert_eq!(err.span(), span, "{src}");
+            assert_eq!(
+                std::format!("{err}"),
+                std::format!("expected an object or array at bytes {span:?}")
+            );
+        }
+    }
+}
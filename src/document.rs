@@ -0,0 +1,52 @@
+use alloc::sync::Arc;
+
+use crate::{Arena, Error, Value};
+
+/// A parsed document that owns its source string.
+///
+/// Unlike an [`Arena`] built with [`Arena::new`], this has no lifetime,
+/// so it can be stored, sent across threads, or returned from a request handler.
+pub struct Document {
+    pub(crate) arena: Arena<'static>,
+    pub(crate) root: Value,
+}
+
+impl Document {
+    pub fn parse(src: impl Into<Arc<str>>) -> Result<Self, Error> {
+        let mut arena = Arena::new_shared(src.into());
+        let root = crate::parse(&mut arena)?;
+        Ok(Self { arena, root })
+    }
+
+    pub fn arena(&self) -> &Arena<'static> {
+        &self.arena
+    }
+
+    /// The top-level value of the document.
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+}
+
+impl core::fmt::Debug for Document {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.arena.debug_fmt_value(&self.root, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::Document;
+
+    #[test]
+    fn owned() {
+        let src = String::from(r#"{"a": [1, "b\nc"]}"#);
+        let doc = Document::parse(src).unwrap();
+        assert_eq!(std::format!("{doc:?}"), r#"{"a": [1, "b\nc"]}"#);
+
+        let doc = std::thread::spawn(move || doc).join().unwrap();
+        assert_eq!(std::format!("{doc:?}"), r#"{"a": [1, "b\nc"]}"#);
+    }
+}
@@ -0,0 +1,280 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::ser::write_decimal;
+use crate::{Arena, JsonPath, LeafValue, PathSegment, Value, ValueKind};
+
+/// How loosely [`Arena::eq_semantic`] compares values.
+///
+/// Object key order and number formatting never matter.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EqOptions {
+    /// Arrays are equal if they have the same elements in any order.
+    pub ignore_array_order: bool,
+    /// Numbers are equal if they differ by at most this much.
+    /// With `0.0`, they must be exactly equal, however they are written.
+    pub numeric_tolerance: f64,
+    /// A missing object member is equal to `null`.
+    pub absent_is_null: bool,
+}
+
+/// Where two values first differ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The JSON pointer to the differing value.
+    pub path: String,
+    /// The span of the value in the left document, or `None` if it is absent.
+    pub left: Option<Range<u32>>,
+    /// The span of the value in the right document, or `None` if it is absent.
+    pub right: Option<Range<u32>>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.left, &self.right) {
+            (Some(_), None) => write!(f, "{:?} is missing on the right", self.path),
+            (None, Some(_)) => write!(f, "{:?} is missing on the left", self.path),
+            _ => write!(f, "values differ at {:?}", self.path),
+        }
+    }
+}
+
+impl Arena<'_> {
+    /// Compare `value` with `other_value` from `other`, ignoring key order and number formatting.
+    pub fn eq_semantic(
+        &self,
+        value: &Value,
+        other: &Arena<'_>,
+        other_value: &Value,
+        options: EqOptions,
+    ) -> bool {
+        self.compare_semantic(value, other, other_value, options)
+            .is_ok()
+    }
+
+    /// Like [`Arena::eq_semantic`], but reports where the values first differ.
+    pub fn compare_semantic(
+        &self,
+        value: &Value,
+        other: &Arena<'_>,
+        other_value: &Value,
+        options: EqOptions,
+    ) -> Result<(), Mismatch> {
+        Comparer {
+            left: self,
+            right: other,
+            options,
+            path: Vec::new(),
+        }
+        .compare(value, other_value)
+    }
+}
+
+struct Comparer<'a, 'l, 'r> {
+    left: &'a Arena<'l>,
+    right: &'a Arena<'r>,
+    options: EqOptions,
+    path: Vec<PathSegment<'a>>,
+}
+
+impl<'a> Comparer<'a, '_, '_> {
+    fn mismatch(&self, left: Option<&Value>, right: Option<&Value>) -> Mismatch {
+        Mismatch {
+            path: JsonPath(self.path.clone()).to_string(),
+            left: left.map(|v| self.left.span(v)),
+            right: right.map(|v| self.right.span(v)),
+        }
+    }
+
+    fn compare(&mut self, a: &'a Value, b: &'a Value) -> Result<(), Mismatch> {
+        let (left, right) = (self.left, self.right);
+        let equal = match (a.kind(), b.kind()) {
+            (ValueKind::Leaf(LeafValue::Null), ValueKind::Leaf(LeafValue::Null)) => true,
+            (ValueKind::Leaf(LeafValue::Bool(x)), ValueKind::Leaf(LeafValue::Bool(y))) => x == y,
+            (ValueKind::Leaf(LeafValue::String), ValueKind::Leaf(LeafValue::String)) => {
+                left.as_str(a) == right.as_str(b)
+            }
+            (
+                ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt),
+                ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt),
+            ) => self.numbers_equal(a, b),
+            (ValueKind::Object(x), ValueKind::Object(y)) => {
+                for (key, v) in left.entries(&x) {
+                    let w = right.get(&y, key);
+                    // duplicates are compared by their last value, as with `get`.
+                    let v = left.get(&x, key).unwrap_or(v);
+                    self.path.push(PathSegment::Key(key));
+                    match w {
+                        Some(w) => self.compare(v, w)?,
+                        None if self.options.absent_is_null && is_null(v) => {}
+                        None => return Err(self.mismatch(Some(v), None)),
+                    }
+                    self.path.pop();
+                }
+                for (key, w) in right.entries(&y) {
+                    if left.get(&x, key).is_none() {
+                        self.path.push(PathSegment::Key(key));
+                        if !(self.options.absent_is_null && is_null(w)) {
+                            return Err(self.mismatch(None, Some(w)));
+                        }
+                        self.path.pop();
+                    }
+                }
+                true
+            }
+            (ValueKind::Array(x), ValueKind::Array(y)) => {
+                let (xs, ys) = (left.array_values(&x), right.array_values(&y));
+                if xs.len() != ys.len() {
+                    false
+                } else if self.options.ignore_array_order {
+                    let mut used = vec![false; ys.len()];
+                    for (i, v) in xs.iter().enumerate() {
+                        self.path.push(PathSegment::Index(i));
+                        let depth = self.path.len();
+                        let found = ys.iter().enumerate().position(|(j, w)| {
+                            let found = !used[j] && self.compare(v, w).is_ok();
+                            self.path.truncate(depth);
+                            found
+                        });
+                        match found {
+                            Some(j) => used[j] = true,
+                            None => return Err(self.mismatch(Some(v), None)),
+                        }
+                        self.path.pop();
+                    }
+                    true
+                } else {
+                    for (i, (v, w)) in core::iter::zip(xs, ys).enumerate() {
+                        self.path.push(PathSegment::Index(i));
+                        self.compare(v, w)?;
+                        self.path.pop();
+                    }
+                    true
+                }
+            }
+            _ => false,
+        };
+
+        match equal {
+            true => Ok(()),
+            false => Err(self.mismatch(Some(a), Some(b))),
+        }
+    }
+
+    fn numbers_equal(&self, a: &Value, b: &Value) -> bool {
+        if self.options.numeric_tolerance > 0.0 {
+            return match (self.left.as_f64(a), self.right.as_f64(b)) {
+                (Some(x), Some(y)) => (x - y).abs() <= self.options.numeric_tolerance,
+                _ => false,
+            };
+        }
+        decimal(self.left, a) == decimal(self.right, b)
+    }
+}
+
+fn is_null(value: &Value) -> bool {
+    matches!(value.kind(), ValueKind::Leaf(LeafValue::Null))
+}
+
+/// A number as its sign, significant digits and exponent,
+/// so numbers written differently compare equal.
+pub(crate) fn decimal(arena: &Arena<'_>, value: &Value) -> Option<(bool, String, i64)> {
+    let mut buf = String::new();
+    let raw = match value.kind() {
+        ValueKind::Leaf(LeafValue::RadixInt(radix)) => {
+            write_decimal(arena.raw(value), radix, &mut buf);
+            &*buf
+        }
+        _ => arena.raw(value),
+    };
+
+    let (neg, raw) = match raw.strip_prefix('-') {
+        Some(raw) => (true, raw),
+        None => (false, raw),
+    };
+    let (mantissa, exp) = match raw.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => (mantissa, exp.parse::<i64>().ok()?),
+        None => (raw, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = [int, frac].concat();
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        // negative zero is zero
+        return Some((false, String::new(), 0));
+    }
+    let exp = exp - frac.len() as i64 + (digits.len() - significant.len()) as i64;
+    Some((neg, significant.to_string(), exp))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Dialect, EqOptions, Mismatch};
+
+    fn compare(a: &str, b: &str, options: EqOptions) -> Result<(), Mismatch> {
+        let dialect = Dialect {
+            radix_numbers: true,
+            ..Dialect::default()
+        };
+        let mut left = Arena::new(a);
+        let a = crate::parse_with_dialect(&mut left, dialect).unwrap();
+        let mut right = Arena::new(b);
+        let b = crate::parse_with_dialect(&mut right, dialect).unwrap();
+        left.compare_semantic(&a, &right, &b, options)
+    }
+
+    #[test]
+    fn eq_semantic() {
+        let strict = EqOptions::default();
+        compare(
+            r#"{"a": 1, "b": [1.0, "x"], "c": 0x10}"#,
+            r#"{"c": 16, "b": [10e-1, "x"], "a": 100E-2}"#,
+            strict,
+        )
+        .unwrap();
+        compare("-0.0", "0", strict).unwrap();
+        compare("12345678901234567890", "12345678901234567890.0", strict).unwrap();
+        assert!(compare("12345678901234567890", "12345678901234567891", strict).is_err());
+
+        let err = compare(r#"{"a": {"b": [1, 2]}}"#, r#"{"a": {"b": [1, 3]}}"#, strict);
+        assert_eq!(
+            err,
+            Err(Mismatch {
+                path: "/a/b/1".into(),
+                left: Some(16..17),
+                right: Some(16..17)
+            })
+        );
+        let err = compare(r#"{"a": null}"#, r#"{}"#, strict).unwrap_err();
+        assert_eq!((&*err.path, err.right), ("/a", None));
+        let err = compare(r#"{}"#, r#"{"a/b": 1}"#, strict).unwrap_err();
+        assert_eq!((&*err.path, err.left), ("/a~1b", None));
+
+        let options = EqOptions {
+            absent_is_null: true,
+            ..strict
+        };
+        compare(r#"{"a": null}"#, r#"{"b": null}"#, options).unwrap();
+        assert!(compare(r#"{"a": 1}"#, r#"{}"#, options).is_err());
+
+        let options = EqOptions {
+            ignore_array_order: true,
+            ..strict
+        };
+        compare(r#"[1, [2, 3], 2]"#, r#"[2, [3, 2], 1]"#, options).unwrap();
+        let err = compare(r#"[[1], 2]"#, r#"[2, [3]]"#, options).unwrap_err();
+        assert_eq!(err.path, "/0");
+        assert!(compare("[1, 1]", "[1, 2]", options).is_err());
+
+        let options = EqOptions {
+            numeric_tolerance: 0.01,
+            ..strict
+        };
+        compare("[1.005]", "[1]", options).unwrap();
+        assert!(compare("[1.1]", "[1]", options).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Finding the values that match a [`Query`] in a large document, without parsing it.
+
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use logos::{Lexer, Logos};
+
+use crate::query::{Selector, Step};
+use crate::{JsonPath, LeafValue, PathSegment, Query, Token};
+
+/// A [`Query`] compiled into an automaton over the keys and indices on the way to a value.
+///
+/// Each step of the query is a state, and a descendant step (`..key`) loops back to itself.
+/// Unlike [`Arena::query`](crate::Arena::query), negative indices never match, since the length
+/// of an array is not known until its end, and every duplicate key matches, not only the last.
+#[derive(Debug, Clone)]
+pub struct PathMatcher {
+    steps: Vec<Step>,
+}
+
+impl PathMatcher {
+    pub fn new(query: &Query) -> Self {
+        Self {
+            steps: query.steps.clone(),
+        }
+    }
+
+    /// Append the states reached from the states at `from` in `states` by `segment`.
+    fn transition(&self, states: &mut Vec<usize>, from: Range<usize>, segment: Segment<'_>) {
+        let start = states.len();
+        for i in from {
+            let state = states[i];
+            let Some(step) = self.steps.get(state) else {
+                continue;
+            };
+            let matches = match (&step.selector, segment) {
+                (Selector::Wildcard, _) => true,
+                (Selector::Key(name), Segment::Key(key)) => name == key,
+                (Selector::Index(i), Segment::Index(j)) => usize::try_from(*i) == Ok(j),
+                _ => false,
+            };
+            for (next, reached) in [(state, step.descendant), (state + 1, matches)] {
+                if reached && !states[start..].contains(&next) {
+                    states.push(next);
+                }
+            }
+        }
+    }
+
+    fn accepts(&self, states: &[usize]) -> bool {
+        states.contains(&self.steps.len())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// A value found by [`filter_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterMatch {
+    /// The path to the value, as a JSON pointer.
+    pub pointer: String,
+    /// The span of the value in the source.
+    pub span: Range<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterError {
+    /// The span of the source where it stopped being valid JSON.
+    pub span: Range<u32>,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed JSON at {:?}", self.span)
+    }
+}
+
+impl core::error::Error for FilterError {}
+
+/// Find the values of `src` that `matcher` matches, in document order, reading it one token at a time.
+///
+/// Nothing is parsed into an [`Arena`](crate::Arena), and containers the matcher can no
+/// longer match inside are skipped, so this can filter documents far larger than memory would allow
+/// parsing. The contents of skipped and matched values are only checked for balanced brackets,
+/// and values inside a match are not matched again.
+pub fn filter_events<'s, 'm>(src: &'s str, matcher: &'m PathMatcher) -> FilterEvents<'s, 'm> {
+    FilterEvents {
+        lexer: Token::lexer(src),
+        matcher,
+        states: Vec::new(),
+        stack: Vec::new(),
+        expect: Expect::Value,
+        done: false,
+    }
+}
+
+/// Iterator returned by [`filter_events`].
+pub struct FilterEvents<'s, 'm> {
+    lexer: Lexer<'s, Token>,
+    matcher: &'m PathMatcher,
+    /// the states of every open container, by [`Frame::states`].
+    states: Vec<usize>,
+    stack: Vec<Frame>,
+    expect: Expect,
+    done: bool,
+}
+
+struct Frame {
+    /// the range of `states` that this container is in.
+    states: Range<usize>,
+    object: bool,
+    /// the span of the current key, with its quotes.
+    key: Option<Range<usize>>,
+    index: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    /// after `[`, an element or `]`.
+    ValueOrClose,
+    /// after `{` or `,` in an object, a key, or `}` if `first`.
+    Key {
+        first: bool,
+    },
+    Colon,
+    CommaOrClose,
+    End,
+}
+
+impl<'s> FilterEvents<'s, '_> {
+    fn malformed(&mut self) -> Option<Result<FilterMatch, FilterError>> {
+        self.done = true;
+        let span = self.lexer.span();
+        Some(Err(FilterError {
+            span: span.start as u32..span.end as u32,
+        }))
+    }
+
+    fn after_value(&mut self) {
+        self.expect = match self.stack.is_empty() {
+            true => Expect::End,
+            false => Expect::CommaOrClose,
+        };
+    }
+
+    /// Read past the rest of the container that was just opened, returning where it ends.
+    fn skip(&mut self) -> Option<usize> {
+        let mut depth = 1usize;
+        while depth > 0 {
+            match self.lexer.next()? {
+                Ok(Token::OpenArray | Token::OpenObject) => depth += 1,
+                Ok(Token::CloseArray | Token::CloseObject) => depth -= 1,
+                Ok(_) => {}
+                Err(()) => return None,
+            }
+        }
+        Some(self.lexer.span().end)
+    }
+
+    /// Handle a value that starts with `token`, returning it if it matches.
+    fn value(&mut self, token: Token) -> Option<Result<FilterMatch, FilterError>> {
+        let start = self.states.len();
+        match self.stack.last() {
+            None => self.states.push(0),
+            Some(frame) => {
+                let src = self.lexer.source();
+                let key = frame.key.clone().map(|span| key(&src[span]));
+                let segment = match &key {
+                    Some(key) => Segment::Key(key),
+                    None => Segment::Index(frame.index),
+                };
+                self.matcher
+                    .transition(&mut self.states, frame.states.clone(), segment);
+            }
+        }
+        let states = start..self.states.len();
+
+        let span = self.lexer.span();
+        let container = matches!(token, Token::OpenArray | Token::OpenObject);
+        let matched = self.matcher.accepts(&self.states[states.clone()]);
+        if container && !matched && !states.is_empty() {
+            self.stack.push(Frame {
+                states,
+                object: token == Token::OpenObject,
+                key: None,
+                index: 0,
+            });
+            self.expect = match token {
+                Token::OpenObject => Expect::Key { first: true },
+                _ => Expect::ValueOrClose,
+            };
+            return None;
+        }
+
+        self.states.truncate(start);
+        let end = match container {
+            true => match self.skip() {
+                Some(end) => end,
+                None => return self.malformed(),
+            },
+            false => span.end,
+        };
+        self.after_value();
+        matched.then(|| {
+            Ok(FilterMatch {
+                pointer: self.pointer(),
+                span: span.start as u32..end as u32,
+            })
+        })
+    }
+
+    fn close(&mut self) {
+        if let Some(frame) = self.stack.pop() {
+            self.states.truncate(frame.states.start);
+        }
+        self.after_value();
+    }
+
+    /// The JSON pointer to the value that was just read.
+    fn pointer(&self) -> String {
+        let src = self.lexer.source();
+        let keys: Vec<Option<Cow<'_, str>>> = self
+            .stack
+            .iter()
+            .map(|frame| frame.key.clone().map(|span| key(&src[span])))
+            .collect();
+        let path = core::iter::zip(&self.stack, &keys)
+            .map(|(frame, key)| match key {
+                Some(key) => PathSegment::Key(key),
+                None => PathSegment::Index(frame.index),
+            })
+            .collect();
+        JsonPath(path).to_string()
+    }
+}
+
+/// The contents of a quoted key.
+fn key(quoted: &str) -> Cow<'_, str> {
+    let s = &quoted[1..quoted.len() - 1];
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::new();
+    match crate::unescape_into(s, b'"', &mut out) {
+        Ok(()) => Cow::Owned(out),
+        Err(()) => Cow::Borrowed(s),
+    }
+}
+
+impl Iterator for FilterEvents<'_, '_> {
+    type Item = Result<FilterMatch, FilterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let Some(token) = self.lexer.next() else {
+                if self.expect == Expect::End {
+                    self.done = true;
+                    return None;
+                }
+                return self.malformed();
+            };
+            let Ok(token) = token else {
+                return self.malformed();
+            };
+            let in_object = self.stack.last().map(|frame| frame.object);
+
+            match (self.expect, token) {
+                (
+                    Expect::Value | Expect::ValueOrClose,
+                    token @ (Token::OpenObject | Token::OpenArray | Token::Leaf(_)),
+                ) => {
+                    if let Some(found) = self.value(token) {
+                        return Some(found);
+                    }
+                }
+                (Expect::ValueOrClose, Token::CloseArray) => self.close(),
+                (Expect::Key { .. }, Token::Leaf(LeafValue::String)) => {
+                    let span = self.lexer.span();
+                    if let Some(frame) = self.stack.last_mut() {
+                        frame.key = Some(span);
+                    }
+                    self.expect = Expect::Colon;
+                }
+                (Expect::Key { first: true }, Token::CloseObject) => self.close(),
+                (Expect::Colon, Token::Colon) => self.expect = Expect::Value,
+                (Expect::CommaOrClose, Token::Comma) => {
+                    if let Some(frame) = self.stack.last_mut() {
+                        frame.index += 1;
+                    }
+                    self.expect = match in_object {
+                        Some(true) => Expect::Key { first: false },
+                        _ => Expect::Value,
+                    };
+                }
+                (Expect::CommaOrClose, Token::CloseObject) if in_object == Some(true) => {
+                    self.close()
+                }
+                (Expect::CommaOrClose, Token::CloseArray) if in_object == Some(false) => {
+                    self.close()
+                }
+                _ => return self.malformed(),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    use super::{filter_events, FilterError, PathMatcher};
+    use crate::Query;
+
+    #[test]
+    fn filter_events_matches_query() {
+        let src = r#"{
+            "items": [
+                {"id": 1, "tags": ["a"], "meta": {"id": "x"}},
+                {"id": 2, "tags": []},
+                {"name": "no id"}
+            ],
+            "id": 0,
+            "k\"ey": {"id": 3}
+        }"#;
+        let matches = |query: &str| -> Vec<(_, &str)> {
+            let matcher = PathMatcher::new(&Query::parse(query).unwrap());
+            filter_events(src, &matcher)
+                .map(|m| {
+                    let m = m.unwrap();
+                    (m.pointer, &src[m.span.start as usize..m.span.end as usize])
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            matches("$.items[*].id"),
+            [("/items/0/id".into(), "1"), ("/items/1/id".into(), "2")]
+        );
+        assert_eq!(
+            matches("$..id"),
+            [
+                ("/items/0/id".into(), "1"),
+                ("/items/0/meta/id".into(), r#""x""#),
+                ("/items/1/id".into(), "2"),
+                ("/id".into(), "0"),
+                ("/k\"ey/id".into(), "3"),
+            ]
+        );
+        assert_eq!(
+            matches("$.items[0].tags"),
+            [("/items/0/tags".into(), r#"["a"]"#)]
+        );
+        assert_eq!(matches("$"), [("".into(), src)]);
+
+        // the same matches as the DOM query
+        let mut arena = crate::Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let query = Query::parse("$..tags[*]").unwrap();
+        let dom: Vec<_> = arena
+            .query(&root, &query)
+            .into_iter()
+            .map(|(path, value)| (path.to_string(), arena.raw(value)))
+            .collect();
+        assert_eq!(matches("$..tags[*]"), dom);
+
+        let matcher = PathMatcher::new(&Query::parse("$.a").unwrap());
+        for src in [r#"{"a": 1,}"#, r#"{"a" 1}"#, "[1, 2", "[1] 2"] {
+            let result: Result<Vec<_>, FilterError> = filter_events(src, &matcher).collect();
+            assert!(result.is_err(), "{src}");
+        }
+    }
+}
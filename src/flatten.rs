@@ -0,0 +1,367 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use foldhash::quality::RandomState;
+use hashbrown::HashMap;
+
+use crate::ser::escape;
+use crate::{Arena, Document, LeafValue, PathSegment, Pending, SpliceError, Value, ValueKind};
+
+/// The path from the root of a document to a value.
+///
+/// Displays as a [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901).
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath<'a>(pub Vec<PathSegment<'a>>);
+
+impl fmt::Display for JsonPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.0 {
+            f.write_str("/")?;
+            match segment {
+                PathSegment::Key(key) => {
+                    for (i, part) in key.split('/').enumerate() {
+                        if i > 0 {
+                            f.write_str("~1")?;
+                        }
+                        for (j, part) in part.split('~').enumerate() {
+                            if j > 0 {
+                                f.write_str("~0")?;
+                            }
+                            f.write_str(part)?;
+                        }
+                    }
+                }
+                PathSegment::Index(i) => write!(f, "{i}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The array index that a JSON pointer segment names, if any.
+///
+/// Only digits are allowed, and no leading zeros, so `01` and `+1` are keys.
+pub(crate) fn array_index(segment: &str) -> Option<usize> {
+    let digits = !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+/// Iterator returned by [`Arena::flatten`].
+pub struct Flatten<'a, 's> {
+    arena: &'a Arena<'s>,
+    path: Vec<PathSegment<'a>>,
+    stack: Vec<(Option<Range<u32>>, Range<u32>, usize)>,
+    root: Option<&'a Value>,
+}
+
+impl<'a> Iterator for Flatten<'a, '_> {
+    type Item = (JsonPath<'a>, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let arena = self.arena;
+        let mut value = match self.root.take() {
+            Some(root) => root,
+            None => loop {
+                let (keys, values, index) = self.stack.last_mut()?;
+                let Some(v) = values.next() else {
+                    self.stack.pop();
+                    self.path.pop();
+                    continue;
+                };
+                self.path.push(match keys {
+                    Some(keys) => {
+                        PathSegment::Key(&arena[&arena.keys[keys.start as usize + *index]])
+                    }
+                    None => PathSegment::Index(*index),
+                });
+                *index += 1;
+                break &arena.values[v as usize];
+            },
+        };
+
+        // descend to the first leaf, or empty container.
+        loop {
+            let (keys, values) = match value.kind() {
+                ValueKind::Object(object) if !object.values().is_empty() => {
+                    (Some(object.keys(&arena.values)), object.values())
+                }
+                ValueKind::Array(array) if !array.values().is_empty() => (None, array.values()),
+                _ => break,
+            };
+            self.path.push(match &keys {
+                Some(keys) => PathSegment::Key(&arena[&arena.keys[keys.start as usize]]),
+                None => PathSegment::Index(0),
+            });
+            value = &arena.values[values.start as usize];
+            self.stack.push((keys, values.start + 1..values.end, 1));
+        }
+
+        let path = JsonPath(self.path.clone());
+        if self.stack.is_empty() {
+            self.path.clear();
+        } else {
+            self.path.pop();
+        }
+        Some((path, value))
+    }
+}
+
+impl<'s> Arena<'s> {
+    /// Iterate over every leaf below `value` along with its path, in document order.
+    ///
+    /// Empty objects and arrays are included as leaves, so the document can be
+    /// rebuilt with [`Unflatten`].
+    pub fn flatten<'a>(&'a self, value: &'a Value) -> Flatten<'a, 's> {
+        Flatten {
+            arena: self,
+            path: Vec::new(),
+            stack: Vec::new(),
+            root: Some(value),
+        }
+    }
+}
+
+/// Builds a document from values and the paths to them, the inverse of [`Arena::flatten`].
+///
+/// An object whose keys are exactly `0` to `n - 1` becomes an array.
+pub struct Unflatten {
+    /// The inserted values, copied out of the arenas they came from.
+    arena: Arena<'static>,
+    root: Node,
+}
+
+enum Node {
+    Missing,
+    Leaf(Value),
+    /// The children in insertion order, and the index of each by key.
+    Object(Vec<Node>, HashMap<String, usize, RandomState>),
+}
+
+impl Default for Unflatten {
+    fn default() -> Self {
+        Self {
+            arena: Arena::new_shared(Arc::from("")),
+            root: Node::Missing,
+        }
+    }
+}
+
+impl Unflatten {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value at the [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) `pointer`
+    /// to a copy of `value`.
+    ///
+    /// Any value already at `pointer` is replaced, as are leaves along the way.
+    pub fn insert(
+        &mut self,
+        pointer: &str,
+        arena: &Arena<'_>,
+        value: &Value,
+    ) -> Result<(), SpliceError> {
+        let segments = match pointer {
+            "" => None,
+            _ => Some(
+                pointer
+                    .strip_prefix('/')
+                    .ok_or(SpliceError::InvalidPointer)?,
+            ),
+        };
+
+        let mut node = &mut self.root;
+        for segment in segments.into_iter().flat_map(|s| s.split('/')) {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            if !matches!(node, Node::Object(..)) {
+                *node = Node::Object(Vec::new(), HashMap::default());
+            }
+            let Node::Object(children, index) = node else {
+                unreachable!()
+            };
+            let i = *index.entry(segment).or_insert_with(|| {
+                children.push(Node::Missing);
+                children.len() - 1
+            });
+            node = &mut children[i];
+        }
+        *node = Node::Leaf(arena.deep_copy_into(value, &mut self.arena));
+        Ok(())
+    }
+
+    /// Build the document. With nothing inserted, it is `null`.
+    pub fn finish(self) -> Document {
+        let mut arena = Arena::new_shared(Arc::from(""));
+        let Pending { span, kind } = self.build(&self.root, &mut arena);
+        let root = arena.root(kind, span);
+        Document { arena, root }
+    }
+
+    /// Copy `node` into `dest`, in document order.
+    ///
+    /// The text of each container is written around the text of its children,
+    /// so that their spans nest as they would in a parsed document.
+    fn build(&self, node: &Node, dest: &mut Arena<'static>) -> Pending {
+        let (children, index) = match node {
+            Node::Missing => {
+                let start = dest.scratch.text.len() as u32;
+                dest.scratch.text.push_str("null");
+                return Pending {
+                    span: start + 4..start,
+                    kind: ValueKind::Leaf(LeafValue::Null),
+                };
+            }
+            Node::Leaf(value) => {
+                let value = self.arena.deep_copy_into(value, dest);
+                return Pending {
+                    span: dest.span(&value),
+                    kind: value.kind(),
+                };
+            }
+            Node::Object(children, index) => (children, index),
+        };
+
+        let mut keys = vec![""; children.len()];
+        for (key, &i) in index {
+            keys[i] = key;
+        }
+        // the children by array index, if the keys are exactly `0` to `n - 1`.
+        let mut order = vec![usize::MAX; keys.len()];
+        let array = keys.iter().enumerate().all(|(i, key)| {
+            match array_index(key).and_then(|n| order.get_mut(n)) {
+                Some(slot) if *slot == usize::MAX => {
+                    *slot = i;
+                    true
+                }
+                _ => false,
+            }
+        });
+        if !array {
+            order = (0..keys.len()).collect();
+        }
+
+        let start = dest.scratch.text.len() as u32;
+        dest.scratch.text.push(if array { '[' } else { '{' });
+        let mut values = Vec::with_capacity(order.len());
+        let mut object_keys = Vec::new();
+        for (n, &i) in order.iter().enumerate() {
+            if n > 0 {
+                dest.scratch.text.push(',');
+            }
+            if !array {
+                let text = &mut dest.scratch.text;
+                text.push('"');
+                escape(keys[i], text);
+                text.push_str("\":");
+                object_keys.push(dest.intern_copy(keys[i]));
+            }
+            values.push(self.build(&children[i], dest));
+        }
+        dest.scratch.text.push(if array { ']' } else { '}' });
+        let span = dest.scratch.text.len() as u32..start;
+
+        let vi = dest.push_children(span.clone(), dest.keys.len() as u32, values.into_iter());
+        let vj = dest.values.len() as u32;
+        let kind = if array {
+            ValueKind::Array(crate::Array::new(vi..vj))
+        } else {
+            dest.keys.extend(object_keys);
+            ValueKind::Object(crate::Object::new(vi..vj))
+        };
+        Pending { span, kind }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    use crate::{Arena, Unflatten};
+
+    #[test]
+    fn round_trip() {
+        let src = r#"{"a": {"b/c": [1, {"d~": null}], "e": {}}, "f": [], "g": "h"}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+
+        let flat: Vec<_> = arena
+            .flatten(&root)
+            .map(|(path, value)| (path.to_string(), arena.to_json(value)))
+            .collect();
+        let expected = [
+            ("/a/b~1c/0", "1"),
+            ("/a/b~1c/1/d~0", "null"),
+            ("/a/e", "{}"),
+            ("/f", "[]"),
+            ("/g", r#""h""#),
+        ];
+        assert_eq!(flat.len(), expected.len());
+        for ((path, json), (p, j)) in core::iter::zip(&flat, expected) {
+            assert_eq!((&**path, &**json), (p, j));
+        }
+
+        let mut unflatten = Unflatten::new();
+        for (path, value) in arena.flatten(&root) {
+            unflatten.insert(&path.to_string(), &arena, value).unwrap();
+        }
+        let doc = unflatten.finish();
+        assert_eq!(doc.arena().to_json(doc.root()), arena.to_json(&root));
+
+        let mut arena = Arena::new("1");
+        let root = crate::parse(&mut arena).unwrap();
+        let flat: Vec<_> = arena.flatten(&root).collect();
+        assert_eq!(flat.len(), 1);
+        assert_eq!(flat[0].0.to_string(), "");
+    }
+
+    #[test]
+    fn unflatten() {
+        let mut arena = Arena::new(r#"[1, "x"]"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let crate::ValueKind::Array(array) = root.kind() else {
+            panic!("expected array")
+        };
+        let [one, x] = arena.array_values(&array) else {
+            panic!("expected two values")
+        };
+
+        let mut unflatten = Unflatten::new();
+        unflatten.insert("/list/1", &arena, x).unwrap();
+        unflatten.insert("/list/0", &arena, one).unwrap();
+        unflatten.insert("/map/1", &arena, one).unwrap();
+        unflatten.insert("/leaf", &arena, one).unwrap();
+        unflatten.insert("/leaf/nested", &arena, x).unwrap();
+        assert!(unflatten.insert("list", &arena, x).is_err());
+
+        let doc = unflatten.finish();
+        assert_eq!(
+            doc.arena().to_json(doc.root()),
+            r#"{"list":[1,"x"],"map":{"1":1},"leaf":{"nested":"x"}}"#
+        );
+        // the text of the built containers is valid, so it can be copied again
+        let mut copy = Arena::new("");
+        let value = doc.arena().deep_copy_into(doc.root(), &mut copy);
+        assert_eq!(copy.to_json(&value), doc.arena().to_json(doc.root()));
+
+        let doc = Unflatten::new().finish();
+        assert_eq!(doc.arena().to_json(doc.root()), "null");
+
+        // only canonical indices make an array
+        for key in ["+0", "00", ""] {
+            let mut unflatten = Unflatten::new();
+            unflatten
+                .insert(&alloc::format!("/{key}"), &arena, one)
+                .unwrap();
+            let doc = unflatten.finish();
+            let expected = alloc::format!(r#"{{"{key}":1}}"#);
+            assert_eq!(doc.arena().to_json(doc.root()), expected);
+        }
+    }
+}
@@ -1,36 +1,106 @@
+use alloc::string::String;
 use core::fmt::{self, Debug};
 
-use crate::{Arena, LeafValue, Value, ValueKind};
+use crate::ser::Serializer;
+use crate::{Arena, ContextItem, Error, LeafValue, Token, Value, ValueKind};
 
-impl Arena<'_> {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token {
+            None if self.span.is_empty() => {
+                write!(f, "unexpected end of input at byte {}", self.span.start)
+            }
+            None => write!(f, "invalid token at bytes {:?}", self.span),
+            // only with `Dialect::require_container`
+            Some(Token::Leaf(_))
+                if self.depth == 0 && matches!(self.context, ContextItem::WaitingValue) =>
+            {
+                write!(f, "expected an object or array at bytes {:?}", self.span)
+            }
+            Some(_) => write!(f, "unexpected token at bytes {:?}", self.span),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+impl<'s> Arena<'s> {
     pub fn debug_fmt_value(&self, value: &Value, f: &mut fmt::Formatter) -> fmt::Result {
         FmtValue { arena: self, value }.fmt(f)
     }
+
+    /// Pair `value` with this arena, so it can be formatted on its own.
+    pub fn value_ref<'a>(&'a self, value: &'a Value) -> ValueRef<'a, 's> {
+        ValueRef { arena: self, value }
+    }
 }
 
-struct FmtValue<'a, 's, 'v> {
+/// A value along with the arena it belongs to.
+///
+/// [`Display`](fmt::Display) writes compact JSON, so it can go straight into
+/// `format!` or a log field. [`Debug`] is the same as [`Arena::debug_fmt_value`].
+#[derive(Clone, Copy)]
+pub struct ValueRef<'a, 's> {
     arena: &'a Arena<'s>,
-    value: &'v Value,
+    value: &'a Value,
+}
+
+impl<'a, 's> ValueRef<'a, 's> {
+    pub fn arena(&self) -> &'a Arena<'s> {
+        self.arena
+    }
+
+    pub fn value(&self) -> &'a Value {
+        self.value
+    }
+}
+
+impl fmt::Display for ValueRef<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // written in chunks, so large values don't need one big buffer.
+        const CHUNK: usize = 4096;
+        let mut serializer = Serializer::new();
+        let mut buf = String::new();
+        loop {
+            let done = serializer.write(self.arena, self.value, &mut buf, CHUNK);
+            f.write_str(&buf)?;
+            if done {
+                return Ok(());
+            }
+            buf.clear();
+        }
+    }
+}
+
+impl fmt::Debug for ValueRef<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.arena.debug_fmt_value(self.value, f)
+    }
+}
+
+pub(crate) struct FmtValue<'a, 's, 'v> {
+    pub(crate) arena: &'a Arena<'s>,
+    pub(crate) value: &'v Value,
 }
 
 impl fmt::Debug for FmtValue<'_, '_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.value.kind {
+        match self.value.kind() {
             ValueKind::Leaf(leaf_value) => match leaf_value {
                 LeafValue::Bool(true) => f.write_str("true"),
                 LeafValue::Bool(false) => f.write_str("false"),
                 LeafValue::Null => f.write_str("null"),
-                LeafValue::String | LeafValue::Number => f.write_str(
-                    &self.arena.scratch.src
-                        [self.value.span.start as usize..self.value.span.end as usize],
-                ),
+                LeafValue::String
+                | LeafValue::Number
+                | LeafValue::RadixInt(_)
+                | LeafValue::BigInt => f.write_str(self.arena.raw(self.value)),
             },
             ValueKind::Object(object) => {
                 let mut f = f.debug_map();
 
-                let keys = &self.arena.keys[object.keys.start as usize..object.keys.end as usize];
-                let values =
-                    &self.arena.values[object.values.start as usize..object.values.end as usize];
+                let keys = self.arena.keys_of(&object);
+                let values = &self.arena.values
+                    [object.values().start as usize..object.values().end as usize];
                 for (k, v) in core::iter::zip(keys, values) {
                     let k = &self.arena[k];
                     f.entry(
@@ -48,7 +118,7 @@ impl fmt::Debug for FmtValue<'_, '_, '_> {
                 let mut f = f.debug_list();
 
                 let values =
-                    &self.arena.values[array.values.start as usize..array.values.end as usize];
+                    &self.arena.values[array.values().start as usize..array.values().end as usize];
                 for v in values {
                     f.entry(&FmtValue {
                         arena: self.arena,
@@ -66,6 +136,21 @@ impl fmt::Debug for FmtValue<'_, '_, '_> {
 mod tests {
     use crate::{fmt::FmtValue, Arena};
 
+    #[test]
+    fn display() {
+        let mut arena = Arena::new(r#"{"a": [1, "b\nc"], "d": {}}"#);
+        let value = crate::parse(&mut arena).unwrap();
+        let value = arena.value_ref(&value);
+        assert_eq!(std::format!("{value}"), r#"{"a":[1,"b\nc"],"d":{}}"#);
+        assert_eq!(std::format!("{value:?}"), r#"{"a": [1, "b\nc"], "d": {}}"#);
+
+        let big = std::format!("[{}1]", "1, ".repeat(5000));
+        let mut arena = Arena::new(&big);
+        let value = crate::parse(&mut arena).unwrap();
+        let json = std::format!("{}", arena.value_ref(&value));
+        assert_eq!(json, arena.to_json(&value));
+    }
+
     #[test]
     fn snapshot() {
         let data = r#"{
@@ -0,0 +1,348 @@
+//! Structural validation for [GeoJSON](https://datatracker.ietf.org/doc/html/rfc7946) documents.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoJsonError {
+    /// The span of the offending value.
+    pub span: Range<u32>,
+    pub kind: GeoJsonErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoJsonErrorKind {
+    ExpectedObject,
+    ExpectedArray,
+    MissingMember(&'static str),
+    /// The `type` member is not a known GeoJSON type.
+    UnknownType,
+    /// The `type` member is valid, but not allowed in this position,
+    /// eg a `Point` inside a `FeatureCollection`.
+    UnexpectedType {
+        expected: &'static str,
+    },
+    /// A position must be an array of at least two numbers.
+    InvalidPosition,
+    TooFewPositions {
+        min: usize,
+    },
+    /// The first and last positions of a linear ring must be equal.
+    UnclosedRing,
+    /// A bounding box must be an array of 2*n numbers.
+    InvalidBbox,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    Any,
+    Geometry,
+    Feature,
+}
+
+/// Validate that `value` is a GeoJSON object, returning all the errors found.
+pub fn validate(arena: &Arena<'_>, value: &Value) -> Result<(), Vec<GeoJsonError>> {
+    let mut v = Validator {
+        arena,
+        errors: Vec::new(),
+    };
+
+    // geometry collections can nest, so use a stack rather than recursion.
+    let mut stack = vec![(value, Expect::Any)];
+    while let Some((value, expect)) = stack.pop() {
+        let ValueKind::Object(object) = value.kind() else {
+            v.error(value, GeoJsonErrorKind::ExpectedObject);
+            continue;
+        };
+
+        let Some(ty) = v.member(value, "type") else {
+            continue;
+        };
+        let Some(ty) = arena.as_str(ty) else {
+            v.error(ty, GeoJsonErrorKind::UnknownType);
+            continue;
+        };
+
+        if let Some(bbox) = arena.get(&object, "bbox") {
+            v.bbox(bbox);
+        }
+
+        let geometry = matches!(
+            &*ty,
+            "Point"
+                | "MultiPoint"
+                | "LineString"
+                | "MultiLineString"
+                | "Polygon"
+                | "MultiPolygon"
+                | "GeometryCollection"
+        );
+        match (&*ty, expect) {
+            ("Feature", Expect::Any | Expect::Feature) => {
+                if let Some(geometry) = v.member(value, "geometry") {
+                    if !matches!(geometry.kind(), ValueKind::Leaf(LeafValue::Null)) {
+                        stack.push((geometry, Expect::Geometry));
+                    }
+                }
+                if let Some(properties) = v.member(value, "properties") {
+                    if !matches!(
+                        properties.kind(),
+                        ValueKind::Object(_) | ValueKind::Leaf(LeafValue::Null)
+                    ) {
+                        v.error(properties, GeoJsonErrorKind::ExpectedObject);
+                    }
+                }
+            }
+            ("FeatureCollection", Expect::Any) => {
+                if let Some(features) = v.member(value, "features").and_then(|f| v.array(f)) {
+                    stack.extend(features.iter().rev().map(|f| (f, Expect::Feature)));
+                }
+            }
+            ("GeometryCollection", Expect::Any | Expect::Geometry) => {
+                if let Some(geometries) = v.member(value, "geometries").and_then(|g| v.array(g)) {
+                    stack.extend(geometries.iter().rev().map(|g| (g, Expect::Geometry)));
+                }
+            }
+            (ty, Expect::Any | Expect::Geometry) if geometry => {
+                if let Some(coordinates) = v.member(value, "coordinates") {
+                    v.coordinates(ty, coordinates);
+                }
+            }
+            (_, Expect::Feature) if geometry || &*ty == "FeatureCollection" => {
+                let ty = arena.get(&object, "type").unwrap();
+                let kind = GeoJsonErrorKind::UnexpectedType {
+                    expected: "Feature",
+                };
+                v.error(ty, kind);
+            }
+            (_, Expect::Geometry) if &*ty == "Feature" || &*ty == "FeatureCollection" => {
+                let ty = arena.get(&object, "type").unwrap();
+                let kind = GeoJsonErrorKind::UnexpectedType {
+                    expected: "a geometry",
+                };
+                v.error(ty, kind);
+            }
+            _ => {
+                let ty = arena.get(&object, "type").unwrap();
+                v.error(ty, GeoJsonErrorKind::UnknownType);
+            }
+        }
+    }
+
+    if v.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(v.errors)
+    }
+}
+
+struct Validator<'a, 's> {
+    arena: &'a Arena<'s>,
+    errors: Vec<GeoJsonError>,
+}
+
+impl<'a> Validator<'a, '_> {
+    fn error(&mut self, value: &Value, kind: GeoJsonErrorKind) {
+        self.errors.push(GeoJsonError {
+            span: self.arena.span(value),
+            kind,
+        });
+    }
+
+    /// Get a required member of an object.
+    fn member(&mut self, value: &'a Value, key: &'static str) -> Option<&'a Value> {
+        let ValueKind::Object(object) = value.kind() else {
+            unreachable!("members are only looked up on objects")
+        };
+        let member = self.arena.get(&object, key);
+        if member.is_none() {
+            self.error(value, GeoJsonErrorKind::MissingMember(key));
+        }
+        member
+    }
+
+    fn array(&mut self, value: &'a Value) -> Option<&'a [Value]> {
+        match value.kind() {
+            ValueKind::Array(array) => Some(self.arena.array_values(&array)),
+            _ => {
+                self.error(value, GeoJsonErrorKind::ExpectedArray);
+                None
+            }
+        }
+    }
+
+    fn bbox(&mut self, value: &Value) {
+        let valid = match value.kind() {
+            ValueKind::Array(array) => {
+                let values = self.arena.array_values(&array);
+                values.len() >= 4
+                    && values.len().is_multiple_of(2)
+                    && values.iter().all(|v| self.arena.as_f64(v).is_some())
+            }
+            _ => false,
+        };
+        if !valid {
+            self.error(value, GeoJsonErrorKind::InvalidBbox);
+        }
+    }
+
+    fn coordinates(&mut self, ty: &str, value: &'a Value) {
+        match ty {
+            "Point" => {
+                self.position(value);
+            }
+            "MultiPoint" => self.positions(value, 0),
+            "LineString" => self.positions(value, 2),
+            "MultiLineString" => {
+                for line in self.array(value).unwrap_or_default() {
+                    self.positions(line, 2);
+                }
+            }
+            "Polygon" => self.polygon(value),
+            "MultiPolygon" => {
+                for polygon in self.array(value).unwrap_or_default() {
+                    self.polygon(polygon);
+                }
+            }
+            _ => unreachable!("not a geometry with coordinates"),
+        }
+    }
+
+    /// Returns the first two coordinates of a valid position.
+    fn position(&mut self, value: &Value) -> Option<[f64; 2]> {
+        if let ValueKind::Array(array) = value.kind() {
+            let values = self.arena.array_values(&array);
+            let mut coords = values.iter().map(|v| self.arena.as_f64(v));
+            if let (Some(Some(x)), Some(Some(y))) = (coords.next(), coords.next()) {
+                if coords.all(|c| c.is_some()) {
+                    return Some([x, y]);
+                }
+            }
+        }
+        self.error(value, GeoJsonErrorKind::InvalidPosition);
+        None
+    }
+
+    fn positions(&mut self, value: &'a Value, min: usize) {
+        let Some(positions) = self.array(value) else {
+            return;
+        };
+        if positions.len() < min {
+            self.error(value, GeoJsonErrorKind::TooFewPositions { min });
+        }
+        for position in positions {
+            self.position(position);
+        }
+    }
+
+    fn polygon(&mut self, value: &'a Value) {
+        for ring in self.array(value).unwrap_or_default() {
+            self.positions(ring, 4);
+
+            let ValueKind::Array(array) = ring.kind() else {
+                continue;
+            };
+            if let [first, .., last] = self.arena.array_values(&array) {
+                let first = self.arena.array_values_f64(first);
+                let last = self.arena.array_values_f64(last);
+                if first != last {
+                    self.error(ring, GeoJsonErrorKind::UnclosedRing);
+                }
+            }
+        }
+    }
+}
+
+impl Arena<'_> {
+    fn array_values_f64(&self, value: &Value) -> Option<Vec<f64>> {
+        let ValueKind::Array(array) = value.kind() else {
+            return None;
+        };
+        self.array_values(&array)
+            .iter()
+            .map(|v| self.as_f64(v))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{validate, GeoJsonError, GeoJsonErrorKind};
+    use crate::Arena;
+
+    fn errors(src: &str) -> Vec<GeoJsonError> {
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        validate(&arena, &value).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn valid() {
+        let src = r#"{
+            "type": "FeatureCollection",
+            "bbox": [0, 0, 10, 10],
+            "features": [
+                {"type": "Feature", "geometry": null, "properties": null},
+                {"type": "Feature", "properties": {"name": "x"}, "geometry": {
+                    "type": "GeometryCollection",
+                    "geometries": [
+                        {"type": "Point", "coordinates": [1.5, 2, 3]},
+                        {"type": "LineString", "coordinates": [[0, 0], [1, 1]]},
+                        {"type": "MultiPolygon", "coordinates": [
+                            [[[0, 0], [1, 0], [1, 1], [0, 0]]]
+                        ]}
+                    ]
+                }}
+            ]
+        }"#;
+        assert_eq!(errors(src), []);
+    }
+
+    #[test]
+    fn invalid() {
+        let src = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Point", "coordinates": [1, 2]},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, "1"]]}},
+                {"type": "Feature", "properties": [], "geometry": {
+                    "type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1]]]
+                }}
+            ]
+        }"#;
+
+        let kinds: Vec<_> = errors(src)
+            .into_iter()
+            .map(|e| (&src[e.span.start as usize..e.span.end as usize], e.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    r#""Point""#,
+                    GeoJsonErrorKind::UnexpectedType {
+                        expected: "Feature"
+                    }
+                ),
+                (
+                    r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, "1"]]}}"#,
+                    GeoJsonErrorKind::MissingMember("properties")
+                ),
+                (
+                    r#"[[0, "1"]]"#,
+                    GeoJsonErrorKind::TooFewPositions { min: 2 }
+                ),
+                (r#"[0, "1"]"#, GeoJsonErrorKind::InvalidPosition),
+                ("[]", GeoJsonErrorKind::ExpectedObject),
+                (
+                    "[[0, 0], [1, 0], [1, 1], [0, 1]]",
+                    GeoJsonErrorKind::UnclosedRing
+                ),
+            ]
+        );
+    }
+}
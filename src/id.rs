@@ -0,0 +1,141 @@
+//! Handles to the values and keys of an arena, by index.
+
+use core::ops::Range;
+
+use crate::{Arena, Array, Object, Value};
+
+/// A value in an [`Arena`], by its index.
+///
+/// An id is only meaningful to the arena that gave it out, and only until that arena is
+/// cleared or compacted. Debug builds check both, rather than silently reading another value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValueId {
+    index: u32,
+    #[cfg(debug_assertions)]
+    brand: u32,
+}
+
+/// A key of an object in an [`Arena`], by its index. Checked like [`ValueId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyId {
+    index: u32,
+    #[cfg(debug_assertions)]
+    brand: u32,
+}
+
+/// A new brand for an arena, so that ids given out before do not match it.
+#[cfg(debug_assertions)]
+pub(crate) fn next_brand() -> u32 {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+impl Arena<'_> {
+    fn value_id(&self, index: u32) -> ValueId {
+        ValueId {
+            index,
+            #[cfg(debug_assertions)]
+            brand: self.brand,
+        }
+    }
+
+    fn key_id(&self, index: u32) -> KeyId {
+        KeyId {
+            index,
+            #[cfg(debug_assertions)]
+            brand: self.brand,
+        }
+    }
+
+    /// The ids of the values of `array`.
+    pub fn array_ids(&self, array: &Array) -> impl ExactSizeIterator<Item = ValueId> + '_ {
+        array.values().map(|i| self.value_id(i))
+    }
+
+    /// The ids of the keys and values of `object`, in source order.
+    pub fn object_ids(
+        &self,
+        object: &Object,
+    ) -> impl ExactSizeIterator<Item = (KeyId, ValueId)> + '_ {
+        core::iter::zip(object.keys(&self.values), object.values())
+            .map(|(k, v)| (self.key_id(k), self.value_id(v)))
+    }
+
+    /// The value with the id `id`.
+    pub fn value(&self, id: ValueId) -> &Value {
+        #[cfg(debug_assertions)]
+        assert_eq!(id.brand, self.brand, "the value id is from another arena");
+        &self.values[id.index as usize]
+    }
+
+    /// The span of the value with the id `id`, where it is in its container.
+    ///
+    /// Unlike [`Arena::span`], this is the span of this one place even when a
+    /// container is stored once for many, as after [`Arena::dedup`].
+    pub fn span_of(&self, id: ValueId) -> Range<u32> {
+        #[cfg(debug_assertions)]
+        assert_eq!(id.brand, self.brand, "the value id is from another arena");
+        self.spans[id.index as usize].clone()
+    }
+
+    /// The key with the id `id`.
+    pub fn key(&self, id: KeyId) -> &str {
+        #[cfg(debug_assertions)]
+        assert_eq!(id.brand, self.brand, "the key id is from another arena");
+        &self[&self.keys[id.index as usize]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{Arena, ValueKind};
+
+    #[test]
+    fn ids() {
+        let mut arena = Arena::new(r#"{"a": [1, 2], "b": true}"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let ValueKind::Object(object) = root.kind() else {
+            panic!("expected an object")
+        };
+
+        let entries: Vec<_> = arena.object_ids(&object).collect();
+        let keys: Vec<_> = entries.iter().map(|(k, _)| arena.key(*k)).collect();
+        assert_eq!(keys, ["a", "b"]);
+        let ValueKind::Array(array) = &arena.value(entries[0].1).kind() else {
+            panic!("expected an array")
+        };
+        let values: Vec<_> = arena
+            .array_ids(array)
+            .map(|id| arena.to_json(arena.value(id)))
+            .collect();
+        assert_eq!(values, ["1", "2"]);
+
+        let spans: Vec<_> = arena.array_ids(array).map(|id| arena.span_of(id)).collect();
+        assert_eq!(spans, [7..8, 10..11]);
+        assert_eq!(arena.span_of(entries[0].1), 6..12);
+        assert_eq!(
+            arena.span_of(entries[1].1),
+            arena.span(arena.value(entries[1].1))
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic = "from another arena"]
+    fn other_arena() {
+        let mut arena = Arena::new("[1]");
+        let root = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = root.kind() else {
+            panic!("expected an array")
+        };
+        let id = arena.array_ids(&array).next().unwrap();
+
+        let mut other = Arena::new("[2]");
+        crate::parse(&mut other).unwrap();
+        other.value(id);
+    }
+}
@@ -0,0 +1,193 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+/// Looks up the variables of [`Arena::interpolate`].
+///
+/// Implemented for closures returning the value, or `None` if the variable is not set,
+/// and for [`EnvVars`].
+pub trait Variables {
+    fn get(&mut self, name: &str) -> Option<String>;
+}
+
+impl<F> Variables for F
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn get(&mut self, name: &str) -> Option<String> {
+        self(name)
+    }
+}
+
+/// The environment variables of the process.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct EnvVars;
+
+#[cfg(feature = "std")]
+impl Variables for EnvVars {
+    fn get(&mut self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpolateError {
+    /// The span of the string with the offending `${`.
+    pub span: Range<u32>,
+    pub kind: InterpolateErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolateErrorKind {
+    /// The variable with this name is not set.
+    Missing(String),
+    /// A `${` has no closing `}`.
+    Unterminated,
+}
+
+impl fmt::Display for InterpolateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            InterpolateErrorKind::Missing(name) => {
+                write!(
+                    f,
+                    "variable {name} is not set, in string at {:?}",
+                    self.span
+                )
+            }
+            InterpolateErrorKind::Unterminated => {
+                write!(f, "unterminated `${{` in string at {:?}", self.span)
+            }
+        }
+    }
+}
+
+impl core::error::Error for InterpolateError {}
+
+impl Arena<'_> {
+    /// Replace every `${NAME}` in the strings of `value` with the variable `NAME`, in place.
+    ///
+    /// `$${` is a literal `${`, and a `$` that is not followed by `{` is left as is.
+    /// If any variable is missing, nothing is replaced.
+    pub fn interpolate(
+        &mut self,
+        value: &mut Value,
+        mut vars: impl Variables,
+    ) -> Result<(), InterpolateError> {
+        // the strings to replace, by the slot of their span.
+        let mut replaced = Vec::new();
+        let mut stack = vec![&*value];
+        while let Some(value) = stack.pop() {
+            let children = match value.kind() {
+                ValueKind::Leaf(LeafValue::String) => {
+                    let s = self.as_str(value).unwrap_or_default();
+                    if let Some(s) = expand(&s, &mut vars).map_err(|kind| InterpolateError {
+                        span: self.span(value),
+                        kind,
+                    })? {
+                        replaced.push((value.slot(), s));
+                    }
+                    continue;
+                }
+                ValueKind::Leaf(_) => continue,
+                ValueKind::Object(object) => object.values(),
+                ValueKind::Array(array) => array.values(),
+            };
+            stack.extend(&self.values[children.start as usize..children.end as usize]);
+        }
+
+        for (slot, s) in replaced {
+            // like values copied from another arena, the new string is stored in `text`
+            // and its span is reversed.
+            let start = self.scratch.text.len() as u32;
+            self.scratch.text.push('"');
+            crate::ser::escape(&s, &mut self.scratch.text);
+            self.scratch.text.push('"');
+            let span = self.scratch.text.len() as u32..start;
+            self.spans[slot as usize] = span;
+        }
+        Ok(())
+    }
+}
+
+/// `s` with its variables replaced, or `None` if it has none.
+fn expand(s: &str, vars: &mut impl Variables) -> Result<Option<String>, InterpolateErrorKind> {
+    if !s.contains("${") {
+        return Ok(None);
+    }
+
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find("${") {
+        if rest[..i].ends_with('$') {
+            // `$${` is an escaped `${`
+            out.push_str(&rest[..i - 1]);
+            out.push_str("${");
+            rest = &rest[i + 2..];
+            continue;
+        }
+        out.push_str(&rest[..i]);
+        let (name, after) = rest[i + 2..]
+            .split_once('}')
+            .ok_or(InterpolateErrorKind::Unterminated)?;
+        let value = vars
+            .get(name)
+            .ok_or_else(|| InterpolateErrorKind::Missing(name.into()))?;
+        out.push_str(&value);
+        rest = after;
+    }
+    out.push_str(rest);
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::{Arena, InterpolateError, InterpolateErrorKind};
+
+    #[test]
+    fn interpolate() {
+        let vars = |name: &str| match name {
+            "HOST" => Some(String::from("db.local")),
+            "PASSWORD" => Some(String::from("p\"w")),
+            _ => None,
+        };
+
+        let src = r#"{"url": "postgres://${HOST}:5432", "password": "${PASSWORD}", "price": "$${5} or $5", "n": 1}"#;
+        let mut arena = Arena::new(src);
+        let mut root = crate::parse(&mut arena).unwrap();
+        arena.interpolate(&mut root, vars).unwrap();
+        assert_eq!(
+            arena.to_json(&root),
+            r#"{"url":"postgres://db.local:5432","password":"p\"w","price":"${5} or $5","n":1}"#
+        );
+
+        let mut arena = Arena::new(r#""${HOST}""#);
+        let mut root = crate::parse(&mut arena).unwrap();
+        arena.interpolate(&mut root, vars).unwrap();
+        assert_eq!(arena.to_json(&root), r#""db.local""#);
+
+        let src = r#"{"a": "${HOST}", "b": ["${USER}"]}"#;
+        let mut arena = Arena::new(src);
+        let mut root = crate::parse(&mut arena).unwrap();
+        assert_eq!(
+            arena.interpolate(&mut root, vars),
+            Err(InterpolateError {
+                span: 23..32,
+                kind: InterpolateErrorKind::Missing(String::from("USER")),
+            })
+        );
+        // nothing was replaced
+        assert_eq!(arena.to_json(&root), src.replace(' ', ""));
+
+        let mut arena = Arena::new(r#"["${HOST"]"#);
+        let mut root = crate::parse(&mut arena).unwrap();
+        let err = arena.interpolate(&mut root, vars).unwrap_err();
+        assert_eq!(err.kind, InterpolateErrorKind::Unterminated);
+    }
+}
@@ -0,0 +1,231 @@
+//! Helpers for the JSON segments of [JOSE](https://datatracker.ietf.org/doc/html/rfc7515) tokens.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt;
+
+use crate::{Arena, Error, Stacks, Value, ValueKind};
+
+/// The decoded header and claims of a compact JWS, such as a JWT.
+///
+/// Both segments are parsed into a single arena.
+/// The signature is **not** verified.
+pub struct Jws {
+    arena: Arena<'static>,
+    header: Value,
+    claims: Value,
+}
+
+#[derive(Debug)]
+pub enum JoseError {
+    /// The token is not three `.` separated segments.
+    Malformed,
+    /// A segment was not valid base64url. `0` is the header, `1` the claims.
+    Base64 {
+        segment: usize,
+    },
+    Utf8 {
+        segment: usize,
+    },
+    Json {
+        segment: usize,
+        error: Error,
+    },
+}
+
+impl fmt::Display for JoseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |segment: &usize| if *segment == 0 { "header" } else { "claims" };
+        match self {
+            JoseError::Malformed => f.write_str("token is not a compact JWS"),
+            JoseError::Base64 { segment } => write!(f, "{} is not base64url", name(segment)),
+            JoseError::Utf8 { segment } => write!(f, "{} is not UTF-8", name(segment)),
+            JoseError::Json { segment, error } => write!(f, "{}: {error}", name(segment)),
+        }
+    }
+}
+
+impl core::error::Error for JoseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            JoseError::Json { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Jws {
+    /// Decode and parse the header and claims of `token`.
+    pub fn decode(token: &str) -> Result<Self, JoseError> {
+        let mut segments = token.split('.');
+        let (Some(header), Some(claims), Some(_signature), None) = (
+            segments.next(),
+            segments.next(),
+            segments.next(),
+            segments.next(),
+        ) else {
+            return Err(JoseError::Malformed);
+        };
+
+        // both segments share one source string, so they can share one arena.
+        let mut src = String::new();
+        let mut ranges = [0..0, 0..0];
+        for (segment, b64) in [header, claims].into_iter().enumerate() {
+            let bytes =
+                crate::base64::decode_url(b64).map_err(|()| JoseError::Base64 { segment })?;
+            // checked on its own, as a char could be split across the two segments.
+            let text = core::str::from_utf8(&bytes).map_err(|_| JoseError::Utf8 { segment })?;
+            let start = src.len();
+            src.push_str(text);
+            ranges[segment] = start..src.len();
+        }
+
+        let mut arena = Arena::new_shared(src.into());
+        let mut stacks = Stacks::default();
+        let [header, claims] = ranges;
+        let header = crate::parse_range(&mut arena, &mut stacks, header, crate::Dialect::default())
+            .map_err(|error| JoseError::Json { segment: 0, error })?;
+        let claims = crate::parse_range(&mut arena, &mut stacks, claims, crate::Dialect::default())
+            .map_err(|error| JoseError::Json { segment: 1, error })?;
+
+        Ok(Self {
+            arena,
+            header,
+            claims,
+        })
+    }
+
+    pub fn arena(&self) -> &Arena<'static> {
+        &self.arena
+    }
+
+    pub fn header(&self) -> &Value {
+        &self.header
+    }
+
+    pub fn claims(&self) -> &Value {
+        &self.claims
+    }
+
+    /// The `alg` header parameter.
+    pub fn alg(&self) -> Option<Cow<'_, str>> {
+        self.arena.as_str(self.member(&self.header, "alg")?)
+    }
+
+    /// The `exp` claim, in seconds since the unix epoch.
+    pub fn exp(&self) -> Option<f64> {
+        self.arena.as_f64(self.member(&self.claims, "exp")?)
+    }
+
+    /// The `iat` claim, in seconds since the unix epoch.
+    pub fn iat(&self) -> Option<f64> {
+        self.arena.as_f64(self.member(&self.claims, "iat")?)
+    }
+
+    /// The `aud` claim, which can be a single string or an array of strings.
+    pub fn aud(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        let aud = self.member(&self.claims, "aud");
+        let values = match aud.map(|v| v.kind()) {
+            Some(ValueKind::Array(array)) => self.arena.array_values(&array),
+            Some(_) => core::slice::from_ref(aud.unwrap()),
+            None => &[],
+        };
+        values.iter().filter_map(|v| self.arena.as_str(v))
+    }
+
+    fn member(&self, value: &Value, key: &str) -> Option<&Value> {
+        match value.kind() {
+            ValueKind::Object(object) => self.arena.get(&object, key),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for Jws {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jws")
+            .field(
+                "header",
+                &crate::fmt::FmtValue {
+                    arena: &self.arena,
+                    value: &self.header,
+                },
+            )
+            .field(
+                "claims",
+                &crate::fmt::FmtValue {
+                    arena: &self.arena,
+                    value: &self.claims,
+                },
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::{JoseError, Jws};
+
+    fn encode(json: impl AsRef<[u8]>) -> String {
+        let mut out = String::new();
+        for chunk in json.as_ref().chunks(3) {
+            let mut n = [0; 3];
+            n[..chunk.len()].copy_from_slice(chunk);
+            let n = u32::from_be_bytes([0, n[0], n[1], n[2]]);
+            for i in 0..=chunk.len() {
+                let c = (n >> (18 - 6 * i)) & 0x3f;
+                out.push(
+                    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"[c as usize]
+                        as char,
+                );
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn decode() {
+        let token = std::format!(
+            "{}.{}.sig",
+            encode(r#"{"alg":"HS256","typ":"JWT"}"#),
+            encode(r#"{"sub":"1234","aud":["a","b\/c"],"exp":1700000000,"iat":1.5e9}"#),
+        );
+        let jws = Jws::decode(&token).unwrap();
+        assert_eq!(jws.alg().as_deref(), Some("HS256"));
+        assert_eq!(jws.exp(), Some(1700000000.0));
+        assert_eq!(jws.iat(), Some(1500000000.0));
+        assert_eq!(jws.aud().collect::<Vec<_>>(), ["a", "b/c"]);
+        assert_eq!(
+            std::format!("{jws:?}"),
+            r#"Jws { header: {"alg": "HS256", "typ": "JWT"}, claims: {"sub": "1234", "aud": ["a", "b\/c"], "exp": 1700000000, "iat": 1.5e9} }"#
+        );
+
+        let token = std::format!("{}.{}.", encode("{}"), encode(r#"{"aud":"x"}"#));
+        let jws = Jws::decode(&token).unwrap();
+        assert_eq!(jws.aud().collect::<Vec<_>>(), ["x"]);
+        assert_eq!(jws.alg(), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(Jws::decode("a.b"), Err(JoseError::Malformed)));
+        assert!(matches!(
+            Jws::decode("e30.!!.sig"),
+            Err(JoseError::Base64 { segment: 1 })
+        ));
+        let token = std::format!("{}.{}.sig", encode("{}"), encode("[1,"));
+        assert!(matches!(
+            Jws::decode(&token),
+            Err(JoseError::Json { segment: 1, .. })
+        ));
+        // an `é` split between the segments is valid UTF-8 only when they are joined.
+        let token = std::format!("{}.{}.sig", encode(b"\"\xC3"), encode(b"\xA9\""));
+        assert!(matches!(
+            Jws::decode(&token),
+            Err(JoseError::Utf8 { segment: 0 })
+        ));
+    }
+}
@@ -0,0 +1,1060 @@
+//! A small [jq](https://jqlang.github.io/jq/)-like language for querying and reshaping documents.
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{self, Write};
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+/// A compiled jq expression, evaluated with [`Arena::jq`].
+///
+/// Supports field access (`.a.b`, `."a b"`), indexing (`.[0]`, `.[-1]`, `.["a"]`), iteration (`.[]`),
+/// pipes (`|`), multiple outputs (`,`), array and object construction (`[.[] | .id]`, `{id, n: .count}`),
+/// arithmetic (`+ - * / %`), comparisons, `and`, `or`, string interpolation (`"id \(.id)"`),
+/// and the functions `select(f)`, `map(f)`, `length`, `keys`, `not`, `empty`, `type`, `add`,
+/// `tostring` and `tonumber`.
+#[derive(Debug, Clone)]
+pub struct Jq {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JqError {
+    /// The expression is not valid from this byte offset.
+    Syntax { offset: usize },
+    /// There is no function with this name, or it does not take arguments like that.
+    UnknownFunction(String),
+    /// An operation was applied to the wrong type of value, like adding a number to a string.
+    Type(String),
+}
+
+impl fmt::Display for JqError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JqError::Syntax { offset } => write!(f, "invalid expression at offset {offset}"),
+            JqError::UnknownFunction(name) => write!(f, "unknown function {name}"),
+            JqError::Type(message) => f.write_str(message),
+        }
+    }
+}
+
+impl core::error::Error for JqError {}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Literal(Jv<'static>),
+    /// A string with `\(...)` interpolations.
+    String(Vec<Part>),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Select(Box<Expr>),
+    Map(Box<Expr>),
+    Builtin(Builtin),
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Builtin {
+    Length,
+    Keys,
+    Not,
+    Empty,
+    Type,
+    Add,
+    ToString,
+    ToNumber,
+}
+
+/// A value during evaluation, either from the arena or computed.
+#[derive(Debug, Clone)]
+enum Jv<'a> {
+    Value(&'a Value),
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Jv<'a>>),
+    Object(Vec<(String, Jv<'a>)>),
+}
+
+impl<'a> Jv<'a> {
+    /// The value, if it is from the arena.
+    fn value(&self) -> Option<&'a Value> {
+        match self {
+            Jv::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Jq {
+    pub fn parse(src: &str) -> Result<Self, JqError> {
+        let mut parser = Parser { src, pos: 0 };
+        let expr = parser.pipe()?;
+        parser.ws();
+        if parser.pos < src.len() {
+            return parser.error();
+        }
+        Ok(Self { expr })
+    }
+}
+
+impl Arena<'_> {
+    /// Run `filter` on `value`, returning each of its outputs as compact JSON.
+    pub fn jq(&self, value: &Value, filter: &Jq) -> Result<Vec<String>, JqError> {
+        let eval = Eval { arena: self };
+        let outputs = eval.eval(&filter.expr, &Jv::Value(value))?;
+        Ok(outputs
+            .iter()
+            .map(|output| {
+                let mut out = String::new();
+                eval.write(output, &mut out);
+                out
+            })
+            .collect())
+    }
+}
+
+struct Parser<'q> {
+    src: &'q str,
+    pos: usize,
+}
+
+impl<'q> Parser<'q> {
+    fn error<T>(&self) -> Result<T, JqError> {
+        Err(JqError::Syntax { offset: self.pos })
+    }
+
+    fn rest(&self) -> &'q str {
+        &self.src[self.pos..]
+    }
+
+    fn ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.ws();
+        let eaten = self.rest().starts_with(token);
+        if eaten {
+            self.pos += token.len();
+        }
+        eaten
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), JqError> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => self.error(),
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'q str> {
+        self.ws();
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        self.pos += end;
+        Some(&rest[..end])
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        let start = self.pos;
+        if self.ident() == Some(word) {
+            return true;
+        }
+        self.pos = start;
+        false
+    }
+
+    fn pipe(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.comma()?;
+        while self.eat("|") {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+        Ok(expr)
+    }
+
+    fn comma(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.or()?;
+        while self.eat(",") {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.or()?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.comparison()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, JqError> {
+        let expr = self.sum()?;
+        let ops = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        match ops.into_iter().find(|(token, _)| self.eat(token)) {
+            Some((_, op)) => Ok(Expr::Binary(op, Box::new(expr), Box::new(self.sum()?))),
+            None => Ok(expr),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.product()?;
+        loop {
+            let op = match () {
+                _ if self.eat("+") => Op::Add,
+                _ if self.eat("-") => Op::Sub,
+                _ => return Ok(expr),
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.postfix()?;
+        loop {
+            let op = match () {
+                _ if self.eat("*") => Op::Mul,
+                _ if self.eat("/") => Op::Div,
+                _ if self.eat("%") => Op::Rem,
+                _ => return Ok(expr),
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.postfix()?));
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.primary()?;
+        loop {
+            let rest = self.rest();
+            if rest.starts_with('[') {
+                self.pos += 1;
+                expr = self.bracket(expr)?;
+            } else if rest.starts_with(".[") {
+                self.pos += 2;
+                expr = self.bracket(expr)?;
+            } else if rest.starts_with('.') {
+                self.pos += 1;
+                let key = self.field()?;
+                expr = Expr::Index(Box::new(expr), Box::new(key));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `name` or `"name"`, after a `.`.
+    fn field(&mut self) -> Result<Expr, JqError> {
+        if self.rest().starts_with('"') {
+            return self.string();
+        }
+        match self.ident() {
+            Some(name) => Ok(Expr::Literal(Jv::String(name.into()))),
+            None => self.error(),
+        }
+    }
+
+    /// `]` or `index]`, after a `[`.
+    fn bracket(&mut self, expr: Expr) -> Result<Expr, JqError> {
+        if self.eat("]") {
+            return Ok(Expr::Iterate(Box::new(expr)));
+        }
+        let index = self.pipe()?;
+        self.expect("]")?;
+        Ok(Expr::Index(Box::new(expr), Box::new(index)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, JqError> {
+        self.ws();
+        let rest = self.rest();
+        if self.eat("(") {
+            let expr = self.pipe()?;
+            self.expect(")")?;
+            Ok(expr)
+        } else if self.eat("[") {
+            if self.eat("]") {
+                return Ok(Expr::Array(None));
+            }
+            let expr = self.pipe()?;
+            self.expect("]")?;
+            Ok(Expr::Array(Some(Box::new(expr))))
+        } else if self.eat("{") {
+            self.object()
+        } else if rest.starts_with('"') {
+            self.string()
+        } else if rest.starts_with(".[") {
+            Ok(Expr::Identity)
+        } else if self.eat(".") {
+            match self
+                .rest()
+                .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '"')
+            {
+                true => Ok(Expr::Index(
+                    Box::new(Expr::Identity),
+                    Box::new(self.field()?),
+                )),
+                false => Ok(Expr::Identity),
+            }
+        } else if rest.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+            self.number()
+        } else if let Some(name) = self.ident() {
+            self.call(name)
+        } else {
+            self.error()
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, JqError> {
+        let rest = self.rest();
+        let mut end = usize::from(rest.starts_with('-'));
+        let bytes = rest.as_bytes();
+        while let Some(&b) = bytes.get(end) {
+            let exponent_sign = matches!(b, b'+' | b'-') && matches!(bytes[end - 1], b'e' | b'E');
+            if !(b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E') || exponent_sign) {
+                break;
+            }
+            end += 1;
+        }
+        match rest[..end].parse() {
+            Ok(n) => {
+                self.pos += end;
+                Ok(Expr::Literal(Jv::Number(n)))
+            }
+            Err(_) => self.error(),
+        }
+    }
+
+    /// A string literal, starting at its opening quote.
+    fn string(&mut self) -> Result<Expr, JqError> {
+        self.pos += 1;
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        loop {
+            let mut chars = self.rest().chars();
+            let Some(c) = chars.next() else {
+                return self.error();
+            };
+            self.pos += c.len_utf8();
+            let c = match c {
+                '"' => break,
+                '\\' => {
+                    let Some(escape) = chars.next() else {
+                        return self.error();
+                    };
+                    self.pos += escape.len_utf8();
+                    match escape {
+                        '(' => {
+                            parts.push(Part::Literal(core::mem::take(&mut literal)));
+                            parts.push(Part::Expr(self.pipe()?));
+                            self.expect(")")?;
+                            continue;
+                        }
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\x08',
+                        'f' => '\x0c',
+                        '"' | '\\' | '/' => escape,
+                        'u' => {
+                            let hex = self.rest().get(..4);
+                            let c = hex.and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            match c.and_then(char::from_u32) {
+                                Some(c) => {
+                                    self.pos += 4;
+                                    c
+                                }
+                                None => return self.error(),
+                            }
+                        }
+                        _ => return self.error(),
+                    }
+                }
+                c => c,
+            };
+            literal.push(c);
+        }
+
+        if parts.is_empty() {
+            return Ok(Expr::Literal(Jv::String(literal)));
+        }
+        parts.push(Part::Literal(literal));
+        Ok(Expr::String(parts))
+    }
+
+    /// The entries of an object, after its `{`.
+    fn object(&mut self) -> Result<Expr, JqError> {
+        let mut entries = Vec::new();
+        if self.eat("}") {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            self.ws();
+            let (key, shorthand) = if self.eat("(") {
+                let key = self.pipe()?;
+                self.expect(")")?;
+                (key, None)
+            } else if self.rest().starts_with('"') {
+                let key = self.string()?;
+                (
+                    key.clone(),
+                    Some(Expr::Index(Box::new(Expr::Identity), Box::new(key))),
+                )
+            } else if let Some(name) = self.ident() {
+                let key = Expr::Literal(Jv::String(name.into()));
+                (
+                    key.clone(),
+                    Some(Expr::Index(Box::new(Expr::Identity), Box::new(key))),
+                )
+            } else {
+                return self.error();
+            };
+
+            let value = match shorthand {
+                _ if self.eat(":") => self.or()?,
+                Some(value) => value,
+                None => return self.error(),
+            };
+            entries.push((key, value));
+
+            if self.eat("}") {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, JqError> {
+        let arg = match self.eat("(") {
+            true => {
+                let arg = self.pipe()?;
+                self.expect(")")?;
+                Some(Box::new(arg))
+            }
+            false => None,
+        };
+        let builtin = match (name, arg) {
+            ("true", None) => return Ok(Expr::Literal(Jv::Bool(true))),
+            ("false", None) => return Ok(Expr::Literal(Jv::Bool(false))),
+            ("null", None) => return Ok(Expr::Literal(Jv::Null)),
+            ("select", Some(f)) => return Ok(Expr::Select(f)),
+            ("map", Some(f)) => return Ok(Expr::Map(f)),
+            ("length", None) => Builtin::Length,
+            ("keys", None) => Builtin::Keys,
+            ("not", None) => Builtin::Not,
+            ("empty", None) => Builtin::Empty,
+            ("type", None) => Builtin::Type,
+            ("add", None) => Builtin::Add,
+            ("tostring", None) => Builtin::ToString,
+            ("tonumber", None) => Builtin::ToNumber,
+            _ => return Err(JqError::UnknownFunction(name.into())),
+        };
+        Ok(Expr::Builtin(builtin))
+    }
+}
+
+struct Eval<'a, 's> {
+    arena: &'a Arena<'s>,
+}
+
+impl<'a> Eval<'a, '_> {
+    fn eval(&self, expr: &Expr, input: &Jv<'a>) -> Result<Vec<Jv<'a>>, JqError> {
+        let outputs = match expr {
+            Expr::Identity => vec![input.clone()],
+            Expr::Literal(value) => vec![value.clone()],
+            Expr::String(parts) => {
+                let mut strings = vec![String::new()];
+                for part in parts {
+                    strings = match part {
+                        Part::Literal(s) => strings.into_iter().map(|out| out + s).collect(),
+                        Part::Expr(expr) => {
+                            let values = self.eval(expr, input)?;
+                            let values: Vec<String> =
+                                values.iter().map(|v| self.to_string(v)).collect();
+                            strings
+                                .iter()
+                                .flat_map(|out| {
+                                    values.iter().map(move |v| alloc::format!("{out}{v}"))
+                                })
+                                .collect()
+                        }
+                    };
+                }
+                strings.into_iter().map(Jv::String).collect()
+            }
+            Expr::Index(expr, index) => {
+                let indices = self.eval(index, input)?;
+                let mut out = Vec::new();
+                for value in self.eval(expr, input)? {
+                    for index in &indices {
+                        out.push(self.index(&value, index)?);
+                    }
+                }
+                out
+            }
+            Expr::Iterate(expr) => {
+                let mut out = Vec::new();
+                for value in self.eval(expr, input)? {
+                    out.extend(self.values(&value)?);
+                }
+                out
+            }
+            Expr::Array(None) => vec![Jv::Array(Vec::new())],
+            Expr::Array(Some(expr)) => vec![Jv::Array(self.eval(expr, input)?)],
+            Expr::Object(entries) => {
+                let mut objects = vec![Vec::new()];
+                for (key, value) in entries {
+                    let keys = self.eval(key, input)?;
+                    let values = self.eval(value, input)?;
+                    let mut next = Vec::new();
+                    for object in &objects {
+                        for key in &keys {
+                            let Some(key) = self.as_str(key) else {
+                                return Err(JqError::Type(alloc::format!(
+                                    "object keys must be strings, not {}",
+                                    self.kind(key)
+                                )));
+                            };
+                            for value in &values {
+                                let mut object = object.clone();
+                                insert(&mut object, key.to_string(), value.clone());
+                                next.push(object);
+                            }
+                        }
+                    }
+                    objects = next;
+                }
+                objects.into_iter().map(Jv::Object).collect()
+            }
+            Expr::Pipe(a, b) => {
+                let mut out = Vec::new();
+                for value in self.eval(a, input)? {
+                    out.extend(self.eval(b, &value)?);
+                }
+                out
+            }
+            Expr::Comma(a, b) => {
+                let mut out = self.eval(a, input)?;
+                out.extend(self.eval(b, input)?);
+                out
+            }
+            Expr::Binary(op, a, b) => {
+                let lhs = self.eval(a, input)?;
+                let mut out = Vec::new();
+                for rhs in self.eval(b, input)? {
+                    for lhs in &lhs {
+                        out.push(self.binary(*op, lhs, &rhs)?);
+                    }
+                }
+                out
+            }
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                let or = matches!(expr, Expr::Or(..));
+                let mut out = Vec::new();
+                for lhs in self.eval(a, input)? {
+                    if self.truthy(&lhs) == or {
+                        out.push(Jv::Bool(or));
+                        continue;
+                    }
+                    for rhs in self.eval(b, input)? {
+                        out.push(Jv::Bool(self.truthy(&rhs)));
+                    }
+                }
+                out
+            }
+            Expr::Select(f) => self
+                .eval(f, input)?
+                .iter()
+                .filter(|keep| self.truthy(keep))
+                .map(|_| input.clone())
+                .collect(),
+            Expr::Map(f) => {
+                let mut out = Vec::new();
+                for value in self.values(input)? {
+                    out.extend(self.eval(f, &value)?);
+                }
+                vec![Jv::Array(out)]
+            }
+            Expr::Builtin(builtin) => return self.builtin(*builtin, input),
+        };
+        Ok(outputs)
+    }
+
+    fn builtin(&self, builtin: Builtin, input: &Jv<'a>) -> Result<Vec<Jv<'a>>, JqError> {
+        let output = match builtin {
+            Builtin::Empty => return Ok(Vec::new()),
+            Builtin::Not => Jv::Bool(!self.truthy(input)),
+            Builtin::Type => Jv::String(self.kind(input).into()),
+            Builtin::Length => match self.kind(input) {
+                "null" => Jv::Number(0.0),
+                "number" => Jv::Number(self.as_f64(input).unwrap_or_default().abs()),
+                "string" => {
+                    Jv::Number(self.as_str(input).unwrap_or_default().chars().count() as f64)
+                }
+                "array" | "object" => Jv::Number(self.values(input)?.len() as f64),
+                kind => return Err(JqError::Type(alloc::format!("{kind} has no length"))),
+            },
+            Builtin::Keys => match self.entries(input) {
+                Some(entries) => {
+                    let mut keys: Vec<String> = entries.into_iter().map(|(key, _)| key).collect();
+                    keys.sort_unstable();
+                    Jv::Array(keys.into_iter().map(Jv::String).collect())
+                }
+                None => {
+                    let len = self.values(input)?.len();
+                    Jv::Array((0..len).map(|i| Jv::Number(i as f64)).collect())
+                }
+            },
+            Builtin::Add => {
+                let mut sum = Jv::Null;
+                for value in self.values(input)? {
+                    sum = self.binary(Op::Add, &sum, &value)?;
+                }
+                sum
+            }
+            Builtin::ToString => Jv::String(self.to_string(input)),
+            Builtin::ToNumber => match (self.as_f64(input), self.as_str(input)) {
+                (Some(n), _) => Jv::Number(n),
+                (None, Some(s)) => match s.trim().parse() {
+                    Ok(n) => Jv::Number(n),
+                    Err(_) => {
+                        return Err(JqError::Type(alloc::format!(
+                            "cannot parse {s:?} as a number"
+                        )))
+                    }
+                },
+                (None, None) => {
+                    return Err(JqError::Type(alloc::format!(
+                        "{} cannot be parsed as a number",
+                        self.kind(input)
+                    )))
+                }
+            },
+        };
+        Ok(vec![output])
+    }
+
+    fn kind(&self, value: &Jv<'a>) -> &'static str {
+        match value {
+            Jv::Null => "null",
+            Jv::Bool(_) => "boolean",
+            Jv::Number(_) => "number",
+            Jv::String(_) => "string",
+            Jv::Array(_) => "array",
+            Jv::Object(_) => "object",
+            Jv::Value(value) => value.json_type().type_name(),
+        }
+    }
+
+    fn truthy(&self, value: &Jv<'a>) -> bool {
+        match value {
+            Jv::Null | Jv::Bool(false) => false,
+            Jv::Value(value) => !matches!(
+                value.kind(),
+                ValueKind::Leaf(LeafValue::Null | LeafValue::Bool(false))
+            ),
+            _ => true,
+        }
+    }
+
+    fn as_f64(&self, value: &Jv<'a>) -> Option<f64> {
+        match value {
+            Jv::Number(n) => Some(*n),
+            Jv::Value(value) => self.arena.as_f64(value),
+            _ => None,
+        }
+    }
+
+    fn as_str<'v>(&self, value: &'v Jv<'a>) -> Option<Cow<'v, str>> {
+        match value {
+            Jv::String(s) => Some(Cow::Borrowed(s)),
+            Jv::Value(value) => self.arena.as_str(value),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self, value: &Jv<'a>) -> Option<bool> {
+        match value {
+            Jv::Bool(b) => Some(*b),
+            Jv::Value(value) => match value.kind() {
+                ValueKind::Leaf(LeafValue::Bool(b)) => Some(b),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The elements of an array, or the values of an object.
+    fn values(&self, value: &Jv<'a>) -> Result<Vec<Jv<'a>>, JqError> {
+        if let Some(array) = value.value().and_then(Value::as_array) {
+            return Ok(self.arena.array_iter(&array).map(Jv::Value).collect());
+        }
+        match value {
+            Jv::Array(values) => Ok(values.clone()),
+            _ => match self.entries(value) {
+                Some(entries) => Ok(entries.into_iter().map(|(_, value)| value).collect()),
+                None => Err(JqError::Type(alloc::format!(
+                    "cannot iterate over {}",
+                    self.kind(value)
+                ))),
+            },
+        }
+    }
+
+    /// The entries of an object, where the last of any duplicate keys wins.
+    fn entries(&self, value: &Jv<'a>) -> Option<Vec<(String, Jv<'a>)>> {
+        if let Some(object) = value.value().and_then(Value::as_object) {
+            let mut entries = Vec::new();
+            for (key, value) in self.arena.entries(&object) {
+                insert(&mut entries, key.into(), Jv::Value(value));
+            }
+            return Some(entries);
+        }
+        match value {
+            Jv::Object(entries) => Some(entries.clone()),
+            _ => None,
+        }
+    }
+
+    fn index(&self, value: &Jv<'a>, index: &Jv<'a>) -> Result<Jv<'a>, JqError> {
+        let found = match (value, self.as_str(index), self.as_f64(index)) {
+            (_, Some(key), _) if self.kind(value) == "object" => match value {
+                Jv::Value(value) => value
+                    .as_object()
+                    .and_then(|object| self.arena.get(&object, &key))
+                    .map(Jv::Value),
+                Jv::Object(entries) => entries
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, value)| value.clone()),
+                _ => None,
+            },
+            (_, _, Some(i)) if self.kind(value) == "array" => {
+                let values = self.values(value)?;
+                let i = i.floor() as i64;
+                let i = match usize::try_from(i) {
+                    Ok(i) => Some(i),
+                    Err(_) => values.len().checked_sub(i.unsigned_abs() as usize),
+                };
+                i.and_then(|i| values.into_iter().nth(i))
+            }
+            _ if self.kind(value) == "null" && matches!(self.kind(index), "string" | "number") => {
+                None
+            }
+            _ => {
+                return Err(JqError::Type(alloc::format!(
+                    "cannot index {} with {}",
+                    self.kind(value),
+                    self.kind(index)
+                )))
+            }
+        };
+        Ok(found.unwrap_or(Jv::Null))
+    }
+
+    fn binary(&self, op: Op, lhs: &Jv<'a>, rhs: &Jv<'a>) -> Result<Jv<'a>, JqError> {
+        let ordering = || self.compare(lhs, rhs);
+        let output = match op {
+            Op::Eq => Jv::Bool(ordering() == Ordering::Equal),
+            Op::Ne => Jv::Bool(ordering() != Ordering::Equal),
+            Op::Lt => Jv::Bool(ordering() == Ordering::Less),
+            Op::Le => Jv::Bool(ordering() != Ordering::Greater),
+            Op::Gt => Jv::Bool(ordering() == Ordering::Greater),
+            Op::Ge => Jv::Bool(ordering() != Ordering::Less),
+            _ => match (op, self.kind(lhs), self.kind(rhs)) {
+                (Op::Add, "null", _) => rhs.clone(),
+                (Op::Add, _, "null") => lhs.clone(),
+                (_, "number", "number") => {
+                    let a = self.as_f64(lhs).unwrap_or_default();
+                    let b = self.as_f64(rhs).unwrap_or_default();
+                    if matches!(op, Op::Div | Op::Rem) && b == 0.0 {
+                        return Err(JqError::Type("division by zero".into()));
+                    }
+                    Jv::Number(match op {
+                        Op::Add => a + b,
+                        Op::Sub => a - b,
+                        Op::Mul => a * b,
+                        Op::Div => a / b,
+                        _ => (a as i64 % b as i64) as f64,
+                    })
+                }
+                (Op::Add, "string", "string") => Jv::String(
+                    self.as_str(lhs).unwrap_or_default().into_owned()
+                        + &self.as_str(rhs).unwrap_or_default(),
+                ),
+                (Op::Div, "string", "string") => {
+                    let s = self.as_str(lhs).unwrap_or_default();
+                    let separator = self.as_str(rhs).unwrap_or_default();
+                    Jv::Array(s.split(&*separator).map(|s| Jv::String(s.into())).collect())
+                }
+                (Op::Add, "array", "array") => {
+                    let mut values = self.values(lhs)?;
+                    values.extend(self.values(rhs)?);
+                    Jv::Array(values)
+                }
+                (Op::Sub, "array", "array") => {
+                    let remove = self.values(rhs)?;
+                    let values = self.values(lhs)?.into_iter().filter(|value| {
+                        !remove
+                            .iter()
+                            .any(|r| self.compare(value, r) == Ordering::Equal)
+                    });
+                    Jv::Array(values.collect())
+                }
+                (Op::Add, "object", "object") => {
+                    let mut entries = self.entries(lhs).unwrap_or_default();
+                    for (key, value) in self.entries(rhs).unwrap_or_default() {
+                        insert(&mut entries, key, value);
+                    }
+                    Jv::Object(entries)
+                }
+                (_, a, b) => {
+                    let verb = match op {
+                        Op::Add => "add",
+                        Op::Sub => "subtract",
+                        Op::Mul => "multiply",
+                        _ => "divide",
+                    };
+                    return Err(JqError::Type(alloc::format!("cannot {verb} {a} and {b}")));
+                }
+            },
+        };
+        Ok(output)
+    }
+
+    /// The order of jq: `null < false < true < numbers < strings < arrays < objects`.
+    fn compare(&self, a: &Jv<'a>, b: &Jv<'a>) -> Ordering {
+        let rank = |value: &Jv<'a>| match self.kind(value) {
+            "null" => 0,
+            "boolean" => 1 + u8::from(self.as_bool(value) == Some(true)),
+            "number" => 3,
+            "string" => 4,
+            "array" => 5,
+            _ => 6,
+        };
+        let ordering = rank(a).cmp(&rank(b));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        match rank(a) {
+            3 => {
+                let a = self.as_f64(a).unwrap_or_default();
+                let b = self.as_f64(b).unwrap_or_default();
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            }
+            4 => self.as_str(a).cmp(&self.as_str(b)),
+            5 => {
+                let a = self.values(a).unwrap_or_default();
+                let b = self.values(b).unwrap_or_default();
+                self.compare_all(&a, &b)
+            }
+            6 => {
+                let sorted = |value| {
+                    let mut entries = self.entries(value).unwrap_or_default();
+                    entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                    entries
+                };
+                let (a, b) = (sorted(a), sorted(b));
+                let keys = |entries: &[(String, Jv<'a>)]| -> Vec<Jv<'a>> {
+                    entries
+                        .iter()
+                        .map(|(key, _)| Jv::String(key.clone()))
+                        .collect()
+                };
+                let values = |entries: Vec<(String, Jv<'a>)>| -> Vec<Jv<'a>> {
+                    entries.into_iter().map(|(_, value)| value).collect()
+                };
+                self.compare_all(&keys(&a), &keys(&b))
+                    .then_with(|| self.compare_all(&values(a), &values(b)))
+            }
+            _ => Ordering::Equal,
+        }
+    }
+
+    fn compare_all(&self, a: &[Jv<'a>], b: &[Jv<'a>]) -> Ordering {
+        core::iter::zip(a, b)
+            .map(|(a, b)| self.compare(a, b))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| a.len().cmp(&b.len()))
+    }
+
+    /// A string as is, or anything else as JSON.
+    fn to_string(&self, value: &Jv<'a>) -> String {
+        match self.as_str(value) {
+            Some(s) => s.into_owned(),
+            None => {
+                let mut out = String::new();
+                self.write(value, &mut out);
+                out
+            }
+        }
+    }
+
+    fn write(&self, value: &Jv<'a>, out: &mut String) {
+        match value {
+            Jv::Value(value) => self.arena.write_json(value, out),
+            Jv::Null => out.push_str("null"),
+            Jv::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Jv::Number(n) if n.is_finite() => {
+                let _ = write!(out, "{n}");
+            }
+            Jv::Number(_) => out.push_str("null"),
+            Jv::String(s) => {
+                out.push('"');
+                crate::ser::escape(s, out);
+                out.push('"');
+            }
+            Jv::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.write(value, out);
+                }
+                out.push(']');
+            }
+            Jv::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push('"');
+                    crate::ser::escape(key, out);
+                    out.push_str("\":");
+                    self.write(value, out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Set `key` in `entries`, keeping its position if it is already there.
+fn insert<'a>(entries: &mut Vec<(String, Jv<'a>)>, key: String, value: Jv<'a>) {
+    match entries.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, v)) => *v = value,
+        None => entries.push((key, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use crate::{Arena, Jq, JqError};
+
+    #[test]
+    fn jq() {
+        let src = r#"{
+            "users": [
+                {"name": "ann", "age": 31, "tags": ["admin"]},
+                {"name": "bob", "age": 17, "tags": []},
+                {"name": "cy", "age": 45, "tags": ["ops", "admin"]}
+            ],
+            "team": "core"
+        }"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let jq = |filter: &str| arena.jq(&root, &Jq::parse(filter).unwrap());
+        let outputs = |filter: &str| -> Vec<String> { jq(filter).unwrap() };
+
+        assert_eq!(outputs(".team"), [r#""core""#]);
+        assert_eq!(
+            outputs(".users[1].name, .users[-1].age"),
+            [r#""bob""#, "45"]
+        );
+        assert_eq!(
+            outputs(".users[] | select(.age >= 18) | .name"),
+            [r#""ann""#, r#""cy""#]
+        );
+        assert_eq!(outputs("[.users[].age] | add / length"), ["31"]);
+        assert_eq!(
+            outputs(r#".users | map({name, admin: (.tags | length > 0)})"#),
+            [
+                r#"[{"name":"ann","admin":true},{"name":"bob","admin":false},{"name":"cy","admin":true}]"#
+            ]
+        );
+        assert_eq!(
+            outputs(r#".users[0] | "\(.name) is \(.age + 1) next year""#),
+            [r#""ann is 32 next year""#]
+        );
+        assert_eq!(outputs(".users[2] | keys"), [r#"["age","name","tags"]"#]);
+        assert_eq!(
+            outputs(r#"[.users[] | select(.tags[] == "admin" and .age > 40) | .name]"#),
+            [r#"["cy"]"#]
+        );
+        assert_eq!(
+            outputs(r#"{(.team): .users[0].tags} + {a: null}"#),
+            [r#"{"core":["admin"],"a":null}"#]
+        );
+        assert_eq!(outputs(r#""a,b" / "," | .[1]"#), [r#""b""#]);
+
+        assert_eq!(
+            jq(".team + 1"),
+            Err(JqError::Type(String::from("cannot add string and number")))
+        );
+        assert_eq!(
+            Jq::parse(".users[").unwrap_err(),
+            JqError::Syntax { offset: 7 }
+        );
+        assert_eq!(
+            Jq::parse("sort").unwrap_err(),
+            JqError::UnknownFunction(String::from("sort"))
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Typed views over [JSON-RPC 2.0](https://www.jsonrpc.org/specification) messages.
+//!
+//! The views borrow from the arena, so `params` and `result` can be forwarded
+//! as raw source text without being copied into a new tree.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::ops::Range;
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+/// An invalid JSON-RPC message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonRpcError {
+    /// The span of the offending value.
+    pub span: Range<u32>,
+    pub kind: JsonRpcErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonRpcErrorKind {
+    ExpectedObject,
+    /// `jsonrpc` is missing or is not `"2.0"`.
+    InvalidVersion,
+    MissingMember(&'static str),
+    InvalidMember(&'static str),
+    /// A response must have exactly one of `result` and `error`.
+    AmbiguousResponse,
+    EmptyBatch,
+}
+
+impl JsonRpcError {
+    /// The JSON-RPC error code to respond with, `-32600 Invalid Request`.
+    pub const CODE: i64 = -32600;
+}
+
+pub enum Envelope<'a, 's> {
+    Single(Message<'a, 's>),
+    /// Each message of a batch is validated on its own.
+    Batch(Vec<Result<Message<'a, 's>, JsonRpcError>>),
+}
+
+pub enum Message<'a, 's> {
+    Request(Request<'a, 's>),
+    Response(Response<'a, 's>),
+}
+
+pub struct Request<'a, 's> {
+    arena: &'a Arena<'s>,
+    method: &'a Value,
+    params: Option<&'a Value>,
+    id: Option<&'a Value>,
+}
+
+pub struct Response<'a, 's> {
+    arena: &'a Arena<'s>,
+    id: &'a Value,
+    result: Result<&'a Value, ErrorObject<'a>>,
+}
+
+#[derive(Debug)]
+pub struct ErrorObject<'a> {
+    pub code: i64,
+    pub message: Cow<'a, str>,
+    pub data: Option<&'a Value>,
+}
+
+/// Interpret `value` as a JSON-RPC message or batch.
+pub fn parse<'a, 's>(
+    arena: &'a Arena<'s>,
+    value: &'a Value,
+) -> Result<Envelope<'a, 's>, JsonRpcError> {
+    match value.kind() {
+        ValueKind::Array(array) => {
+            let values = arena.array_values(&array);
+            if values.is_empty() {
+                return Err(error(arena, value, JsonRpcErrorKind::EmptyBatch));
+            }
+            Ok(Envelope::Batch(
+                values.iter().map(|v| message(arena, v)).collect(),
+            ))
+        }
+        _ => message(arena, value).map(Envelope::Single),
+    }
+}
+
+fn error(arena: &Arena<'_>, value: &Value, kind: JsonRpcErrorKind) -> JsonRpcError {
+    JsonRpcError {
+        span: arena.span(value),
+        kind,
+    }
+}
+
+fn message<'a, 's>(
+    arena: &'a Arena<'s>,
+    value: &'a Value,
+) -> Result<Message<'a, 's>, JsonRpcError> {
+    let ValueKind::Object(object) = value.kind() else {
+        return Err(error(arena, value, JsonRpcErrorKind::ExpectedObject));
+    };
+
+    let version = arena.get(&object, "jsonrpc");
+    if version.and_then(|v| arena.as_str(v)).as_deref() != Some("2.0") {
+        return Err(error(
+            arena,
+            version.unwrap_or(value),
+            JsonRpcErrorKind::InvalidVersion,
+        ));
+    }
+
+    let id = arena.get(&object, "id");
+    if let Some(id) = id {
+        if !matches!(
+            id.kind(),
+            ValueKind::Leaf(
+                LeafValue::String | LeafValue::Number | LeafValue::BigInt | LeafValue::Null
+            )
+        ) {
+            return Err(error(arena, id, JsonRpcErrorKind::InvalidMember("id")));
+        }
+    }
+
+    if let Some(method) = arena.get(&object, "method") {
+        if !matches!(method.kind(), ValueKind::Leaf(LeafValue::String)) {
+            return Err(error(
+                arena,
+                method,
+                JsonRpcErrorKind::InvalidMember("method"),
+            ));
+        }
+        let params = arena.get(&object, "params");
+        if let Some(params) = params {
+            if !matches!(params.kind(), ValueKind::Object(_) | ValueKind::Array(_)) {
+                return Err(error(
+                    arena,
+                    params,
+                    JsonRpcErrorKind::InvalidMember("params"),
+                ));
+            }
+        }
+        return Ok(Message::Request(Request {
+            arena,
+            method,
+            params,
+            id,
+        }));
+    }
+
+    let Some(id) = id else {
+        return Err(error(
+            arena,
+            value,
+            JsonRpcErrorKind::MissingMember("method"),
+        ));
+    };
+    let result = match (arena.get(&object, "result"), arena.get(&object, "error")) {
+        (Some(result), None) => Ok(result),
+        (None, Some(error)) => Err(error_object(arena, error)?),
+        _ => return Err(error(arena, value, JsonRpcErrorKind::AmbiguousResponse)),
+    };
+    Ok(Message::Response(Response { arena, id, result }))
+}
+
+fn error_object<'a>(
+    arena: &'a Arena<'_>,
+    value: &'a Value,
+) -> Result<ErrorObject<'a>, JsonRpcError> {
+    let invalid = || error(arena, value, JsonRpcErrorKind::InvalidMember("error"));
+    let ValueKind::Object(object) = value.kind() else {
+        return Err(invalid());
+    };
+
+    let code = arena
+        .get(&object, "code")
+        .filter(|code| matches!(code.kind(), ValueKind::Leaf(LeafValue::Number)))
+        .and_then(|code| arena.raw(code).parse().ok())
+        .ok_or_else(invalid)?;
+    let message = arena
+        .get(&object, "message")
+        .and_then(|message| arena.as_str(message))
+        .ok_or_else(invalid)?;
+    let data = arena.get(&object, "data");
+
+    Ok(ErrorObject {
+        code,
+        message,
+        data,
+    })
+}
+
+impl<'a> Request<'a, '_> {
+    pub fn method(&self) -> Cow<'a, str> {
+        self.arena.as_str(self.method).unwrap_or_default()
+    }
+
+    pub fn params(&self) -> Option<&'a Value> {
+        self.params
+    }
+
+    /// The source text of `params`, for forwarding without re-serializing.
+    pub fn params_raw(&self) -> Option<&'a str> {
+        self.params.map(|params| self.arena.raw(params))
+    }
+
+    pub fn id(&self) -> Option<&'a Value> {
+        self.id
+    }
+
+    /// The source text of `id`, to be echoed back in the response.
+    pub fn id_raw(&self) -> Option<&'a str> {
+        self.id.map(|id| self.arena.raw(id))
+    }
+
+    /// Notifications have no `id`, and must not be responded to.
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
+impl<'a> Response<'a, '_> {
+    pub fn id(&self) -> &'a Value {
+        self.id
+    }
+
+    pub fn id_raw(&self) -> &'a str {
+        self.arena.raw(self.id)
+    }
+
+    pub fn result(&self) -> Result<&'a Value, &ErrorObject<'a>> {
+        self.result.as_ref().copied()
+    }
+
+    /// The source text of `result`, for forwarding without re-serializing.
+    pub fn result_raw(&self) -> Option<&'a str> {
+        self.result
+            .as_ref()
+            .ok()
+            .map(|result| self.arena.raw(result))
+    }
+}
+
+/// Writes JSON-RPC responses, or a batch of responses.
+///
+/// Ids, results and error data are given as raw JSON text, such as from
+/// [`Request::id_raw`] or a result produced by another service.
+pub struct ResponseBuilder {
+    out: String,
+    batch: bool,
+    len: usize,
+}
+
+impl ResponseBuilder {
+    /// A single response.
+    pub fn single() -> Self {
+        Self {
+            out: String::new(),
+            batch: false,
+            len: 0,
+        }
+    }
+
+    /// A batch of responses.
+    pub fn batch() -> Self {
+        Self {
+            out: String::from("["),
+            batch: true,
+            len: 0,
+        }
+    }
+
+    fn begin(&mut self, id: &str) {
+        assert!(
+            self.batch || self.len == 0,
+            "a single response can only be written once"
+        );
+        if self.len > 0 {
+            self.out.push(',');
+        }
+        self.len += 1;
+        let _ = write!(self.out, r#"{{"jsonrpc":"2.0","id":{id},"#);
+    }
+
+    pub fn result(&mut self, id: &str, result: &str) -> &mut Self {
+        self.begin(id);
+        let _ = write!(self.out, r#""result":{result}}}"#);
+        self
+    }
+
+    pub fn error(&mut self, id: &str, code: i64, message: &str, data: Option<&str>) -> &mut Self {
+        self.begin(id);
+        let _ = write!(self.out, r#""error":{{"code":{code},"message":""#);
+        crate::ser::escape(message, &mut self.out);
+        self.out.push('"');
+        if let Some(data) = data {
+            let _ = write!(self.out, r#","data":{data}"#);
+        }
+        self.out.push_str("}}");
+        self
+    }
+
+    /// The number of responses written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The response text.
+    ///
+    /// Returns an empty string for a batch with no responses,
+    /// as a batch of only notifications must not be responded to.
+    pub fn finish(mut self) -> String {
+        if self.batch {
+            if self.len == 0 {
+                return String::new();
+            }
+            self.out.push(']');
+        }
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{parse, Envelope, JsonRpcErrorKind, Message, ResponseBuilder};
+    use crate::Arena;
+
+    #[test]
+    fn request() {
+        let src = r#"{"jsonrpc": "2.0", "method": "sum", "params": [1, {"a": 2}], "id": "x"}"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+
+        let Ok(Envelope::Single(Message::Request(req))) = parse(&arena, &value) else {
+            panic!("expected a request")
+        };
+        assert_eq!(req.method(), "sum");
+        assert_eq!(req.params_raw(), Some(r#"[1, {"a": 2}]"#));
+        assert_eq!(req.id_raw(), Some(r#""x""#));
+        assert!(!req.is_notification());
+
+        let mut res = ResponseBuilder::single();
+        res.result(req.id_raw().unwrap(), req.params_raw().unwrap());
+        assert_eq!(
+            res.finish(),
+            r#"{"jsonrpc":"2.0","id":"x","result":[1, {"a": 2}]}"#
+        );
+    }
+
+    #[test]
+    fn batch() {
+        let src = r#"[
+            {"jsonrpc": "2.0", "method": "notify"},
+            {"jsonrpc": "2.0", "id": 1, "result": {"ok": true}},
+            {"jsonrpc": "2.0", "id": 2, "error": {"code": -32601, "message": "not \"found\""}},
+            {"jsonrpc": "1.0", "method": "old"},
+            {"jsonrpc": "2.0", "method": "bad", "params": 1},
+            {"jsonrpc": "2.0", "id": 3, "result": 1, "error": null},
+            1
+        ]"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+
+        let Ok(Envelope::Batch(messages)) = parse(&arena, &value) else {
+            panic!("expected a batch")
+        };
+
+        let mut res = ResponseBuilder::batch();
+        let mut errors = Vec::new();
+        for message in messages {
+            match message {
+                Ok(Message::Request(req)) => assert!(req.is_notification()),
+                Ok(Message::Response(resp)) => match resp.result() {
+                    Ok(_) => {
+                        res.result(resp.id_raw(), resp.result_raw().unwrap());
+                    }
+                    Err(e) => {
+                        res.error(resp.id_raw(), e.code, &e.message, None);
+                    }
+                },
+                Err(e) => {
+                    res.error("null", super::JsonRpcError::CODE, "invalid", None);
+                    errors.push((&src[e.span.start as usize..e.span.end as usize], e.kind));
+                }
+            }
+        }
+
+        assert_eq!(
+            errors,
+            [
+                (r#""1.0""#, JsonRpcErrorKind::InvalidVersion),
+                ("1", JsonRpcErrorKind::InvalidMember("params")),
+                (
+                    r#"{"jsonrpc": "2.0", "id": 3, "result": 1, "error": null}"#,
+                    JsonRpcErrorKind::AmbiguousResponse
+                ),
+                ("1", JsonRpcErrorKind::ExpectedObject),
+            ]
+        );
+        assert_eq!(res.len(), 6);
+        assert!(res.finish().starts_with(
+            r#"[{"jsonrpc":"2.0","id":1,"result":{"ok": true}},{"jsonrpc":"2.0","id":2,"error":{"code":-32601,"message":"not \"found\""}},"#
+        ));
+
+        assert!(ResponseBuilder::batch().finish().is_empty());
+
+        let mut arena = Arena::new("[]");
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(
+            parse(&arena, &value).err().unwrap().kind,
+            JsonRpcErrorKind::EmptyBatch
+        );
+    }
+}
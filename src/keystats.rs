@@ -0,0 +1,76 @@
+use alloc::vec::Vec;
+use core::hash::BuildHasher;
+use hashbrown::HashTable;
+
+use crate::Arena;
+
+/// How often each object key occurs in an arena, returned by [`Arena::key_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStats<'a> {
+    /// The number of object members, over every object.
+    pub occurrences: usize,
+    /// Every distinct key with its number of occurrences, the most frequent first.
+    ///
+    /// Keys that occur equally often are sorted by name.
+    pub keys: Vec<(&'a str, usize)>,
+}
+
+impl<'a> KeyStats<'a> {
+    /// The number of distinct keys.
+    pub fn unique(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// The `n` most frequent keys.
+    pub fn hottest(&self, n: usize) -> &[(&'a str, usize)] {
+        &self.keys[..n.min(self.keys.len())]
+    }
+}
+
+impl Arena<'_> {
+    /// Count the keys of every object in this arena.
+    ///
+    /// A few keys that occur very often, like in arrays of uniform records,
+    /// are what make interning keys worthwhile.
+    pub fn key_stats(&self) -> KeyStats<'_> {
+        let mut counts = HashTable::<(&str, usize)>::new();
+        for key in &self.keys {
+            let key = &self[key];
+            let hash = self.hasher.hash_one(key);
+            counts
+                .entry(hash, |(k, _)| *k == key, |(k, _)| self.hasher.hash_one(k))
+                .or_insert((key, 0))
+                .into_mut()
+                .1 += 1;
+        }
+
+        let mut keys: Vec<_> = counts.into_iter().collect();
+        keys.sort_unstable_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+        KeyStats {
+            occurrences: self.keys.len(),
+            keys,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Arena;
+
+    #[test]
+    fn key_stats() {
+        let src = r#"[
+            {"id": 1, "name": "a", "tags": {"id": 2}},
+            {"id": 3, "name": "b"},
+            {"id": 4, "extra": null}
+        ]"#;
+        let mut arena = Arena::new(src);
+        crate::parse(&mut arena).unwrap();
+
+        let stats = arena.key_stats();
+        assert_eq!(stats.occurrences, 8);
+        assert_eq!(stats.unique(), 4);
+        assert_eq!(stats.hottest(2), [("id", 4), ("name", 2)]);
+        assert_eq!(stats.hottest(10)[2..], [("extra", 1), ("tags", 1)]);
+    }
+}
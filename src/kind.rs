@@ -0,0 +1,129 @@
+use core::fmt;
+
+use crate::{Array, LeafValue, Object, Repr, Value, ValueKind};
+
+/// The JSON type of a [`Value`], whatever its representation in the arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonType {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    /// The name of the type, as in JSON Schema, like `"boolean"`.
+    pub fn type_name(self) -> &'static str {
+        match self {
+            JsonType::Null => "null",
+            JsonType::Bool => "boolean",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        }
+    }
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.type_name())
+    }
+}
+
+impl Value {
+    pub fn kind(&self) -> ValueKind {
+        match self.repr() {
+            Repr::Leaf { leaf, .. } => ValueKind::Leaf(leaf),
+            Repr::Object(object) => ValueKind::Object(object),
+            Repr::Array(array) => ValueKind::Array(array),
+            Repr::Header { .. } => unreachable!("container headers are not values"),
+        }
+    }
+
+    pub fn json_type(&self) -> JsonType {
+        match self.kind() {
+            ValueKind::Leaf(LeafValue::Null) => JsonType::Null,
+            ValueKind::Leaf(LeafValue::Bool(_)) => JsonType::Bool,
+            ValueKind::Leaf(LeafValue::String) => JsonType::String,
+            ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt) => {
+                JsonType::Number
+            }
+            ValueKind::Array(_) => JsonType::Array,
+            ValueKind::Object(_) => JsonType::Object,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.json_type() == JsonType::Null
+    }
+
+    pub fn is_bool(&self) -> bool {
+        self.json_type() == JsonType::Bool
+    }
+
+    /// Whether the value is a number, including those only allowed by a [`Dialect`](crate::Dialect).
+    pub fn is_number(&self) -> bool {
+        self.json_type() == JsonType::Number
+    }
+
+    pub fn is_string(&self) -> bool {
+        self.json_type() == JsonType::String
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.json_type() == JsonType::Array
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.json_type() == JsonType::Object
+    }
+
+    /// The object, if the value is one.
+    pub fn as_object(&self) -> Option<Object> {
+        match self.kind() {
+            ValueKind::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// The array, if the value is one.
+    pub fn as_array(&self) -> Option<Array> {
+        match self.kind() {
+            ValueKind::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{Arena, JsonType};
+
+    #[test]
+    fn json_type() {
+        let mut arena = Arena::new(r#"[null, true, 1.5, "s", [], {}]"#);
+        let value = crate::parse(&mut arena).unwrap();
+        assert!(value.is_array());
+
+        let array = value.as_array().unwrap();
+        assert!(value.as_object().is_none());
+        let types: Vec<_> = arena.array_iter(&array).map(|v| v.json_type()).collect();
+        assert_eq!(
+            types,
+            [
+                JsonType::Null,
+                JsonType::Bool,
+                JsonType::Number,
+                JsonType::String,
+                JsonType::Array,
+                JsonType::Object
+            ]
+        );
+        assert_eq!(JsonType::Bool.type_name(), "boolean");
+    }
+}
@@ -0,0 +1,183 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Arena, Object, StringKey, Value};
+
+/// A fixed set of object keys, known before parsing, with a perfect hash.
+///
+/// Each key has a dense id, its position in the set. An arena given these keys with
+/// [`Arena::set_known_keys`] interns them by the perfect hash, which never collides,
+/// instead of its hash table. Keys outside the set are interned as usual.
+#[derive(Debug, Clone)]
+pub struct KnownKeys {
+    keys: Vec<String>,
+    seed: u64,
+    /// The id of the key in each slot, or `u32::MAX` if it is empty.
+    slots: Vec<u32>,
+}
+
+/// A key given twice to [`KnownKeys::new`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateKey(pub String);
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate known key {:?}", self.0)
+    }
+}
+
+impl core::error::Error for DuplicateKey {}
+
+const EMPTY: u32 = u32::MAX;
+
+impl KnownKeys {
+    /// Build the perfect hash of `keys`, which get the ids `0..n` in order.
+    pub fn new<K: Into<String>>(keys: impl IntoIterator<Item = K>) -> Result<Self, DuplicateKey> {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+
+        let mut slots = vec![EMPTY; (keys.len() * 2).next_power_of_two()];
+        let mut seed = 0;
+        'search: loop {
+            slots.fill(EMPTY);
+            for (id, key) in keys.iter().enumerate() {
+                let slot = slot(seed, slots.len(), key);
+                if slots[slot] != EMPTY {
+                    let other = &keys[slots[slot] as usize];
+                    if other == key {
+                        return Err(DuplicateKey(key.clone()));
+                    }
+                    // try another seed, and eventually more room.
+                    seed += 1;
+                    if seed % 16 == 0 {
+                        slots = vec![EMPTY; slots.len() * 2];
+                    }
+                    continue 'search;
+                }
+                slots[slot] = id as u32;
+            }
+            break;
+        }
+
+        Ok(Self { keys, seed, slots })
+    }
+
+    /// The id of `key`, if it is one of these keys.
+    pub fn id(&self, key: &str) -> Option<usize> {
+        let id = self.slots[slot(self.seed, self.slots.len(), key)] as usize;
+        (self.keys.get(id)? == key).then_some(id)
+    }
+
+    /// The key with `id`.
+    pub fn key(&self, id: usize) -> Option<&str> {
+        self.keys.get(id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// The slot of `key` in a table of `len` slots, a power of two.
+fn slot(seed: u64, len: usize, key: &str) -> usize {
+    // FNV-1a, starting from the seed.
+    let hash = key.bytes().fold(seed ^ 0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    (hash ^ hash >> 32) as usize & (len - 1)
+}
+
+/// The known keys of an arena, and where each was first interned.
+pub(crate) struct Known {
+    pub(crate) keys: Arc<KnownKeys>,
+    pub(crate) interned: Vec<Option<StringKey>>,
+}
+
+impl Known {
+    /// Forget where the keys were interned, when the keys of the arena are.
+    pub(crate) fn clear(known: &mut Option<Known>) {
+        if let Some(known) = known {
+            known.interned.fill(None);
+        }
+    }
+}
+
+impl Arena<'_> {
+    /// Intern the keys in `keys` by their perfect hash from now on.
+    pub fn set_known_keys(&mut self, keys: Arc<KnownKeys>) {
+        self.known = Some(Known {
+            interned: vec![None; keys.len()],
+            keys,
+        });
+    }
+
+    /// The members of `object` in source order, with the id of each key in the
+    /// [`KnownKeys`] of this arena, or `None` if it is not one of them.
+    pub fn known_entries<'a>(
+        &'a self,
+        object: &Object,
+    ) -> impl DoubleEndedIterator<Item = (Option<usize>, &'a Value)> + 'a {
+        let known = self.known.as_ref().map(|known| &*known.keys);
+        self.entries(object)
+            .map(move |(key, value)| (known.and_then(|known| known.id(key)), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    use super::{DuplicateKey, KnownKeys};
+    use crate::{Arena, ValueKind};
+
+    #[test]
+    fn known_keys() {
+        let names = [
+            "ts", "level", "msg", "user", "id", "", "trace_id", "span_id",
+        ];
+        let keys = Arc::new(KnownKeys::new(names).unwrap());
+        for (id, name) in names.iter().enumerate() {
+            assert_eq!(keys.id(name), Some(id));
+            assert_eq!(keys.key(id), Some(*name));
+        }
+        assert_eq!(keys.id("other"), None);
+        assert_eq!(
+            KnownKeys::new(["a", "b", "a"]).unwrap_err(),
+            DuplicateKey("a".into())
+        );
+
+        let src = r#"[
+            {"ts": 1, "level": "info", "msg": "a", "extra": true},
+            {"ts": 2, "level": "warn", "msg": "b", "extra": false}
+        ]"#;
+        let mut arena = Arena::new(src);
+        arena.set_known_keys(keys);
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(
+            arena.to_json(&value),
+            r#"[{"ts":1,"level":"info","msg":"a","extra":true},{"ts":2,"level":"warn","msg":"b","extra":false}]"#
+        );
+
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+        for element in arena.array_values(&array) {
+            let ValueKind::Object(object) = element.kind() else {
+                panic!("expected object")
+            };
+            let ids: Vec<_> = arena.known_entries(&object).map(|(id, _)| id).collect();
+            assert_eq!(ids, [Some(0), Some(1), Some(2), None]);
+        }
+
+        // known keys are shared like any other interned key.
+        assert_eq!(arena.keys[0], arena.keys[4]);
+        assert_eq!(arena.keys[1], arena.keys[5]);
+        assert_eq!(arena.keys[3], arena.keys[7]);
+    }
+}
@@ -4,25 +4,211 @@
 #[macro_use(vec)]
 extern crate alloc;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
+use alloc::boxed::Box;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::hash::BuildHasher;
-use core::ops::{Index, Range, RangeFrom};
+use core::ops::{Deref, Index, Range, RangeFrom};
 use core::task::Poll;
 use foldhash::quality::RandomState;
-use hashbrown::hash_table::Entry;
+use hashbrown::hash_table;
 use hashbrown::HashTable;
 use memchr::memchr2;
 
 use logos::{Lexer, Logos};
 
-mod fmt;
+/// Run `$e`, counting any growth of `$arena.$buf` in the [`ParseStats`] of the arena.
+macro_rules! track_growth {
+    ($arena:ident.$buf:ident, $e:expr) => {{
+        #[cfg(feature = "alloc-stats")]
+        let before = crate::stats::Capacity::capacity_bytes(&$arena.$buf);
+        let result = $e;
+        #[cfg(feature = "alloc-stats")]
+        $arena
+            .stats
+            .$buf
+            .record(before, crate::stats::Capacity::capacity_bytes(&$arena.$buf));
+        result
+    }};
+}
+
+/// Record what the parser did in the [`ParseTrace`] of `$arena`.
+macro_rules! trace {
+    ($arena:ident, $trace:ident => $e:expr) => {
+        #[cfg(feature = "trace")]
+        {
+            let $trace = &mut $arena.trace;
+            $e;
+        }
+    };
+}
 
-#[derive(Logos, Debug, PartialEq)]
+mod access;
+mod archive;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod assert;
+mod base64;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod brand;
+mod buffers;
+mod builder;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+mod coerce;
+mod columns;
+mod compact;
+mod concat;
+mod copy;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(any(feature = "base64", feature = "uuid"))]
+mod decode;
+mod dedup;
+mod dialect;
+mod document;
+mod eq;
+mod filter;
+mod flatten;
+mod fmt;
+pub mod geojson;
+mod id;
+mod interpolate;
+pub mod jose;
+#[cfg(feature = "jq")]
+mod jq;
+pub mod jsonrpc;
+mod keystats;
+mod kind;
+mod known;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod number;
+mod parser;
+mod partial;
+mod pool;
+pub mod prelude;
+mod query;
+mod redact;
+pub mod refs;
+mod rename;
+mod reserve;
+mod resume;
+mod rollback;
+mod rows;
+mod schema;
+mod ser;
+#[cfg(feature = "serde")]
+mod serialize;
+mod set;
+mod snapshot;
+mod sniff;
+mod sort;
+mod sse;
+#[cfg(feature = "alloc-stats")]
+mod stats;
+mod summary;
+#[cfg(feature = "chrono")]
+mod time;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+mod transcode;
+mod variant;
+#[cfg(feature = "std")]
+mod watch;
+#[cfg(feature = "axum")]
+mod web;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+pub use access::Entry;
+pub use archive::InvalidArchive;
+#[cfg(feature = "arrow")]
+pub use arrow::{to_record_batch, RecordBatchError};
+#[doc(hidden)]
+pub use assert::__json_eq_message;
+pub use brand::{Branded, Scope};
+pub use buffers::Buffers;
+pub use builder::{Builder, SpliceError};
+#[cfg(feature = "std")]
+pub use cache::{CacheError, CachedParser};
+pub use coerce::{Coerce, Coercion, NumberStrings};
+pub use columns::{Column, ColumnError, ColumnType, ExtractColumns};
+pub use concat::parse_all;
+#[cfg(feature = "csv")]
+pub use csv::{to_csv, CsvError};
+#[cfg(feature = "serde")]
+pub use de::{DeError, Deserializer};
+#[cfg(any(feature = "base64", feature = "uuid"))]
+pub use decode::{DecodeError, DecodeErrorKind};
+pub use dialect::{parse_with_dialect, Dialect};
+pub use document::Document;
+pub use eq::{EqOptions, Mismatch};
+pub use filter::{filter_events, FilterError, FilterEvents, FilterMatch, PathMatcher};
+pub use flatten::{Flatten, JsonPath, Unflatten};
+pub use fmt::ValueRef;
+pub use id::{KeyId, ValueId};
+#[cfg(feature = "std")]
+pub use interpolate::EnvVars;
+pub use interpolate::{InterpolateError, InterpolateErrorKind, Variables};
+#[cfg(feature = "jq")]
+pub use jq::{Jq, JqError};
+pub use keystats::KeyStats;
+pub use kind::JsonType;
+pub use known::{DuplicateKey, KnownKeys};
+pub use number::Number;
+pub use parser::Parser;
+pub use partial::{parse_keep_partial, parse_partial, Container, OpenContainer, Partial};
+#[cfg(feature = "std")]
+pub use pool::{pooled_stats, with_pooled_arena};
+pub use pool::{ArenaPool, DocumentGuard, PoolStats};
+pub use query::{Query, QueryError};
+pub use redact::{PathSegment, Redact, RedactPaths, Redaction};
+pub use rename::{Case, RenameKey};
+pub use reserve::Reserve;
+pub use resume::{parse_resumable, Checkpoint, InvalidCheckpoint, Resume};
+pub use schema::{FieldSchema, Fields, ObjectSchema, Schema, SchemaType, Violation, ViolationKind};
+pub use ser::{Exponent, FloatFormat, InexactNumber, NumberFormat};
+#[cfg(feature = "serde")]
+pub use serialize::SerializeValue;
+pub use set::NotScalar;
+pub use snapshot::Snapshot;
+pub use sniff::{parse_any, AnyDocument, AnyError, Format};
+pub use sse::{SseDecoder, SseEvent};
+#[cfg(feature = "alloc-stats")]
+pub use stats::{Growth, ParseStats};
+pub use summary::{Limits, Summary};
+#[cfg(feature = "chrono")]
+pub use time::{TimeError, TimeErrorKind};
+#[cfg(feature = "toml")]
+pub use toml::{parse_toml, TomlDocument, TomlError};
+#[cfg(feature = "trace")]
+pub use trace::ParseTrace;
+pub use variant::{Variant, VariantError};
+#[cfg(feature = "std")]
+pub use watch::{watch, Watch};
+#[cfg(feature = "axum")]
+pub use web::{SonnyJson, SonnyJsonRejection};
+#[cfg(feature = "yaml")]
+pub use yaml::{parse_yaml, YamlDocument, YamlError};
+
+#[derive(Logos, Debug, Clone, Copy, PartialEq)]
 #[logos(skip r"[ \t\r\n]+")] // Ignore this regex pattern between tokens
+#[logos(extras = Extras)]
 enum Token {
     #[token("{")]
     OpenObject,
@@ -43,23 +229,222 @@ enum Token {
     #[token("false", |_| LeafValue::Bool(false))]
     #[token("true", |_| LeafValue::Bool(true))]
     #[token("null", |_| LeafValue::Null)]
-    #[regex(r"[-\d][\deE+\-\.]*", |_| LeafValue::Number)]
-    #[regex("\"", lex_string)]
+    #[regex(r"[-0-9]", lex_number)]
+    #[regex(r"-?0[xX][0-9a-fA-F]+", |lexer| lex_radix(lexer, Radix::Hex))]
+    #[regex(r"-?0[oO][0-7]+", |lexer| lex_radix(lexer, Radix::Octal))]
+    #[regex(r"-?0[bB][01]+", |lexer| lex_radix(lexer, Radix::Binary))]
+    #[regex("\"", |lexer| lex_quoted(lexer, b'"'))]
+    #[regex("'", lex_single_quoted)]
     Leaf(LeafValue),
+
+    #[regex(r"[A-Za-z_$][A-Za-z0-9_$]*", |lexer| lexer.extras.dialect.unquoted_keys.then_some(()).ok_or(()))]
+    Ident,
+}
+
+/// The tokens that are a single byte, by that byte.
+const STRUCTURAL: [Option<Token>; 256] = {
+    let mut table = [None; 256];
+    table[b'{' as usize] = Some(Token::OpenObject);
+    table[b'[' as usize] = Some(Token::OpenArray);
+    table[b'}' as usize] = Some(Token::CloseObject);
+    table[b']' as usize] = Some(Token::CloseArray);
+    table[b':' as usize] = Some(Token::Colon);
+    table[b',' as usize] = Some(Token::Comma);
+    table
+};
+
+/// What the lexer carries from one token to the next.
+#[derive(Clone, Default)]
+struct Extras {
+    dialect: Dialect,
+    /// Whether the last string had any escapes.
+    escaped: bool,
+}
+
+impl From<Dialect> for Extras {
+    fn from(dialect: Dialect) -> Self {
+        Self {
+            dialect,
+            escaped: false,
+        }
+    }
+}
+
+/// The length of the whitespace at the start of `s`.
+///
+/// Indentation is mostly spaces, so runs of spaces are skipped 8 bytes at a time,
+/// and the other whitespace one byte at a time.
+#[inline]
+fn skip_whitespace(s: &[u8]) -> usize {
+    const SPACES: u64 = u64::from_le_bytes([b' '; 8]);
+    const HIGH: u64 = u64::from_le_bytes([0x80; 8]);
+
+    let mut i = 0;
+    loop {
+        match s.get(i) {
+            Some(b' ') => {}
+            Some(b'\n' | b'\r' | b'\t') => {
+                i += 1;
+                continue;
+            }
+            _ => return i,
+        }
+        while let Some(chunk) = s.get(i..i + 8) {
+            let x = u64::from_le_bytes(chunk.try_into().unwrap()) ^ SPACES;
+            // the high bit of each byte that is not a space.
+            let other = (((x & !HIGH) + !HIGH) | x) & HIGH;
+            if other != 0 {
+                i += other.trailing_zeros() as usize / 8;
+                break;
+            }
+            i += 8;
+        }
+        // fewer than 8 bytes left
+        if s.len() - i < 8 {
+            i += s[i..].iter().take_while(|&&b| b == b' ').count();
+        }
+    }
+}
+
+/// Scan the number at the start of `s`, following the JSON grammar.
+///
+/// Returns its length, and whether it is an integer, or the offset of the first byte
+/// that breaks the grammar. The offset is `s.len()` if `s` is a number cut short.
+pub(crate) fn scan_number(s: &[u8]) -> Result<(usize, bool), usize> {
+    fn digits(s: &[u8], i: usize) -> usize {
+        i + s[i..].iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+
+    let mut i = 0;
+    if let Some(b'-') = s.first() {
+        i += 1;
+    }
+    i = match s.get(i) {
+        Some(b'0') => i + 1,
+        Some(b'1'..=b'9') => digits(s, i + 1),
+        _ => return Err(i),
+    };
+
+    let mut integer = true;
+    if let Some(b'.') = s.get(i) {
+        let j = digits(s, i + 1);
+        if j == i + 1 {
+            return Err(j);
+        }
+        (i, integer) = (j, false);
+    }
+    if let Some(b'e' | b'E') = s.get(i) {
+        i += 1;
+        if let Some(b'+' | b'-') = s.get(i) {
+            i += 1;
+        }
+        let j = digits(s, i);
+        if j == i {
+            return Err(j);
+        }
+        (i, integer) = (j, false);
+    }
+    Ok((i, integer))
+}
+
+/// Whether `raw` is a whole token of the kind `leaf`, as the lexer would have found it.
+///
+/// Spans that were loaded rather than lexed are checked with this, so reading them cannot panic.
+pub(crate) fn is_leaf_token(leaf: LeafValue, raw: &str) -> bool {
+    let bytes = raw.as_bytes();
+    match leaf {
+        LeafValue::Bool(b) => raw == if b { "true" } else { "false" },
+        LeafValue::Null => raw == "null",
+        LeafValue::String => matches!(bytes, [quote @ (b'"' | b'\''), .., end] if end == quote),
+        LeafValue::Number | LeafValue::BigInt => {
+            matches!(scan_number(bytes), Ok((len, _)) if len == bytes.len())
+        }
+        LeafValue::RadixInt(radix) => {
+            let prefix = match radix {
+                Radix::Binary => b'b',
+                Radix::Octal => b'o',
+                Radix::Hex => b'x',
+            };
+            match raw.strip_prefix('-').unwrap_or(raw).as_bytes() {
+                [b'0', p, digits @ ..] if p.to_ascii_lowercase() == prefix => {
+                    !digits.is_empty()
+                        && digits.iter().all(|&b| char::from(b).is_digit(radix.base()))
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+fn lex_number(lexer: &mut Lexer<Token>) -> Result<LeafValue, ()> {
+    let start = lexer.span().start;
+    let (len, integer) = match scan_number(&lexer.source().as_bytes()[start..]) {
+        Ok(number) => number,
+        Err(i) => {
+            lexer.bump(i.saturating_sub(1));
+            return Err(());
+        }
+    };
+    // the first byte was already matched
+    lexer.bump(len - 1);
+
+    if !integer || !lexer.extras.dialect.big_ints {
+        return Ok(LeafValue::Number);
+    }
+    // integers beyond 2^53 might not survive a round trip through `f64`.
+    const MAX_SAFE: u64 = 1 << 53;
+    let digits = lexer.slice().strip_prefix('-').unwrap_or(lexer.slice());
+    match digits.parse::<u64>() {
+        Ok(n) if n <= MAX_SAFE => Ok(LeafValue::Number),
+        _ => Ok(LeafValue::BigInt),
+    }
 }
 
-fn lex_string(lexer: &mut Lexer<Token>) -> Result<LeafValue, ()> {
+fn lex_radix(lexer: &mut Lexer<Token>, radix: Radix) -> Result<LeafValue, ()> {
+    if !lexer.extras.dialect.radix_numbers {
+        return Err(());
+    }
+    Ok(LeafValue::RadixInt(radix))
+}
+
+fn lex_single_quoted(lexer: &mut Lexer<Token>) -> Result<LeafValue, ()> {
+    if !lexer.extras.dialect.single_quotes {
+        return Err(());
+    }
+    lex_quoted(lexer, b'\'')
+}
+
+/// Find the closing quote, noting whether the string has escapes on the way.
+fn lex_quoted(lexer: &mut Lexer<Token>, quote: u8) -> Result<LeafValue, ()> {
     let s = lexer.remainder();
+    lexer.extras.escaped = false;
 
     let mut i = 0;
     loop {
         let Some(b) = s.as_bytes().get(i..) else {
             break Err(());
         };
-        match memchr2(b'\\', b'"', b) {
+        match memchr2(b'\\', quote, b) {
             Some(j) => {
                 if b[j] == b'\\' {
-                    i += j + 2;
+                    // without unicode escapes, `\u` is rejected while lexing.
+                    #[cfg(not(feature = "unicode-escapes"))]
+                    if b.get(j + 1) == Some(&b'u') {
+                        break Err(());
+                    }
+                    lexer.extras.escaped = true;
+                    if lexer.extras.dialect.validate_escapes {
+                        let mut end = i + j + 1;
+                        if unescape_char(s.as_bytes(), &mut end, quote).is_err() {
+                            // point at the invalid escape, which may be a multi-byte char
+                            let at = i + j + 1;
+                            lexer.bump(at + s[at..].chars().next().map_or(0, char::len_utf8));
+                            break Err(());
+                        }
+                        i = end;
+                    } else {
+                        i += j + 2;
+                    }
                 } else {
                     i += j + 1;
                     lexer.bump(i);
@@ -77,6 +462,30 @@ pub enum LeafValue {
     Null,
     Number,
     String,
+    /// An integer like `0xff`, allowed by [`Dialect::radix_numbers`].
+    RadixInt(Radix),
+    /// An integer too large for an `f64` to hold exactly, with [`Dialect::big_ints`].
+    BigInt,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Radix {
+    /// `0b101`
+    Binary,
+    /// `0o17`
+    Octal,
+    /// `0xff`
+    Hex,
+}
+
+impl Radix {
+    pub fn base(self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Hex => 16,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -91,6 +500,13 @@ enum StackItemKind {
     Object(u32, u32),
 }
 
+/// A value the parser has finished, that goes into the arena when its container closes.
+#[derive(Debug, Clone)]
+struct Pending {
+    span: Range<u32>,
+    kind: ValueKind,
+}
+
 #[derive(Debug, Clone)]
 enum ContextItem {
     WaitingKey,
@@ -99,53 +515,363 @@ enum ContextItem {
     Value { span: Range<u32>, value: ValueKind },
 }
 
+/// How many of the innermost open containers an [`Error`] remembers.
+pub const ERROR_FRAMES: usize = 3;
+
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct Error {
     token: Option<Token>,
     span: Range<u32>,
-    stack: Vec<StackItem>,
+    /// how many containers were open.
+    depth: u32,
+    /// the innermost open containers, outermost first.
+    ///
+    /// Only a summary is kept, so that the parser keeps its stack to reuse.
+    frames: [Option<ErrorFrame>; ERROR_FRAMES],
     context: ContextItem,
+    /// what was parsed before the error, with [`parse_keep_partial`].
+    partial: Option<Box<Partial>>,
 }
 
 #[derive(Debug, Clone)]
-pub struct Value {
-    pub span: Range<u32>,
-    pub kind: ValueKind,
+struct ErrorFrame {
+    start: u32,
+    /// the key or index of the member that was being parsed.
+    segment: ErrorSegment,
 }
 
 #[derive(Debug, Clone)]
+enum ErrorSegment {
+    Index(u32),
+    Key(StringKey),
+    /// in an object, before the key.
+    NoKey,
+}
+
+impl Error {
+    /// The byte range of the source where parsing failed.
+    pub fn span(&self) -> Range<u32> {
+        self.span.clone()
+    }
+
+    /// How many objects and arrays were open where parsing failed.
+    pub fn depth(&self) -> usize {
+        self.depth as usize
+    }
+
+    /// The innermost containers that were open, outermost first.
+    ///
+    /// At most [`ERROR_FRAMES`] are kept, so there are fewer than [`depth`](Self::depth)
+    /// in deep documents.
+    pub fn open(&self) -> impl Iterator<Item = OpenContainer> + '_ {
+        self.frames.iter().flatten().map(|frame| OpenContainer {
+            kind: match frame.segment {
+                ErrorSegment::Index(_) => Container::Array,
+                ErrorSegment::Key(_) | ErrorSegment::NoKey => Container::Object,
+            },
+            start: frame.start,
+        })
+    }
+
+    /// What was parsed before the error, if it was kept by [`parse_keep_partial`].
+    pub fn partial(&self) -> Option<&Partial> {
+        self.partial.as_deref()
+    }
+
+    /// The path to where parsing failed, through the containers in [`open`](Self::open).
+    ///
+    /// `arena` must be the arena that was being parsed into.
+    pub fn path<'a>(&self, arena: &'a Arena<'_>) -> JsonPath<'a> {
+        let path = self
+            .frames
+            .iter()
+            .flatten()
+            .filter_map(|frame| match &frame.segment {
+                ErrorSegment::Index(i) => Some(PathSegment::Index(*i as usize)),
+                ErrorSegment::Key(key) => Some(PathSegment::Key(&arena[key])),
+                ErrorSegment::NoKey => None,
+            });
+        JsonPath(path.collect())
+    }
+}
+
+/// A value in an [`Arena`], as a handle to where it is stored there.
+///
+/// Read it with [`Value::kind`] and the accessors of its arena. Its span is kept apart,
+/// in an array next to the values, and looked up with [`Arena::span`].
+///
+/// It is packed into 8 bytes. The top two bits of `head` tag what it is, and the rest hold
+/// the [`LeafValue`] of a leaf or the length of a container. `tail` holds the slot of a leaf,
+/// where the values of a container start, or where the keys of an object start in its header.
+#[derive(Clone, Copy)]
+pub struct Value {
+    head: u32,
+    tail: u32,
+}
+
+/// A [`Value`], unpacked.
+#[derive(Debug, Clone, Copy)]
+enum Repr {
+    /// A leaf, whose span is in `slot`.
+    Leaf { leaf: LeafValue, slot: u32 },
+    /// An object, whose span and keys are in the header before its values.
+    Object(Object),
+    /// An array, whose span is in the header before its values.
+    Array(Array),
+    /// The slot just before the values of a container, holding its span, and where its keys
+    /// start if it is an object.
+    Header { keys: u32 },
+}
+
+/// The most values a single object or array can hold, as its length has 30 bits.
+const MAX_LEN: u32 = (1 << 30) - 1;
+
+impl Value {
+    const TAG_LEAF: u32 = 0;
+    const TAG_ARRAY: u32 = 1;
+    const TAG_OBJECT: u32 = 2;
+    const TAG_HEADER: u32 = 3;
+
+    const fn pack(tag: u32, payload: u32, tail: u32) -> Self {
+        Value {
+            head: tag << 30 | payload,
+            tail,
+        }
+    }
+
+    /// A value of `kind`. If it is a leaf, its span is in `slot`.
+    ///
+    /// Panics if a container is longer than [`MAX_LEN`].
+    pub(crate) fn new(kind: ValueKind, slot: u32) -> Self {
+        match kind {
+            ValueKind::Leaf(leaf) => Self::pack(Self::TAG_LEAF, leaf.pack(), slot),
+            ValueKind::Object(object) => {
+                Self::container(Self::TAG_OBJECT, object.len, object.start)
+            }
+            ValueKind::Array(array) => Self::container(Self::TAG_ARRAY, array.len, array.start),
+        }
+    }
+
+    fn container(tag: u32, len: u32, start: u32) -> Self {
+        assert!(
+            len <= MAX_LEN,
+            "a container can hold at most {MAX_LEN} values"
+        );
+        Self::pack(tag, len, start)
+    }
+
+    /// The header of a container, for an object whose keys start at `keys`.
+    const fn header(keys: u32) -> Self {
+        Self::pack(Self::TAG_HEADER, 0, keys)
+    }
+
+    /// The header of an array, or a placeholder for a slot to be filled in later.
+    const HEADER: Self = Self::header(0);
+
+    fn repr(&self) -> Repr {
+        let payload = self.head & MAX_LEN;
+        match self.head >> 30 {
+            Self::TAG_LEAF => Repr::Leaf {
+                leaf: LeafValue::unpack(payload),
+                slot: self.tail,
+            },
+            Self::TAG_ARRAY => Repr::Array(Array {
+                start: self.tail,
+                len: payload,
+            }),
+            Self::TAG_OBJECT => Repr::Object(Object {
+                start: self.tail,
+                len: payload,
+            }),
+            _ => Repr::Header { keys: self.tail },
+        }
+    }
+
+    /// Whether this is the header slot of a container, rather than a value.
+    fn is_header(&self) -> bool {
+        self.head >> 30 == Self::TAG_HEADER
+    }
+
+    /// Where the span of the value is.
+    fn slot(&self) -> u32 {
+        match self.repr() {
+            Repr::Leaf { slot, .. } => slot,
+            Repr::Object(object) => object.start - 1,
+            Repr::Array(array) => array.start - 1,
+            Repr::Header { .. } => unreachable!("container headers have no slot of their own"),
+        }
+    }
+}
+
+impl core::fmt::Debug for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.repr() {
+            Repr::Header { keys } => f.debug_struct("Header").field("keys", &keys).finish(),
+            _ => self.kind().fmt(f),
+        }
+    }
+}
+
+impl LeafValue {
+    fn pack(self) -> u32 {
+        match self {
+            LeafValue::Bool(b) => b as u32,
+            LeafValue::Null => 2,
+            LeafValue::Number => 3,
+            LeafValue::String => 4,
+            LeafValue::RadixInt(Radix::Binary) => 5,
+            LeafValue::RadixInt(Radix::Octal) => 6,
+            LeafValue::RadixInt(Radix::Hex) => 7,
+            LeafValue::BigInt => 8,
+        }
+    }
+
+    fn unpack(bits: u32) -> Self {
+        match bits {
+            0 | 1 => LeafValue::Bool(bits == 1),
+            2 => LeafValue::Null,
+            3 => LeafValue::Number,
+            4 => LeafValue::String,
+            5 => LeafValue::RadixInt(Radix::Binary),
+            6 => LeafValue::RadixInt(Radix::Octal),
+            7 => LeafValue::RadixInt(Radix::Hex),
+            8 => LeafValue::BigInt,
+            _ => unreachable!("not a packed leaf"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum ValueKind {
     Leaf(LeafValue),
     Object(Object),
     Array(Array),
 }
 
-#[derive(Debug, Clone)]
+/// An object has as many keys as values. Where its keys start is kept in its header.
+#[derive(Clone, Copy)]
 pub struct Object {
-    keys: Range<u32>,
-    values: Range<u32>,
+    start: u32,
+    len: u32,
 }
 
-#[derive(Debug, Clone)]
+impl Object {
+    pub(crate) fn new(values: Range<u32>) -> Self {
+        Self {
+            start: values.start,
+            len: values.end - values.start,
+        }
+    }
+
+    /// Where the keys of the object are in the arena, read from its header in `values`.
+    pub(crate) fn keys(&self, values: &[Value]) -> Range<u32> {
+        let Repr::Header { keys } = values[self.start as usize - 1].repr() else {
+            unreachable!("the values of an object follow its header")
+        };
+        keys..keys + self.len
+    }
+
+    /// Where the values of the object are in the arena.
+    pub(crate) fn values(&self) -> Range<u32> {
+        self.start..self.start + self.len
+    }
+}
+
+impl core::fmt::Debug for Object {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Object")
+            .field("values", &self.values())
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy)]
 pub struct Array {
-    values: Range<u32>,
+    start: u32,
+    len: u32,
+}
+
+impl Array {
+    pub(crate) fn new(values: Range<u32>) -> Self {
+        Self {
+            start: values.start,
+            len: values.end - values.start,
+        }
+    }
+
+    /// Where the values of the array are in the arena.
+    pub(crate) fn values(&self) -> Range<u32> {
+        self.start..self.start + self.len
+    }
+}
+
+impl core::fmt::Debug for Array {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Array")
+            .field("values", &self.values())
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct StringKey(Range<u32>);
 
+/// The source string of an [`Arena`].
+#[derive(Clone)]
+enum Source<'a> {
+    Borrowed(&'a str),
+    Shared(Arc<str>),
+    /// An owned string, moved into an `Arc` rather than copied.
+    Owned(Arc<String>),
+}
+
+impl Deref for Source<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Source::Borrowed(src) => src,
+            Source::Shared(src) => src,
+            Source::Owned(src) => src,
+        }
+    }
+}
+
 struct Scratch<'a> {
-    src: &'a str,
+    src: Source<'a>,
     scratch: String,
+    /// The most bytes that parsing may grow `scratch` to.
+    max_scratch: usize,
+    /// The source text of values copied in from other arenas.
+    /// Values with a reversed span, `end < start`, point into here.
+    text: String,
 }
 
 pub struct Arena<'a> {
     scratch: Scratch<'a>,
     hasher: RandomState,
     table: HashTable<StringKey>,
+    /// The keys of a small document, which are not hashed into `table` until there are too many.
+    small: Vec<StringKey>,
     keys: Vec<StringKey>,
     values: Vec<Value>,
+    /// The span of the value in each slot of `values`, and of each container in its header.
+    spans: Vec<Range<u32>>,
+    reserve: Reserve,
+    known: Option<known::Known>,
+    numbers: Option<number::NumberCache>,
+    /// The parser stacks, kept between parses so that they only allocate once.
+    stacks: Stacks,
+    /// Which arena a [`ValueId`] or [`KeyId`] came from, changed whenever they are invalidated.
+    #[cfg(debug_assertions)]
+    brand: u32,
+    /// Whether keys are interned, which only [`bench::parse_no_intern`] turns off.
+    #[cfg(feature = "bench")]
+    intern: bool,
+    #[cfg(feature = "alloc-stats")]
+    stats: ParseStats,
+    #[cfg(feature = "trace")]
+    trace: ParseTrace,
 }
 
 impl<'a> Index<&StringKey> for Scratch<'a> {
@@ -161,6 +887,39 @@ impl<'a> Index<&StringKey> for Scratch<'a> {
     }
 }
 
+/// Documents with at most this many distinct keys intern them by a linear scan,
+/// since setting up and hashing into the table costs more.
+const SMALL_KEYS: usize = 8;
+
+impl Scratch<'_> {
+    /// Find `str` among the keys of a small document, without hashing.
+    ///
+    /// Returns `Err(())` once the document has too many keys for this,
+    /// after moving them all into `table`.
+    fn find_small(
+        &self,
+        small: &mut Vec<StringKey>,
+        hasher: &RandomState,
+        table: &mut HashTable<StringKey>,
+        str: &str,
+    ) -> Result<Option<StringKey>, ()> {
+        if !table.is_empty() {
+            return Err(());
+        }
+        if let Some(key) = small.iter().find(|key| &self[key] == str) {
+            return Ok(Some(key.clone()));
+        }
+        if small.len() < SMALL_KEYS {
+            return Ok(None);
+        }
+        for key in small.drain(..) {
+            let hash = hasher.hash_one(&self[&key]);
+            table.insert_unique(hash, key, |k| hasher.hash_one(&self[k]));
+        }
+        Err(())
+    }
+}
+
 impl<'a> Index<&StringKey> for Arena<'a> {
     type Output = str;
 
@@ -169,184 +928,438 @@ impl<'a> Index<&StringKey> for Arena<'a> {
     }
 }
 
+impl Arena<'static> {
+    /// Create an arena that owns a shared reference to its source string.
+    pub fn new_shared(src: Arc<str>) -> Self {
+        Self::with_source(Source::Shared(src))
+    }
+}
+
 impl<'a> Arena<'a> {
     pub fn new(src: &'a str) -> Self {
-        Self {
-            scratch: Scratch {
-                src,
-                scratch: String::new(),
-            },
-            hasher: RandomState::default(),
-            table: HashTable::new(),
-            keys: Vec::new(),
-            values: Vec::new(),
+        Self::with_source(Source::Borrowed(src))
+    }
+
+    /// Create an arena that decodes escaped keys into `scratch`, rather than a new allocation.
+    ///
+    /// Anything already in `scratch` is cleared. Take it back with [`Arena::into_scratch`],
+    /// to share one allocation between many arenas in turn.
+    pub fn with_scratch(src: &'a str, mut scratch: String) -> Self {
+        scratch.clear();
+        let mut arena = Self::new(src);
+        arena.scratch.scratch = scratch;
+        arena
+    }
+
+    /// Give back the scratch buffer, for the next [`Arena::with_scratch`].
+    pub fn into_scratch(self) -> String {
+        let mut scratch = self.scratch.scratch;
+        scratch.clear();
+        scratch
+    }
+
+    /// Fail parsing rather than grow the scratch buffer beyond `max` bytes.
+    ///
+    /// The scratch buffer holds keys that had escapes, decoded.
+    pub fn set_max_scratch(&mut self, max: usize) {
+        self.scratch.max_scratch = max;
+    }
+
+    fn with_source(src: Source<'a>) -> Self {
+        Self::with_buffers(src, Buffers::default())
+    }
+
+    /// Intern the key at `span`, which has escapes if `escaped`.
+    fn intern_string(&mut self, span: Range<u32>, escaped: bool) -> Result<StringKey, ()> {
+        #[cfg(feature = "alloc-stats")]
+        let before = (
+            crate::stats::Capacity::capacity_bytes(&self.scratch.scratch),
+            crate::stats::Capacity::capacity_bytes(&self.table),
+            crate::stats::Capacity::capacity_bytes(&self.small),
+        );
+        let key = self.intern_key(span, escaped);
+        #[cfg(feature = "alloc-stats")]
+        {
+            use crate::stats::Capacity;
+            let stats = &mut self.stats;
+            stats
+                .scratch
+                .record(before.0, self.scratch.scratch.capacity_bytes());
+            stats.table.record(before.1, self.table.capacity_bytes());
+            stats.small.record(before.2, self.small.capacity_bytes());
         }
+        key
     }
 
-    fn intern_string(&mut self, span: Range<u32>) -> Result<StringKey, ()> {
+    fn intern_key(&mut self, span: Range<u32>, escaped: bool) -> Result<StringKey, ()> {
+        #[cfg(feature = "bench")]
+        let intern = self.intern;
         let Self {
             scratch,
             hasher,
             table,
+            small,
+            known,
             ..
         } = self;
 
-        // check that this actually points to a string...
-        debug_assert!(span.start + 2 <= span.end);
-        debug_assert_eq!(scratch.src.as_bytes()[span.start as usize], b'"');
-        debug_assert_eq!(scratch.src.as_bytes()[span.end as usize - 1], b'"');
-
-        let mut start = span.start as usize + 1;
-        let end = span.end as usize - 1;
+        let quote = scratch.src.as_bytes()[span.start as usize];
+        let (start, end) = if quote == b'"' || quote == b'\'' {
+            // check that this actually points to a string...
+            debug_assert!(span.start + 2 <= span.end);
+            debug_assert_eq!(scratch.src.as_bytes()[span.end as usize - 1], quote);
+            (span.start as usize + 1, span.end as usize - 1)
+        } else {
+            // a bare identifier key, which has no escapes.
+            (span.start as usize, span.end as usize)
+        };
+        let raw = &scratch.src[start..end];
 
         let scratch_start = scratch.scratch.len();
 
-        loop {
-            let b = scratch.src.as_bytes();
-            let Some(escape) = memchr::memchr(b'\\', &b[start..end]) else {
-                break;
-            };
-            scratch
-                .scratch
-                .push_str(&scratch.src[start..start + escape]);
-
-            start += escape;
-            start += 1;
-            let ctrl = b[start];
-            start += 1;
-
-            match ctrl {
-                b'"' => scratch.scratch.push('"'),
-                b'\\' => scratch.scratch.push('\\'),
-                b'/' => scratch.scratch.push('/'),
-                b'b' => scratch.scratch.push('\x08'),
-                b'f' => scratch.scratch.push('\x0c'),
-                b'n' => scratch.scratch.push('\n'),
-                b'r' => scratch.scratch.push('\r'),
-                b't' => scratch.scratch.push('\t'),
-                b'u' => {
-                    // TODO: is this even right???
-                    // \u1234 -> U+1234
-                    // TODO: maybe support utf16
-
-                    let hex_bytes: [u8; 4] = *b[start..].first_chunk().ok_or(())?;
-                    let mut code = [0; 2];
-                    hex::decode_to_slice(hex_bytes, &mut code).map_err(|_| ())?;
-
-                    if let Some(c) = char::from_u32(u16::from_be_bytes(code) as u32) {
-                        scratch.scratch.push(c);
-                    } else {
-                        return Err(());
-                    }
-
-                    start += 4;
-                }
-                _ => return Err(()),
-            }
-        }
-
         let span;
         let str;
-        if scratch_start < scratch.scratch.len() {
-            scratch.scratch.push_str(&scratch.src[start..end]);
+        // keys that point into the source must be valid json string contents,
+        // so single quoted keys containing `"` are decoded into the scratch.
+        if escaped || (quote == b'\'' && memchr::memchr(b'"', raw.as_bytes()).is_some()) {
+            unescape_into(raw, quote, &mut scratch.scratch)?;
+            if scratch.scratch.len() > scratch.max_scratch {
+                scratch.scratch.truncate(scratch_start);
+                return Err(());
+            }
             span = scratch.scratch.len() as u32..scratch_start as u32;
             str = &scratch.scratch[scratch_start..];
         } else {
             span = start as u32..end as u32;
-            str = &scratch.src[start..end];
+            str = raw;
         };
 
+        #[cfg(feature = "bench")]
+        if !intern {
+            return Ok(StringKey(span));
+        }
+
+        if let Some(known) = known {
+            if let Some(id) = known.keys.id(str) {
+                let interned = &mut known.interned[id];
+                if let Some(key) = interned {
+                    scratch.scratch.truncate(scratch_start);
+                    return Ok(key.clone());
+                }
+                return Ok(interned.insert(StringKey(span)).clone());
+            }
+        }
+
+        match scratch.find_small(small, hasher, table, str) {
+            Ok(Some(key)) => {
+                scratch.scratch.truncate(scratch_start);
+                return Ok(key);
+            }
+            Ok(None) => {
+                small.push(StringKey(span.clone()));
+                return Ok(StringKey(span));
+            }
+            Err(()) => {}
+        }
+
         let hash = hasher.hash_one(str);
         match table.entry(
             hash,
             |key| &scratch[key] == str,
             |key| hasher.hash_one(&scratch[key]),
         ) {
-            Entry::Occupied(occupied_entry) => {
+            hash_table::Entry::Occupied(occupied_entry) => {
                 scratch.scratch.truncate(scratch_start);
                 Ok(occupied_entry.get().clone())
             }
-            Entry::Vacant(vacant_entry) => Ok(vacant_entry.insert(StringKey(span)).get().clone()),
+            hash_table::Entry::Vacant(vacant_entry) => {
+                Ok(vacant_entry.insert(StringKey(span)).get().clone())
+            }
+        }
+    }
+}
+
+impl Arena<'_> {
+    /// Start a container at `span`, with a header slot to hold its span, and where its keys
+    /// start if it is an object. Arrays pass `0` for `keys`.
+    ///
+    /// Returns where its values start, right after the header.
+    pub(crate) fn push_header(&mut self, span: Range<u32>, keys: u32) -> u32 {
+        track_growth!(self.values, self.values.push(Value::header(keys)));
+        track_growth!(self.spans, self.spans.push(span));
+        self.values.len() as u32
+    }
+
+    /// Start a container at `span`, and fill it with `children`.
+    ///
+    /// Returns where its values start.
+    fn push_children(
+        &mut self,
+        span: Range<u32>,
+        keys: u32,
+        children: impl ExactSizeIterator<Item = Pending>,
+    ) -> u32 {
+        let start = self.push_header(span, keys);
+        track_growth!(self.spans, self.spans.reserve(children.len()));
+        track_growth!(
+            self.values,
+            self.values.extend(children.enumerate().map(|(i, child)| {
+                self.spans.push(child.span);
+                Value::new(child.kind, start + i as u32)
+            }))
+        );
+        start
+    }
+
+    /// Put a value of `kind` into the next slot, with its span.
+    pub(crate) fn push(&mut self, kind: ValueKind, span: Range<u32>) -> Value {
+        let value = Value::new(kind, self.values.len() as u32);
+        self.values.push(value);
+        self.spans.push(span);
+        value
+    }
+
+    /// Put a copy of `value`, from this arena, into the next slot.
+    pub(crate) fn push_value(&mut self, value: Value) -> Value {
+        let span = self.span(&value);
+        self.push(value.kind(), span)
+    }
+
+    /// Put a value of `kind` into a slot reserved earlier.
+    pub(crate) fn set(&mut self, slot: u32, kind: ValueKind, span: Range<u32>) {
+        self.values[slot as usize] = Value::new(kind, slot);
+        self.spans[slot as usize] = span;
+    }
+
+    /// A value of `kind` that is not in a container.
+    ///
+    /// A container is already in the arena, but a leaf needs a slot of its own for its span.
+    pub(crate) fn root(&mut self, kind: ValueKind, span: Range<u32>) -> Value {
+        match kind {
+            ValueKind::Leaf(_) => self.push(kind, span),
+            _ => Value::new(kind, 0),
         }
     }
 }
 
-struct Parser<'a, 's> {
+/// Decode the escapes in the contents of a JSON string, appending the result to `out`.
+///
+/// `quote` is the quote character of the string. `\'` is only valid in single quoted strings.
+fn unescape_into(s: &str, quote: u8, out: &mut String) -> Result<(), ()> {
+    let b = s.as_bytes();
+    let mut start = 0;
+
+    while let Some(escape) = memchr::memchr(b'\\', &b[start..]) {
+        out.push_str(&s[start..start + escape]);
+
+        start += escape + 1;
+        out.push(unescape_char(b, &mut start, quote)?);
+    }
+
+    out.push_str(&s[start..]);
+    Ok(())
+}
+
+/// Decode the escape that starts at `b[*start]`, just after its backslash, moving `start` past it.
+fn unescape_char(b: &[u8], start: &mut usize, quote: u8) -> Result<char, ()> {
+    let ctrl = *b.get(*start).ok_or(())?;
+    *start += 1;
+
+    let c = match ctrl {
+        b'"' => '"',
+        b'\'' if quote == b'\'' => '\'',
+        b'\\' => '\\',
+        b'/' => '/',
+        b'b' => '\x08',
+        b'f' => '\x0c',
+        b'n' => '\n',
+        b'r' => '\r',
+        b't' => '\t',
+        #[cfg(feature = "unicode-escapes")]
+        b'u' => {
+            let high = hex4(&b[*start..]).ok_or(())?;
+            *start += 4;
+
+            // characters outside the BMP are written as a UTF-16 surrogate pair.
+            let code = match high {
+                0xd800..=0xdbff => {
+                    let low = match b.get(*start..*start + 2) {
+                        Some(b"\\u") => hex4(&b[*start + 2..]).ok_or(())?,
+                        _ => return Err(()),
+                    };
+                    if !(0xdc00..=0xdfff).contains(&low) {
+                        return Err(());
+                    }
+                    *start += 6;
+                    0x10000 + ((high as u32 - 0xd800) << 10) + (low as u32 - 0xdc00)
+                }
+                _ => high as u32,
+            };
+            char::from_u32(code).ok_or(())?
+        }
+        _ => return Err(()),
+    };
+    Ok(c)
+}
+
+/// The value of 4 hex digits at the start of `b`.
+#[cfg(feature = "unicode-escapes")]
+fn hex4(b: &[u8]) -> Option<u16> {
+    let digits: &[u8; 4] = b.first_chunk()?;
+    digits
+        .iter()
+        .try_fold(0, |code, &d| Some(code << 4 | hex_digit(d)? as u16))
+}
+
+#[cfg(any(feature = "unicode-escapes", feature = "uuid"))]
+pub(crate) fn hex_digit(d: u8) -> Option<u8> {
+    match d {
+        b'0'..=b'9' => Some(d - b'0'),
+        b'a'..=b'f' => Some(d - b'a' + 10),
+        b'A'..=b'F' => Some(d - b'A' + 10),
+        _ => None,
+    }
+}
+
+struct RawParser<'a, 's> {
     arena: &'a mut Arena<'s>,
-    lexer: Lexer<'s, Token>,
+    lexer: Lexer<'a, Token>,
 
     /// tracks which object or array we are in
     stack: Vec<StackItem>,
     /// values used by the current/parent objects or arrays.
-    value_stack: Vec<Value>,
+    value_stack: Vec<Pending>,
     /// keys used by the current/parent objects
     key_stack: Vec<StringKey>,
+    /// what kind of token we are expecting next
+    context: ContextItem,
 }
 
 enum PollParse {
     Ready(Value),
-    Pending(ContextItem),
+    Pending,
 }
 
-impl Parser<'_, '_> {
+impl RawParser<'_, '_> {
     #[cold]
     fn early_eof(&mut self, context: ContextItem) -> Error {
-        let src = self.arena.scratch.src;
-        Error {
-            token: None,
-            span: src.len() as u32..src.len() as u32,
-            stack: core::mem::take(&mut self.stack),
-            context,
-        }
+        let len = self.lexer.source().len() as u32;
+        self.error(context, None, len..len)
     }
 
     #[cold]
     fn parse_error(&mut self, context: ContextItem, token: Token, span: Range<u32>) -> Error {
-        Error {
-            token: Some(token),
-            span,
-            stack: core::mem::take(&mut self.stack),
-            context,
-        }
+        self.error(context, Some(token), span)
     }
 
     #[cold]
     fn token_error(&mut self, context: ContextItem, span: Range<u32>) -> Error {
+        self.error(context, None, span)
+    }
+
+    /// Summarize the open containers into an error, leaving the stacks as they are.
+    fn error(&self, context: ContextItem, token: Option<Token>, span: Range<u32>) -> Error {
+        let skip = self.stack.len().saturating_sub(ERROR_FRAMES);
+        let mut frames = [const { None }; ERROR_FRAMES];
+        for (i, item) in self.stack.iter().enumerate().skip(skip) {
+            // the values of a container end where the next one's start.
+            let end = match self.stack.get(i + 1) {
+                Some(StackItem {
+                    kind: StackItemKind::Array(v) | StackItemKind::Object(v, _),
+                    ..
+                }) => *v as usize,
+                None => self.value_stack.len(),
+            };
+            let segment = match item.kind {
+                StackItemKind::Array(v) => ErrorSegment::Index(end as u32 - v),
+                StackItemKind::Object(v, k) => {
+                    let key = self.key_stack.get(k as usize + end - v as usize);
+                    match (key, &context) {
+                        (Some(key), _) => ErrorSegment::Key(key.clone()),
+                        (None, ContextItem::Key { key, .. }) if i + 1 == self.stack.len() => {
+                            ErrorSegment::Key(key.clone())
+                        }
+                        (None, _) => ErrorSegment::NoKey,
+                    }
+                }
+            };
+            frames[i - skip] = Some(ErrorFrame {
+                start: item.span.start,
+                segment,
+            });
+        }
+
         Error {
-            token: None,
+            token,
             span,
-            stack: core::mem::take(&mut self.stack),
+            depth: self.stack.len() as u32,
+            frames,
             context,
+            partial: None,
         }
     }
 
     #[inline]
-    fn step(&mut self, mut context: ContextItem) -> Result<PollParse, Error> {
+    fn step(&mut self) -> Result<PollParse, Error> {
+        let mut unlimited = usize::MAX;
+        self.step_within(&mut unlimited)
+    }
+
+    /// Read the next token, or a run of leaves in an array, which takes two tokens from
+    /// `budget` for each leaf after the first.
+    #[inline]
+    fn step_within(&mut self, budget: &mut usize) -> Result<PollParse, Error> {
         let Self {
             arena,
             lexer,
             stack,
             value_stack,
             key_stack,
+            context,
         } = self;
-
-        let token = match lexer.next() {
-            Some(Ok(token)) => token,
-            Some(Err(())) => {
-                let span = lexer.span();
-                let span = (span.start as u32)..(span.end as u32);
-                return Err(self.token_error(context, span));
+        let mut context = core::mem::replace(context, ContextItem::WaitingValue);
+
+        lexer.bump(skip_whitespace(lexer.remainder().as_bytes()));
+
+        // the structural tokens are a single byte, so they skip the lexer.
+        let start = lexer.span().end;
+        let structural = lexer.remainder().as_bytes().first();
+        let (token, span) = match structural.and_then(|&b| STRUCTURAL[b as usize]) {
+            Some(token) => {
+                lexer.bump(1);
+                trace!(arena, t => t.structural += 1);
+                (token, start as u32..start as u32 + 1)
             }
-            None => match context {
-                ContextItem::Value { span, value } if stack.is_empty() => {
-                    return Ok(PollParse::Ready(Value { span, kind: value }))
+            None => match lexer.next() {
+                Some(Ok(token)) => {
+                    let span = lexer.span();
+                    (token, (span.start as u32)..(span.end as u32))
+                }
+                Some(Err(())) => {
+                    let span = lexer.span();
+                    let span = (span.start as u32)..(span.end as u32);
+                    return Err(self.token_error(context, span));
                 }
-                context => return Err(self.early_eof(context)),
+                None => match context {
+                    ContextItem::Value { span, value } if stack.is_empty() => {
+                        return Ok(PollParse::Ready(arena.root(value, span)))
+                    }
+                    context => return Err(self.early_eof(context)),
+                },
             },
         };
 
-        let span = lexer.span();
-        let span = (span.start as u32)..(span.end as u32);
+        if lexer.extras.dialect.salvage {
+            context = dialect::recover(
+                &token,
+                lexer.source(),
+                span.start,
+                context,
+                stack,
+                value_stack,
+                key_stack,
+            );
+        }
 
         macro_rules! bail {
             ($context:expr) => {
@@ -356,8 +1369,56 @@ impl Parser<'_, '_> {
 
         match token {
             Token::Leaf(value) => match context {
+                ContextItem::WaitingValue
+                    if stack.is_empty() && lexer.extras.dialect.require_container =>
+                {
+                    bail!(context)
+                }
                 // in value position, a leaf value is always ok
                 ContextItem::WaitingValue => {
+                    let (mut span, mut value) = (span, value);
+                    trace!(arena, t => t.leaf(&value, lexer.extras.escaped));
+
+                    // inside an array, take a run of `leaf, leaf, ...` in one go, within budget.
+                    // the run stops before a leaf that ends the input,
+                    // as that might be a number with more digits to come.
+                    if matches!(
+                        stack.last(),
+                        Some(StackItem {
+                            kind: StackItemKind::Array(_),
+                            ..
+                        })
+                    ) && !lexer.extras.dialect.salvage
+                    {
+                        while *budget >= 2 {
+                            let mut ahead = lexer.clone();
+                            let Some(Ok(Token::Comma)) = ahead.next() else {
+                                break;
+                            };
+                            let Some(Ok(Token::Leaf(next))) = ahead.next() else {
+                                break;
+                            };
+                            if ahead.remainder().is_empty() {
+                                break;
+                            }
+
+                            value_stack.push(Pending {
+                                span,
+                                kind: ValueKind::Leaf(value),
+                            });
+                            let next_span = ahead.span();
+                            span = (next_span.start as u32)..(next_span.end as u32);
+                            value = next;
+                            *lexer = ahead;
+                            *budget -= 2;
+                            trace!(arena, t => {
+                                t.structural += 1;
+                                t.structural_lexed += 1;
+                                t.leaf(&value, lexer.extras.escaped);
+                            });
+                        }
+                    }
+
                     context = ContextItem::Value {
                         span,
                         value: ValueKind::Leaf(value),
@@ -365,8 +1426,23 @@ impl Parser<'_, '_> {
                 }
                 // in a key position, only string values are ok
                 ContextItem::WaitingKey if value == LeafValue::String => {
+                    trace!(arena, t => t.key(lexer.extras.escaped));
                     context = ContextItem::Key {
-                        key: match arena.intern_string(span.clone()) {
+                        key: match arena.intern_string(span.clone(), lexer.extras.escaped) {
+                            Ok(key) => key,
+                            Err(()) => bail!(context),
+                        },
+                        span,
+                    }
+                }
+                context => bail!(context),
+            },
+            // bare identifiers are only ok as keys
+            Token::Ident => match context {
+                ContextItem::WaitingKey => {
+                    trace!(arena, t => t.key(false));
+                    context = ContextItem::Key {
+                        key: match arena.intern_string(span.clone(), false) {
                             Ok(key) => key,
                             Err(()) => bail!(context),
                         },
@@ -385,6 +1461,10 @@ impl Parser<'_, '_> {
                             key_stack.len() as u32,
                         ),
                     });
+                    trace!(arena, t => {
+                        t.objects += 1;
+                        t.open(stack.len());
+                    });
                     context = ContextItem::WaitingKey;
                 }
                 context => bail!(context),
@@ -396,6 +1476,10 @@ impl Parser<'_, '_> {
                         span: span.start..,
                         kind: StackItemKind::Array(value_stack.len() as u32),
                     });
+                    trace!(arena, t => {
+                        t.arrays += 1;
+                        t.open(stack.len());
+                    });
                     context = ContextItem::WaitingValue;
                 }
                 context => bail!(context),
@@ -419,37 +1503,45 @@ impl Parser<'_, '_> {
 
                         match context {
                             ContextItem::WaitingKey if value_stack.len() == vindex as usize => {
+                                let vi = arena.push_header(span.clone(), arena.keys.len() as u32);
                                 context = ContextItem::Value {
                                     span,
-                                    value: ValueKind::Object(Object {
-                                        keys: 0..0,
-                                        values: 0..0,
-                                    }),
+                                    value: ValueKind::Object(Object::new(vi..vi)),
                                 };
                             }
-                            ContextItem::Value { span, value: kind } => {
-                                value_stack.push(Value {
-                                    span: span.clone(),
+                            ContextItem::Value {
+                                span: value_span,
+                                value: kind,
+                            } => {
+                                value_stack.push(Pending {
+                                    span: value_span,
                                     kind,
                                 });
 
-                                let vi = arena.values.len();
-                                arena.values.extend(value_stack.drain(vindex as usize..));
-                                let vj = arena.values.len();
-
-                                let ki = arena.keys.len();
-                                arena.keys.extend(key_stack.drain(kindex as usize..));
-                                let kj = arena.keys.len();
+                                let vi = arena.push_children(
+                                    span.clone(),
+                                    arena.keys.len() as u32,
+                                    value_stack.drain(vindex as usize..),
+                                );
+                                let vj = arena.values.len() as u32;
+                                track_growth!(
+                                    arena.keys,
+                                    arena.keys.extend(key_stack.drain(kindex as usize..))
+                                );
 
                                 context = ContextItem::Value {
                                     span,
-                                    value: ValueKind::Object(Object {
-                                        keys: ki as u32..kj as u32,
-                                        values: vi as u32..vj as u32,
-                                    }),
+                                    value: ValueKind::Object(Object::new(vi..vj)),
                                 };
                             }
-                            context => bail!(context),
+                            context => {
+                                // it is still open where the error is
+                                stack.push(StackItem {
+                                    span: start..,
+                                    kind: StackItemKind::Object(vindex, kindex),
+                                });
+                                bail!(context)
+                            }
                         }
                     }
                     Some(v) => {
@@ -478,29 +1570,41 @@ impl Parser<'_, '_> {
 
                         match context {
                             ContextItem::WaitingValue if value_stack.len() == vindex as usize => {
+                                let vi = arena.push_header(span.clone(), 0);
                                 context = ContextItem::Value {
                                     span,
-                                    value: ValueKind::Array(Array { values: 0..0 }),
+                                    value: ValueKind::Array(Array::new(vi..vi)),
                                 };
                             }
-                            ContextItem::Value { span, value: kind } => {
-                                value_stack.push(Value {
-                                    span: span.clone(),
+                            ContextItem::Value {
+                                span: value_span,
+                                value: kind,
+                            } => {
+                                value_stack.push(Pending {
+                                    span: value_span,
                                     kind,
                                 });
 
-                                let vi = arena.values.len();
-                                arena.values.extend(value_stack.drain(vindex as usize..));
-                                let vj = arena.values.len();
+                                let vi = arena.push_children(
+                                    span.clone(),
+                                    0,
+                                    value_stack.drain(vindex as usize..),
+                                );
+                                let vj = arena.values.len() as u32;
 
                                 context = ContextItem::Value {
                                     span,
-                                    value: ValueKind::Array(Array {
-                                        values: vi as u32..vj as u32,
-                                    }),
+                                    value: ValueKind::Array(Array::new(vi..vj)),
                                 };
                             }
-                            context => bail!(context),
+                            context => {
+                                // it is still open where the error is
+                                stack.push(StackItem {
+                                    span: start..,
+                                    kind: StackItemKind::Array(vindex),
+                                });
+                                bail!(context)
+                            }
                         }
                     }
                     Some(v) => {
@@ -528,7 +1632,7 @@ impl Parser<'_, '_> {
             // commas may only follow value items if we are in an object or array
             Token::Comma => match context {
                 ContextItem::Value { span, value } if !stack.is_empty() => {
-                    value_stack.push(Value { span, kind: value });
+                    value_stack.push(Pending { span, kind: value });
                     match stack.last_mut().unwrap().kind {
                         StackItemKind::Object(_, _) => context = ContextItem::WaitingKey,
                         StackItemKind::Array(_) => context = ContextItem::WaitingValue,
@@ -538,80 +1642,180 @@ impl Parser<'_, '_> {
             },
         }
 
-        Ok(PollParse::Pending(context))
+        self.context = context;
+        Ok(PollParse::Pending)
     }
 
-    fn step_while(
-        &mut self,
-        mut f: impl FnMut() -> bool,
-        mut context: ContextItem,
-    ) -> Result<PollParse, Error> {
-        while f() {
-            match self.step(context)? {
-                PollParse::Ready(value) => return Ok(PollParse::Ready(value)),
-                PollParse::Pending(c) => context = c,
+    /// Read up to `n` tokens, counting each leaf of a run.
+    fn step_n(&mut self, mut n: usize) -> Result<PollParse, Error> {
+        while n > 0 {
+            n -= 1;
+            if let PollParse::Ready(value) = self.step_within(&mut n)? {
+                return Ok(PollParse::Ready(value));
             }
         }
-        Ok(PollParse::Pending(context))
+        Ok(PollParse::Pending)
     }
 }
 
+/// Parse the source of `arena` into it.
+///
+/// If parsing fails, the values and keys it added are removed again,
+/// so the arena can still be used for what it held before.
 pub fn parse(arena: &mut Arena<'_>) -> Result<Value, Error> {
-    let lexer = Token::lexer(arena.scratch.src);
+    let mut stacks = core::mem::take(&mut arena.stacks);
+    let res = parse_with(arena, &mut stacks);
+    arena.stacks = stacks;
+    res
+}
+
+/// The parser stacks, kept separately so their allocations can be reused.
+#[derive(Default)]
+struct Stacks {
+    stack: Vec<StackItem>,
+    value_stack: Vec<Pending>,
+    key_stack: Vec<StringKey>,
+}
+
+impl Stacks {
+    fn clear(&mut self) {
+        self.stack.clear();
+        self.value_stack.clear();
+        self.key_stack.clear();
+    }
+}
+
+fn parse_with(arena: &mut Arena<'_>, stacks: &mut Stacks) -> Result<Value, Error> {
+    let len = arena.scratch.src.len();
+    parse_range(arena, stacks, 0..len, Dialect::default())
+}
 
-    let mut parser = Parser {
+/// Parse only the value within `range` of the arena source.
+///
+/// Spans are still relative to the start of the source.
+fn parse_range(
+    arena: &mut Arena<'_>,
+    stacks: &mut Stacks,
+    range: Range<usize>,
+    dialect: Dialect,
+) -> Result<Value, Error> {
+    let src = arena.scratch.src.clone();
+    arena.reserve_for(range.len());
+    // a [`Parser`] dropped part way through may have left its stacks behind.
+    stacks.clear();
+    let mut lexer = Token::lexer_with_extras(&src[..range.end], dialect.into());
+    lexer.bump(range.start);
+    let mark = arena.mark();
+
+    let mut parser = RawParser {
         arena,
         lexer,
-        stack: vec![],
-        value_stack: vec![],
-        key_stack: vec![],
+        stack: core::mem::take(&mut stacks.stack),
+        value_stack: core::mem::take(&mut stacks.value_stack),
+        key_stack: core::mem::take(&mut stacks.key_stack),
+        // to start, we expect a value item.
+        context: ContextItem::WaitingValue,
     };
 
-    // what kind of token are we expecting.
-    // to start, we expect a value item.
-    let mut context = ContextItem::WaitingValue;
-
-    loop {
-        match parser.step(context)? {
-            PollParse::Ready(value) => break Ok(value),
-            PollParse::Pending(c) => context = c,
+    let res = loop {
+        match parser.step() {
+            Ok(PollParse::Ready(value)) => break Ok(value),
+            Ok(PollParse::Pending) => {}
+            Err(e) => break Err(e),
         }
+    };
+    if res.is_err() {
+        parser.arena.rollback(mark);
     }
+
+    stacks.stack = parser.stack;
+    stacks.value_stack = parser.value_stack;
+    stacks.key_stack = parser.key_stack;
+    stacks.stack.clear();
+    stacks.value_stack.clear();
+    stacks.key_stack.clear();
+
+    res
 }
 
 const YIELD_AFTER: usize = 4096;
 
 pub async fn parse_async(arena: &mut Arena<'_>) -> Result<Value, Error> {
-    let lexer = Token::lexer(arena.scratch.src);
+    let mut stacks = core::mem::take(&mut arena.stacks);
+    let res = parse_async_with(arena, &mut stacks).await;
+    arena.stacks = stacks;
+    res
+}
+
+async fn parse_async_with(arena: &mut Arena<'_>, stacks: &mut Stacks) -> Result<Value, Error> {
+    arena.reserve_for(arena.scratch.src.len());
+    stacks.clear();
+    let src = arena.scratch.src.clone();
+    let lexer = Token::lexer(&src);
+    let mark = arena.mark();
 
-    let mut parser = Parser {
+    let mut parser = RawParser {
         arena,
         lexer,
-        stack: vec![],
-        value_stack: vec![],
-        key_stack: vec![],
+        stack: core::mem::take(&mut stacks.stack),
+        value_stack: core::mem::take(&mut stacks.value_stack),
+        key_stack: core::mem::take(&mut stacks.key_stack),
+        // to start, we expect a value item.
+        context: ContextItem::WaitingValue,
     };
 
-    // what kind of token are we expecting.
-    // to start, we expect a value item.
-    let mut context = ContextItem::WaitingValue;
-
-    core::future::poll_fn(move |cx| {
-        let mut i = 0..YIELD_AFTER;
-        match parser.step_while(|| i.next().is_some(), context.clone())? {
-            PollParse::Ready(value) => return Poll::Ready(Ok(value)),
-            PollParse::Pending(c) => context = c,
+    let res = core::future::poll_fn(|cx| {
+        if let PollParse::Ready(value) = parser.step_n(YIELD_AFTER)? {
+            return Poll::Ready(Ok(value));
         }
 
         cx.waker().wake_by_ref();
         Poll::Pending
     })
-    .await
+    .await;
+    if res.is_err() {
+        parser.arena.rollback(mark);
+    }
+
+    stacks.stack = parser.stack;
+    stacks.value_stack = parser.value_stack;
+    stacks.key_stack = parser.key_stack;
+    stacks.stack.clear();
+    stacks.value_stack.clear();
+    stacks.key_stack.clear();
+
+    res
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Arena;
+    use alloc::string::String;
+
+    use crate::{Arena, Value};
+
+    #[test]
+    fn unicode_escapes() {
+        let src = r#"{"caf\u00e9": "\u00e9"}"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena);
+        #[cfg(feature = "unicode-escapes")]
+        assert_eq!(arena.to_json(&value.unwrap()), r#"{"café":"\u00e9"}"#);
+        #[cfg(feature = "unicode-escapes")]
+        for (src, expected) in [
+            (r#""\ud83e\udd80""#, Some("🦀")),
+            (r#""\uD83E\uDD80!""#, Some("🦀!")),
+            (r#""\ud83e""#, None),
+            (r#""\ud83e\u0041""#, None),
+            (r#""\udd80""#, None),
+            (r#""\u00g9""#, None),
+        ] {
+            let mut arena = Arena::new(src);
+            let value = crate::parse(&mut arena).unwrap();
+            assert_eq!(arena.as_str(&value).as_deref(), expected, "{src}");
+        }
+        #[cfg(not(feature = "unicode-escapes"))]
+        assert_eq!(value.unwrap_err().span(), 1..2);
+    }
 
     #[test]
     fn massive_stack() {
@@ -624,6 +1828,128 @@ mod tests {
         crate::parse(&mut Arena::new(&input)).unwrap();
     }
 
+    #[test]
+    fn error_summary() {
+        use std::string::ToString;
+
+        let cases = [
+            (r#"{"a": [1, {"b": x}]}"#, 3, "/a/1/b"),
+            (r#"{"a": [1, 2 3]}"#, 2, "/a/1"),
+            (r#"{"a": {"b" 1}}"#, 2, "/a/b"),
+            (r#"{"a": {,}}"#, 2, "/a"),
+            ("[[[[[[1,]]]]]]", 6, "/0/0/1"),
+        ];
+        let mut stacks = crate::Stacks::default();
+        for (src, depth, path) in cases {
+            let mut arena = Arena::new(src);
+            let err = crate::parse_with(&mut arena, &mut stacks).unwrap_err();
+            assert_eq!(err.depth(), depth, "{src}");
+            assert_eq!(err.open().count(), depth.min(crate::ERROR_FRAMES), "{src}");
+            assert_eq!(err.path(&arena).to_string(), path, "{src}");
+            // the stack is kept for the next parse.
+            assert!(stacks.stack.capacity() > 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-escapes")]
+    fn small_keys() {
+        // the first keys are interned without hashing, until there are too many.
+        let mut src = String::from("[");
+        for i in 0..2 * super::SMALL_KEYS {
+            src.push_str(&std::format!(r#"{{"k{i}": 0, "k0": 1, "k\u0031": 2}},"#));
+        }
+        src.pop();
+        src.push(']');
+
+        let mut arena = Arena::new(&src);
+        crate::parse(&mut arena).unwrap();
+        assert!(arena.small.is_empty());
+        assert_eq!(arena.table.len(), 2 * super::SMALL_KEYS);
+        for i in 0..arena.keys.len() {
+            for j in 0..arena.keys.len() {
+                let (a, b) = (&arena.keys[i], &arena.keys[j]);
+                assert_eq!(a == b, arena[a] == arena[b]);
+            }
+        }
+    }
+
+    #[test]
+    fn value_size() {
+        // the arena is mostly values, so keep them packed.
+        assert_eq!(core::mem::size_of::<Value>(), 8);
+    }
+
+    #[test]
+    fn leaf_runs() {
+        let src = r#"[1,"a",true,[2,3],null,{"k":[4]},5]"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(arena.to_json(&value), src);
+
+        let mut arena = Arena::new(r#"[1, 2, 3 4]"#);
+        assert_eq!(crate::parse(&mut arena).unwrap_err().span(), 9..10);
+        let mut arena = Arena::new(r#"[1, 2, "3]"#);
+        assert_eq!(crate::parse(&mut arena).unwrap_err().span(), 7..8);
+    }
+
+    #[test]
+    fn number_grammar() {
+        for src in ["0", "-0", "12", "1.5", "-0.25e-3", "1E+9", "10e10"] {
+            let mut arena = Arena::new(src);
+            let value = crate::parse(&mut arena).unwrap();
+            assert_eq!(arena.span(&value), 0..src.len() as u32);
+        }
+        for src in [
+            "01", "-", "1.", ".5", "1e", "1e+", "1.2.3", "--1", "1-2", "+1",
+        ] {
+            let mut arena = Arena::new(src);
+            assert!(crate::parse(&mut arena).is_err(), "{src}");
+        }
+
+        // a number cut short by the end of the input
+        let mut arena = Arena::new("[1, 2.");
+        let partial = crate::parse_partial(&mut arena).unwrap();
+        assert_eq!(partial.incomplete, Some(4..6));
+    }
+
+    #[test]
+    fn whitespace_runs() {
+        for n in 0..20 {
+            let ws: String = "  \n \t\r".chars().cycle().take(n).collect();
+            let src = std::format!("{ws}[{ws}1{ws},{ws}\"a\"]{ws}");
+            let mut arena = Arena::new(&src);
+            let value = crate::parse(&mut arena).unwrap();
+            assert_eq!(arena.span(&value), n as u32..src.len() as u32 - n as u32);
+            assert_eq!(arena.to_json(&value), r#"[1,"a"]"#);
+
+            let spaces = " ".repeat(n);
+            assert_eq!(
+                super::skip_whitespace(std::format!("{spaces}x").as_bytes()),
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn shared_scratch() {
+        let mut scratch = String::with_capacity(64);
+        let ptr = scratch.as_ptr();
+        for src in [r#"{"a\nb":1}"#, r#"{"c\td":2}"#] {
+            let mut arena = Arena::with_scratch(src, scratch);
+            let value = crate::parse(&mut arena).unwrap();
+            assert_eq!(arena.to_json(&value), src);
+            scratch = arena.into_scratch();
+        }
+        assert_eq!(scratch.as_ptr(), ptr);
+
+        let src = r#"{"short\n": 1, "much longer\n": 2}"#;
+        let mut arena = Arena::new(src);
+        arena.set_max_scratch(8);
+        let err = crate::parse(&mut arena).unwrap_err();
+        assert_eq!(err.span(), 15..30);
+    }
+
     #[pollster::test]
     async fn non_blocking() {
         let cool_factor = 1_000_000;
@@ -633,6 +1959,18 @@ mod tests {
         let input = std::format!("{first_half}{second_half}");
 
         crate::parse_async(&mut Arena::new(&input)).await.unwrap();
+
+        // a flat array is read in runs of leaves, which must still yield.
+        let input = std::format!("[{}1]", "1,".repeat(cool_factor));
+        let mut arena = Arena::new(&input);
+        let mut parse = core::pin::pin!(crate::parse_async(&mut arena));
+        let mut polls = 0;
+        let waker = core::task::Waker::noop();
+        let mut cx = core::task::Context::from_waker(waker);
+        while core::future::Future::poll(parse.as_mut(), &mut cx).is_pending() {
+            polls += 1;
+        }
+        assert!(polls >= 2 * cool_factor / super::YIELD_AFTER, "{polls}");
     }
 
     #[test]
@@ -662,6 +2000,12 @@ mod tests {
 
         let mut arena = Arena::new(data);
         let parsed = crate::parse(&mut arena).unwrap();
-        insta::assert_debug_snapshot!((parsed, arena.scratch.scratch, arena.values, arena.keys));
+        insta::assert_debug_snapshot!((
+            parsed,
+            arena.scratch.scratch,
+            arena.values,
+            arena.spans,
+            arena.keys
+        ));
     }
 }
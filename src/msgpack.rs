@@ -0,0 +1,133 @@
+use alloc::string::String;
+
+use crate::transcode::{JsonWriter, Position};
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: u64) -> Result<&'a [u8], usize> {
+        let end = usize::try_from(n)
+            .ok()
+            .and_then(|n| self.pos.checked_add(n))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(self.pos)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, usize> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint<const N: usize>(&mut self) -> Result<u64, usize> {
+        let mut buf = [0; 8];
+        buf[8 - N..].copy_from_slice(self.take(N as u64)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn int<const N: usize>(&mut self) -> Result<i64, usize> {
+        let n = self.uint::<N>()?;
+        // sign extend
+        let shift = 64 - 8 * N as u32;
+        Ok(((n << shift) as i64) >> shift)
+    }
+}
+
+/// Transcode a single MessagePack object into JSON text.
+///
+/// Binary data becomes unpadded base64url strings. Extension types are not supported.
+/// On failure, returns the byte offset of the error.
+pub(crate) fn to_json(bytes: &[u8]) -> Result<String, usize> {
+    let mut r = Reader { bytes, pos: 0 };
+    let mut w = JsonWriter::new();
+
+    while !w.is_done() {
+        let start = r.pos;
+        let marker = r.u8()?;
+        let position = w.item();
+
+        match marker {
+            0x00..=0x7f => w.scalar(&position, format_args!("{marker}")),
+            0xe0..=0xff => w.scalar(&position, format_args!("{}", marker as i8)),
+            0xc0 => w.scalar(&position, format_args!("null")),
+            0xc2 => w.scalar(&position, format_args!("false")),
+            0xc3 => w.scalar(&position, format_args!("true")),
+            0xcc => w.scalar(&position, format_args!("{}", r.uint::<1>()?)),
+            0xcd => w.scalar(&position, format_args!("{}", r.uint::<2>()?)),
+            0xce => w.scalar(&position, format_args!("{}", r.uint::<4>()?)),
+            0xcf => w.scalar(&position, format_args!("{}", r.uint::<8>()?)),
+            0xd0 => w.scalar(&position, format_args!("{}", r.int::<1>()?)),
+            0xd1 => w.scalar(&position, format_args!("{}", r.int::<2>()?)),
+            0xd2 => w.scalar(&position, format_args!("{}", r.int::<4>()?)),
+            0xd3 => w.scalar(&position, format_args!("{}", r.int::<8>()?)),
+            0xca => w.float(&position, f32::from_bits(r.uint::<4>()? as u32) as f64),
+            0xcb => w.float(&position, f64::from_bits(r.uint::<8>()?)),
+            0xa0..=0xbf | 0xd9 | 0xda | 0xdb => {
+                let len = match marker {
+                    0xd9 => r.uint::<1>()?,
+                    0xda => r.uint::<2>()?,
+                    0xdb => r.uint::<4>()?,
+                    _ => (marker & 0x1f) as u64,
+                };
+                let s = core::str::from_utf8(r.take(len)?).map_err(|_| start)?;
+                w.string(s);
+            }
+            0xc4..=0xc6 => {
+                let len = match marker {
+                    0xc4 => r.uint::<1>()?,
+                    0xc5 => r.uint::<2>()?,
+                    _ => r.uint::<4>()?,
+                };
+                w.bytes(r.take(len)?);
+            }
+            0x80..=0x9f | 0xdc..=0xdf if position == Position::Value => {
+                let (object, len) = match marker {
+                    0xdc => (false, r.uint::<2>()?),
+                    0xdd => (false, r.uint::<4>()?),
+                    0xde => (true, r.uint::<2>()?),
+                    0xdf => (true, r.uint::<4>()?),
+                    _ => (marker < 0x90, (marker & 0x0f) as u64),
+                };
+                w.begin(object, Some(len));
+            }
+            _ => return Err(start),
+        }
+    }
+
+    if r.pos != bytes.len() {
+        return Err(r.pos);
+    }
+    Ok(w.out)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn transcode() {
+        // {"a": [1, -2, 1.5], "b": bin[1, 2], 3: [-200, true, nil], "c": {}}
+        let msgpack = [
+            0x84, 0xa1, b'a', 0x93, 0x01, 0xfe, 0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0, 0xa1, b'b',
+            0xc4, 0x02, 0x01, 0x02, 0x03, 0x93, 0xd1, 0xff, 0x38, 0xc3, 0xc0, 0xa1, b'c', 0x80,
+        ];
+        assert_eq!(
+            super::to_json(&msgpack).unwrap(),
+            r#"{"a":[1,-2,1.5],"b":"AQI","3":[-200,true,null],"c":{}}"#
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        // truncated array
+        assert_eq!(super::to_json(&[0x92, 0x01]), Err(2));
+        // trailing bytes
+        assert_eq!(super::to_json(&[0x01, 0x01]), Err(1));
+        // extension types
+        assert_eq!(super::to_json(&[0xd4, 0x01, 0x01]), Err(0));
+        // array as key
+        assert_eq!(super::to_json(&[0x81, 0x90, 0x01]), Err(1));
+    }
+}
@@ -0,0 +1,140 @@
+//! Reading numbers as `i64`, `u64` or `f64`, with an optional cache of the results.
+
+use foldhash::quality::RandomState;
+use hashbrown::HashMap;
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+/// A number, as the narrowest of `i64`, `u64` or `f64` that holds it exactly.
+///
+/// Integers without a fraction or exponent are `I64` if they fit, then `U64`. Anything else,
+/// including integers too large for either, is `F64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+}
+
+impl Number {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::I64(n) => n as f64,
+            Number::U64(n) => n as f64,
+            Number::F64(n) => n,
+        }
+    }
+
+    pub fn as_i64(self) -> Option<i64> {
+        match self {
+            Number::I64(n) => Some(n),
+            Number::U64(_) | Number::F64(_) => None,
+        }
+    }
+
+    pub fn as_u64(self) -> Option<u64> {
+        match self {
+            Number::I64(n) => n.try_into().ok(),
+            Number::U64(n) => Some(n),
+            Number::F64(_) => None,
+        }
+    }
+}
+
+/// The numbers parsed by [`Arena::cache_numbers`], by span.
+pub(crate) type NumberCache = HashMap<(u32, u32), Number, RandomState>;
+
+impl Arena<'_> {
+    /// The value of a number, from the cache if [`Arena::cache_numbers`] has filled it.
+    pub fn as_number(&self, value: &Value) -> Option<Number> {
+        if let Some(cache) = &self.numbers {
+            let span = self.span(value);
+            if let Some(n) = cache.get(&(span.start, span.end)) {
+                return Some(*n);
+            }
+        }
+        self.parse_number(value)
+    }
+
+    /// Parse every number in the arena now, so that [`Arena::as_number`] only looks them up.
+    ///
+    /// Numbers parsed into the arena later are parsed again on each access, until this is
+    /// called again.
+    pub fn cache_numbers(&mut self) {
+        let mut cache = self.numbers.take().unwrap_or_default();
+        for (value, span) in core::iter::zip(&self.values, &self.spans) {
+            if value.is_header() {
+                continue;
+            }
+            let span = (span.start, span.end);
+            // values copied in from other arenas point into text that can be cleared,
+            // so only cache those in the source, which never changes.
+            if span.0 <= span.1 && !cache.contains_key(&span) {
+                if let Some(n) = self.parse_number(value) {
+                    cache.insert(span, n);
+                }
+            }
+        }
+        self.numbers = Some(cache);
+    }
+
+    fn parse_number(&self, value: &Value) -> Option<Number> {
+        let ValueKind::Leaf(leaf) = value.kind() else {
+            return None;
+        };
+        let int = match leaf {
+            LeafValue::Number | LeafValue::BigInt => self.raw(value).parse::<i128>().ok(),
+            LeafValue::RadixInt(_) => self.as_i128(value),
+            _ => return None,
+        };
+        let n = match int {
+            Some(n) => match (i64::try_from(n), u64::try_from(n)) {
+                (Ok(n), _) => Number::I64(n),
+                (_, Ok(n)) => Number::U64(n),
+                _ => Number::F64(n as f64),
+            },
+            None => Number::F64(self.raw(value).parse().ok()?),
+        };
+        Some(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::Number;
+    use crate::{Arena, ValueKind};
+
+    #[test]
+    fn numbers() {
+        let src = "[-1, 18446744073709551615, 18446744073709551616, 1.0, 2e3, \"1\"]";
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = value.kind() else {
+            panic!("expected array")
+        };
+
+        let read = |arena: &Arena<'_>| -> Vec<_> {
+            arena
+                .array_iter(&array)
+                .map(|v| arena.as_number(v))
+                .collect()
+        };
+        let expected = [
+            Some(Number::I64(-1)),
+            Some(Number::U64(u64::MAX)),
+            Some(Number::F64(18446744073709551616.0)),
+            Some(Number::F64(1.0)),
+            Some(Number::F64(2000.0)),
+            None,
+        ];
+        assert_eq!(read(&arena), expected);
+
+        arena.cache_numbers();
+        assert_eq!(arena.numbers.as_ref().unwrap().len(), 5);
+        assert_eq!(read(&arena), expected);
+        assert_eq!(Number::I64(-1).as_u64(), None);
+        assert_eq!(Number::U64(3).as_f64(), 3.0);
+    }
+}
@@ -0,0 +1,161 @@
+use logos::Logos;
+
+use crate::rollback::Mark;
+use crate::{Arena, ContextItem, Dialect, Error, PollParse, RawParser, Source, Token, Value};
+
+/// A parser that is driven one token at a time, for building custom drivers
+/// like [`parse_async`](crate::parse_async) that can stop and continue between tokens.
+///
+/// ```
+/// let mut arena = sonny_jim::Arena::new(r#"{"a": [1, 2]}"#);
+/// let mut parser = sonny_jim::Parser::new(&mut arena);
+/// let value = loop {
+///     if let Some(value) = parser.step().unwrap() {
+///         break value;
+///     }
+/// };
+/// let (arena, _) = parser.into_parts();
+/// assert_eq!(arena.to_json(&value), r#"{"a":[1,2]}"#);
+/// ```
+///
+/// The open objects and arrays are kept in the arena's own stacks between steps,
+/// so their allocations are reused like they are by [`parse`](crate::parse).
+pub struct Parser<'a, 's> {
+    arena: &'a mut Arena<'s>,
+    src: Source<'s>,
+    dialect: Dialect,
+    /// how far into the source the parser has read
+    offset: usize,
+    /// what the arena held before, to return to on an error.
+    mark: Mark,
+    context: ContextItem,
+}
+
+impl<'a, 's> Parser<'a, 's> {
+    pub fn new(arena: &'a mut Arena<'s>) -> Self {
+        Self::with_dialect(arena, Dialect::default())
+    }
+
+    pub fn with_dialect(arena: &'a mut Arena<'s>, dialect: Dialect) -> Self {
+        arena.reserve_for(arena.scratch.src.len());
+        // a parser dropped part way through may have left its stacks behind.
+        arena.stacks.clear();
+        Self {
+            src: arena.scratch.src.clone(),
+            mark: arena.mark(),
+            arena,
+            dialect,
+            offset: 0,
+            context: ContextItem::WaitingValue,
+        }
+    }
+
+    /// Read the next token, and return the value once the document is complete.
+    ///
+    /// Don't step the parser again after it returns a value or an error.
+    /// After an error, the arena is as it was before the parser was created.
+    pub fn step(&mut self) -> Result<Option<Value>, Error> {
+        self.step_n(1)
+    }
+
+    /// Read up to `n` tokens, and return the value once the document is complete.
+    pub fn step_n(&mut self, n: usize) -> Result<Option<Value>, Error> {
+        // the lexer only borrows the source, so picking up where the last step
+        // stopped is just a matter of seeking to the offset, and nothing is rescanned.
+        let mut lexer = Token::lexer_with_extras(&self.src, self.dialect.into());
+        lexer.bump(self.offset);
+
+        let mut stacks = core::mem::take(&mut self.arena.stacks);
+        let mut parser = RawParser {
+            arena: self.arena,
+            lexer,
+            stack: core::mem::take(&mut stacks.stack),
+            value_stack: core::mem::take(&mut stacks.value_stack),
+            key_stack: core::mem::take(&mut stacks.key_stack),
+            context: core::mem::replace(&mut self.context, ContextItem::WaitingValue),
+        };
+
+        let res = parser.step_n(n);
+
+        self.offset = parser.lexer.span().end;
+        stacks.stack = parser.stack;
+        stacks.value_stack = parser.value_stack;
+        stacks.key_stack = parser.key_stack;
+        self.context = parser.context;
+
+        if !matches!(res, Ok(PollParse::Pending)) {
+            stacks.clear();
+        }
+        self.arena.stacks = stacks;
+        if res.is_err() {
+            self.arena.rollback(self.mark);
+        }
+        match res? {
+            PollParse::Ready(value) => Ok(Some(value)),
+            PollParse::Pending => Ok(None),
+        }
+    }
+
+    /// How far into the source the parser has read, in bytes.
+    pub fn offset(&self) -> u32 {
+        self.offset as u32
+    }
+
+    /// Give back the arena, and how far into the source the parser read.
+    pub fn into_parts(self) -> (&'a mut Arena<'s>, u32) {
+        (self.arena, self.offset as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Dialect, Parser};
+
+    #[test]
+    fn step_by_step() {
+        let src = r#"{"a": [1, 2, {"b": null}], "c": "d"}"#;
+        let mut arena = Arena::new(src);
+        let mut parser = Parser::new(&mut arena);
+
+        let mut steps = 0;
+        let value = loop {
+            let before = parser.offset();
+            let value = parser.step().unwrap();
+            assert!(parser.offset() >= before);
+            steps += 1;
+            if let Some(value) = value {
+                break value;
+            }
+        };
+        assert!(steps > 1);
+
+        let (arena, offset) = parser.into_parts();
+        assert_eq!(offset as usize, src.len());
+        assert_eq!(arena.to_json(&value), src.replace(' ', ""));
+        // the stacks went back to the arena, empty, for the next parse to reuse.
+        assert!(arena.stacks.value_stack.is_empty());
+        assert!(arena.stacks.value_stack.capacity() > 0);
+
+        // a parser dropped part way through doesn't get in the way of the next one.
+        let mut arena = Arena::new("[[1, 2], 3]");
+        let mut parser = Parser::new(&mut arena);
+        assert!(parser.step_n(4).unwrap().is_none());
+        drop(parser);
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(arena.to_json(&value), "[[1,2],3]");
+
+        let dialect = Dialect {
+            unquoted_keys: true,
+            ..Dialect::default()
+        };
+        let mut arena = Arena::new("{a: [1, 2]}");
+        assert!(Parser::new(&mut arena).step_n(usize::MAX).is_err());
+        let mut parser = Parser::with_dialect(&mut arena, dialect);
+        assert!(parser.step_n(usize::MAX).unwrap().is_some());
+
+        let mut arena = Arena::new("[1, 2");
+        let mut parser = Parser::new(&mut arena);
+        let err = parser.step_n(100).unwrap_err();
+        assert_eq!(err.span(), 5..5);
+    }
+}
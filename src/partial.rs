@@ -0,0 +1,308 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Range;
+use logos::Logos;
+
+use crate::{
+    Arena, Array, ContextItem, Error, Object, Pending, PollParse, RawParser, StackItemKind,
+    StringKey, Token, Value, ValueKind,
+};
+
+/// The result of [`parse_partial`].
+#[derive(Debug)]
+pub struct Partial {
+    /// The tree parsed so far, with any open containers closed.
+    ///
+    /// `None` if not even the first value has started.
+    pub value: Option<Value>,
+    /// The containers that were still open, outermost first.
+    pub open: Vec<OpenContainer>,
+    /// An object key that has no value yet.
+    pub dangling_key: Option<StringKey>,
+    /// The span of a token cut off by the end of the input, such as `"hel`, `tr` or `2.`.
+    /// It is not included in [`value`](Self::value).
+    ///
+    /// From [`parse_keep_partial`], the span where parsing failed.
+    pub incomplete: Option<Range<u32>>,
+}
+
+impl Partial {
+    /// Whether the input was a complete document.
+    pub fn is_complete(&self) -> bool {
+        self.value.is_some()
+            && self.open.is_empty()
+            && self.dangling_key.is_none()
+            && self.incomplete.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenContainer {
+    pub kind: Container,
+    /// The offset of the opening bracket.
+    pub start: u32,
+}
+
+/// Parse a document that may be truncated, such as JSON that is still being streamed.
+///
+/// Containers that are still open are closed at the end of the input,
+/// so their spans run to the end of the input. A number at the very end
+/// of the input is kept, even though more digits might follow.
+///
+/// Syntax errors before the end of the input are still errors.
+pub fn parse_partial(arena: &mut Arena<'_>) -> Result<Partial, Error> {
+    let src = arena.scratch.src.clone();
+    let len = src.len() as u32;
+    arena.reserve_for(src.len());
+
+    let mut parser = RawParser {
+        arena,
+        lexer: Token::lexer(&src),
+        stack: Vec::new(),
+        value_stack: Vec::new(),
+        key_stack: Vec::new(),
+        context: ContextItem::WaitingValue,
+    };
+
+    let error = loop {
+        match parser.step() {
+            Ok(PollParse::Ready(value)) => {
+                return Ok(Partial {
+                    value: Some(value),
+                    open: Vec::new(),
+                    dangling_key: None,
+                    incomplete: None,
+                })
+            }
+            Ok(PollParse::Pending) => {}
+            Err(e) => break e,
+        }
+    };
+
+    let incomplete = match error.token {
+        None if error.span.start == len => None,
+        None if truncated(&src[error.span.start as usize..]) => Some(error.span.start..len),
+        _ => return Err(error),
+    };
+
+    let mut partial = close(parser, error.context, len);
+    partial.incomplete = incomplete;
+    Ok(partial)
+}
+
+/// Parse like [`parse`](crate::parse), but on an error, keep what was parsed before it
+/// in [`Error::partial`].
+///
+/// The containers that were open are closed where the error is, so they hold the values
+/// that were complete before it, such as the first records of a corrupted array.
+/// Unlike [`parse`](crate::parse), those values are left in the arena.
+pub fn parse_keep_partial(arena: &mut Arena<'_>) -> Result<Value, Error> {
+    let src = arena.scratch.src.clone();
+    arena.reserve_for(src.len());
+
+    let mut parser = RawParser {
+        arena,
+        lexer: Token::lexer(&src),
+        stack: Vec::new(),
+        value_stack: Vec::new(),
+        key_stack: Vec::new(),
+        context: ContextItem::WaitingValue,
+    };
+
+    let mut error = loop {
+        match parser.step() {
+            Ok(PollParse::Ready(value)) => return Ok(value),
+            Ok(PollParse::Pending) => {}
+            Err(e) => break e,
+        }
+    };
+
+    let mut partial = close(parser, error.context.clone(), error.span.start);
+    partial.incomplete = Some(error.span.clone());
+    error.partial = Some(Box::new(partial));
+    Err(error)
+}
+
+/// Close the containers that `parser` has open, with spans up to `end`.
+fn close(parser: RawParser<'_, '_>, context: ContextItem, end: u32) -> Partial {
+    let RawParser {
+        arena,
+        stack,
+        mut value_stack,
+        mut key_stack,
+        ..
+    } = parser;
+
+    let mut dangling_key = None;
+    let mut value = match context {
+        ContextItem::Value { span, value } => Some(Pending { span, kind: value }),
+        ContextItem::Key { key, .. } => {
+            dangling_key = Some(key);
+            None
+        }
+        ContextItem::WaitingKey | ContextItem::WaitingValue => None,
+    };
+
+    let open = stack
+        .iter()
+        .map(|item| OpenContainer {
+            kind: match item.kind {
+                StackItemKind::Object(..) => Container::Object,
+                StackItemKind::Array(_) => Container::Array,
+            },
+            start: item.span.start,
+        })
+        .collect();
+
+    // close the open containers, innermost first.
+    for item in stack.into_iter().rev() {
+        value_stack.extend(value.take());
+
+        let span = item.span.start..end;
+        let kind = match item.kind {
+            StackItemKind::Array(vindex) => {
+                let vi = arena.push_children(span.clone(), 0, value_stack.drain(vindex as usize..));
+                let vj = arena.values.len() as u32;
+
+                ValueKind::Array(Array::new(vi..vj))
+            }
+            StackItemKind::Object(vindex, kindex) => {
+                // a key followed by a colon, but no value
+                if key_stack.len() - kindex as usize > value_stack.len() - vindex as usize {
+                    dangling_key = key_stack.pop();
+                }
+
+                let vi = arena.push_children(
+                    span.clone(),
+                    arena.keys.len() as u32,
+                    value_stack.drain(vindex as usize..),
+                );
+                let vj = arena.values.len() as u32;
+                arena.keys.extend(key_stack.drain(kindex as usize..));
+
+                ValueKind::Object(Object::new(vi..vj))
+            }
+        };
+
+        value = Some(Pending { span, kind });
+    }
+
+    Partial {
+        value: value.map(|value| arena.root(value.kind, value.span)),
+        open,
+        dangling_key,
+        incomplete: None,
+    }
+}
+
+/// Whether `rest` is the start of a token that was cut off by the end of the input.
+pub(crate) fn truncated(rest: &str) -> bool {
+    // the lexer only fails on a string if it has no closing quote,
+    // and on a number if it breaks the grammar, which might be the end of the input.
+    rest.starts_with('"')
+        || crate::scan_number(rest.as_bytes()) == Err(rest.len())
+        || ["true", "false", "null"]
+            .iter()
+            .any(|lit| lit.len() > rest.len() && lit.starts_with(rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_keep_partial, parse_partial, Container, OpenContainer};
+    use crate::Arena;
+
+    #[test]
+    fn partial() {
+        let src = r#"{"a": [1, {"b": tr"#;
+        let mut arena = Arena::new(src);
+        let partial = parse_partial(&mut arena).unwrap();
+
+        assert!(!partial.is_complete());
+        assert_eq!(
+            partial.open,
+            [
+                OpenContainer {
+                    kind: Container::Object,
+                    start: 0
+                },
+                OpenContainer {
+                    kind: Container::Array,
+                    start: 6
+                },
+                OpenContainer {
+                    kind: Container::Object,
+                    start: 10
+                },
+            ]
+        );
+        assert_eq!(&arena[partial.dangling_key.as_ref().unwrap()], "b");
+        assert_eq!(partial.incomplete, Some(16..18));
+        assert_eq!(
+            arena.to_json(partial.value.as_ref().unwrap()),
+            r#"{"a":[1,{}]}"#
+        );
+    }
+
+    #[test]
+    fn prefixes() {
+        let src = r#"{"k\"ey": [1, "two", {"x": null}], "s": "str\"ing"}"#;
+        let expected = [
+            (r#"{"k\"ey""#, "{}"),
+            (r#"{"k\"ey": [1, "t"#, r#"{"k\"ey":[1]}"#),
+            (
+                r#"{"k\"ey": [1, "two", {"x": n"#,
+                r#"{"k\"ey":[1,"two",{}]}"#,
+            ),
+            (
+                r#"{"k\"ey": [1, "two", {"x": null}], "s":"#,
+                r#"{"k\"ey":[1,"two",{"x":null}]}"#,
+            ),
+        ];
+        for (prefix, json) in expected {
+            let mut arena = Arena::new(prefix);
+            let partial = parse_partial(&mut arena).unwrap();
+            assert_eq!(arena.to_json(partial.value.as_ref().unwrap()), json);
+        }
+
+        for i in 0..=src.len() {
+            let mut arena = Arena::new(&src[..i]);
+            let partial = parse_partial(&mut arena).unwrap();
+            assert_eq!(partial.is_complete(), i == src.len());
+        }
+
+        let mut arena = Arena::new("");
+        assert!(parse_partial(&mut arena).unwrap().value.is_none());
+        let mut arena = Arena::new("[1 2");
+        assert!(parse_partial(&mut arena).is_err());
+    }
+
+    #[test]
+    fn keep_partial() {
+        let src = r#"{"records": [{"id": 1}, {"id": 2}, {"id": 3 "x"}], "done": true}"#;
+        let mut arena = Arena::new(src);
+        let error = parse_keep_partial(&mut arena).unwrap_err();
+        assert_eq!(error.span(), 44..47);
+
+        let partial = error.partial().unwrap();
+        assert!(!partial.is_complete());
+        assert_eq!(partial.incomplete, Some(44..47));
+        assert_eq!(partial.open.len(), 3);
+        let value = partial.value.as_ref().unwrap();
+        assert_eq!(arena.span(value), 0..44);
+        assert_eq!(
+            arena.to_json(value),
+            r#"{"records":[{"id":1},{"id":2},{"id":3}]}"#
+        );
+
+        let mut arena = Arena::new(src);
+        assert!(crate::parse(&mut arena).unwrap_err().partial().is_none());
+        let mut arena = Arena::new("[1]");
+        assert!(parse_keep_partial(&mut arena).is_ok());
+    }
+}
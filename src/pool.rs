@@ -0,0 +1,289 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::ops::Deref;
+
+use crate::{Arena, Buffers, Document, Error, Source, Value};
+
+/// A pool of reusable arena allocations, for parsing many small documents.
+///
+/// Each parsed document borrows its buffers from the pool, and returns them
+/// when the [`DocumentGuard`] is dropped.
+/// At most [`max_idle`](Self::with_max_idle) buffers are kept, and any more are freed.
+pub struct ArenaPool {
+    free: RefCell<Vec<Buffers>>,
+    max_idle: usize,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+/// The idle buffers an [`ArenaPool`] keeps by default.
+const DEFAULT_MAX_IDLE: usize = 16;
+
+impl Default for ArenaPool {
+    fn default() -> Self {
+        Self::with_max_idle(DEFAULT_MAX_IDLE)
+    }
+}
+
+/// How often an [`ArenaPool`] could reuse buffers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Parses that reused idle buffers.
+    pub hits: u64,
+    /// Parses that had to allocate fresh buffers.
+    pub misses: u64,
+}
+
+impl PoolStats {
+    /// The fraction of parses that reused idle buffers, or `0.0` if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl ArenaPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a pool that keeps at most `max_idle` idle buffers.
+    ///
+    /// Buffers given back to a full pool are freed, so a burst of concurrent parses
+    /// doesn't keep its memory around forever.
+    pub fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            free: RefCell::default(),
+            max_idle,
+            hits: Cell::default(),
+            misses: Cell::default(),
+        }
+    }
+
+    /// The number of idle buffers in the pool.
+    pub fn idle(&self) -> usize {
+        self.free.borrow().len()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+        }
+    }
+
+    /// Parse `src` into an arena backed by pooled buffers.
+    pub fn parse_next<'p, 's>(&'p self, src: &'s str) -> Result<DocumentGuard<'p, 's>, Error> {
+        let mut arena = Arena::with_buffers(Source::Borrowed(src), self.take());
+        let res = crate::parse(&mut arena);
+        self.guard(arena, res)
+    }
+
+    /// Like [`parse_next`](Self::parse_next), but yields to the executor
+    /// periodically, like [`parse_async`](crate::parse_async).
+    pub async fn parse_next_async<'p, 's>(
+        &'p self,
+        src: &'s str,
+    ) -> Result<DocumentGuard<'p, 's>, Error> {
+        let mut arena = Arena::with_buffers(Source::Borrowed(src), self.take());
+        let res = crate::parse_async(&mut arena).await;
+        self.guard(arena, res)
+    }
+
+    /// Parse `src` into a [`Document`] backed by pooled buffers, without copying it.
+    ///
+    /// Give the buffers back with [`recycle`](Self::recycle) once the document is done with.
+    pub fn parse_document(&self, src: String) -> Result<Document, Error> {
+        let mut arena = Arena::with_buffers(Source::Owned(Arc::new(src)), self.take());
+        match crate::parse(&mut arena) {
+            Ok(root) => Ok(Document { arena, root }),
+            Err(e) => {
+                self.release(arena);
+                Err(e)
+            }
+        }
+    }
+
+    /// Return the buffers of `doc` to the pool.
+    pub fn recycle(&self, doc: Document) {
+        self.release(doc.arena);
+    }
+
+    fn release(&self, arena: Arena<'_>) {
+        let mut free = self.free.borrow_mut();
+        if free.len() < self.max_idle {
+            free.push(arena.into_buffers());
+        }
+    }
+
+    fn take(&self) -> Buffers {
+        match self.free.borrow_mut().pop() {
+            Some(buffers) => {
+                self.hits.set(self.hits.get() + 1);
+                buffers
+            }
+            None => {
+                self.misses.set(self.misses.get() + 1);
+                Default::default()
+            }
+        }
+    }
+
+    fn guard<'p, 's>(
+        &'p self,
+        arena: Arena<'s>,
+        res: Result<Value, Error>,
+    ) -> Result<DocumentGuard<'p, 's>, Error> {
+        match res {
+            Ok(root) => Ok(DocumentGuard {
+                pool: self,
+                arena,
+                root,
+            }),
+            Err(e) => {
+                self.release(arena);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// A document parsed by an [`ArenaPool`].
+///
+/// Derefs to the [`Arena`] the document was parsed into.
+pub struct DocumentGuard<'p, 's> {
+    pool: &'p ArenaPool,
+    arena: Arena<'s>,
+    root: Value,
+}
+
+impl DocumentGuard<'_, '_> {
+    /// The top-level value of the document.
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+}
+
+impl<'s> Deref for DocumentGuard<'_, 's> {
+    type Target = Arena<'s>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl Drop for DocumentGuard<'_, '_> {
+    fn drop(&mut self) {
+        let arena = core::mem::replace(&mut self.arena, Arena::new(""));
+        self.pool.release(arena);
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    pub(crate) static POOL: ArenaPool = ArenaPool::new();
+}
+
+/// Parse `src` using this thread's [`ArenaPool`], and pass the document to `f`.
+#[cfg(feature = "std")]
+pub fn with_pooled_arena<R>(
+    src: &str,
+    f: impl FnOnce(&DocumentGuard<'_, '_>) -> R,
+) -> Result<R, Error> {
+    POOL.with(|pool| pool.parse_next(src).map(|doc| f(&doc)))
+}
+
+/// The [`PoolStats`] of this thread's [`ArenaPool`].
+#[cfg(feature = "std")]
+pub fn pooled_stats() -> PoolStats {
+    POOL.with(|pool| pool.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArenaPool;
+
+    #[test]
+    fn reuse() {
+        let small = include_str!("../testdata/small.json");
+        let pool = ArenaPool::new();
+
+        let doc = pool.parse_next(small).unwrap();
+        let values = doc.values.as_ptr();
+        let expected = std::format!(
+            "{:?}",
+            crate::fmt::FmtValue {
+                arena: &doc,
+                value: doc.root()
+            }
+        );
+        drop(doc);
+        assert_eq!(pool.idle(), 1);
+
+        for _ in 0..10 {
+            let doc = pool.parse_next(small).unwrap();
+            assert_eq!(doc.values.as_ptr(), values);
+            let debug = std::format!(
+                "{:?}",
+                crate::fmt::FmtValue {
+                    arena: &doc,
+                    value: doc.root()
+                }
+            );
+            assert_eq!(debug, expected);
+        }
+
+        // failed parses also return their buffers
+        assert!(pool.parse_next("[1, 2").is_err());
+        assert_eq!(pool.idle(), 1);
+
+        let a = pool.parse_next(small).unwrap();
+        let b = pool.parse_next(small).unwrap();
+        assert_ne!(a.values.as_ptr(), b.values.as_ptr());
+        drop((a, b));
+        assert_eq!(pool.idle(), 2);
+
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses), (12, 2));
+    }
+
+    #[test]
+    fn max_idle() {
+        let pool = ArenaPool::with_max_idle(2);
+        let docs: std::vec::Vec<_> = (0..4).map(|_| pool.parse_next("[1]").unwrap()).collect();
+        drop(docs);
+        assert_eq!(pool.idle(), 2);
+
+        let pool = ArenaPool::with_max_idle(0);
+        drop(pool.parse_next("[1]").unwrap());
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_local() {
+        let small = include_str!("../testdata/small.json");
+
+        let before = crate::pooled_stats();
+        for _ in 0..4 {
+            let len = crate::with_pooled_arena(small, |doc| doc.values.len()).unwrap();
+            // eight values, and a header for each of the three containers
+            assert_eq!(len, 11);
+        }
+        let nested = crate::with_pooled_arena("[1]", |_| {
+            crate::with_pooled_arena("[2]", |doc| doc.values.len())
+        });
+        assert_eq!(nested.unwrap().unwrap(), 2);
+
+        let after = crate::pooled_stats();
+        // one miss for the first parse, and one for the nested parse
+        assert_eq!(after.hits - before.hits, 4);
+        assert_eq!(after.misses - before.misses, 2);
+    }
+}
@@ -0,0 +1,22 @@
+//! The stable core of the API, for `use sonny_jim::prelude::*`.
+//!
+//! Everything here only exposes the arena through opaque handles and accessor methods,
+//! so the layout of the arena can change without breaking code that sticks to it.
+//! In particular, read values with [`Value::json_type`], [`Value::as_object`], [`Arena::get`],
+//! [`Arena::as_str`] and the like, rather than matching on [`Value::kind`], which is not covered.
+//!
+//! ```
+//! use sonny_jim::prelude::*;
+//!
+//! let mut arena = Arena::new(r#"{"ports": [80, 443]}"#);
+//! let root = parse(&mut arena).unwrap();
+//! let object = root.as_object().unwrap();
+//! let ports = arena.get(&object, "ports").unwrap();
+//! assert_eq!(ports.json_type(), JsonType::Array);
+//! assert_eq!(arena.span(ports), 10..19);
+//! ```
+
+pub use crate::{
+    parse, parse_all, parse_with_dialect, Arena, Array, Dialect, Entry, Error, JsonType, KeyId,
+    Object, Query, Value, ValueId, ValueRef,
+};
@@ -0,0 +1,355 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+
+use crate::{Arena, JsonPath, PathSegment, Value, ValueKind};
+
+/// A [JSONPath](https://www.rfc-editor.org/rfc/rfc9535) query, like `$.definitions[*].properties`.
+///
+/// Supports names (`.key` or `['key']`), indices (`[0]`, or `[-1]` from the end),
+/// wildcards (`.*` or `[*]`) and descendants (`..key`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub(crate) steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Step {
+    /// Whether this step applies to every descendant, as in `..key`, rather than only the children.
+    pub(crate) descendant: bool,
+    pub(crate) selector: Selector,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Selector {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    /// The byte offset in the query where it stops being valid.
+    pub offset: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query at offset {}", self.offset)
+    }
+}
+
+impl core::error::Error for QueryError {}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let error = |rest: &str| QueryError {
+            offset: query.len() - rest.len(),
+        };
+        let mut rest = query.strip_prefix('$').ok_or_else(|| error(query))?;
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let descendant = rest.starts_with("..");
+            let (selector, after) = if let Some(r) = rest.strip_prefix('[') {
+                parse_bracket(r).ok_or_else(|| error(rest))?
+            } else if let Some(r) = rest.strip_prefix(if descendant { ".." } else { "." }) {
+                match r.strip_prefix('[') {
+                    Some(r) if descendant => parse_bracket(r).ok_or_else(|| error(rest))?,
+                    _ => parse_name(r).ok_or_else(|| error(r))?,
+                }
+            } else {
+                return Err(error(rest));
+            };
+            steps.push(Step {
+                descendant,
+                selector,
+            });
+            rest = after;
+        }
+        Ok(Self { steps })
+    }
+}
+
+/// `key` or `*`, after a `.`.
+fn parse_name(s: &str) -> Option<(Selector, &str)> {
+    if let Some(rest) = s.strip_prefix('*') {
+        return Some((Selector::Wildcard, rest));
+    }
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    let (name, rest) = s.split_at(end);
+    (!name.is_empty()).then(|| (Selector::Key(name.into()), rest))
+}
+
+/// `*]`, `0]` or `'key']`, after a `[`.
+fn parse_bracket(s: &str) -> Option<(Selector, &str)> {
+    let (selector, rest) = match s.chars().next()? {
+        '*' => (Selector::Wildcard, &s[1..]),
+        quote @ ('\'' | '"') => {
+            let mut key = String::new();
+            let mut chars = s[1..].char_indices();
+            loop {
+                match chars.next()? {
+                    (i, c) if c == quote => break (Selector::Key(key), &s[i + 2..]),
+                    (_, '\\') => key.push(chars.next()?.1),
+                    (_, c) => key.push(c),
+                }
+            }
+        }
+        _ => {
+            let end = s.find(']')?;
+            (Selector::Index(s[..end].parse().ok()?), &s[end..])
+        }
+    };
+    Some((selector, rest.strip_prefix(']')?))
+}
+
+impl<'s> Arena<'s> {
+    /// Every value below `value` that `query` matches, along with its path, in document order.
+    ///
+    /// The path is the concrete one to the match, without wildcards, so it can be
+    /// logged as [`JsonPath::to_jsonpath`] or as a JSON pointer.
+    pub fn query<'a>(&'a self, value: &'a Value, query: &Query) -> Vec<(JsonPath<'a>, &'a Value)> {
+        let mut nodes = vec![(Vec::new(), value)];
+        for step in &query.steps {
+            let candidates = if step.descendant {
+                nodes
+                    .into_iter()
+                    .flat_map(|(path, value)| self.descendants(path, value))
+                    .collect()
+            } else {
+                nodes
+            };
+            nodes = candidates
+                .into_iter()
+                .flat_map(|(path, value)| self.select(path, value, &step.selector))
+                .collect();
+        }
+        nodes
+            .into_iter()
+            .map(|(path, value)| (JsonPath(path), value))
+            .collect()
+    }
+
+    /// `value` and every value below it, depth first.
+    fn descendants<'a>(
+        &'a self,
+        path: Vec<PathSegment<'a>>,
+        value: &'a Value,
+    ) -> Vec<(Vec<PathSegment<'a>>, &'a Value)> {
+        let mut out = Vec::new();
+        let mut stack = vec![(path, value)];
+        while let Some((path, value)) = stack.pop() {
+            let mut children = self.select(path.clone(), value, &Selector::Wildcard);
+            children.reverse();
+            stack.extend(children);
+            out.push((path, value));
+        }
+        out
+    }
+
+    /// The children of `value` that `selector` matches.
+    fn select<'a>(
+        &'a self,
+        path: Vec<PathSegment<'a>>,
+        value: &'a Value,
+        selector: &Selector,
+    ) -> Vec<(Vec<PathSegment<'a>>, &'a Value)> {
+        let child = |segment| {
+            let mut path = path.clone();
+            path.push(segment);
+            path
+        };
+        match (value.kind(), selector) {
+            (ValueKind::Object(object), Selector::Wildcard) => self
+                .entries(&object)
+                .map(|(key, value)| (child(PathSegment::Key(key)), value))
+                .collect(),
+            (ValueKind::Object(object), Selector::Key(name)) => self
+                .entries(&object)
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(key, value)| (child(PathSegment::Key(key)), value))
+                .into_iter()
+                .collect(),
+            (ValueKind::Array(array), Selector::Wildcard) => self
+                .array_values(&array)
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (child(PathSegment::Index(i)), value))
+                .collect(),
+            (ValueKind::Array(array), Selector::Index(i)) => {
+                let values = self.array_values(&array);
+                let i = match usize::try_from(*i) {
+                    Ok(i) => Some(i),
+                    Err(_) => values.len().checked_sub(i.unsigned_abs() as usize),
+                };
+                i.and_then(|i| Some((child(PathSegment::Index(i)), values.get(i)?)))
+                    .into_iter()
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl JsonPath<'_> {
+    /// The path as a normalized JSONPath, like `$.definitions['io.k8s.api'].properties`.
+    pub fn to_jsonpath(&self) -> String {
+        let mut out = String::from("$");
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => {
+                    out.push('.');
+                    out.push_str(key);
+                }
+                PathSegment::Key(key) => {
+                    out.push_str("['");
+                    for c in key.chars() {
+                        if matches!(c, '\'' | '\\') {
+                            out.push('\\');
+                        }
+                        out.push(c);
+                    }
+                    out.push_str("']");
+                }
+                PathSegment::Index(i) => {
+                    let _ = write!(out, "[{i}]");
+                }
+            }
+        }
+        out
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(feature = "serde")]
+impl Query {
+    /// Deserialize the values below `value` that this query matches, in document order,
+    /// as a sequence such as a `Vec<T>`.
+    pub fn select_as<'a, T: serde::Deserialize<'a>>(
+        &self,
+        arena: &'a Arena<'_>,
+        value: &'a Value,
+    ) -> Result<T, crate::DeError> {
+        let values = arena
+            .query(value, self)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        T::deserialize(crate::de::Matches {
+            de: arena.deserializer(value),
+            values,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    use crate::{Arena, Query, QueryError};
+
+    #[test]
+    fn query() {
+        let src = r#"{
+            "definitions": {
+                "io.k8s.api.core.v1.Pod": {"properties": {"key": {"type": "string"}}},
+                "io.k8s.api.core.v1.Node": {"properties": {"name": {"type": "string"}, "it's": 1}}
+            },
+            "items": [{"key": 1}, {"key": 2}, {"other": 3}]
+        }"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let matches = |query: &str| -> Vec<(String, String)> {
+            let query = Query::parse(query).unwrap();
+            arena
+                .query(&root, &query)
+                .into_iter()
+                .map(|(path, value)| (path.to_jsonpath(), arena.to_json(value)))
+                .collect()
+        };
+
+        assert_eq!(
+            matches("$.definitions[*].properties.key"),
+            [(
+                String::from("$.definitions['io.k8s.api.core.v1.Pod'].properties.key"),
+                String::from(r#"{"type":"string"}"#)
+            )]
+        );
+        assert_eq!(
+            matches("$..key"),
+            [
+                (
+                    "$.definitions['io.k8s.api.core.v1.Pod'].properties.key".into(),
+                    r#"{"type":"string"}"#.into()
+                ),
+                ("$.items[0].key".into(), "1".into()),
+                ("$.items[1].key".into(), "2".into()),
+            ]
+        );
+        assert_eq!(
+            matches("$['items'][-1]"),
+            [("$.items[2]".into(), r#"{"other":3}"#.into())]
+        );
+        assert_eq!(
+            matches("$.definitions.*.properties[\"it's\"]"),
+            [(
+                String::from(r"$.definitions['io.k8s.api.core.v1.Node'].properties['it\'s']"),
+                String::from("1")
+            )]
+        );
+
+        // the path is also a JSON pointer
+        let query = Query::parse("$.items[1].key").unwrap();
+        assert_eq!(arena.query(&root, &query)[0].0.to_string(), "/items/1/key");
+
+        assert_eq!(Query::parse("$.a[").unwrap_err(), QueryError { offset: 3 });
+        assert_eq!(Query::parse("a").unwrap_err(), QueryError { offset: 0 });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn select_as() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Item<'a> {
+            name: &'a str,
+            count: u32,
+        }
+
+        let src =
+            r#"{"items": [{"name": "a", "count": 1}, {"name": "b", "count": 2}], "other": 3}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+
+        let query = Query::parse("$.items[*]").unwrap();
+        let items: Vec<Item<'_>> = query.select_as(&arena, &root).unwrap();
+        assert_eq!(
+            items,
+            [
+                Item {
+                    name: "a",
+                    count: 1
+                },
+                Item {
+                    name: "b",
+                    count: 2
+                }
+            ]
+        );
+
+        let query = Query::parse("$..count").unwrap();
+        let counts: Vec<u32> = query.select_as(&arena, &root).unwrap();
+        assert_eq!(counts, [1, 2]);
+        let err = query.select_as::<Vec<&str>>(&arena, &root).unwrap_err();
+        assert_eq!(err.span, Some(34..35));
+    }
+}
@@ -0,0 +1,263 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::copy::Copier;
+use crate::{Arena, Document, LeafValue, Value, ValueKind};
+
+/// What to do with a selected value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Redaction {
+    /// Replace the value with `"***"`.
+    Mask,
+    /// Leave the value, and its key, out entirely.
+    Drop,
+}
+
+/// One step of the path from the root to a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Selects the values to redact.
+///
+/// Implemented for closures taking the path to a value and the value itself,
+/// and for [`RedactPaths`].
+pub trait Redact {
+    fn redact(&mut self, path: &[PathSegment<'_>], value: &Value) -> Option<Redaction>;
+}
+
+impl<F> Redact for F
+where
+    F: FnMut(&[PathSegment<'_>], &Value) -> Option<Redaction>,
+{
+    fn redact(&mut self, path: &[PathSegment<'_>], value: &Value) -> Option<Redaction> {
+        self(path, value)
+    }
+}
+
+/// Redacts the values at a set of [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901),
+/// where a `*` segment matches any key or index.
+pub struct RedactPaths {
+    paths: Vec<Vec<String>>,
+    redaction: Redaction,
+}
+
+impl RedactPaths {
+    /// Pointers that are not empty and do not start with `/` never match.
+    pub fn new<'p>(pointers: impl IntoIterator<Item = &'p str>, redaction: Redaction) -> Self {
+        let paths = pointers
+            .into_iter()
+            .filter_map(|pointer| match pointer {
+                "" => Some(Vec::new()),
+                _ => Some(
+                    pointer
+                        .strip_prefix('/')?
+                        .split('/')
+                        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                        .collect(),
+                ),
+            })
+            .collect();
+        Self { paths, redaction }
+    }
+}
+
+impl Redact for RedactPaths {
+    fn redact(&mut self, path: &[PathSegment<'_>], _: &Value) -> Option<Redaction> {
+        let matches = |pattern: &Vec<String>| {
+            pattern.len() == path.len()
+                && core::iter::zip(pattern, path).all(|(pattern, segment)| match segment {
+                    _ if pattern == "*" => true,
+                    PathSegment::Key(key) => pattern == key,
+                    PathSegment::Index(i) => crate::flatten::array_index(pattern) == Some(*i),
+                })
+        };
+        self.paths.iter().any(matches).then_some(self.redaction)
+    }
+}
+
+struct Frame {
+    keys: Option<Range<u32>>,
+    values: Range<u32>,
+    index: usize,
+    first: bool,
+}
+
+impl Arena<'_> {
+    /// Serialize `value` as compact JSON, with the values selected by `select`
+    /// masked or dropped.
+    ///
+    /// Dropping the root value gives `null`.
+    pub fn redact(&self, value: &Value, mut select: impl Redact) -> String {
+        let mut out = String::new();
+        let mut path = Vec::new();
+        let mut stack = Vec::new();
+
+        match select.redact(&path, value) {
+            Some(Redaction::Mask) => out.push_str("\"***\""),
+            Some(Redaction::Drop) => out.push_str("null"),
+            None => self.open(value, &mut stack, &mut out),
+        }
+
+        while let Some(frame) = stack.last_mut() {
+            let Some(v) = frame.values.next() else {
+                out.push(match frame.keys {
+                    Some(_) => '}',
+                    None => ']',
+                });
+                stack.pop();
+                path.pop();
+                continue;
+            };
+
+            let index = frame.index;
+            frame.index += 1;
+            let key = frame
+                .keys
+                .as_ref()
+                .map(|keys| &self.keys[keys.start as usize + index]);
+            path.push(match key {
+                Some(key) => PathSegment::Key(&self[key]),
+                None => PathSegment::Index(index),
+            });
+
+            let value = &self.values[v as usize];
+            let redaction = select.redact(&path, value);
+            if redaction == Some(Redaction::Drop) {
+                path.pop();
+                continue;
+            }
+
+            if !core::mem::take(&mut frame.first) {
+                out.push(',');
+            }
+            if let Some(key) = key {
+                self.write_key(key, &mut out);
+                out.push(':');
+            }
+
+            match (redaction, value.kind()) {
+                (Some(_), _) => out.push_str("\"***\""),
+                // the container's closing pops its path segment.
+                (None, ValueKind::Object(_) | ValueKind::Array(_)) => {
+                    self.open(value, &mut stack, &mut out);
+                    continue;
+                }
+                (None, ValueKind::Leaf(_)) => self.write_json(value, &mut out),
+            }
+            path.pop();
+        }
+
+        out
+    }
+
+    /// Copy `value`, keeping only the values for which `keep` returns `true`.
+    ///
+    /// Dropping a container drops everything below it,
+    /// and dropping the root value gives `null`.
+    pub fn filter(
+        &self,
+        value: &Value,
+        mut keep: impl FnMut(&[PathSegment<'_>], &Value) -> bool,
+    ) -> Document {
+        if !keep(&[], value) {
+            let mut arena = Arena::new_shared(Arc::from("null"));
+            let root = arena.root(ValueKind::Leaf(LeafValue::Null), 0..4);
+            return Document { arena, root };
+        }
+
+        let mut arena = Arena::new_shared(Arc::from(""));
+        let copy = Copier::new(self, &mut arena, None)
+            .with_filter(&mut keep)
+            .copy(value);
+        let root = match copy {
+            Ok(value) => value,
+            Err(_) => unreachable!("copies without refs cannot fail"),
+        };
+        Document { arena, root }
+    }
+
+    fn open(&self, value: &Value, stack: &mut Vec<Frame>, out: &mut String) {
+        let (open, keys, values) = match value.kind() {
+            ValueKind::Leaf(_) => return self.write_json(value, out),
+            ValueKind::Object(object) => ('{', Some(object.keys(&self.values)), object.values()),
+            ValueKind::Array(array) => ('[', None, array.values()),
+        };
+        out.push(open);
+        stack.push(Frame {
+            keys,
+            values,
+            index: 0,
+            first: true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, LeafValue, PathSegment, RedactPaths, Redaction, ValueKind};
+
+    #[test]
+    fn redact() {
+        let src = r#"{"user": {"email": "a@b.c", "name": "a"}, "tokens": ["x", "y"], "n": 1}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+
+        let paths = RedactPaths::new(["/user/email", "/tokens/*"], Redaction::Mask);
+        assert_eq!(
+            arena.redact(&root, paths),
+            r#"{"user":{"email":"***","name":"a"},"tokens":["***","***"],"n":1}"#
+        );
+
+        let paths = RedactPaths::new(["/tokens/+1", "/tokens/01"], Redaction::Mask);
+        assert_eq!(arena.redact(&root, paths), arena.to_json(&root));
+
+        let paths = RedactPaths::new(["/user", "/tokens/0"], Redaction::Drop);
+        assert_eq!(arena.redact(&root, paths), r#"{"tokens":["y"],"n":1}"#);
+
+        // drop every string under a key containing "e"
+        let json = arena.redact(&root, |path: &[PathSegment<'_>], value: &crate::Value| {
+            let keyed = path
+                .iter()
+                .any(|s| matches!(s, PathSegment::Key(k) if k.contains('e')));
+            let string = matches!(value.kind(), ValueKind::Leaf(LeafValue::String));
+            (keyed && string).then_some(Redaction::Drop)
+        });
+        assert_eq!(json, r#"{"user":{},"tokens":[],"n":1}"#);
+
+        assert_eq!(
+            arena.redact(&root, RedactPaths::new([""], Redaction::Mask)),
+            r#""***""#
+        );
+    }
+
+    #[test]
+    fn filter() {
+        let src = r#"{"paths": {"/": {"description": "root", "get": {"description": "list"}}}, "description": 1}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+
+        let doc = arena.filter(&root, |path, _| {
+            path.last() != Some(&PathSegment::Key("description"))
+        });
+        assert_eq!(
+            doc.arena().to_json(doc.root()),
+            r#"{"paths":{"/":{"get":{}}}}"#
+        );
+
+        // only keep the first element of arrays
+        let mut arena = Arena::new("[[1, 2], [3]]");
+        let root = crate::parse(&mut arena).unwrap();
+        let doc = arena.filter(&root, |path, _| {
+            !matches!(path.last(), Some(PathSegment::Index(1..)))
+        });
+        assert_eq!(doc.arena().to_json(doc.root()), "[[1]]");
+
+        let doc = arena.filter(&root, |_, _| false);
+        assert_eq!(doc.arena().to_json(doc.root()), "null");
+    }
+}
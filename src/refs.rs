@@ -0,0 +1,293 @@
+//! Resolving internal `$ref` pointers, as used by JSON Schema and OpenAPI documents.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+mod overlay;
+mod workspace;
+
+pub use overlay::{MergedValueRef, Overlay};
+pub use workspace::{DocId, LoadError, Workspace, WorkspaceError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefError {
+    /// The span of the offending `$ref` value.
+    pub span: Range<u32>,
+    pub kind: RefErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefErrorKind {
+    /// Only pointers into the same document, like `#/definitions/foo`, are supported.
+    Unsupported,
+    /// The pointer does not point to a value.
+    NotFound,
+    /// Following the reference leads back to itself.
+    Cycle,
+    /// The reference is to a document that is not in the [`Workspace`].
+    UnknownDocument,
+}
+
+/// Resolves `$ref` pointers within a single document.
+pub struct Refs<'a, 's> {
+    arena: &'a Arena<'s>,
+    root: &'a Value,
+}
+
+impl<'a, 's> Refs<'a, 's> {
+    pub fn new(arena: &'a Arena<'s>, root: &'a Value) -> Self {
+        Self { arena, root }
+    }
+
+    /// Look up a [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901),
+    /// like `/definitions/foo`, from the root of the document.
+    pub fn pointer(&self, pointer: &str) -> Option<&'a Value> {
+        let mut value = self.root;
+        if pointer.is_empty() {
+            return Some(value);
+        }
+
+        let mut segments = pointer.strip_prefix('/')?.split('/');
+        segments.try_for_each(|segment| {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            value = match value.kind() {
+                ValueKind::Object(object) => self.arena.get(&object, &segment)?,
+                ValueKind::Array(array) => {
+                    let index = crate::flatten::array_index(&segment)?;
+                    self.arena.array_values(&array).get(index)?
+                }
+                ValueKind::Leaf(_) => return None,
+            };
+            Some(())
+        })?;
+        Some(value)
+    }
+
+    /// The `$ref` string of `value`, if it is a reference object.
+    pub(crate) fn ref_of(&self, value: &'a Value) -> Option<&'a Value> {
+        let ValueKind::Object(object) = value.kind() else {
+            return None;
+        };
+        self.arena
+            .get(&object, "$ref")
+            .filter(|r| matches!(r.kind(), ValueKind::Leaf(LeafValue::String)))
+    }
+
+    /// Follow `value` if it is a `{"$ref": "#/..."}` object, until reaching a value that is not a reference.
+    ///
+    /// Values that are not references are returned as is.
+    pub fn resolve(&self, mut value: &'a Value) -> Result<&'a Value, RefError> {
+        let mut seen = Vec::new();
+        while let Some(r) = self.ref_of(value) {
+            if seen.iter().any(|v| core::ptr::eq(*v, r)) {
+                return Err(error(self.arena, r, RefErrorKind::Cycle));
+            }
+            seen.push(r);
+
+            let pointer = self.arena.as_str(r).unwrap_or_default();
+            let Some(pointer) = pointer.strip_prefix('#') else {
+                return Err(error(self.arena, r, RefErrorKind::Unsupported));
+            };
+            value = self
+                .pointer(pointer)
+                .ok_or_else(|| error(self.arena, r, RefErrorKind::NotFound))?;
+        }
+        Ok(value)
+    }
+
+    /// Copy `value` into `dest`, with every reference replaced by a copy of its target.
+    ///
+    /// Recursive schemas cannot be inlined, and return a [`RefErrorKind::Cycle`] error.
+    /// Note that the source text of the copied containers still contains the references.
+    pub fn inline_into(&self, value: &'a Value, dest: &mut Arena<'_>) -> Result<Value, RefError> {
+        crate::copy::Copier::new(self.arena, dest, Some(self)).copy(value)
+    }
+
+    /// Serialize `value` as compact JSON, with every reference replaced by its target.
+    ///
+    /// Recursive schemas cannot be inlined, and return a [`RefErrorKind::Cycle`] error.
+    pub fn inline_json(&self, value: &'a Value) -> Result<String, RefError> {
+        let mut out = String::new();
+        let mut stack: Vec<Frame> = Vec::new();
+        // the targets of the references currently being inlined.
+        let mut active: Vec<&'a Value> = Vec::new();
+
+        self.write_value(value, &mut stack, &mut active, &mut out)?;
+        while let Some(frame) = stack.last_mut() {
+            let next = match frame.keys.as_mut() {
+                Some(keys) => keys
+                    .next()
+                    .zip(frame.values.next())
+                    .map(|(k, v)| (Some(k), v)),
+                None => frame.values.next().map(|v| (None, v)),
+            };
+            match next {
+                Some((key, v)) => {
+                    if !core::mem::take(&mut frame.first) {
+                        out.push(',');
+                    }
+                    if let Some(k) = key {
+                        self.arena.write_key(&self.arena.keys[k as usize], &mut out);
+                        out.push(':');
+                    }
+                    let value = &self.arena.values[v as usize];
+                    self.write_value(value, &mut stack, &mut active, &mut out)?;
+                }
+                None => {
+                    out.push(if frame.keys.is_some() { '}' } else { ']' });
+                    if frame.inlined {
+                        active.pop();
+                    }
+                    stack.pop();
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn write_value(
+        &self,
+        value: &'a Value,
+        stack: &mut Vec<Frame>,
+        active: &mut Vec<&'a Value>,
+        out: &mut String,
+    ) -> Result<(), RefError> {
+        let target = self.resolve(value)?;
+        let inlined = !core::ptr::eq(target, value);
+        if inlined {
+            if active.iter().any(|v| core::ptr::eq(*v, target)) {
+                let r = self.ref_of(value).unwrap_or(value);
+                return Err(error(self.arena, r, RefErrorKind::Cycle));
+            }
+            active.push(target);
+        }
+
+        let (open, keys, values) = match target.kind() {
+            ValueKind::Leaf(_) => {
+                self.arena.write_json(target, out);
+                if inlined {
+                    active.pop();
+                }
+                return Ok(());
+            }
+            ValueKind::Object(object) => {
+                ('{', Some(object.keys(&self.arena.values)), object.values())
+            }
+            ValueKind::Array(array) => ('[', None, array.values()),
+        };
+        out.push(open);
+        stack.push(Frame {
+            keys,
+            values,
+            first: true,
+            inlined,
+        });
+        Ok(())
+    }
+}
+
+struct Frame {
+    keys: Option<Range<u32>>,
+    values: Range<u32>,
+    first: bool,
+    /// whether this frame is the target of a reference, in `active`.
+    inlined: bool,
+}
+
+fn error(arena: &Arena<'_>, value: &Value, kind: RefErrorKind) -> RefError {
+    RefError {
+        span: arena.span(value),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RefErrorKind, Refs};
+    use crate::Arena;
+
+    #[test]
+    fn small() {
+        let src = r##"{
+            "definitions": {
+                "a": {"type": "object", "properties": {"b": {"$ref": "#/definitions/b"}}},
+                "b": {"$ref": "#/definitions/c~1d"},
+                "c/d": {"type": "array", "items": [{"$ref": "#/definitions/e/0"}]},
+                "e": ["string"],
+                "loop": {"properties": {"self": {"$ref": "#/definitions/loop"}}},
+                "x": {"$ref": "#/definitions/y"},
+                "y": {"$ref": "#/definitions/x"},
+                "missing": {"$ref": "#/definitions/nope"},
+                "remote": {"$ref": "other.json#/a"}
+            }
+        }"##;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let refs = Refs::new(&arena, &root);
+
+        let a = refs.pointer("/definitions/a").unwrap();
+        assert_eq!(
+            refs.inline_json(a).unwrap(),
+            r#"{"type":"object","properties":{"b":{"type":"array","items":["string"]}}}"#
+        );
+
+        let b = refs.pointer("/definitions/b").unwrap();
+        let resolved = refs.resolve(b).unwrap();
+        assert_eq!(
+            arena.to_json(resolved),
+            r##"{"type":"array","items":[{"$ref":"#/definitions/e/0"}]}"##
+        );
+
+        let kind = |name: &str| {
+            let value = refs.pointer(&std::format!("/definitions/{name}")).unwrap();
+            refs.inline_json(value).unwrap_err().kind
+        };
+        let mut dest = Arena::new("");
+        let copy = refs.inline_into(a, &mut dest).unwrap();
+        assert_eq!(dest.to_json(&copy), refs.inline_json(a).unwrap());
+        // the source text of a container is copied as is, with its refs.
+        assert_eq!(
+            dest.raw(&copy),
+            r##"{"type": "object", "properties": {"b": {"$ref": "#/definitions/b"}}}"##
+        );
+
+        assert_eq!(kind("loop"), RefErrorKind::Cycle);
+        assert_eq!(kind("x"), RefErrorKind::Cycle);
+        assert_eq!(kind("missing"), RefErrorKind::NotFound);
+        assert_eq!(kind("remote"), RefErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn kubernetes() {
+        let src = include_str!("../testdata/kubernetes-oapi.json");
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let refs = Refs::new(&arena, &root);
+
+        let deployment = refs
+            .pointer("/definitions/io.k8s.api.apps.v1.Deployment")
+            .unwrap();
+        let inlined = refs.inline_json(deployment).unwrap();
+        assert!(!inlined.contains("$ref"));
+        assert!(inlined.contains("terminationGracePeriodSeconds"));
+
+        let props = refs
+            .pointer("/definitions/io.k8s.apiextensions-apiserver.pkg.apis.apiextensions.v1.JSONSchemaProps")
+            .unwrap();
+        assert_eq!(
+            refs.inline_json(props).unwrap_err().kind,
+            RefErrorKind::Cycle
+        );
+
+        let mut dest = Arena::new("");
+        let copy = refs.inline_into(deployment, &mut dest).unwrap();
+        assert_eq!(dest.to_json(&copy), inlined);
+        assert_eq!(
+            refs.inline_into(props, &mut dest).unwrap_err().kind,
+            RefErrorKind::Cycle
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::{DocId, Workspace};
+use crate::{Value, ValueKind, ValueRef};
+
+/// Layered documents merged into one, from [`Workspace::overlay`].
+///
+/// Objects are merged key by key, and any other value in a higher layer replaces the one below it.
+/// Every value is read from the document it came from, so [`MergedValueRef::origin`] says where it was set.
+pub struct Overlay<'w> {
+    workspace: &'w Workspace,
+    /// the layers, highest first
+    layers: Vec<DocId>,
+}
+
+impl Workspace {
+    /// Merge `layers`, like a base config followed by its overrides.
+    /// Later layers take precedence over earlier ones.
+    pub fn overlay(&self, layers: impl IntoIterator<Item = DocId>) -> Overlay<'_> {
+        let mut layers: Vec<DocId> = layers.into_iter().collect();
+        layers.reverse();
+        Overlay {
+            workspace: self,
+            layers,
+        }
+    }
+}
+
+impl<'w> Overlay<'w> {
+    /// The merged document.
+    pub fn root(&self) -> MergedValueRef<'w> {
+        let roots = self
+            .layers
+            .iter()
+            .map(|&id| (id, self.workspace.document(id).root()));
+        MergedValueRef {
+            workspace: self.workspace,
+            layers: merged(roots),
+        }
+    }
+
+    /// The merged value at `path`.
+    ///
+    /// Path segments are object keys, or decimal indices for arrays.
+    pub fn get(&self, path: &[&str]) -> Option<MergedValueRef<'w>> {
+        path.iter()
+            .try_fold(self.root(), |value, segment| value.get(segment))
+    }
+
+    /// Serialize the merged document as compact JSON.
+    pub fn to_json(&self) -> String {
+        self.root().to_json()
+    }
+}
+
+/// A value of an [`Overlay`], which knows the document it came from.
+#[derive(Clone)]
+pub struct MergedValueRef<'w> {
+    workspace: &'w Workspace,
+    /// the values that are merged together, highest first. Never empty.
+    layers: Vec<(DocId, &'w Value)>,
+}
+
+impl<'w> MergedValueRef<'w> {
+    /// The document this value came from, and its span in that document.
+    ///
+    /// For a merged object, this is the object in the highest layer that has one.
+    pub fn origin(&self) -> (DocId, Range<u32>) {
+        let (id, value) = self.layers[0];
+        (id, self.workspace.document(id).arena().span(value))
+    }
+
+    /// The value as written in the highest layer.
+    ///
+    /// For a merged object, only the keys of this layer are visible through it.
+    pub fn value_ref(&self) -> ValueRef<'w, 'static> {
+        let (id, value) = self.layers[0];
+        self.workspace.document(id).arena().value_ref(value)
+    }
+
+    /// The merged value of `segment`, an object key or a decimal array index.
+    pub fn get(&self, segment: &str) -> Option<Self> {
+        let children = self.layers.iter().filter_map(|&(id, value)| {
+            let arena = self.workspace.document(id).arena();
+            let child = match value.kind() {
+                ValueKind::Object(object) => arena.get(&object, segment),
+                ValueKind::Array(array) => arena
+                    .array_values(&array)
+                    .get(crate::flatten::array_index(segment)?),
+                ValueKind::Leaf(_) => None,
+            };
+            child.map(|child| (id, child))
+        });
+        let layers = merged(children);
+        (!layers.is_empty()).then_some(Self {
+            workspace: self.workspace,
+            layers,
+        })
+    }
+
+    /// The keys of a merged object, in the order they are first set, from the lowest layer up.
+    pub fn keys(&self) -> Vec<&'w str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for &(id, value) in self.layers.iter().rev() {
+            let ValueKind::Object(object) = value.kind() else {
+                continue;
+            };
+            for (key, _) in self.workspace.document(id).arena().entries(&object) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys
+    }
+
+    /// Serialize the merged value as compact JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    fn write_json(&self, out: &mut String) {
+        if self.layers.len() == 1 {
+            self.value_ref().arena().write_json(self.layers[0].1, out);
+            return;
+        }
+
+        out.push('{');
+        for (i, key) in self.keys().into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push('"');
+            crate::ser::escape(key, out);
+            out.push_str("\":");
+            if let Some(child) = self.get(key) {
+                child.write_json(out);
+            }
+        }
+        out.push('}');
+    }
+}
+
+/// The values that merge into one, from the highest layer down.
+///
+/// A value that is not an object hides everything below it,
+/// and objects only merge with the objects directly below them.
+fn merged<'w>(mut layers: impl Iterator<Item = (DocId, &'w Value)>) -> Vec<(DocId, &'w Value)> {
+    let is_object = |(_, value): &(DocId, &Value)| matches!(value.kind(), ValueKind::Object(_));
+    match layers.next() {
+        None => Vec::new(),
+        Some(top) if !is_object(&top) => vec![top],
+        Some(top) => core::iter::once(top)
+            .chain(layers.take_while(is_object))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use crate::refs::Workspace;
+    use crate::Document;
+
+    #[test]
+    fn overlay() {
+        let mut workspace = Workspace::new();
+        let mut layer = |name: &str, src: &str| {
+            workspace.insert(name, Document::parse(String::from(src)).unwrap())
+        };
+        let base = layer(
+            "base.json",
+            r#"{"db": {"host": "localhost", "port": 5432}, "features": ["a"], "log": {"level": "info"}}"#,
+        );
+        let env = layer(
+            "prod.json",
+            r#"{"db": {"host": "db.prod"}, "features": ["b", "c"], "log": "off"}"#,
+        );
+        let local = layer("local.json", r#"{"db": {"port": 6543, "user": "me"}}"#);
+        let overlay = workspace.overlay([base, env, local]);
+
+        let host = overlay.get(&["db", "host"]).unwrap();
+        let (id, span) = host.origin();
+        assert_eq!(workspace.name(id), "prod.json");
+        assert_eq!(span, 16..25);
+        assert_eq!(workspace.line_column(id, span.start), (1, 17));
+        assert_eq!(host.value_ref().to_string(), r#""db.prod""#);
+
+        let db = overlay.get(&["db"]).unwrap();
+        assert_eq!(db.get("port").unwrap().origin().0, local);
+        assert_eq!(db.keys(), ["host", "port", "user"]);
+        assert_eq!(overlay.get(&["features", "1"]).unwrap().origin().0, env);
+        // a string hides the object below it
+        assert!(overlay.get(&["log", "level"]).is_none());
+
+        assert_eq!(
+            overlay.to_json(),
+            r#"{"db":{"host":"db.prod","port":6543,"user":"me"},"features":["b","c"],"log":"off"}"#
+        );
+    }
+}
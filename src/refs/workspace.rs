@@ -0,0 +1,336 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use super::{RefErrorKind, Refs};
+use crate::{Document, Error, Value};
+
+/// A document in a [`Workspace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DocId(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceError {
+    /// The document with the offending `$ref` value.
+    pub document: DocId,
+    /// The span of the offending `$ref` value.
+    pub span: Range<u32>,
+    pub kind: RefErrorKind,
+}
+
+/// The error from [`Workspace::load_references`].
+#[derive(Debug)]
+pub enum LoadError<E> {
+    /// The loader failed to load the document with this name.
+    Load { name: String, error: E },
+    /// The document with this name is not valid JSON.
+    Json { name: String, error: Error },
+}
+
+impl<E: fmt::Display> fmt::Display for LoadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Load { name, error } => write!(f, "could not load {name}: {error}"),
+            LoadError::Json { name, error } => write!(f, "could not parse {name}: {error}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for LoadError<E> {}
+
+/// Several documents that can refer to each other, like an OpenAPI document split over many files.
+///
+/// Each document has a name, like a path or URL, that references from the other documents
+/// are resolved against. `{"$ref": "schemas/pet.json#/Pet"}` in `api/root.json` refers to
+/// `/Pet` in the document named `api/schemas/pet.json`.
+#[derive(Default)]
+pub struct Workspace {
+    documents: Vec<(String, Document)>,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `document` with this name, replacing any document that already has it.
+    pub fn insert(&mut self, name: impl Into<String>, document: Document) -> DocId {
+        let name = name.into();
+        match self.id(&name) {
+            Some(id) => {
+                self.documents[id.0].1 = document;
+                id
+            }
+            None => {
+                self.documents.push((name, document));
+                DocId(self.documents.len() - 1)
+            }
+        }
+    }
+
+    /// The document with this name.
+    pub fn id(&self, name: &str) -> Option<DocId> {
+        self.documents
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(DocId)
+    }
+
+    pub fn name(&self, id: DocId) -> &str {
+        &self.documents[id.0].0
+    }
+
+    pub fn document(&self, id: DocId) -> &Document {
+        &self.documents[id.0].1
+    }
+
+    /// Load every document that `id` refers to, and every document those refer to, and so on.
+    ///
+    /// `load` is called with the name of each document that is not in the workspace yet,
+    /// resolved against the name of the document that refers to it, and returns its source.
+    /// This leaves it to the application to decide how, and whether, to read files or fetch URLs.
+    pub fn load_references<E>(
+        &mut self,
+        id: DocId,
+        mut load: impl FnMut(&str) -> Result<String, E>,
+    ) -> Result<(), LoadError<E>> {
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            for name in self.external_names(id) {
+                if self.id(&name).is_some() {
+                    continue;
+                }
+                let document = match load(&name) {
+                    Ok(src) => Document::parse(src),
+                    Err(error) => return Err(LoadError::Load { name, error }),
+                };
+                match document {
+                    Ok(document) => pending.push(self.insert(name, document)),
+                    Err(error) => return Err(LoadError::Json { name, error }),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The names of the documents that `id` refers to.
+    fn external_names(&self, id: DocId) -> Vec<String> {
+        let refs = self.refs(id);
+        let document = self.document(id);
+        let arena = document.arena();
+        let mut names: Vec<String> = arena
+            .values
+            .iter()
+            .filter(|value| !value.is_header())
+            .chain([document.root()])
+            .filter_map(|value| arena.as_str(refs.ref_of(value)?))
+            .filter_map(|reference| {
+                let name = reference.split('#').next().unwrap_or_default();
+                (!name.is_empty()).then(|| join(self.name(id), name))
+            })
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    fn refs(&self, id: DocId) -> Refs<'_, 'static> {
+        let document = self.document(id);
+        Refs::new(document.arena(), document.root())
+    }
+
+    /// The 1-based line and column, in characters, of `offset` in a document.
+    ///
+    /// Together with [`name`](Self::name), this points diagnostics at the file a value came from.
+    pub fn line_column(&self, id: DocId, offset: u32) -> (usize, usize) {
+        let src = &self.document(id).arena().scratch.src;
+        let before = src.get(..offset as usize).unwrap_or(src);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// Look up a JSON pointer, like `/components/schemas/Pet`, from the root of a document.
+    pub fn pointer(&self, id: DocId, pointer: &str) -> Option<&Value> {
+        self.refs(id).pointer(pointer)
+    }
+
+    /// Look up a reference, like `schemas/pet.json#/Pet` or `#/Pet`, as it would be resolved from `from`.
+    pub fn reference(&self, from: DocId, reference: &str) -> Option<(DocId, &Value)> {
+        self.lookup(from, reference).ok()
+    }
+
+    fn lookup(&self, from: DocId, reference: &str) -> Result<(DocId, &Value), RefErrorKind> {
+        let (name, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        let id = if name.is_empty() {
+            from
+        } else {
+            self.id(&join(self.name(from), name))
+                .ok_or(RefErrorKind::UnknownDocument)?
+        };
+        let value = self.pointer(id, pointer).ok_or(RefErrorKind::NotFound)?;
+        Ok((id, value))
+    }
+
+    /// Follow `value`, from the document `id`, if it is a `{"$ref": "..."}` object,
+    /// until reaching a value that is not a reference.
+    ///
+    /// Values that are not references are returned as is.
+    pub fn resolve<'w>(
+        &'w self,
+        mut id: DocId,
+        mut value: &'w Value,
+    ) -> Result<(DocId, &'w Value), WorkspaceError> {
+        let mut seen = Vec::new();
+        while let Some(r) = self.refs(id).ref_of(value) {
+            let error = |kind| WorkspaceError {
+                document: id,
+                span: self.document(id).arena().span(r),
+                kind,
+            };
+            if seen.iter().any(|v| core::ptr::eq(*v, r)) {
+                return Err(error(RefErrorKind::Cycle));
+            }
+            seen.push(r);
+
+            let reference = self.document(id).arena().as_str(r).unwrap_or_default();
+            (id, value) = self.lookup(id, &reference).map_err(error)?;
+        }
+        Ok((id, value))
+    }
+}
+
+/// Resolve `reference` relative to the document named `base`.
+fn join(base: &str, reference: &str) -> String {
+    let path = if reference.starts_with('/') || reference.contains("://") {
+        reference.into()
+    } else {
+        let dir = base.rfind('/').map_or("", |i| &base[..=i]);
+        alloc::format!("{dir}{reference}")
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "." => {}
+            ".." if segments.last().is_some_and(|s| !s.is_empty() && *s != "..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{join, LoadError, Workspace};
+    use crate::refs::RefErrorKind;
+    use crate::Document;
+
+    #[test]
+    fn workspace() {
+        let mut workspace = Workspace::new();
+        let root = workspace.insert(
+            "api/root.json",
+            Document::parse(
+                r##"{
+                    "pet": {"$ref": "schemas/pet.json#/Pet"},
+                    "owner": {"name": {"type": "string"}},
+                    "missing": {"$ref": "nope.json#/a"},
+                    "loop": {"$ref": "schemas/pet.json#/Loop"}
+                }"##,
+            )
+            .unwrap(),
+        );
+        let pet = workspace.insert(
+            "api/schemas/pet.json",
+            Document::parse(
+                r##"{
+                    "Pet": {"$ref": "#/Animal"},
+                    "Animal": {"owner": {"$ref": "../root.json#/owner"}},
+                    "Loop": {"$ref": "../root.json#/loop"}
+                }"##,
+            )
+            .unwrap(),
+        );
+        assert_eq!(workspace.id("api/schemas/pet.json"), Some(pet));
+
+        let value = workspace.pointer(root, "/pet").unwrap();
+        let start = workspace.document(root).arena().span(value).start;
+        assert_eq!(workspace.line_column(root, start), (2, 28));
+        let (id, animal) = workspace.resolve(root, value).unwrap();
+        assert_eq!(id, pet);
+        assert_eq!(
+            workspace.document(id).arena().to_json(animal),
+            r##"{"owner":{"$ref":"../root.json#/owner"}}"##
+        );
+
+        let (id, owner) = workspace
+            .reference(pet, "../root.json#/owner/name")
+            .unwrap();
+        assert_eq!(id, root);
+        assert_eq!(
+            workspace.document(id).arena().to_json(owner),
+            r#"{"type":"string"}"#
+        );
+
+        let error = |name: &str| {
+            let value = workspace.pointer(root, name).unwrap();
+            workspace.resolve(root, value).unwrap_err()
+        };
+        assert_eq!(error("/missing").kind, RefErrorKind::UnknownDocument);
+        assert_eq!(error("/loop").kind, RefErrorKind::Cycle);
+
+        assert_eq!(join("a/b.json", "./c/../d.json"), "a/d.json");
+        assert_eq!(
+            join("https://x.io/a/b.json", "../c.json"),
+            "https://x.io/c.json"
+        );
+        assert_eq!(join("a.json", "/abs/b.json"), "/abs/b.json");
+    }
+
+    #[test]
+    fn load_references() {
+        let files = [
+            (
+                "root.json",
+                r##"{"a": {"$ref": "a.json"}, "b": {"$ref": "dir/b.json#/b"}}"##,
+            ),
+            ("a.json", r##"{"$ref": "dir/b.json#/b"}"##),
+            (
+                "dir/b.json",
+                r##"{"b": {"$ref": "../a.json#/x"}, "c": {"$ref": "c.json"}}"##,
+            ),
+            ("dir/c.json", "[1, 2]"),
+        ];
+        let mut loads = std::vec::Vec::new();
+        let mut load = |name: &str| {
+            loads.push(String::from(name));
+            let (_, src) = files.iter().find(|(n, _)| *n == name).ok_or("not found")?;
+            Ok::<_, &str>(String::from(*src))
+        };
+
+        let mut workspace = Workspace::new();
+        let root = workspace.insert("root.json", Document::parse(files[0].1).unwrap());
+        workspace.load_references(root, &mut load).unwrap();
+        // each document is loaded once
+        assert_eq!(loads, ["a.json", "dir/b.json", "dir/c.json"]);
+
+        let c = workspace.reference(root, "dir/c.json#/1").unwrap();
+        assert_eq!(workspace.document(c.0).arena().to_json(c.1), "2");
+
+        let mut workspace = Workspace::new();
+        let root = workspace.insert(
+            "root.json",
+            Document::parse(r#"{"$ref": "x.json"}"#).unwrap(),
+        );
+        let err = workspace
+            .load_references(root, |_| Err::<String, _>("not found"))
+            .unwrap_err();
+        assert!(matches!(err, LoadError::Load { name, error: "not found" } if name == "x.json"));
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use foldhash::quality::RandomState;
+use hashbrown::HashMap;
+
+use crate::{Arena, StringKey, Value, ValueKind};
+
+/// Picks new names for object keys.
+///
+/// Implemented for closures returning the new name, or `None` to keep the key,
+/// and for [`Case`].
+pub trait RenameKey {
+    fn rename(&mut self, key: &str) -> Option<String>;
+}
+
+impl<F> RenameKey for F
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn rename(&mut self, key: &str) -> Option<String> {
+        self(key)
+    }
+}
+
+/// A key naming convention.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    /// `userId`
+    Camel,
+    /// `user_id`
+    Snake,
+}
+
+impl Case {
+    /// Convert `key` to this case.
+    pub fn convert(self, key: &str) -> Cow<'_, str> {
+        match self {
+            Case::Camel if key.trim_start_matches('_').contains('_') => {
+                let prefix = key.len() - key.trim_start_matches('_').len();
+                let mut out = String::from(&key[..prefix]);
+                let mut upper = false;
+                for c in key[prefix..].chars() {
+                    match c {
+                        '_' => upper = true,
+                        c if upper => {
+                            upper = false;
+                            out.extend(c.to_uppercase());
+                        }
+                        c => out.push(c),
+                    }
+                }
+                Cow::Owned(out)
+            }
+            Case::Snake if key.chars().any(char::is_uppercase) => {
+                let chars: Vec<char> = key.chars().collect();
+                let mut out = String::with_capacity(key.len() + 4);
+                for (i, &c) in chars.iter().enumerate() {
+                    if c.is_uppercase() {
+                        // a word starts at `aB`, or at the `Bc` of `ABc`
+                        let prev = i.checked_sub(1).map(|i| chars[i]);
+                        let next = chars.get(i + 1);
+                        let boundary = match prev {
+                            Some(p) if p.is_lowercase() || p.is_ascii_digit() => true,
+                            Some(p) if p.is_uppercase() => next.is_some_and(|n| n.is_lowercase()),
+                            _ => false,
+                        };
+                        if boundary {
+                            out.push('_');
+                        }
+                        out.extend(c.to_lowercase());
+                    } else {
+                        out.push(c);
+                    }
+                }
+                Cow::Owned(out)
+            }
+            _ => Cow::Borrowed(key),
+        }
+    }
+}
+
+impl RenameKey for Case {
+    fn rename(&mut self, key: &str) -> Option<String> {
+        match self.convert(key) {
+            Cow::Owned(key) => Some(key),
+            Cow::Borrowed(_) => None,
+        }
+    }
+}
+
+impl Arena<'_> {
+    /// Rename the keys of every object in `value`, in place.
+    ///
+    /// Keys are interned, so `rename` is only called once for each distinct key.
+    pub fn rename_keys(&mut self, value: &Value, mut rename: impl RenameKey) {
+        let mut objects = Vec::new();
+        let mut stack = Vec::from([value]);
+        while let Some(value) = stack.pop() {
+            let children = match value.kind() {
+                ValueKind::Leaf(_) => continue,
+                ValueKind::Object(object) => {
+                    objects.push(object.keys(&self.values));
+                    object.values()
+                }
+                ValueKind::Array(array) => array.values(),
+            };
+            stack.extend(&self.values[children.start as usize..children.end as usize]);
+        }
+
+        let mut renamed: HashMap<StringKey, StringKey, RandomState> = HashMap::default();
+        for keys in objects {
+            for i in keys {
+                let key = &self.keys[i as usize];
+                let new = match renamed.get(key) {
+                    Some(new) => new.clone(),
+                    None => {
+                        let new = match rename.rename(&self[key]) {
+                            Some(name) => self.intern_copy(&name),
+                            None => key.clone(),
+                        };
+                        renamed.insert(self.keys[i as usize].clone(), new.clone());
+                        new
+                    }
+                };
+                self.keys[i as usize] = new;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{Arena, Case};
+
+    #[test]
+    fn case() {
+        let cases = [
+            ("userId", "user_id"),
+            ("HTTPServer", "http_server"),
+            ("getHTTPResponse2Code", "get_http_response2_code"),
+            ("_privateField", "_private_field"),
+        ];
+        for (camel, snake) in cases {
+            assert_eq!(Case::Snake.convert(camel), snake);
+            assert_eq!(Case::Snake.convert(snake), snake);
+        }
+        assert_eq!(Case::Camel.convert("user_id"), "userId");
+        assert_eq!(Case::Camel.convert("_private_field"), "_privateField");
+        assert_eq!(Case::Camel.convert("userId"), "userId");
+    }
+
+    #[test]
+    fn rename_keys() {
+        let src = r#"{"userId": 1, "items": [{"itemName": "a\"b"}, {"itemName": "userId"}]}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+
+        arena.rename_keys(&root, Case::Snake);
+        assert_eq!(
+            arena.to_json(&root),
+            r#"{"user_id":1,"items":[{"item_name":"a\"b"},{"item_name":"userId"}]}"#
+        );
+
+        arena.rename_keys(&root, |key: &str| {
+            (key == "items").then(|| "list".to_string())
+        });
+        arena.rename_keys(&root, Case::Camel);
+        assert_eq!(
+            arena.to_json(&root),
+            r#"{"userId":1,"list":[{"itemName":"a\"b"},{"itemName":"userId"}]}"#
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::Arena;
+
+/// How much room an arena makes for values and keys before parsing, from the length of the source.
+///
+/// Reserving up front saves the arena from reallocating and copying its values
+/// several times over while parsing a large document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reserve {
+    /// Reserve room for one value per this many bytes of source. `0` reserves none.
+    pub bytes_per_value: usize,
+    /// Reserve room for one key per this many bytes of source. `0` reserves none.
+    pub bytes_per_key: usize,
+}
+
+impl Reserve {
+    /// Reserve nothing, and grow only as needed.
+    pub const NONE: Self = Self {
+        bytes_per_value: 0,
+        bytes_per_key: 0,
+    };
+
+    /// A little more than a pretty-printed OpenAPI document needs,
+    /// which has a value every 58 bytes and a key every 76.
+    pub const DEFAULT: Self = Self {
+        bytes_per_value: 48,
+        bytes_per_key: 64,
+    };
+}
+
+impl Default for Reserve {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Arena<'_> {
+    /// Change how much room the arena makes for values and keys before parsing.
+    pub fn set_reserve(&mut self, reserve: Reserve) {
+        self.reserve = reserve;
+    }
+
+    /// Make room for the values and keys of `len` bytes of source.
+    pub(crate) fn reserve_for(&mut self, len: usize) {
+        let Reserve {
+            bytes_per_value,
+            bytes_per_key,
+        } = self.reserve;
+        if let Some(values) = len.checked_div(bytes_per_value) {
+            track_growth!(self.values, self.values.reserve(values));
+            track_growth!(self.spans, self.spans.reserve(values));
+        }
+        if let Some(keys) = len.checked_div(bytes_per_key) {
+            track_growth!(self.keys, self.keys.reserve(keys));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Reserve};
+
+    #[test]
+    fn reserve() {
+        let src = std::format!("[{}0]", "0, ".repeat(1000));
+
+        let mut arena = Arena::new(&src);
+        crate::parse(&mut arena).unwrap();
+        assert!(arena.values.capacity() >= src.len() / Reserve::DEFAULT.bytes_per_value);
+
+        let mut arena = Arena::new(&src);
+        // room for every value, and the array's header
+        arena.set_reserve(Reserve {
+            bytes_per_value: 2,
+            bytes_per_key: 0,
+        });
+        crate::parse(&mut arena).unwrap();
+        assert_eq!(arena.values.capacity(), src.len() / 2);
+        assert_eq!(arena.spans.capacity(), src.len() / 2);
+        assert_eq!(arena.keys.capacity(), 0);
+    }
+}
@@ -0,0 +1,656 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::ops::Range;
+use hashbrown::hash_table::Entry;
+use logos::Logos;
+
+use crate::{
+    Arena, Array, ContextItem, Error, LeafValue, Object, Pending, PollParse, Radix, RawParser,
+    Repr, StackItem, StackItemKind, StringKey, Token, Value, ValueKind, MAX_LEN,
+};
+
+/// The state of an unfinished parse, from [`parse_resumable`].
+///
+/// A checkpoint can be turned into bytes with [`to_bytes`](Self::to_bytes),
+/// so a parse of a growing input can be continued in another process.
+#[derive(Debug)]
+pub struct Checkpoint {
+    /// where to continue lexing from.
+    offset: u32,
+    context: ContextItem,
+    stack: Vec<StackItem>,
+    value_stack: Vec<Pending>,
+    key_stack: Vec<StringKey>,
+    // the contents of the arena so far
+    values: Vec<Value>,
+    spans: Vec<Range<u32>>,
+    keys: Vec<StringKey>,
+    scratch: String,
+}
+
+pub enum Resume {
+    Complete(Value),
+    /// The input ended before the document did.
+    Suspended(Checkpoint),
+}
+
+/// [`Checkpoint::from_bytes`] was given bytes that are not a checkpoint.
+#[derive(Debug, PartialEq)]
+pub struct InvalidCheckpoint;
+
+impl fmt::Display for InvalidCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid parser checkpoint")
+    }
+}
+
+impl core::error::Error for InvalidCheckpoint {}
+
+/// Parse a document that might not be fully written yet.
+///
+/// If the input ends before the document does, the parser state is returned
+/// as a [`Checkpoint`]. A number at the very end of the input might have more
+/// digits to come, so it is left for the next resume. Once more input is available, pass it to
+/// [`Checkpoint::resume`] to continue where this parse left off.
+pub fn parse_resumable(arena: &mut Arena<'_>) -> Result<Resume, Error> {
+    Checkpoint {
+        offset: 0,
+        context: ContextItem::WaitingValue,
+        stack: Vec::new(),
+        value_stack: Vec::new(),
+        key_stack: Vec::new(),
+        values: Vec::new(),
+        spans: Vec::new(),
+        keys: Vec::new(),
+        scratch: String::new(),
+    }
+    .resume(arena)
+}
+
+impl Checkpoint {
+    /// The number of bytes of input consumed so far.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Continue parsing.
+    ///
+    /// `arena` must be new, and its source must start with the same input
+    /// that the checkpoint was taken from.
+    ///
+    /// If the source does not match the checkpoint, such as a string where the checkpoint
+    /// has a number, an error is returned at the first span that does not.
+    ///
+    /// # Panics
+    ///
+    /// If the source is shorter than [`offset`](Self::offset), or `arena` is not empty.
+    pub fn resume(self, arena: &mut Arena<'_>) -> Result<Resume, Error> {
+        assert!(
+            arena.values.is_empty() && arena.keys.is_empty(),
+            "arena must be empty"
+        );
+        let src = arena.scratch.src.clone();
+        let len = src.len() as u32;
+        assert!(self.offset <= len, "source is shorter than the checkpoint");
+        let mismatch = self.check_source(&src).err();
+
+        arena.values = self.values;
+        arena.spans = self.spans;
+        arena.keys = self.keys;
+        arena.scratch.scratch = self.scratch;
+
+        let mut lexer = Token::lexer(&src);
+        if mismatch.is_none() {
+            lexer.bump(self.offset as usize);
+        }
+        let mut parser = RawParser {
+            arena,
+            lexer,
+            stack: self.stack,
+            value_stack: self.value_stack,
+            key_stack: self.key_stack,
+            context: self.context,
+        };
+        if let Some(span) = mismatch {
+            return Err(parser.token_error(parser.context.clone(), span));
+        }
+        parser
+            .arena
+            .rebuild_table(parser.key_stack.iter().chain(match &parser.context {
+                ContextItem::Key { key, .. } => Some(key),
+                _ => None,
+            }));
+
+        let mut offset = self.offset;
+        let (context, stack) = loop {
+            // where the lexer was before this step
+            offset = offset.max(parser.lexer.span().end as u32);
+            let before = parser.context.clone();
+            match parser.step() {
+                Ok(PollParse::Ready(value)) => return Ok(Resume::Complete(value)),
+                // a number at the end of the input might have more digits to come,
+                // so suspend before it.
+                Ok(PollParse::Pending)
+                    if matches!(
+                        &parser.context,
+                        ContextItem::Value {
+                            span,
+                            value: ValueKind::Leaf(LeafValue::Number | LeafValue::BigInt),
+                        } if span.end == len
+                    ) =>
+                {
+                    break (before, core::mem::take(&mut parser.stack))
+                }
+                Ok(PollParse::Pending) => {}
+                // out of input
+                Err(e) if e.token.is_none() && e.span.start == len => {
+                    offset = len;
+                    break (e.context, core::mem::take(&mut parser.stack));
+                }
+                // a token cut off by the end of the input
+                Err(e)
+                    if e.token.is_none()
+                        && crate::partial::truncated(&src[e.span.start as usize..]) =>
+                {
+                    offset = e.span.start;
+                    break (e.context, core::mem::take(&mut parser.stack));
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let RawParser {
+            arena,
+            value_stack,
+            key_stack,
+            ..
+        } = parser;
+
+        Ok(Resume::Suspended(Checkpoint {
+            offset,
+            context,
+            stack,
+            value_stack,
+            key_stack,
+            values: core::mem::take(&mut arena.values),
+            spans: core::mem::take(&mut arena.spans),
+            keys: core::mem::take(&mut arena.keys),
+            scratch: core::mem::take(&mut arena.scratch.scratch),
+        }))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::new());
+        w.0.push(VERSION);
+        w.u32(self.offset);
+
+        match &self.context {
+            ContextItem::WaitingKey => w.0.push(0),
+            ContextItem::Key { span, key } => {
+                w.0.push(1);
+                w.range(span);
+                w.range(&key.0);
+            }
+            ContextItem::WaitingValue => w.0.push(2),
+            ContextItem::Value { span, value } => {
+                w.0.push(3);
+                w.range(span);
+                w.kind(Some(*value));
+            }
+        }
+
+        w.u32(self.stack.len() as u32);
+        for item in &self.stack {
+            w.u32(item.span.start);
+            match item.kind {
+                StackItemKind::Array(v) => {
+                    w.0.push(0);
+                    w.u32(v);
+                }
+                StackItemKind::Object(v, k) => {
+                    w.0.push(1);
+                    w.u32(v);
+                    w.u32(k);
+                }
+            }
+        }
+
+        w.u32(self.value_stack.len() as u32);
+        for v in &self.value_stack {
+            w.range(&v.span);
+            w.kind(Some(v.kind));
+        }
+        w.u32(self.values.len() as u32);
+        for (v, span) in core::iter::zip(&self.values, &self.spans) {
+            w.range(span);
+            match v.repr() {
+                Repr::Header { keys } => {
+                    w.kind(None);
+                    w.u32(keys);
+                }
+                _ => w.kind(Some(v.kind())),
+            }
+        }
+        for keys in [&self.key_stack, &self.keys] {
+            w.u32(keys.len() as u32);
+            keys.iter().for_each(|k| w.range(&k.0));
+        }
+        w.u32(self.scratch.len() as u32);
+        w.0.extend_from_slice(self.scratch.as_bytes());
+
+        w.0
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidCheckpoint> {
+        let mut r = Reader(bytes);
+        if r.u8()? != VERSION {
+            return Err(InvalidCheckpoint);
+        }
+        let offset = r.u32()?;
+
+        let context = match r.u8()? {
+            0 => ContextItem::WaitingKey,
+            1 => ContextItem::Key {
+                span: r.range()?,
+                key: StringKey(r.range()?),
+            },
+            2 => ContextItem::WaitingValue,
+            3 => {
+                let Pending { span, kind } = r.pending()?;
+                ContextItem::Value { span, value: kind }
+            }
+            _ => return Err(InvalidCheckpoint),
+        };
+
+        let mut stack = Vec::new();
+        for _ in 0..r.u32()? {
+            let start = r.u32()?;
+            let kind = match r.u8()? {
+                0 => StackItemKind::Array(r.u32()?),
+                1 => StackItemKind::Object(r.u32()?, r.u32()?),
+                _ => return Err(InvalidCheckpoint),
+            };
+            stack.push(StackItem {
+                span: start..,
+                kind,
+            });
+        }
+
+        let value_stack = (0..r.u32()?)
+            .map(|_| r.pending())
+            .collect::<Result<_, _>>()?;
+        let (values, spans) = r.values()?;
+        let key_stack = r.keys()?;
+        let keys = r.keys()?;
+        let scratch_len = r.u32()? as usize;
+        let scratch = r.take(scratch_len)?;
+        let scratch = String::from_utf8(scratch.to_vec()).map_err(|_| InvalidCheckpoint)?;
+        if !r.0.is_empty() {
+            return Err(InvalidCheckpoint);
+        }
+
+        let checkpoint = Self {
+            offset,
+            context,
+            stack,
+            value_stack,
+            key_stack,
+            values,
+            spans,
+            keys,
+            scratch,
+        };
+        checkpoint.validate()?;
+        Ok(checkpoint)
+    }
+
+    /// Check the spans against the source being resumed, which [`validate`](Self::validate)
+    /// cannot see: each must be on char boundaries, and each leaf must span a token of its kind.
+    ///
+    /// Returns the first span that does not.
+    fn check_source(&self, src: &str) -> Result<(), Range<u32>> {
+        let text = |span: &Range<u32>| src.get(span.start as usize..span.end as usize);
+        let key = |k: &StringKey| k.0.start > k.0.end || text(&k.0).is_some();
+        let value = |span: &Range<u32>, kind: Option<ValueKind>| match (text(span), kind) {
+            (Some(raw), Some(ValueKind::Leaf(leaf))) => crate::is_leaf_token(leaf, raw),
+            (raw, _) => raw.is_some(),
+        };
+
+        if !src.is_char_boundary(self.offset as usize) {
+            return Err(self.offset..src.len() as u32);
+        }
+        let mut starts = self.stack.iter().map(|item| item.span.start);
+        if let Some(start) = starts.find(|&s| !src.is_char_boundary(s as usize)) {
+            return Err(start..self.offset);
+        }
+        let mut keys = self
+            .keys
+            .iter()
+            .chain(&self.key_stack)
+            .chain(match &self.context {
+                ContextItem::Key { key, .. } => Some(key),
+                _ => None,
+            });
+        if let Some(k) = keys.find(|k| !key(k)) {
+            return Err(k.0.clone());
+        }
+        let mut values = core::iter::zip(&self.values, &self.spans)
+            .map(|(v, span)| (span, (!v.is_header()).then(|| v.kind())))
+            .chain(self.value_stack.iter().map(|v| (&v.span, Some(v.kind))))
+            .chain(match &self.context {
+                ContextItem::Value { span, value } => Some((span, Some(*value))),
+                _ => None,
+            });
+        match values.find(|(span, kind)| !value(span, *kind)) {
+            Some((span, _)) => Err(span.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that every range is in bounds of the checkpoint, so that resuming cannot panic.
+    ///
+    /// Spans into the source are checked against it when resuming.
+    fn validate(&self) -> Result<(), InvalidCheckpoint> {
+        let key = |k: &StringKey| {
+            let Range { start, end } = k.0;
+            if end < start {
+                self.scratch.get(end as usize..start as usize).is_some()
+            } else {
+                end <= self.offset
+            }
+        };
+        // the headers before each slot, so a container can be checked to hold none.
+        let mut headers = vec![0];
+        for v in &self.values {
+            headers.push(headers[headers.len() - 1] + v.is_header() as u32);
+        }
+        let children = |values: Range<u32>| {
+            values.start >= 1
+                && values.end as usize <= self.values.len()
+                && self.values[values.start as usize - 1].is_header()
+                && headers[values.start as usize] == headers[values.end as usize]
+        };
+        // checked after `children`, so that the header is there.
+        let keys = |object: Object| match self.values[object.values().start as usize - 1].repr() {
+            Repr::Header { keys } => (keys as usize)
+                .checked_add(object.values().len())
+                .is_some_and(|end| end <= self.keys.len()),
+            _ => false,
+        };
+        let value = |span: &Range<u32>, kind: Option<ValueKind>| {
+            span.start <= span.end
+                && match kind {
+                    None => true,
+                    Some(ValueKind::Leaf(_)) => span.end <= self.offset,
+                    Some(ValueKind::Object(object)) => children(object.values()) && keys(object),
+                    Some(ValueKind::Array(array)) => children(array.values()),
+                }
+        };
+        // the stack markers must be in order, and within the value and key stacks.
+        let (mut last_v, mut last_k) = (0, 0);
+        let stack = self.stack.iter().all(|item| {
+            let (v, k) = match item.kind {
+                StackItemKind::Array(v) => (v, last_k),
+                StackItemKind::Object(v, k) => (v, k),
+            };
+            let valid = last_v <= v && last_k <= k;
+            (last_v, last_k) = (v, k);
+            valid
+        }) && last_v as usize <= self.value_stack.len()
+            && last_k as usize <= self.key_stack.len();
+        let context = match &self.context {
+            ContextItem::Key { key: k, .. } => key(k),
+            ContextItem::Value { span, value: kind } => value(span, Some(*kind)),
+            ContextItem::WaitingKey | ContextItem::WaitingValue => true,
+        };
+
+        let valid = stack
+            && context
+            && self.keys.iter().chain(&self.key_stack).all(key)
+            && core::iter::zip(&self.values, &self.spans)
+                .all(|(v, span)| value(span, (!v.is_header()).then(|| v.kind())))
+            && self
+                .value_stack
+                .iter()
+                .all(|v| value(&v.span, Some(v.kind)));
+        if valid {
+            Ok(())
+        } else {
+            Err(InvalidCheckpoint)
+        }
+    }
+}
+
+const VERSION: u8 = 3;
+
+impl Arena<'_> {
+    /// Intern all the keys in the arena, plus `extra`.
+    fn rebuild_table<'k>(&mut self, extra: impl Iterator<Item = &'k StringKey>) {
+        let Self {
+            scratch,
+            hasher,
+            table,
+            small,
+            keys,
+            known,
+            ..
+        } = self;
+        table.clear();
+        small.clear();
+        crate::known::Known::clear(known);
+        let mut insert = |key: &StringKey| {
+            let str = &scratch[key];
+            let hash = hasher.hash_one(str);
+            if let Entry::Vacant(entry) = table.entry(
+                hash,
+                |k| &scratch[k] == str,
+                |k| hasher.hash_one(&scratch[k]),
+            ) {
+                entry.insert(key.clone());
+            }
+        };
+        keys.iter().for_each(&mut insert);
+        extra.for_each(insert);
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn range(&mut self, r: &Range<u32>) {
+        self.u32(r.start);
+        self.u32(r.end);
+    }
+
+    /// The tag and fields of a value of `kind`, or the tag of a container header if `None`,
+    /// which is followed by where its keys start.
+    fn kind(&mut self, kind: Option<ValueKind>) {
+        let Some(kind) = kind else {
+            return self.0.push(11);
+        };
+        match kind {
+            ValueKind::Leaf(LeafValue::Bool(false)) => self.0.push(0),
+            ValueKind::Leaf(LeafValue::Bool(true)) => self.0.push(1),
+            ValueKind::Leaf(LeafValue::Null) => self.0.push(2),
+            ValueKind::Leaf(LeafValue::Number) => self.0.push(3),
+            ValueKind::Leaf(LeafValue::String) => self.0.push(4),
+            ValueKind::Leaf(LeafValue::RadixInt(Radix::Binary)) => self.0.push(7),
+            ValueKind::Leaf(LeafValue::RadixInt(Radix::Octal)) => self.0.push(8),
+            ValueKind::Leaf(LeafValue::RadixInt(Radix::Hex)) => self.0.push(9),
+            ValueKind::Leaf(LeafValue::BigInt) => self.0.push(10),
+            ValueKind::Object(object) => {
+                self.0.push(5);
+                self.range(&object.values());
+            }
+            ValueKind::Array(array) => {
+                self.0.push(6);
+                self.range(&array.values());
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], InvalidCheckpoint> {
+        if self.0.len() < n {
+            return Err(InvalidCheckpoint);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, InvalidCheckpoint> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, InvalidCheckpoint> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn range(&mut self) -> Result<Range<u32>, InvalidCheckpoint> {
+        Ok(self.u32()?..self.u32()?)
+    }
+
+    /// The values of a container, which must fit in its length.
+    fn children(&mut self) -> Result<Range<u32>, InvalidCheckpoint> {
+        let values = self.range()?;
+        if values.start > values.end || values.end - values.start > MAX_LEN {
+            return Err(InvalidCheckpoint);
+        }
+        Ok(values)
+    }
+
+    fn pending(&mut self) -> Result<Pending, InvalidCheckpoint> {
+        let span = self.range()?;
+        let kind = self.kind()?.ok_or(InvalidCheckpoint)?;
+        Ok(Pending { span, kind })
+    }
+
+    /// A value of a kind, or a container header, as `None`, whose keys are still to be read.
+    fn kind(&mut self) -> Result<Option<ValueKind>, InvalidCheckpoint> {
+        let kind = match self.u8()? {
+            0 => ValueKind::Leaf(LeafValue::Bool(false)),
+            1 => ValueKind::Leaf(LeafValue::Bool(true)),
+            2 => ValueKind::Leaf(LeafValue::Null),
+            3 => ValueKind::Leaf(LeafValue::Number),
+            4 => ValueKind::Leaf(LeafValue::String),
+            7 => ValueKind::Leaf(LeafValue::RadixInt(Radix::Binary)),
+            8 => ValueKind::Leaf(LeafValue::RadixInt(Radix::Octal)),
+            9 => ValueKind::Leaf(LeafValue::RadixInt(Radix::Hex)),
+            10 => ValueKind::Leaf(LeafValue::BigInt),
+            5 => ValueKind::Object(Object::new(self.children()?)),
+            6 => ValueKind::Array(Array::new(self.children()?)),
+            11 => return Ok(None),
+            _ => return Err(InvalidCheckpoint),
+        };
+        Ok(Some(kind))
+    }
+
+    /// The values of the arena, with their spans.
+    fn values(&mut self) -> Result<(Vec<Value>, Vec<Range<u32>>), InvalidCheckpoint> {
+        let len = self.u32()?;
+        (0..len)
+            .map(|slot| {
+                let span = self.range()?;
+                let value = match self.kind()? {
+                    Some(kind) => Value::new(kind, slot),
+                    None => Value::header(self.u32()?),
+                };
+                Ok((value, span))
+            })
+            .collect()
+    }
+
+    fn keys(&mut self) -> Result<Vec<StringKey>, InvalidCheckpoint> {
+        let len = self.u32()?;
+        (0..len).map(|_| self.range().map(StringKey)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{parse_resumable, Checkpoint, InvalidCheckpoint, Resume};
+    use crate::Arena;
+
+    #[test]
+    fn resume() {
+        let src = r#"{"a": [1, 23, {"b\nc": "d\"e"}], "f": null, "b\nc": -4.5e6, "g": [true]}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let expected = arena.to_json(&root);
+
+        // grow the input by a few bytes at a time, restarting from the serialized checkpoint.
+        for step in [1, 3, 7] {
+            let mut checkpoint: Option<Vec<u8>> = None;
+            let mut end = 0;
+            let json = loop {
+                end = (end + step).min(src.len());
+                let mut arena = Arena::new(&src[..end]);
+                let resume = match checkpoint.take() {
+                    None => parse_resumable(&mut arena),
+                    Some(bytes) => Checkpoint::from_bytes(&bytes).unwrap().resume(&mut arena),
+                };
+                match resume.unwrap() {
+                    Resume::Complete(value) => break arena.to_json(&value),
+                    Resume::Suspended(c) => {
+                        assert!(c.offset() as usize <= end);
+                        checkpoint = Some(c.to_bytes());
+                    }
+                }
+            };
+            assert_eq!(json, expected);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let mut arena = Arena::new(r#"{"a": [1, "#);
+        let Resume::Suspended(checkpoint) = parse_resumable(&mut arena).unwrap() else {
+            panic!("expected to suspend")
+        };
+        let bytes = checkpoint.to_bytes();
+        assert!(Checkpoint::from_bytes(&bytes).is_ok());
+
+        assert_eq!(Checkpoint::from_bytes(&[]).unwrap_err(), InvalidCheckpoint);
+        assert_eq!(
+            Checkpoint::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            InvalidCheckpoint
+        );
+        // more keys on the key stack than there are bytes for
+        let mut corrupt = bytes.clone();
+        let i = corrupt.len() - 4 - 4 - 8 - 4;
+        corrupt[i] = 0xff;
+        assert!(Checkpoint::from_bytes(&corrupt).is_err());
+
+        let mut arena = Arena::new("[1 2");
+        assert!(parse_resumable(&mut arena).is_err());
+
+        // resumed with a source that does not start with the same input
+        for (src, span) in [
+            (r#"{"a": [x, 2]}"#, 7..8),
+            (r#"{"a": ["x", 2]}"#, 7..8),
+            // the key, and then the offset, are in the middle of `é`
+            (r#"{"é": [1, 2]}"#, 2..3),
+            (r#"{"a": [1,é2]}"#, 10..14),
+        ] {
+            let checkpoint = Checkpoint::from_bytes(&bytes).unwrap();
+            let mut arena = Arena::new(src);
+            let Err(err) = checkpoint.resume(&mut arena) else {
+                panic!("expected an error for {src}")
+            };
+            assert_eq!(err.span(), span, "{src}");
+        }
+    }
+}
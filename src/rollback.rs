@@ -0,0 +1,101 @@
+//! Undoing what a failed parse left in an arena.
+
+use crate::{Arena, StringKey};
+
+/// The lengths of the buffers of an arena, to truncate them back to.
+#[derive(Clone, Copy)]
+pub(crate) struct Mark {
+    values: usize,
+    keys: usize,
+    scratch: usize,
+}
+
+impl Arena<'_> {
+    pub(crate) fn mark(&self) -> Mark {
+        Mark {
+            values: self.values.len(),
+            keys: self.keys.len(),
+            scratch: self.scratch.scratch.len(),
+        }
+    }
+
+    /// Forget the values and keys added since `mark`.
+    ///
+    /// Keys interned since then that point into the source stay interned,
+    /// since they are still valid, but those decoded into the scratch are dropped with it.
+    /// Ids given out before no longer match the arena, as the slots they point at may be reused.
+    pub(crate) fn rollback(&mut self, mark: Mark) {
+        #[cfg(debug_assertions)]
+        if self.values.len() != mark.values || self.keys.len() != mark.keys {
+            self.brand = crate::id::next_brand();
+        }
+        self.values.truncate(mark.values);
+        self.spans.truncate(mark.values);
+        self.keys.truncate(mark.keys);
+        if self.scratch.scratch.len() == mark.scratch {
+            return;
+        }
+        self.scratch.scratch.truncate(mark.scratch);
+
+        let kept =
+            |key: &StringKey| key.0.end >= key.0.start || (key.0.start as usize) <= mark.scratch;
+        self.table.retain(|key| kept(key));
+        self.small.retain(|key| kept(key));
+        if let Some(known) = &mut self.known {
+            for key in &mut known.interned {
+                if key.as_ref().is_some_and(|key| !kept(key)) {
+                    *key = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Arena;
+
+    #[test]
+    fn rollback_on_error() {
+        let src = r#"[{"a\n": 1}, {"b\n": [1, 2]}, {"a\n": {"c\t": x}}]"#;
+        let mut arena = Arena::new(src);
+        let first = arena.parse_element(&mut Default::default(), 1..11).unwrap();
+        let before = (
+            arena.values.len(),
+            arena.keys.len(),
+            arena.scratch.scratch.clone(),
+        );
+
+        assert!(crate::parse(&mut arena).is_err());
+        assert_eq!(
+            (
+                arena.values.len(),
+                arena.keys.len(),
+                arena.scratch.scratch.clone()
+            ),
+            before
+        );
+        assert_eq!(arena.to_json(&first), r#"{"a\n":1}"#);
+
+        // keys decoded by the failed parse are no longer interned.
+        let second = arena
+            .parse_element(&mut Default::default(), 13..28)
+            .unwrap();
+        assert_eq!(arena.to_json(&second), r#"{"b\n":[1,2]}"#);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic = "from another arena"]
+    fn rollback_rebrands() {
+        let mut arena = Arena::new("[[1], [2, x]]");
+        let first = arena.parse_element(&mut Default::default(), 1..4).unwrap();
+        let crate::ValueKind::Array(array) = first.kind() else {
+            panic!("expected an array")
+        };
+        let id = arena.array_ids(&array).next().unwrap();
+
+        assert!(crate::parse(&mut arena).is_err());
+        arena.value(id);
+    }
+}
@@ -0,0 +1,112 @@
+//! Parsing the elements of a large top-level array one at a time.
+
+use core::ops::Range;
+use logos::{Lexer, Logos};
+
+use crate::{Arena, Error, Stacks, Token, Value};
+
+impl Arena<'_> {
+    /// Parse the element of the source at `range`.
+    pub(crate) fn parse_element(
+        &mut self,
+        stacks: &mut Stacks,
+        range: Range<usize>,
+    ) -> Result<Value, Error> {
+        crate::parse_range(self, stacks, range, crate::Dialect::default())
+    }
+
+    /// Forget all parsed values, keeping the allocations.
+    pub(crate) fn clear(&mut self) {
+        self.scratch.scratch.clear();
+        self.scratch.text.clear();
+        self.table.clear();
+        self.small.clear();
+        crate::known::Known::clear(&mut self.known);
+        self.keys.clear();
+        self.values.clear();
+        self.spans.clear();
+        #[cfg(debug_assertions)]
+        {
+            self.brand = crate::id::next_brand();
+        }
+    }
+}
+
+/// Splits a top-level array into the source ranges of its elements,
+/// without parsing them.
+///
+/// If the source is not a single array, yields the span where it went wrong.
+pub(crate) struct Elements<'s> {
+    lexer: Lexer<'s, Token>,
+    started: bool,
+    /// a comma was just seen, so an element must follow.
+    comma: bool,
+    done: bool,
+}
+
+impl<'s> Elements<'s> {
+    pub(crate) fn new(src: &'s str) -> Self {
+        Self {
+            lexer: Token::lexer(src),
+            started: false,
+            comma: false,
+            done: false,
+        }
+    }
+
+    fn malformed(&mut self) -> Option<Result<Range<usize>, Range<u32>>> {
+        self.done = true;
+        let span = self.lexer.span();
+        Some(Err(span.start as u32..span.end as u32))
+    }
+}
+
+impl Iterator for Elements<'_> {
+    type Item = Result<Range<usize>, Range<u32>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            if self.lexer.next() != Some(Ok(Token::OpenArray)) {
+                return self.malformed();
+            }
+        }
+
+        let mut depth = 0usize;
+        let mut element: Option<Range<usize>> = None;
+        loop {
+            let Some(token) = self.lexer.next() else {
+                // unclosed array
+                return self.malformed();
+            };
+            let span = self.lexer.span();
+
+            match (depth, token) {
+                (0, Ok(Token::Comma)) => {
+                    self.comma = true;
+                    return element.map(Ok).or_else(|| self.malformed());
+                }
+                (0, Ok(Token::CloseArray)) => {
+                    self.done = true;
+                    if self.lexer.next().is_some() || (element.is_none() && self.comma) {
+                        // trailing tokens, or a trailing comma
+                        return self.malformed();
+                    }
+                    return element.map(Ok);
+                }
+                (_, Ok(Token::OpenArray | Token::OpenObject)) => depth += 1,
+                (1.., Ok(Token::CloseArray | Token::CloseObject)) => depth -= 1,
+                // anything else is left for the parser to reject.
+                _ => {}
+            }
+
+            match &mut element {
+                Some(element) => element.end = span.end,
+                None => element = Some(span),
+            }
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Arena, Coercion, LeafValue, Object, Value, ValueKind};
+
+/// A value that failed validation by an [`ObjectSchema`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The span of the offending value, or of the object missing a field.
+    pub span: Range<u32>,
+    /// The [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) to the value.
+    pub path: String,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    /// The field is required, and has no default.
+    Missing,
+    /// The value is not of this type, like "an integer".
+    Type(&'static str),
+    /// The integer does not fit in the type of the field.
+    OutOfRange,
+    /// A [`Schema::check`] failed, with its message.
+    Invalid(&'static str),
+    /// The object has a field that the schema does not, with [`ObjectSchema::deny_unknown`].
+    Unknown,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        match self.kind {
+            ViolationKind::Missing => write!(f, "{path}: missing")?,
+            ViolationKind::Type(expected) => write!(f, "{path}: expected {expected}")?,
+            ViolationKind::OutOfRange => write!(f, "{path}: out of range")?,
+            ViolationKind::Invalid(message) => write!(f, "{path}: {message}")?,
+            ViolationKind::Unknown => write!(f, "{path}: unknown field")?,
+        }
+        write!(f, " at {:?}", self.span)
+    }
+}
+
+impl core::error::Error for Violation {}
+
+/// Types that a field of an [`ObjectSchema`] can have.
+pub trait SchemaType: Clone + Sized + 'static {
+    /// The schema of a required field of this type.
+    fn schema() -> Schema<Self> {
+        Schema {
+            default: None,
+            optional: false,
+            checks: Vec::new(),
+        }
+    }
+
+    fn read(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Result<Self, ViolationKind>;
+}
+
+impl SchemaType for bool {
+    fn read(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Result<Self, ViolationKind> {
+        arena
+            .coerce(value, coercion)
+            .ok_or(ViolationKind::Type("a boolean"))
+    }
+}
+
+impl SchemaType for f64 {
+    fn read(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Result<Self, ViolationKind> {
+        arena
+            .coerce(value, coercion)
+            .ok_or(ViolationKind::Type("a number"))
+    }
+}
+
+impl SchemaType for String {
+    fn read(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Result<Self, ViolationKind> {
+        arena
+            .coerce(value, coercion)
+            .ok_or(ViolationKind::Type("a string"))
+    }
+}
+
+macro_rules! integers {
+    ($($t:ty),*) => {$(
+        impl SchemaType for $t {
+            fn read(arena: &Arena<'_>, value: &Value, coercion: Coercion) -> Result<Self, ViolationKind> {
+                let n: i64 = arena
+                    .coerce(value, coercion)
+                    .ok_or(ViolationKind::Type("an integer"))?;
+                n.try_into().map_err(|_| ViolationKind::OutOfRange)
+            }
+        }
+    )*};
+}
+
+integers!(u8, u16, u32, u64, usize, i8, i16, i32, i64);
+
+/// A [`Schema::check`], and the message it reports.
+type Rule<T> = (Box<dyn Fn(&T) -> bool>, &'static str);
+
+/// The schema of a field of type `T`, from [`SchemaType::schema`].
+pub struct Schema<T> {
+    default: Option<T>,
+    optional: bool,
+    checks: Vec<Rule<T>>,
+}
+
+impl Schema<()> {
+    /// The schema of an object, with no fields yet.
+    pub fn object() -> ObjectSchema {
+        ObjectSchema::default()
+    }
+}
+
+impl<T: SchemaType> Schema<T> {
+    /// The value of the field when it is missing.
+    pub fn default(mut self, value: T) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    /// Allow the field to be missing, with no default.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Report `message` for a value that `valid` rejects.
+    pub fn check(mut self, valid: impl Fn(&T) -> bool + 'static, message: &'static str) -> Self {
+        self.checks.push((Box::new(valid), message));
+        self
+    }
+}
+
+/// The schema of an object, from [`Schema::object`].
+#[derive(Default)]
+pub struct ObjectSchema {
+    fields: Vec<(&'static str, FieldSchema)>,
+    deny_unknown: bool,
+    coercion: Option<Coercion>,
+}
+
+/// A [`Schema`] or [`ObjectSchema`], for [`ObjectSchema::field`].
+pub struct FieldSchema(Box<dyn Check>);
+
+impl<T: SchemaType> From<Schema<T>> for FieldSchema {
+    fn from(schema: Schema<T>) -> Self {
+        Self(Box::new(schema))
+    }
+}
+
+impl From<ObjectSchema> for FieldSchema {
+    fn from(schema: ObjectSchema) -> Self {
+        Self(Box::new(schema))
+    }
+}
+
+impl ObjectSchema {
+    pub fn field(mut self, name: &'static str, schema: impl Into<FieldSchema>) -> Self {
+        self.fields.push((name, schema.into()));
+        self
+    }
+
+    /// Report fields that are not in the schema.
+    pub fn deny_unknown(mut self) -> Self {
+        self.deny_unknown = true;
+        self
+    }
+
+    /// Read values as another type as `coercion` allows, like `"8080"` as a `u16`.
+    /// The default is [`Coercion::STRICT`].
+    ///
+    /// This applies to the fields of nested objects too, unless they set their own.
+    pub fn coercion(mut self, coercion: Coercion) -> Self {
+        self.coercion = Some(coercion);
+        self
+    }
+
+    /// Check `value` against the schema, reporting every violation.
+    pub fn validate<'a, 's>(
+        &'a self,
+        arena: &'a Arena<'s>,
+        value: &'a Value,
+    ) -> Result<Fields<'a, 's>, Vec<Violation>> {
+        let mut violations = Vec::new();
+        let coercion = self.coercion.unwrap_or_default();
+        self.check(arena, value, coercion, &mut String::new(), &mut violations);
+        if !violations.is_empty() {
+            return Err(violations);
+        }
+        let ValueKind::Object(object) = value.kind() else {
+            unreachable!("validated as an object")
+        };
+        Ok(Fields {
+            schema: self,
+            arena,
+            object,
+            coercion,
+        })
+    }
+
+    fn schema(&self, name: &str) -> &dyn Check {
+        let (_, schema) = self
+            .fields
+            .iter()
+            .find(|(n, _)| *n == name)
+            .unwrap_or_else(|| panic!("no field {name:?} in the schema"));
+        &*schema.0
+    }
+}
+
+/// The fields of an object that passed [`ObjectSchema::validate`].
+pub struct Fields<'a, 's> {
+    schema: &'a ObjectSchema,
+    arena: &'a Arena<'s>,
+    object: Object,
+    coercion: Coercion,
+}
+
+impl<'a, 's> Fields<'a, 's> {
+    /// The value of a field, or its default if it is missing.
+    ///
+    /// # Panics
+    ///
+    /// If the field is not in the schema with type `T`, or is optional and missing.
+    pub fn get<T: SchemaType>(&self, name: &str) -> T {
+        self.get_opt(name)
+            .unwrap_or_else(|| panic!("optional field {name:?} is missing"))
+    }
+
+    /// The value of a field, or its default, or `None` if it is optional and missing.
+    ///
+    /// # Panics
+    ///
+    /// If the field is not in the schema with type `T`.
+    pub fn get_opt<T: SchemaType>(&self, name: &str) -> Option<T> {
+        let schema = self.schema.schema(name);
+        let Some(default) = schema.as_any().downcast_ref::<Schema<T>>() else {
+            panic!("field {name:?} is not a {}", core::any::type_name::<T>())
+        };
+        match self.arena.get(&self.object, name).filter(|v| !is_null(v)) {
+            Some(value) => Some(
+                T::read(self.arena, value, self.coercion)
+                    .unwrap_or_else(|_| unreachable!("validated")),
+            ),
+            None => default.default.clone(),
+        }
+    }
+
+    /// The fields of a nested object.
+    ///
+    /// # Panics
+    ///
+    /// If the field is not an object in the schema, or is missing.
+    pub fn object(&self, name: &str) -> Fields<'a, 's> {
+        let Some(schema) = self
+            .schema
+            .schema(name)
+            .as_any()
+            .downcast_ref::<ObjectSchema>()
+        else {
+            panic!("field {name:?} is not an object")
+        };
+        let value = self
+            .arena
+            .get(&self.object, name)
+            .unwrap_or_else(|| panic!("field {name:?} is missing"));
+        let ValueKind::Object(object) = value.kind() else {
+            unreachable!("validated as an object")
+        };
+        Fields {
+            schema,
+            arena: self.arena,
+            object,
+            coercion: schema.coercion.unwrap_or(self.coercion),
+        }
+    }
+}
+
+/// A type-erased [`Schema`] or [`ObjectSchema`].
+trait Check {
+    /// Report the violations in `value`, at `path`.
+    fn check(
+        &self,
+        arena: &Arena<'_>,
+        value: &Value,
+        coercion: Coercion,
+        path: &mut String,
+        violations: &mut Vec<Violation>,
+    );
+
+    /// Whether the field may be missing.
+    fn optional(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: SchemaType> Check for Schema<T> {
+    fn check(
+        &self,
+        arena: &Arena<'_>,
+        value: &Value,
+        coercion: Coercion,
+        path: &mut String,
+        violations: &mut Vec<Violation>,
+    ) {
+        let kind = match T::read(arena, value, coercion) {
+            Ok(v) => match self.checks.iter().find(|(valid, _)| !valid(&v)) {
+                Some((_, message)) => ViolationKind::Invalid(message),
+                None => return,
+            },
+            Err(kind) => kind,
+        };
+        violations.push(Violation {
+            span: arena.span(value),
+            path: path.clone(),
+            kind,
+        });
+    }
+
+    fn optional(&self) -> bool {
+        self.optional || self.default.is_some()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Check for ObjectSchema {
+    fn check(
+        &self,
+        arena: &Arena<'_>,
+        value: &Value,
+        coercion: Coercion,
+        path: &mut String,
+        violations: &mut Vec<Violation>,
+    ) {
+        let mut violation = |path: &str, span: &Range<u32>, kind| {
+            violations.push(Violation {
+                span: span.clone(),
+                path: path.into(),
+                kind,
+            })
+        };
+        let ValueKind::Object(object) = value.kind() else {
+            return violation(path, &arena.span(value), ViolationKind::Type("an object"));
+        };
+
+        let coercion = self.coercion.unwrap_or(coercion);
+        let len = path.len();
+        if self.deny_unknown {
+            for (key, v) in arena.entries(&object) {
+                if !self.fields.iter().any(|(name, _)| *name == key) {
+                    push_segment(path, key);
+                    violation(path, &arena.span(v), ViolationKind::Unknown);
+                    path.truncate(len);
+                }
+            }
+        }
+        for (name, schema) in &self.fields {
+            push_segment(path, name);
+            match arena.get(&object, name) {
+                // an explicit null is missing too
+                Some(v) if !is_null(v) => schema.0.check(arena, v, coercion, path, violations),
+                _ if schema.0.optional() => {}
+                _ => violations.push(Violation {
+                    span: arena.span(value),
+                    path: path.clone(),
+                    kind: ViolationKind::Missing,
+                }),
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn optional(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn is_null(value: &Value) -> bool {
+    matches!(value.kind(), ValueKind::Leaf(LeafValue::Null))
+}
+
+/// Add `key` to a JSON pointer.
+fn push_segment(path: &mut String, key: &str) {
+    path.push('/');
+    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::{Arena, Coercion, Schema, SchemaType, ViolationKind};
+
+    #[test]
+    fn schema() {
+        let schema = Schema::object()
+            .field("host", String::schema())
+            .field("port", u16::schema().default(8080))
+            .field(
+                "workers",
+                u32::schema()
+                    .default(4)
+                    .check(|n| *n > 0, "must be positive"),
+            )
+            .field("tls", Schema::object().field("cert", String::schema()))
+            .field("name", String::schema().optional())
+            .deny_unknown();
+
+        let mut arena = Arena::new(r#"{"host": "localhost", "tls": {"cert": "a.pem"}}"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let fields = schema.validate(&arena, &root).unwrap();
+        assert_eq!(fields.get::<String>("host"), "localhost");
+        assert_eq!(fields.get::<u16>("port"), 8080);
+        assert_eq!(fields.get::<u32>("workers"), 4);
+        assert_eq!(fields.get_opt::<String>("name"), None);
+        assert_eq!(fields.object("tls").get::<String>("cert"), "a.pem");
+
+        // every violation is reported at once
+        let src = r#"{"port": 70000, "workers": 0, "tls": {"cert": 1}, "extra": null}"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let violations = schema.validate(&arena, &root).err().unwrap();
+        let found: std::vec::Vec<_> = violations
+            .iter()
+            .map(|v| {
+                (
+                    v.path.as_str(),
+                    v.kind.clone(),
+                    &src[v.span.start as usize..v.span.end as usize],
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/extra", ViolationKind::Unknown, "null"),
+                ("/host", ViolationKind::Missing, src),
+                ("/port", ViolationKind::OutOfRange, "70000"),
+                ("/workers", ViolationKind::Invalid("must be positive"), "0"),
+                ("/tls/cert", ViolationKind::Type("a string"), "1"),
+            ]
+        );
+        assert_eq!(
+            std::format!("{}", violations[2]),
+            "/port: out of range at 9..14"
+        );
+
+        let lenient = Schema::object()
+            .field("port", u16::schema())
+            .coercion(Coercion::LENIENT);
+        let mut arena = Arena::new(r#"{"port": "9000"}"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let fields = lenient.validate(&arena, &root).unwrap();
+        assert_eq!(fields.get::<u16>("port"), 9000);
+    }
+}
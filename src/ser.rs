@@ -0,0 +1,542 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+
+use crate::{Arena, LeafValue, Radix, StringKey, Value, ValueKind};
+
+impl Arena<'_> {
+    /// Serialize `value` as compact JSON.
+    pub fn to_json(&self, value: &Value) -> String {
+        let mut out = String::new();
+        self.write_json(value, &mut out);
+        out
+    }
+
+    /// Serialize `value` as compact JSON, appending to `out`.
+    pub fn write_json(&self, value: &Value, out: &mut String) {
+        Serializer::new().write(self, value, out, usize::MAX);
+    }
+
+    /// Serialize `value` as compact JSON, with every number exactly as it appears in the source.
+    ///
+    /// Fails rather than write a number any other way, such as a hex integer as decimal.
+    pub fn to_json_exact(&self, value: &Value) -> Result<String, InexactNumber> {
+        let mut out = String::new();
+        let mut serializer = Serializer::new();
+        serializer.write(self, value, &mut out, usize::MAX);
+        match serializer.inexact {
+            Some(span) => Err(InexactNumber { span }),
+            None => Ok(out),
+        }
+    }
+
+    /// Serialize `value` as compact JSON, writing numbers as `numbers` says.
+    pub fn to_json_with(&self, value: &Value, numbers: NumberFormat) -> String {
+        let mut out = String::new();
+        Serializer::with_numbers(numbers).write(self, value, &mut out, usize::MAX);
+        out
+    }
+
+    pub(crate) fn write_key(&self, key: &StringKey, out: &mut String) {
+        let Range { start, end } = key.0;
+        out.push('"');
+        if end < start {
+            // scratch keys had escapes, which have been decoded.
+            escape(&self[key], out);
+        } else {
+            // source keys had no escapes, so they can be written as is.
+            out.push_str(&self[key]);
+        }
+        out.push('"');
+    }
+}
+
+pub(crate) fn escape(s: &str, out: &mut String) {
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let escaped = match b {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0..0x20 => "",
+            _ => continue,
+        };
+        out.push_str(&s[start..i]);
+        if escaped.is_empty() {
+            const HEX: &[u8; 16] = b"0123456789abcdef";
+            out.push_str("\\u00");
+            out.push(HEX[(b >> 4) as usize] as char);
+            out.push(HEX[(b & 0xf) as usize] as char);
+        } else {
+            out.push_str(escaped);
+        }
+        start = i + 1;
+    }
+    out.push_str(&s[start..]);
+}
+
+/// A number that [`Arena::to_json_exact`] could not write as it appears in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InexactNumber {
+    pub span: Range<u32>,
+}
+
+impl fmt::Display for InexactNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "number at {:?} cannot be written exactly", self.span)
+    }
+}
+
+impl core::error::Error for InexactNumber {}
+
+/// How the serializer writes numbers.
+///
+/// The default writes every JSON number exactly as it appears in the source,
+/// and integers in other bases as decimal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// How numbers with a fraction or exponent are written.
+    pub floats: FloatFormat,
+    /// Write integers as they appear in the source, however large.
+    /// Otherwise they are formatted as floats.
+    pub integer_passthrough: bool,
+    /// When [`FloatFormat::Shortest`] uses an exponent.
+    pub exponent: Exponent,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            floats: FloatFormat::Raw,
+            integer_passthrough: true,
+            exponent: Exponent::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// As written in the source.
+    Raw,
+    /// The shortest digits that parse back to the same `f64`.
+    Shortest,
+    /// Rounded to this many digits after the decimal point.
+    Fixed(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exponent {
+    /// Like JavaScript, only for magnitudes below `1e-6` or from `1e21`.
+    Auto,
+    /// Never, like `10000000` or `0.00000001`.
+    Never,
+    /// Always, like `1e7` or `1.5e0`.
+    Always,
+}
+
+impl NumberFormat {
+    /// Returns whether the number was written exactly as `raw`.
+    fn write(&self, raw: &str, radix: Option<Radix>, out: &mut String) -> bool {
+        let integer = match radix {
+            Some(_) => true,
+            None => !raw.contains(['.', 'e', 'E']),
+        };
+        if integer && self.integer_passthrough || !integer && self.floats == FloatFormat::Raw {
+            return match radix {
+                Some(radix) => {
+                    write_decimal(raw, radix, out);
+                    false
+                }
+                None => {
+                    out.push_str(raw);
+                    true
+                }
+            };
+        }
+
+        let n = match radix {
+            Some(radix) => {
+                let mut decimal = String::new();
+                write_decimal(raw, radix, &mut decimal);
+                decimal.parse::<f64>()
+            }
+            None => raw.parse::<f64>(),
+        };
+        let n = match n {
+            Ok(n) if n.is_finite() => n,
+            // JSON has no infinity, so keep numbers that overflow as they are.
+            _ => {
+                out.push_str(raw);
+                return true;
+            }
+        };
+
+        let start = out.len();
+        match self.floats {
+            FloatFormat::Fixed(precision) => {
+                let _ = write!(out, "{n:.precision$}");
+            }
+            FloatFormat::Raw | FloatFormat::Shortest => self.write_shortest(n, out),
+        }
+        out[start..] == *raw
+    }
+
+    fn write_shortest(&self, n: f64, out: &mut String) {
+        if n == 0.0 {
+            // keep the sign of `-0.0`, like serde_json does.
+            if n.is_sign_negative() {
+                out.push('-');
+            }
+            return out.push('0');
+        }
+
+        // `{:e}` is the shortest round-trip digits, like `-1.25e-7`.
+        let mut buf = String::new();
+        let _ = write!(buf, "{:e}", n.abs());
+        let (mantissa, exp) = buf.split_once('e').unwrap_or((&buf, "0"));
+        let exp: i32 = exp.parse().unwrap_or(0);
+        let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+
+        if n < 0.0 {
+            out.push('-');
+        }
+        let scientific = match self.exponent {
+            Exponent::Auto => !(-6..21).contains(&exp),
+            Exponent::Never => false,
+            Exponent::Always => true,
+        };
+        if scientific {
+            out.push_str(&digits[..1]);
+            if digits.len() > 1 {
+                out.push('.');
+                out.push_str(&digits[1..]);
+            }
+            let _ = write!(out, "e{exp}");
+        } else if exp < 0 {
+            out.push_str("0.");
+            out.extend(core::iter::repeat_n('0', (-exp - 1) as usize));
+            out.push_str(&digits);
+        } else {
+            // the number of digits before the decimal point.
+            let int = exp as usize + 1;
+            if digits.len() > int {
+                out.push_str(&digits[..int]);
+                out.push('.');
+                out.push_str(&digits[int..]);
+            } else {
+                out.push_str(&digits);
+                out.extend(core::iter::repeat_n('0', int - digits.len()));
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Frame {
+    Array {
+        values: Range<u32>,
+        first: bool,
+    },
+    Object {
+        keys: Range<u32>,
+        values: Range<u32>,
+        first: bool,
+    },
+}
+
+/// Write an integer literal like `-0xff` in decimal.
+pub(crate) fn write_decimal(raw: &str, radix: Radix, out: &mut String) {
+    let (neg, raw) = match raw.strip_prefix('-') {
+        Some(raw) => (true, raw),
+        None => (false, raw),
+    };
+    // skip the `0x` prefix
+    let digits = &raw[2..];
+
+    if let Ok(n) = u128::from_str_radix(digits, radix.base()) {
+        if neg && n != 0 {
+            out.push('-');
+        }
+        let _ = write!(out, "{n}");
+        return;
+    }
+
+    // too big for a u128, so convert to base 10^9 limbs, least significant first.
+    const BASE: u64 = 1_000_000_000;
+    let mut limbs: Vec<u32> = Vec::new();
+    for digit in digits.chars() {
+        let mut carry = digit.to_digit(radix.base()).unwrap_or(0) as u64;
+        for limb in &mut limbs {
+            let n = *limb as u64 * radix.base() as u64 + carry;
+            *limb = (n % BASE) as u32;
+            carry = n / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+    }
+
+    if neg {
+        out.push('-');
+    }
+    let mut limbs = limbs.iter().rev();
+    if let Some(first) = limbs.next() {
+        let _ = write!(out, "{first}");
+    }
+    for limb in limbs {
+        let _ = write!(out, "{limb:09}");
+    }
+}
+
+/// An iterative JSON serializer that can be paused between chunks of output.
+pub(crate) struct Serializer {
+    stack: Vec<Frame>,
+    started: bool,
+    numbers: NumberFormat,
+    /// The first number that was not written as it appears in the source.
+    inexact: Option<Range<u32>>,
+}
+
+impl Serializer {
+    pub(crate) fn new() -> Self {
+        Self::with_numbers(NumberFormat::default())
+    }
+
+    pub(crate) fn with_numbers(numbers: NumberFormat) -> Self {
+        Self {
+            stack: Vec::new(),
+            started: false,
+            numbers,
+            inexact: None,
+        }
+    }
+
+    /// Write more of `root` into `out`, pausing once `out` is at least `limit` bytes long.
+    ///
+    /// Returns `true` once the entire value has been written.
+    pub(crate) fn write(
+        &mut self,
+        arena: &Arena<'_>,
+        root: &Value,
+        out: &mut String,
+        limit: usize,
+    ) -> bool {
+        if !self.started {
+            self.started = true;
+            self.write_value(arena, root, out);
+        }
+
+        while out.len() < limit {
+            let Some(frame) = self.stack.last_mut() else {
+                return true;
+            };
+
+            match frame {
+                Frame::Array { values, first } => match values.next() {
+                    Some(i) => {
+                        if !core::mem::take(first) {
+                            out.push(',');
+                        }
+                        self.write_value(arena, &arena.values[i as usize], out);
+                    }
+                    None => {
+                        self.stack.pop();
+                        out.push(']');
+                    }
+                },
+                Frame::Object {
+                    keys,
+                    values,
+                    first,
+                } => match (keys.next(), values.next()) {
+                    (Some(k), Some(v)) => {
+                        if !core::mem::take(first) {
+                            out.push(',');
+                        }
+                        arena.write_key(&arena.keys[k as usize], out);
+                        out.push(':');
+                        self.write_value(arena, &arena.values[v as usize], out);
+                    }
+                    _ => {
+                        self.stack.pop();
+                        out.push('}');
+                    }
+                },
+            }
+        }
+
+        self.stack.is_empty()
+    }
+
+    fn write_value(&mut self, arena: &Arena<'_>, value: &Value, out: &mut String) {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Bool(true)) => out.push_str("true"),
+            ValueKind::Leaf(LeafValue::Bool(false)) => out.push_str("false"),
+            ValueKind::Leaf(LeafValue::Null) => out.push_str("null"),
+            ValueKind::Leaf(LeafValue::String) if arena.raw(value).starts_with('\'') => {
+                // single quoted strings are re-quoted
+                out.push('"');
+                escape(&arena.as_str(value).unwrap_or_default(), out);
+                out.push('"');
+            }
+            ValueKind::Leaf(LeafValue::String) => out.push_str(arena.raw(value)),
+            // never converted through floats, whatever the number format.
+            ValueKind::Leaf(LeafValue::BigInt) => out.push_str(arena.raw(value)),
+            ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_)) => {
+                let radix = match value.kind() {
+                    ValueKind::Leaf(LeafValue::RadixInt(radix)) => Some(radix),
+                    _ => None,
+                };
+                if !self.numbers.write(arena.raw(value), radix, out) && self.inexact.is_none() {
+                    self.inexact = Some(arena.span(value));
+                }
+            }
+            ValueKind::Object(object) => {
+                out.push('{');
+                self.stack.push(Frame::Object {
+                    keys: object.keys(&arena.values),
+                    values: object.values(),
+                    first: true,
+                });
+            }
+            ValueKind::Array(array) => {
+                out.push('[');
+                self.stack.push(Frame::Array {
+                    values: array.values(),
+                    first: true,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Dialect, Exponent, FloatFormat, InexactNumber, NumberFormat};
+
+    #[test]
+    fn compact() {
+        let data = include_str!("../testdata/small.json");
+        let mut arena = Arena::new(data);
+        let value = crate::parse(&mut arena).unwrap();
+
+        let json = arena.to_json(&value);
+        assert_eq!(
+            json,
+            r#"{"key":-1.23e45,"nested":["data",{"is":"ok","escaped\nstrings":"are\tok\ntoo"},true,null]}"#
+        );
+
+        let mut arena2 = Arena::new(&json);
+        let value2 = crate::parse(&mut arena2).unwrap();
+        assert_eq!(arena2.to_json(&value2), json);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-escapes")]
+    fn chunked() {
+        use alloc::string::String;
+
+        let data = r#"{"a": [[], {}, [1, 2, 3]], "b\"\u0001": "c"}"#;
+        let mut arena = Arena::new(data);
+        let value = crate::parse(&mut arena).unwrap();
+
+        let mut serializer = super::Serializer::new();
+        let mut out = String::new();
+        let mut steps = 0;
+        while !serializer.write(&arena, &value, &mut out, steps) {
+            steps += 1;
+        }
+
+        assert_eq!(out, r#"{"a":[[],{},[1,2,3]],"b\"\u0001":"c"}"#);
+        assert!(steps > 1);
+    }
+
+    #[test]
+    fn exact_numbers() {
+        let data = r#"{"amount": 12345678901234567890.1234567890, "rate": 1.10, "big": 1E+400}"#;
+        let mut arena = Arena::new(data);
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(
+            arena.to_json_exact(&value).unwrap(),
+            r#"{"amount":12345678901234567890.1234567890,"rate":1.10,"big":1E+400}"#
+        );
+
+        let data = "[1.0, 0x10]";
+        let mut arena = Arena::new(data);
+        let dialect = Dialect {
+            radix_numbers: true,
+            ..Dialect::default()
+        };
+        let value = crate::parse_with_dialect(&mut arena, dialect).unwrap();
+        assert_eq!(
+            arena.to_json_exact(&value),
+            Err(InexactNumber { span: 6..10 })
+        );
+    }
+
+    #[test]
+    fn number_format() {
+        let data = "[1e7, 10000000, 1.50, -2.5e-8, 0.1, 123456789012345678901234567890, 0x10, -0.0, 1e400]";
+        let mut arena = Arena::new(data);
+        let dialect = Dialect {
+            radix_numbers: true,
+            ..Dialect::default()
+        };
+        let value = crate::parse_with_dialect(&mut arena, dialect).unwrap();
+
+        let shortest = NumberFormat {
+            floats: FloatFormat::Shortest,
+            ..NumberFormat::default()
+        };
+        assert_eq!(
+            arena.to_json_with(&value, shortest),
+            "[10000000,10000000,1.5,-2.5e-8,0.1,123456789012345678901234567890,16,-0,1e400]"
+        );
+
+        let always = NumberFormat {
+            integer_passthrough: false,
+            exponent: Exponent::Always,
+            ..shortest
+        };
+        assert_eq!(
+            arena.to_json_with(&value, always),
+            "[1e7,1e7,1.5e0,-2.5e-8,1e-1,1.2345678901234568e29,1.6e1,-0,1e400]"
+        );
+
+        let never = NumberFormat {
+            exponent: Exponent::Never,
+            ..shortest
+        };
+        let mut small = Arena::new("[2.5e-8, 1e23, 12.5e1]");
+        let small_value = crate::parse(&mut small).unwrap();
+        assert_eq!(
+            small.to_json_with(&small_value, never),
+            "[0.000000025,100000000000000000000000,125]"
+        );
+
+        let fixed = NumberFormat {
+            floats: FloatFormat::Fixed(2),
+            ..NumberFormat::default()
+        };
+        assert_eq!(
+            arena.to_json_with(&value, fixed),
+            "[10000000.00,10000000,1.50,-0.00,0.10,123456789012345678901234567890,16,-0.00,1e400]"
+        );
+    }
+
+    #[test]
+    fn negative_zero() {
+        let shortest = NumberFormat {
+            floats: FloatFormat::Shortest,
+            ..NumberFormat::default()
+        };
+        let mut arena = Arena::new("[-0.0, 0.0, -0e5, 0.000]");
+        let value = crate::parse(&mut arena).unwrap();
+        assert_eq!(arena.to_json_with(&value, shortest), "[-0,0,-0,0]");
+    }
+}
@@ -0,0 +1,87 @@
+//! [`serde::Serialize`] for parsed values, to write them with any serde serializer.
+
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::{Arena, LeafValue, Number, Value, ValueKind};
+
+/// A value along with its arena, which serializes as the JSON it was parsed from.
+///
+/// Objects keep every key in source order, duplicates included.
+#[derive(Clone, Copy)]
+pub struct SerializeValue<'a> {
+    arena: &'a Arena<'a>,
+    value: &'a Value,
+}
+
+impl<'s> Arena<'s> {
+    /// Pair `value` with this arena, so it can be passed to a serde serializer.
+    pub fn serialize_value<'a>(&'a self, value: &'a Value) -> SerializeValue<'a> {
+        SerializeValue { arena: self, value }
+    }
+}
+
+impl<'a> SerializeValue<'a> {
+    fn child(&self, value: &'a Value) -> Self {
+        SerializeValue { value, ..*self }
+    }
+}
+
+impl Serialize for SerializeValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let arena = self.arena;
+        let value = self.value;
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Null) => serializer.serialize_unit(),
+            ValueKind::Leaf(LeafValue::Bool(b)) => serializer.serialize_bool(b),
+            ValueKind::Leaf(LeafValue::String) => match arena.as_str(value) {
+                Some(s) => serializer.serialize_str(&s),
+                None => Err(S::Error::custom("invalid escape in string")),
+            },
+            ValueKind::Leaf(_) => match arena.as_number(value) {
+                Some(Number::I64(n)) => serializer.serialize_i64(n),
+                Some(Number::U64(n)) => serializer.serialize_u64(n),
+                Some(Number::F64(n)) => match arena.as_i128(value) {
+                    Some(n) => serializer.serialize_i128(n),
+                    None => serializer.serialize_f64(n),
+                },
+                None => Err(S::Error::custom("invalid number")),
+            },
+            ValueKind::Array(array) => {
+                let mut seq = serializer.serialize_seq(Some(array.len()))?;
+                for v in arena.array_iter(&array) {
+                    seq.serialize_element(&self.child(v))?;
+                }
+                seq.end()
+            }
+            ValueKind::Object(object) => {
+                let mut map = serializer.serialize_map(Some(object.values().len()))?;
+                for (k, v) in arena.entries(&object) {
+                    map.serialize_entry(k, &self.child(v))?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Arena;
+
+    #[test]
+    fn serialize() {
+        let src = r#"{"a\n":[1,-2,1.5,18446744073709551615,0x10],"b":{"c":null,"c":true},"d":"é"}"#;
+        let mut arena = Arena::new(src);
+        let dialect = crate::Dialect {
+            radix_numbers: true,
+            ..crate::Dialect::default()
+        };
+        let root = crate::parse_with_dialect(&mut arena, dialect).unwrap();
+
+        let json = serde_json::to_string(&arena.serialize_value(&root)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"a\n":[1,-2,1.5,18446744073709551615,16],"b":{"c":null,"c":true},"d":"é"}"#
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use foldhash::quality::RandomState;
+use hashbrown::HashSet;
+
+use crate::eq::decimal;
+use crate::{Arena, Array, LeafValue, StringKey, Value, ValueKind};
+
+/// An array element that is an object or array, which set operations do not support.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotScalar {
+    pub span: Range<u32>,
+}
+
+impl fmt::Display for NotScalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected a scalar at {:?}", self.span)
+    }
+}
+
+impl core::error::Error for NotScalar {}
+
+/// The identity of a scalar, so equal values written differently are the same element.
+#[derive(Hash, PartialEq, Eq)]
+enum Scalar {
+    Null,
+    Bool(bool),
+    Number(Option<(bool, String, i64)>),
+    String(StringKey),
+}
+
+impl Arena<'_> {
+    fn scalar(&mut self, value: &Value) -> Result<Scalar, NotScalar> {
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Null) => Ok(Scalar::Null),
+            ValueKind::Leaf(LeafValue::Bool(b)) => Ok(Scalar::Bool(b)),
+            ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt) => {
+                Ok(Scalar::Number(decimal(self, value)))
+            }
+            ValueKind::Leaf(LeafValue::String) => {
+                let s = self.as_str(value).unwrap_or_default().into_owned();
+                Ok(Scalar::String(self.intern_copy(&s)))
+            }
+            ValueKind::Object(_) | ValueKind::Array(_) => Err(NotScalar {
+                span: self.span(value),
+            }),
+        }
+    }
+
+    fn scalars(&mut self, array: &Array) -> Result<Vec<(Scalar, Value)>, NotScalar> {
+        let values = self.array_values(array).to_vec();
+        values
+            .into_iter()
+            .map(|value| Ok((self.scalar(&value)?, value)))
+            .collect()
+    }
+
+    /// Add the distinct elements of `a`, then of `b`, that `pick` keeps given whether they are in each.
+    fn set_op(
+        &mut self,
+        a: &Array,
+        b: &Array,
+        mut pick: impl FnMut(bool, bool) -> bool,
+    ) -> Result<Array, NotScalar> {
+        let span = self.container_span(a.values());
+        let a = self.scalars(a)?;
+        let b = self.scalars(b)?;
+        fn set(items: &[(Scalar, Value)]) -> HashSet<&Scalar, RandomState> {
+            let mut set = HashSet::with_hasher(RandomState::default());
+            set.extend(items.iter().map(|(s, _)| s));
+            set
+        }
+        let (in_a, in_b) = (set(&a), set(&b));
+
+        let mut seen = HashSet::with_hasher(RandomState::default());
+        let values: Vec<Value> = a
+            .iter()
+            .chain(&b)
+            .filter(|(s, _)| pick(in_a.contains(s), in_b.contains(s)) && seen.insert(s))
+            .map(|(_, value)| *value)
+            .collect();
+        Ok(self.push_array(span, values))
+    }
+
+    /// Add the distinct elements that are in `a` or `b` to the arena, in order of first appearance.
+    ///
+    /// Elements must be scalars. Strings are the same if their contents are equal,
+    /// and numbers if their values are, however they are written.
+    pub fn union_arrays(&mut self, a: &Array, b: &Array) -> Result<Array, NotScalar> {
+        self.set_op(a, b, |_, _| true)
+    }
+
+    /// Add the distinct elements of `a` that are also in `b` to the arena.
+    ///
+    /// See [`Arena::union_arrays`] for when elements are the same.
+    pub fn intersect_arrays(&mut self, a: &Array, b: &Array) -> Result<Array, NotScalar> {
+        self.set_op(a, b, |in_a, in_b| in_a && in_b)
+    }
+
+    /// Add the distinct elements of `a` that are not in `b` to the arena.
+    ///
+    /// See [`Arena::union_arrays`] for when elements are the same.
+    pub fn difference_arrays(&mut self, a: &Array, b: &Array) -> Result<Array, NotScalar> {
+        self.set_op(a, b, |in_a, in_b| in_a && !in_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::{Arena, Array, NotScalar, Value, ValueKind};
+
+    #[test]
+    fn set_operations() {
+        let src =
+            r#"[["read", "write", "read", 1, 2.0, null], ["write", "admin", 20e-1, null], [[1]]]"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(root) = root.kind() else {
+            panic!("expected array")
+        };
+        let arrays: [Array; 3] = core::array::from_fn(|i| {
+            let ValueKind::Array(array) = &arena.array_values(&root)[i].kind() else {
+                panic!("expected array")
+            };
+            *array
+        });
+        let [a, b, nested] = &arrays;
+
+        let json = |arena: &Arena, array: Array| -> String {
+            arena.to_json(&Value::new(ValueKind::Array(array), 0))
+        };
+
+        let union = arena.union_arrays(a, b).unwrap();
+        assert_eq!(
+            json(&arena, union),
+            r#"["read","write",1,2.0,null,"admin"]"#
+        );
+        let intersection = arena.intersect_arrays(a, b).unwrap();
+        assert_eq!(json(&arena, intersection), r#"["write",2.0,null]"#);
+        let difference = arena.difference_arrays(a, b).unwrap();
+        assert_eq!(json(&arena, difference), r#"["read",1]"#);
+        let difference = arena.difference_arrays(b, a).unwrap();
+        assert_eq!(json(&arena, difference), r#"["admin"]"#);
+
+        let err = arena.union_arrays(a, nested).unwrap_err();
+        assert_eq!(err, NotScalar { span: 76..79 });
+    }
+}
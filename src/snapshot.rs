@@ -0,0 +1,138 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ser::{escape, write_decimal};
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+/// Formats a value as pretty JSON for snapshot tests.
+///
+/// The output only depends on the document, never on how the arena stores it:
+/// strings are re-escaped the same way however they were written,
+/// and keys can be sorted.
+pub struct Snapshot<'a, 's> {
+    arena: &'a Arena<'s>,
+    value: &'a Value,
+    indent: usize,
+    sort_keys: bool,
+}
+
+impl<'s> Arena<'s> {
+    /// Format `value` for a snapshot test, indented by two spaces.
+    pub fn snapshot<'a>(&'a self, value: &'a Value) -> Snapshot<'a, 's> {
+        Snapshot {
+            arena: self,
+            value,
+            indent: 2,
+            sort_keys: false,
+        }
+    }
+}
+
+impl Snapshot<'_, '_> {
+    /// Indent by `indent` spaces per level. With `0`, everything is on one line.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Write object members sorted by key, rather than in source order.
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    fn newline(&self, depth: usize, out: &mut String) {
+        if self.indent > 0 {
+            out.push('\n');
+            out.extend(core::iter::repeat_n(' ', depth * self.indent));
+        }
+    }
+
+    fn write(&self, value: &Value, depth: usize, out: &mut String) {
+        let arena = self.arena;
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Bool(true)) => out.push_str("true"),
+            ValueKind::Leaf(LeafValue::Bool(false)) => out.push_str("false"),
+            ValueKind::Leaf(LeafValue::Null) => out.push_str("null"),
+            ValueKind::Leaf(LeafValue::String) => {
+                out.push('"');
+                escape(&arena.as_str(value).unwrap_or_default(), out);
+                out.push('"');
+            }
+            ValueKind::Leaf(LeafValue::Number | LeafValue::BigInt) => {
+                out.push_str(arena.raw(value))
+            }
+            ValueKind::Leaf(LeafValue::RadixInt(radix)) => {
+                write_decimal(arena.raw(value), radix, out)
+            }
+            ValueKind::Object(object) if object.values().is_empty() => out.push_str("{}"),
+            ValueKind::Object(object) => {
+                let mut entries: Vec<_> = arena.entries(&object).collect();
+                if self.sort_keys {
+                    entries.sort_by_key(|(k, _)| *k);
+                }
+
+                out.push('{');
+                for (i, (k, v)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.newline(depth + 1, out);
+                    out.push('"');
+                    escape(k, out);
+                    out.push_str(if self.indent > 0 { "\": " } else { "\":" });
+                    self.write(v, depth + 1, out);
+                }
+                self.newline(depth, out);
+                out.push('}');
+            }
+            ValueKind::Array(array) if array.values().is_empty() => out.push_str("[]"),
+            ValueKind::Array(array) => {
+                out.push('[');
+                for (i, v) in arena.array_values(&array).iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.newline(depth + 1, out);
+                    self.write(v, depth + 1, out);
+                }
+                self.newline(depth, out);
+                out.push(']');
+            }
+        }
+    }
+}
+
+impl fmt::Display for Snapshot<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write(self.value, 0, &mut out);
+        f.write_str(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{Arena, Dialect};
+
+    #[test]
+    fn snapshot() {
+        let data = r#"{"b": [1, 0x1F, {}], "aA": 'x"y', "c": {"z": null, "y": []}}"#;
+        let mut arena = Arena::new(data);
+        let dialect = Dialect {
+            single_quotes: true,
+            radix_numbers: true,
+            ..Dialect::default()
+        };
+        let value = crate::parse_with_dialect(&mut arena, dialect).unwrap();
+
+        insta::assert_snapshot!(arena.snapshot(&value).sort_keys(true).to_string());
+        assert_eq!(
+            arena.snapshot(&value).indent(0).to_string(),
+            r#"{"b":[1,31,{}],"aA":"x\"y","c":{"z":null,"y":[]}}"#
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::fmt::FmtValue;
+use crate::{Arena, Error, Stacks, Value};
+
+/// The input formats recognised by [`parse_any`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    /// Newline delimited JSON, one value per line.
+    NdJson,
+    /// Requires the `cbor` feature.
+    Cbor,
+    /// Requires the `msgpack` feature.
+    MessagePack,
+}
+
+/// A document parsed by [`parse_any`].
+pub struct AnyDocument<'s> {
+    format: Format,
+    arena: Arena<'s>,
+    roots: Vec<Value>,
+}
+
+impl<'s> AnyDocument<'s> {
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// For the binary formats, the arena source is the input transcoded to JSON text.
+    pub fn arena(&self) -> &Arena<'s> {
+        &self.arena
+    }
+
+    /// The top-level values. There is exactly one unless the format is [`Format::NdJson`].
+    pub fn roots(&self) -> &[Value] {
+        &self.roots
+    }
+}
+
+impl fmt::Debug for AnyDocument<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let roots = self.roots.iter().map(|value| FmtValue {
+            arena: &self.arena,
+            value,
+        });
+        f.debug_struct("AnyDocument")
+            .field("format", &self.format)
+            .field("roots", &DebugList(roots))
+            .finish()
+    }
+}
+
+struct DebugList<I>(I);
+
+impl<I: Iterator<Item = T> + Clone, T: fmt::Debug> fmt::Debug for DebugList<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.clone()).finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum AnyError {
+    /// The input looked like text, but was not UTF-8.
+    Utf8(core::str::Utf8Error),
+    Json(Error),
+    /// The input was not valid in any of the enabled binary formats.
+    /// This reports the format that could decode the most input.
+    Binary {
+        format: Format,
+        offset: usize,
+    },
+    /// The input did not look like JSON, and no binary formats are enabled.
+    UnknownFormat,
+}
+
+impl fmt::Display for AnyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnyError::Utf8(e) => e.fmt(f),
+            AnyError::Json(e) => e.fmt(f),
+            AnyError::Binary { format, offset } => {
+                write!(f, "invalid {format:?} data at byte {offset}")
+            }
+            AnyError::UnknownFormat => f.write_str("unrecognised input format"),
+        }
+    }
+}
+
+impl core::error::Error for AnyError {}
+
+/// Parse JSON, NDJSON, CBOR or MessagePack input, detected from the leading bytes.
+///
+/// Input that starts with a JSON token (after whitespace) is parsed as JSON,
+/// falling back to NDJSON if it contains several lines of values.
+/// Anything else is tried as CBOR and then MessagePack, if those features are enabled.
+pub fn parse_any(bytes: &[u8]) -> Result<AnyDocument<'_>, AnyError> {
+    let first = bytes
+        .iter()
+        .find(|b| !matches!(b, b' ' | b'\t' | b'\r' | b'\n'));
+
+    match first {
+        None | Some(b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n') => {
+            parse_text(core::str::from_utf8(bytes).map_err(AnyError::Utf8)?)
+        }
+        Some(_) => parse_binary(bytes),
+    }
+}
+
+fn parse_text(src: &str) -> Result<AnyDocument<'_>, AnyError> {
+    let mut arena = Arena::new(src);
+    let err = match crate::parse(&mut arena) {
+        Ok(root) => {
+            return Ok(AnyDocument {
+                format: Format::Json,
+                arena,
+                roots: vec![root],
+            })
+        }
+        Err(err) => err,
+    };
+
+    if !src.contains('\n') {
+        return Err(AnyError::Json(err));
+    }
+
+    let mut arena = Arena::new(src);
+    let mut stacks = Stacks::default();
+    let mut roots = Vec::new();
+    let mut start = 0;
+    for line in src.split_inclusive('\n') {
+        let range = start..start + line.len();
+        start = range.end;
+
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        match crate::parse_range(&mut arena, &mut stacks, range, crate::Dialect::default()) {
+            Ok(root) => roots.push(root),
+            Err(_) => return Err(AnyError::Json(err)),
+        }
+    }
+
+    Ok(AnyDocument {
+        format: Format::NdJson,
+        arena,
+        roots,
+    })
+}
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+fn parse_binary(bytes: &[u8]) -> Result<AnyDocument<'_>, AnyError> {
+    use alloc::string::String;
+
+    fn transcoded(format: Format, json: String) -> Result<AnyDocument<'static>, AnyError> {
+        let mut arena = Arena::new_shared(json.into());
+        let root = crate::parse(&mut arena).map_err(AnyError::Json)?;
+        Ok(AnyDocument {
+            format,
+            arena,
+            roots: vec![root],
+        })
+    }
+
+    let mut err = AnyError::UnknownFormat;
+    let mut furthest = 0;
+    type Transcode = fn(&[u8]) -> Result<String, usize>;
+    let decoders: [(Format, Transcode); _] = [
+        #[cfg(feature = "cbor")]
+        (Format::Cbor, crate::cbor::to_json),
+        #[cfg(feature = "msgpack")]
+        (Format::MessagePack, crate::msgpack::to_json),
+    ];
+    for (format, to_json) in decoders {
+        match to_json(bytes) {
+            Ok(json) => return transcoded(format, json),
+            Err(offset) if offset >= furthest => {
+                furthest = offset;
+                err = AnyError::Binary { format, offset };
+            }
+            Err(_) => {}
+        }
+    }
+    Err(err)
+}
+
+#[cfg(not(any(feature = "cbor", feature = "msgpack")))]
+fn parse_binary(_: &[u8]) -> Result<AnyDocument<'_>, AnyError> {
+    Err(AnyError::UnknownFormat)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_any, AnyError, Format};
+
+    #[test]
+    fn json() {
+        let doc = parse_any(b"  {\"a\": [1, 2]}\n").unwrap();
+        assert_eq!(doc.format(), Format::Json);
+        assert_eq!(doc.roots().len(), 1);
+        assert_eq!(doc.arena().to_json(&doc.roots()[0]), r#"{"a":[1,2]}"#);
+
+        let err = parse_any(b"{\"a\": }").unwrap_err();
+        assert!(matches!(err, AnyError::Json(_)));
+    }
+
+    #[test]
+    fn ndjson() {
+        let doc = parse_any(b"{\"a\": 1}\n\n[true]\r\n\"x\"").unwrap();
+        assert_eq!(doc.format(), Format::NdJson);
+
+        let roots = doc.roots();
+        assert_eq!(roots.len(), 3);
+        assert_eq!(doc.arena().to_json(&roots[0]), r#"{"a":1}"#);
+        assert_eq!(doc.arena().to_json(&roots[1]), "[true]");
+        assert_eq!(doc.arena().to_json(&roots[2]), r#""x""#);
+
+        // a broken line reports the error as JSON
+        let err = parse_any(b"{\"a\": 1}\n{").unwrap_err();
+        assert!(matches!(err, AnyError::Json(_)));
+    }
+
+    #[cfg(not(any(feature = "cbor", feature = "msgpack")))]
+    #[test]
+    fn binary_disabled() {
+        assert!(matches!(parse_any(&[0xa0]), Err(AnyError::UnknownFormat)));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor() {
+        let doc = parse_any(&[0xd9, 0xd9, 0xf7, 0xa1, 0x61, b'a', 0x01]).unwrap();
+        assert_eq!(doc.format(), Format::Cbor);
+        assert_eq!(doc.arena().to_json(&doc.roots()[0]), r#"{"a":1}"#);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack() {
+        let doc = parse_any(&[0x81, 0xa1, b'a', 0x01]).unwrap();
+        assert_eq!(doc.format(), Format::MessagePack);
+        assert_eq!(doc.arena().to_json(&doc.roots()[0]), r#"{"a":1}"#);
+    }
+}
@@ -0,0 +1,152 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+
+use crate::eq::decimal;
+use crate::{Arena, Array, LeafValue, Value, ValueKind};
+
+impl<'s> Arena<'s> {
+    /// A total order over values, for sorting.
+    ///
+    /// Values are ordered by type, `null` < booleans < numbers < strings < arrays < objects,
+    /// then numbers by value, strings by their decoded contents,
+    /// and arrays and objects element by element, in source order.
+    pub fn cmp_values(&self, a: &Value, b: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value.kind() {
+                ValueKind::Leaf(LeafValue::Null) => 0,
+                ValueKind::Leaf(LeafValue::Bool(_)) => 1,
+                ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt) => {
+                    2
+                }
+                ValueKind::Leaf(LeafValue::String) => 3,
+                ValueKind::Array(_) => 4,
+                ValueKind::Object(_) => 5,
+            }
+        }
+
+        match (a.kind(), b.kind()) {
+            (ValueKind::Leaf(LeafValue::Bool(x)), ValueKind::Leaf(LeafValue::Bool(y))) => x.cmp(&y),
+            (ValueKind::Leaf(LeafValue::String), ValueKind::Leaf(LeafValue::String)) => {
+                self.as_str(a).cmp(&self.as_str(b))
+            }
+            (
+                ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt),
+                ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt),
+            ) => cmp_numbers(decimal(self, a), decimal(self, b)),
+            (ValueKind::Array(x), ValueKind::Array(y)) => {
+                let (xs, ys) = (self.array_values(&x), self.array_values(&y));
+                core::iter::zip(xs, ys)
+                    .map(|(v, w)| self.cmp_values(v, w))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(xs.len().cmp(&ys.len()))
+            }
+            (ValueKind::Object(x), ValueKind::Object(y)) => {
+                core::iter::zip(self.entries(&x), self.entries(&y))
+                    .map(|((k, v), (l, w))| k.cmp(l).then_with(|| self.cmp_values(v, w)))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(x.values().len().cmp(&y.values().len()))
+            }
+            _ => rank(a).cmp(&rank(b)),
+        }
+    }
+
+    /// Add a sorted copy of the elements of `array` to the arena.
+    ///
+    /// The sort is stable. Pass [`Arena::cmp_values`] to sort by value.
+    pub fn sort_array(
+        &mut self,
+        array: &Array,
+        mut by: impl FnMut(&Arena<'s>, &Value, &Value) -> Ordering,
+    ) -> Array {
+        let mut values = self.array_values(array).to_vec();
+        values.sort_by(|a, b| by(self, a, b));
+        self.push_array(self.container_span(array.values()), values)
+    }
+
+    /// Add a copy of `array` without consecutive duplicates to the arena, like [`Vec::dedup_by`].
+    ///
+    /// Sort the array first to remove all duplicates.
+    pub fn dedup_array(
+        &mut self,
+        array: &Array,
+        mut same: impl FnMut(&Arena<'s>, &Value, &Value) -> bool,
+    ) -> Array {
+        let mut values = self.array_values(array).to_vec();
+        values.dedup_by(|b, a| same(self, a, b));
+        self.push_array(self.container_span(array.values()), values)
+    }
+
+    /// Add an array of `values`, from this arena, with `span` in its header.
+    pub(crate) fn push_array(&mut self, span: Range<u32>, values: Vec<Value>) -> Array {
+        let start = self.push_header(span, 0);
+        for value in values {
+            self.push_value(value);
+        }
+        Array::new(start..self.values.len() as u32)
+    }
+}
+
+fn cmp_numbers(a: Option<(bool, String, i64)>, b: Option<(bool, String, i64)>) -> Ordering {
+    // numbers that did not parse sort first.
+    let ((a_neg, a_digits, a_exp), (b_neg, b_digits, b_exp)) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        (a, b) => return a.is_some().cmp(&b.is_some()),
+    };
+    let sign = |neg: bool, digits: &str| match (neg, digits.is_empty()) {
+        (_, true) => 0,
+        (true, false) => -1,
+        (false, false) => 1,
+    };
+    let (a_sign, b_sign) = (sign(a_neg, &a_digits), sign(b_neg, &b_digits));
+    if a_sign != b_sign || a_sign == 0 {
+        return a_sign.cmp(&b_sign);
+    }
+
+    // the position of the leading digit, then the digits themselves.
+    let magnitude = (a_digits.len() as i64 + a_exp)
+        .cmp(&(b_digits.len() as i64 + b_exp))
+        .then_with(|| a_digits.cmp(&b_digits));
+    if a_sign < 0 {
+        magnitude.reverse()
+    } else {
+        magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Array, ValueKind};
+
+    #[test]
+    fn sort_dedup() {
+        let src = r#"["b", 10, 9.5, -1, "a", null, [1], {"k": 1}, true, 1e1, "a", -20, 0, -0.0]"#;
+        let mut arena = Arena::new(src);
+        let root = crate::parse(&mut arena).unwrap();
+        let ValueKind::Array(array) = root.kind() else {
+            panic!("expected array")
+        };
+
+        let sorted = arena.sort_array(&array, Arena::cmp_values);
+        let json = |arena: &Arena, array: &Array| {
+            arena.to_json(&crate::Value::new(ValueKind::Array(*array), 0))
+        };
+        assert_eq!(
+            json(&arena, &sorted),
+            r#"[null,true,-20,-1,0,-0.0,9.5,10,1e1,"a","a","b",[1],{"k":1}]"#
+        );
+
+        let deduped = arena.dedup_array(&sorted, |arena, a, b| arena.cmp_values(a, b).is_eq());
+        assert_eq!(
+            json(&arena, &deduped),
+            r#"[null,true,-20,-1,0,9.5,10,"a","b",[1],{"k":1}]"#
+        );
+
+        // the original is untouched
+        assert_eq!(
+            arena.to_json(&root),
+            src.replace(", ", ",").replace(": ", ":")
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Decoding JSON documents from a [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html) stream.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use crate::{ArenaPool, DocumentGuard, Error};
+
+/// Splits an SSE byte stream into events, and parses the `data` of each event as JSON.
+///
+/// Bytes can be pushed in arbitrary chunks as they arrive from the network.
+/// Each event is parsed into pooled buffers, so the arena allocations are reused
+/// across events.
+///
+/// A `data: [DONE]` event, as sent by many LLM APIs, ends the stream.
+#[derive(Default)]
+pub struct SseDecoder {
+    pool: ArenaPool,
+    /// bytes that have been pushed, but not yet split into lines.
+    pending: Vec<u8>,
+    /// a `\r` ended the last line, so a leading `\n` must be skipped.
+    skip_lf: bool,
+    /// the event being built.
+    event: String,
+    data: String,
+    /// the last complete event, borrowed by [`SseEvent`].
+    ready_event: String,
+    ready_data: String,
+    last_event_id: String,
+    done: bool,
+}
+
+/// A parsed event from an [`SseDecoder`].
+///
+/// Derefs to the parsed document.
+pub struct SseEvent<'a> {
+    event: &'a str,
+    doc: DocumentGuard<'a, 'a>,
+}
+
+impl<'a> SseEvent<'a> {
+    /// The `event` field, which defaults to `message`.
+    pub fn event(&self) -> &'a str {
+        self.event
+    }
+}
+
+impl<'a> Deref for SseEvent<'a> {
+    type Target = DocumentGuard<'a, 'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.doc
+    }
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add bytes received from the stream.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// The last `id` field received.
+    pub fn last_event_id(&self) -> &str {
+        &self.last_event_id
+    }
+
+    /// Whether a `[DONE]` event has been received.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Parse the next complete event.
+    ///
+    /// Returns `None` if more bytes need to be pushed, or the stream is done.
+    pub fn next_event(&mut self) -> Option<Result<SseEvent<'_>, Error>> {
+        if !self.frame() {
+            return None;
+        }
+        let doc = self.pool.parse_next(&self.ready_data);
+        Some(doc.map(|doc| SseEvent {
+            event: &self.ready_event,
+            doc,
+        }))
+    }
+
+    /// Like [`next_event`](Self::next_event), but large events are parsed
+    /// with [`ArenaPool::parse_next_async`].
+    pub async fn next_event_async(&mut self) -> Option<Result<SseEvent<'_>, Error>> {
+        if !self.frame() {
+            return None;
+        }
+        let doc = self.pool.parse_next_async(&self.ready_data).await;
+        Some(doc.map(|doc| SseEvent {
+            event: &self.ready_event,
+            doc,
+        }))
+    }
+
+    /// Consume lines until an event is complete, and move it into `ready_*`.
+    fn frame(&mut self) -> bool {
+        let mut start = 0;
+        let mut found = false;
+
+        while !self.done {
+            if self.skip_lf && start < self.pending.len() {
+                self.skip_lf = false;
+                if self.pending[start] == b'\n' {
+                    start += 1;
+                }
+            }
+            let Some(len) = memchr::memchr2(b'\n', b'\r', &self.pending[start..]) else {
+                break;
+            };
+            let end = start + len;
+            self.skip_lf = self.pending[end] == b'\r';
+
+            let line = String::from_utf8_lossy(&self.pending[start..end]);
+            start = end + 1;
+
+            if line.is_empty() {
+                if self.data.is_empty() {
+                    self.event.clear();
+                    continue;
+                }
+                // the final data line does not get a trailing newline.
+                self.data.pop();
+                if self.data == "[DONE]" {
+                    self.done = true;
+                    break;
+                }
+
+                core::mem::swap(&mut self.ready_data, &mut self.data);
+                core::mem::swap(&mut self.ready_event, &mut self.event);
+                self.data.clear();
+                self.event.clear();
+                if self.ready_event.is_empty() {
+                    self.ready_event.push_str("message");
+                }
+                found = true;
+                break;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (&*line, ""),
+            };
+            match field {
+                // a comment
+                "" => {}
+                "data" => {
+                    self.data.push_str(value);
+                    self.data.push('\n');
+                }
+                "event" => {
+                    self.event.clear();
+                    self.event.push_str(value);
+                }
+                "id" if !value.contains('\0') => {
+                    self.last_event_id.clear();
+                    self.last_event_id.push_str(value);
+                }
+                _ => {}
+            }
+        }
+
+        self.pending.drain(..start);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::SseDecoder;
+
+    const STREAM: &str = ": keep-alive\r\n\r\nid: 1\r\ndata: {\"delta\": \"Hel\"}\r\n\r\nevent: usage\rdata: {\"tokens\":\rdata: 3}\r\rdata: [DONE]\n\ndata: {}\n\n";
+
+    fn collect(chunks: impl Iterator<Item = &'static [u8]>) -> Vec<String> {
+        let mut decoder = SseDecoder::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            decoder.push(chunk);
+            while let Some(event) = decoder.next_event() {
+                let event = event.unwrap();
+                out.push(std::format!(
+                    "{}: {}",
+                    event.event(),
+                    event.to_json(event.root())
+                ));
+            }
+        }
+        assert!(decoder.is_done());
+        assert_eq!(decoder.last_event_id(), "1");
+        out
+    }
+
+    #[test]
+    fn events() {
+        let expected = [r#"message: {"delta":"Hel"}"#, r#"usage: {"tokens":3}"#];
+        assert_eq!(collect([STREAM.as_bytes()].into_iter()), expected);
+        // byte-at-a-time, to split every line ending
+        assert_eq!(collect(STREAM.as_bytes().chunks(1)), expected);
+    }
+
+    #[pollster::test]
+    async fn events_async() {
+        let mut decoder = SseDecoder::new();
+        decoder.push(b"data: [1,\ndata: 2]\n");
+        assert!(decoder.next_event_async().await.is_none());
+        decoder.push(b"\ndata: [\n\n");
+
+        let event = decoder.next_event_async().await.unwrap().unwrap();
+        assert_eq!(event.to_json(event.root()), "[1,2]");
+        drop(event);
+        assert!(decoder.next_event_async().await.unwrap().is_err());
+        assert!(decoder.next_event_async().await.is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use hashbrown::HashTable;
+
+use crate::Arena;
+
+/// How often, and by how much, one of the buffers of an arena grew.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Growth {
+    /// The number of times the buffer was allocated or reallocated.
+    pub allocations: usize,
+    /// The bytes of capacity added, over all of those allocations.
+    pub bytes: usize,
+}
+
+impl Growth {
+    pub(crate) fn record(&mut self, before: usize, after: usize) {
+        if after > before {
+            self.allocations += 1;
+            self.bytes += after - before;
+        }
+    }
+}
+
+/// The allocations an arena made while parsing, by what they were for.
+///
+/// Growth is measured from the capacity of each buffer around the places that can grow it,
+/// so reusing buffers with [`Arena::from_buffers`] shows up as fewer allocations here.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// The values of objects and arrays.
+    pub values: Growth,
+    /// The spans of values, kept next to them.
+    pub spans: Growth,
+    /// The keys of objects.
+    pub keys: Growth,
+    /// The table of interned keys. Its bytes leave out the control bytes of the hash table.
+    pub table: Growth,
+    /// The short list that keys are interned into, before there are enough for the table.
+    pub small: Growth,
+    /// Keys that had escapes, decoded.
+    pub scratch: Growth,
+}
+
+impl Arena<'_> {
+    /// The allocations this arena has made while parsing, since it was created.
+    pub fn parse_stats(&self) -> ParseStats {
+        self.stats
+    }
+}
+
+/// The bytes a buffer has room for.
+pub(crate) trait Capacity {
+    fn capacity_bytes(&self) -> usize;
+}
+
+impl<T> Capacity for Vec<T> {
+    fn capacity_bytes(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+impl Capacity for String {
+    fn capacity_bytes(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T> Capacity for HashTable<T> {
+    fn capacity_bytes(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, Reserve};
+
+    #[test]
+    fn parse_stats() {
+        let src = std::format!(
+            "[{}]",
+            (0..100)
+                .map(|i| std::format!(r#"{{"ke\ny{i}": [{i}]}}"#))
+                .collect::<std::vec::Vec<_>>()
+                .join(",")
+        );
+
+        let mut arena = Arena::new(&src);
+        arena.set_reserve(Reserve::NONE);
+        crate::parse(&mut arena).unwrap();
+        let stats = arena.parse_stats();
+        assert!(stats.values.allocations > 1);
+        assert!(stats.values.bytes >= 301 * size_of::<crate::Value>());
+        assert!(stats.keys.allocations > 1);
+        assert!(stats.table.allocations > 0);
+        assert!(stats.small.allocations > 0);
+        assert!(stats.scratch.allocations > 0);
+
+        // with the buffers of the last parse, nothing needs to grow.
+        let mut arena = Arena::from_buffers(&src, arena.into_buffers());
+        arena.set_reserve(Reserve::NONE);
+        crate::parse(&mut arena).unwrap();
+        assert_eq!(arena.parse_stats(), Default::default());
+    }
+}
@@ -0,0 +1,171 @@
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use crate::ser::{escape, write_decimal};
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+/// How much of a value [`Arena::summarize`] shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Characters of each string.
+    pub max_string: usize,
+    /// Elements of each array, or members of each object.
+    pub max_items: usize,
+    /// Levels of nesting. Deeper containers are shown as `{…}` or `[…]`.
+    pub max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_string: 64,
+            max_items: 8,
+            max_depth: 4,
+        }
+    }
+}
+
+/// A truncated preview of a value, returned by [`Arena::summarize`].
+pub struct Summary<'a, 's> {
+    arena: &'a Arena<'s>,
+    value: &'a Value,
+    limits: Limits,
+}
+
+impl<'s> Arena<'s> {
+    /// Preview `value` for logging, like `{"data": [1, 2, …(+998)], "id": "abc…"}`.
+    ///
+    /// Only the elements and members that are shown are visited,
+    /// so this is cheap even for very large values.
+    pub fn summarize<'a>(&'a self, value: &'a Value, limits: Limits) -> Summary<'a, 's> {
+        Summary {
+            arena: self,
+            value,
+            limits,
+        }
+    }
+}
+
+impl Summary<'_, '_> {
+    fn write(&self, value: &Value, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arena = self.arena;
+        match value.kind() {
+            ValueKind::Leaf(LeafValue::Bool(true)) => f.write_str("true"),
+            ValueKind::Leaf(LeafValue::Bool(false)) => f.write_str("false"),
+            ValueKind::Leaf(LeafValue::Null) => f.write_str("null"),
+            ValueKind::Leaf(LeafValue::String) => {
+                let s = arena.as_str(value).unwrap_or_default();
+                let (s, truncated) = match s.char_indices().nth(self.limits.max_string) {
+                    Some((i, _)) => (&s[..i], true),
+                    None => (&*s, false),
+                };
+                let mut out = String::with_capacity(s.len() + 2);
+                out.push('"');
+                escape(s, &mut out);
+                if truncated {
+                    out.push('…');
+                }
+                out.push('"');
+                f.write_str(&out)
+            }
+            ValueKind::Leaf(LeafValue::Number | LeafValue::BigInt) => f.write_str(arena.raw(value)),
+            ValueKind::Leaf(LeafValue::RadixInt(radix)) => {
+                let mut out = String::new();
+                write_decimal(arena.raw(value), radix, &mut out);
+                f.write_str(&out)
+            }
+            ValueKind::Object(object) if object.values().is_empty() => f.write_str("{}"),
+            ValueKind::Object(_) if depth >= self.limits.max_depth => f.write_str("{…}"),
+            ValueKind::Object(object) => {
+                f.write_char('{')?;
+                for (i, (k, v)) in arena
+                    .entries(&object)
+                    .take(self.limits.max_items)
+                    .enumerate()
+                {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    let mut key = String::with_capacity(k.len() + 2);
+                    key.push('"');
+                    escape(k, &mut key);
+                    key.push_str("\": ");
+                    f.write_str(&key)?;
+                    self.write(v, depth + 1, f)?;
+                }
+                self.write_rest(object.values().len(), f)?;
+                f.write_char('}')
+            }
+            ValueKind::Array(array) if array.values().is_empty() => f.write_str("[]"),
+            ValueKind::Array(_) if depth >= self.limits.max_depth => f.write_str("[…]"),
+            ValueKind::Array(array) => {
+                f.write_char('[')?;
+                let values = arena.array_values(&array);
+                for (i, v) in values.iter().take(self.limits.max_items).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    self.write(v, depth + 1, f)?;
+                }
+                self.write_rest(values.len(), f)?;
+                f.write_char(']')
+            }
+        }
+    }
+
+    /// Note how many of `len` items were left out.
+    fn write_rest(&self, len: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match len.checked_sub(self.limits.max_items) {
+            None | Some(0) => Ok(()),
+            Some(rest) if self.limits.max_items == 0 => write!(f, "…(+{rest})"),
+            Some(rest) => write!(f, ", …(+{rest})"),
+        }
+    }
+}
+
+impl fmt::Display for Summary<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(self.value, 0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use crate::{Arena, Limits};
+
+    #[test]
+    fn summarize() {
+        let mut data = String::from(r#"{"data": ["#);
+        for i in 0..1000 {
+            if i > 0 {
+                data.push(',');
+            }
+            data.push_str(&i.to_string());
+        }
+        data.push_str(r#"], "id": "abcdefgh\n", "deep": {"a": {"b": [1]}}, "e": [], "f": null}"#);
+        let mut arena = Arena::new(&data);
+        let value = crate::parse(&mut arena).unwrap();
+
+        let limits = Limits {
+            max_string: 4,
+            max_items: 3,
+            max_depth: 2,
+        };
+        assert_eq!(
+            arena.summarize(&value, limits).to_string(),
+            r#"{"data": [0, 1, 2, …(+997)], "id": "abcd…", "deep": {"a": {…}}, …(+2)}"#
+        );
+
+        let limits = Limits {
+            max_items: 0,
+            ..limits
+        };
+        assert_eq!(arena.summarize(&value, limits).to_string(), "{…(+5)}");
+        assert_eq!(
+            arena.summarize(&value, Limits::default()).to_string(),
+            r#"{"data": [0, 1, 2, 3, 4, 5, 6, 7, …(+992)], "id": "abcdefgh\n", "deep": {"a": {"b": [1]}}, "e": [], "f": null}"#
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use core::fmt;
+use core::ops::Range;
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+use crate::{Arena, LeafValue, Value, ValueKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeError {
+    /// The span of the offending value.
+    pub span: Range<u32>,
+    pub kind: TimeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeErrorKind {
+    ExpectedString,
+    ExpectedNumber,
+    /// The string is not an RFC 3339 date and time.
+    Invalid(chrono::ParseError),
+    /// The timestamp is outside the range `DateTime` can represent.
+    OutOfRange,
+}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TimeErrorKind::ExpectedString => write!(f, "expected a string at {:?}", self.span),
+            TimeErrorKind::ExpectedNumber => write!(f, "expected a number at {:?}", self.span),
+            TimeErrorKind::Invalid(e) => write!(f, "invalid date at {:?}: {e}", self.span),
+            TimeErrorKind::OutOfRange => write!(f, "timestamp at {:?} is out of range", self.span),
+        }
+    }
+}
+
+impl core::error::Error for TimeError {}
+
+impl Arena<'_> {
+    /// Read a string like `"2024-05-01T12:30:00+02:00"` as an RFC 3339 date and time.
+    pub fn as_rfc3339(&self, value: &Value) -> Result<DateTime<FixedOffset>, TimeError> {
+        let error = |kind| TimeError {
+            span: self.span(value),
+            kind,
+        };
+        let s = self
+            .as_str(value)
+            .ok_or_else(|| error(TimeErrorKind::ExpectedString))?;
+        DateTime::parse_from_rfc3339(&s).map_err(|e| error(TimeErrorKind::Invalid(e)))
+    }
+
+    /// Read a number of seconds since the Unix epoch, which may have a fraction.
+    pub fn as_unix_timestamp(&self, value: &Value) -> Result<DateTime<Utc>, TimeError> {
+        let error = |kind| TimeError {
+            span: self.span(value),
+            kind,
+        };
+        if !matches!(
+            value.kind(),
+            ValueKind::Leaf(LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt)
+        ) {
+            return Err(error(TimeErrorKind::ExpectedNumber));
+        }
+
+        let time = match self.as_i128(value) {
+            Some(secs) => i64::try_from(secs)
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            None => self.as_f64(value).and_then(|secs| {
+                let whole = secs.floor();
+                let nanos = ((secs - whole) * 1e9).round().min(999_999_999.0);
+                if !(i64::MIN as f64..i64::MAX as f64).contains(&whole) {
+                    return None;
+                }
+                DateTime::from_timestamp(whole as i64, nanos as u32)
+            }),
+        };
+        time.ok_or_else(|| error(TimeErrorKind::OutOfRange))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Timelike};
+
+    use crate::{Arena, TimeErrorKind, ValueKind};
+
+    #[test]
+    fn timestamps() {
+        let src = r#"{"at": "2024-05-01T12:30:00+02:00", "bad": "2024-13-01", "unix": 1714559400, "frac": -1.5, "far": 1e300, "n": 1}"#;
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).unwrap();
+        let ValueKind::Object(object) = value.kind() else {
+            panic!("expected object")
+        };
+        let get = |key| arena.get(&object, key).unwrap();
+
+        let at = arena.as_rfc3339(get("at")).unwrap();
+        assert_eq!(at.to_utc(), arena.as_unix_timestamp(get("unix")).unwrap());
+
+        let err = arena.as_rfc3339(get("bad")).unwrap_err();
+        assert_eq!(err.span, 43..55);
+        assert!(matches!(err.kind, TimeErrorKind::Invalid(_)));
+        let err = arena.as_rfc3339(get("n")).unwrap_err();
+        assert_eq!(err.kind, TimeErrorKind::ExpectedString);
+
+        let frac = arena.as_unix_timestamp(get("frac")).unwrap();
+        assert_eq!(frac.timestamp(), -2);
+        assert_eq!(frac.nanosecond(), 500_000_000);
+        assert_eq!(
+            arena.as_unix_timestamp(get("far")).unwrap_err().kind,
+            TimeErrorKind::OutOfRange
+        );
+        assert_eq!(
+            arena.as_unix_timestamp(get("at")).unwrap_err().kind,
+            TimeErrorKind::ExpectedNumber
+        );
+        assert_eq!(
+            arena.as_unix_timestamp(get("n")).unwrap(),
+            DateTime::from_timestamp(1, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Reading TOML into an arena, with the TOML span of every value.
+//!
+//! As with YAML, the TOML is transcoded to JSON text for the arena, and
+//! [`TomlDocument::toml_span`] maps a value back to where it came from.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+use toml_edit::{ImDocument, Item, Table};
+
+use crate::{Arena, Error, Value};
+
+/// A TOML document parsed by [`parse_toml`].
+pub struct TomlDocument {
+    arena: Arena<'static>,
+    root: Value,
+    /// Where each value starts in the JSON text, and its span in the TOML, ordered by the former.
+    spans: Vec<(u32, Range<u32>)>,
+}
+
+impl TomlDocument {
+    /// The arena source is the input transcoded to JSON text, so value spans point into that.
+    pub fn arena(&self) -> &Arena<'static> {
+        &self.arena
+    }
+
+    /// The root table.
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    /// The span of `value` in the TOML input.
+    ///
+    /// A table with a `[header]` spans from its header to its last key, and the root table
+    /// is the whole input.
+    /// Tables that are only implied by the keys of others, like `a` in `[a.b]`, have no span.
+    pub fn toml_span(&self, value: &Value) -> Option<Range<u32>> {
+        let i = self
+            .spans
+            .binary_search_by_key(&self.arena.span(value).start, |(start, _)| *start)
+            .ok()?;
+        Some(self.spans[i].1.clone())
+    }
+}
+
+impl fmt::Debug for TomlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TomlDocument")
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub enum TomlError {
+    /// The input is not valid TOML.
+    Syntax {
+        span: Option<Range<u32>>,
+        message: String,
+    },
+    /// A float that JSON cannot hold, like `inf` or `nan`.
+    NotFinite {
+        span: Option<Range<u32>>,
+    },
+    Json(Error),
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlError::Syntax {
+                span: Some(span),
+                message,
+            } => write!(f, "{message} at bytes {span:?}"),
+            TomlError::Syntax {
+                span: None,
+                message,
+            } => f.write_str(message),
+            TomlError::NotFinite { span } => {
+                write!(f, "float at bytes {span:?} cannot be represented in JSON")
+            }
+            TomlError::Json(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for TomlError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            TomlError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a TOML document into an arena.
+///
+/// Tables and arrays of tables become objects and arrays, and date-times become strings
+/// in their RFC 3339 form.
+pub fn parse_toml(src: &str) -> Result<TomlDocument, TomlError> {
+    let doc = ImDocument::parse(src).map_err(|e| TomlError::Syntax {
+        span: e.span().map(span),
+        message: e.message().into(),
+    })?;
+
+    let mut transcoder = Transcoder {
+        out: String::new(),
+        spans: Vec::new(),
+    };
+    transcoder.table(doc.as_table(), Some(0..src.len()))?;
+
+    let Transcoder { out, spans } = transcoder;
+    let mut arena = Arena::new_shared(out.into());
+    let root = crate::parse(&mut arena).map_err(TomlError::Json)?;
+    Ok(TomlDocument { arena, root, spans })
+}
+
+fn span(span: Range<usize>) -> Range<u32> {
+    span.start as u32..span.end as u32
+}
+
+struct Transcoder {
+    out: String,
+    spans: Vec<(u32, Range<u32>)>,
+}
+
+impl Transcoder {
+    /// Record the TOML span of the value about to be written.
+    fn start(&mut self, span: Option<Range<usize>>) {
+        if let Some(span) = span {
+            self.spans.push((self.out.len() as u32, self::span(span)));
+        }
+    }
+
+    fn key(&mut self, i: usize, key: &str) {
+        if i > 0 {
+            self.out.push(',');
+        }
+        self.string(key);
+        self.out.push(':');
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        crate::ser::escape(s, &mut self.out);
+        self.out.push('"');
+    }
+
+    fn table(&mut self, table: &Table, span: Option<Range<usize>>) -> Result<(), TomlError> {
+        self.start(span);
+        self.out.push('{');
+        for (i, (key, item)) in table.iter().enumerate() {
+            self.key(i, key);
+            self.item(item)?;
+        }
+        self.out.push('}');
+        Ok(())
+    }
+
+    fn item(&mut self, item: &Item) -> Result<(), TomlError> {
+        match item {
+            // the parser does not produce empty items.
+            Item::None => self.out.push_str("null"),
+            Item::Value(value) => self.value(value)?,
+            Item::Table(table) => self.table(table, table.span())?,
+            Item::ArrayOfTables(tables) => {
+                self.start(tables.span());
+                self.out.push('[');
+                for (i, table) in tables.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.table(table, table.span())?;
+                }
+                self.out.push(']');
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, value: &toml_edit::Value) -> Result<(), TomlError> {
+        use toml_edit::Value as V;
+
+        self.start(value.span());
+        match value {
+            V::String(s) => self.string(s.value()),
+            V::Integer(n) => {
+                let _ = write!(self.out, "{}", n.value());
+            }
+            V::Float(f) if f.value().is_finite() => {
+                let _ = write!(self.out, "{:?}", f.value());
+            }
+            V::Float(_) => {
+                return Err(TomlError::NotFinite {
+                    span: value.span().map(span),
+                })
+            }
+            V::Boolean(b) => self.out.push_str(if *b.value() { "true" } else { "false" }),
+            V::Datetime(dt) => self.string(&alloc::format!("{}", dt.value())),
+            V::Array(array) => {
+                self.out.push('[');
+                for (i, value) in array.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.value(value)?;
+                }
+                self.out.push(']');
+            }
+            V::InlineTable(table) => {
+                self.out.push('{');
+                for (i, (key, value)) in table.iter().enumerate() {
+                    self.key(i, key);
+                    self.value(value)?;
+                }
+                self.out.push('}');
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_toml, TomlError};
+    use crate::refs::Refs;
+
+    #[test]
+    fn toml() {
+        let src = r#"name = "sonny-jim"
+version = 0x10
+ratio = 1.5
+released = 2024-06-01T12:00:00Z
+tags = ["json", 'toml']
+deps = { logos = { version = "0.14" } }
+
+[features]
+default = []
+
+[[bin]]
+name = "cli"
+
+[[bin]]
+name = "server"
+"#;
+        let doc = parse_toml(src).unwrap();
+        let arena = doc.arena();
+        assert_eq!(
+            arena.to_json(doc.root()),
+            r#"{"name":"sonny-jim","version":16,"ratio":1.5,"released":"2024-06-01T12:00:00Z","tags":["json","toml"],"deps":{"logos":{"version":"0.14"}},"features":{"default":[]},"bin":[{"name":"cli"},{"name":"server"}]}"#
+        );
+
+        let span = |path: &str| {
+            let value = Refs::new(arena, doc.root()).pointer(path).unwrap();
+            let span = doc.toml_span(value).unwrap();
+            &src[span.start as usize..span.end as usize]
+        };
+        assert_eq!(span(""), src);
+        assert_eq!(span("/version"), "0x10");
+        assert_eq!(span("/tags/1"), "'toml'");
+        assert_eq!(span("/deps/logos"), r#"{ version = "0.14" }"#);
+        assert_eq!(span("/features"), "[features]\ndefault = []");
+        assert_eq!(span("/bin/1/name"), r#""server""#);
+
+        let err = parse_toml("a = [1,").unwrap_err();
+        assert!(matches!(err, TomlError::Syntax { span: Some(_), .. }));
+        let err = parse_toml("a = nan").unwrap_err();
+        assert!(matches!(err, TomlError::NotFinite { span: Some(span) } if span == (4..7)));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{Arena, LeafValue};
+
+/// What the lexer and parser of an arena did, counted in terms that line up with the
+/// two stages of simd-json, to explain where a benchmark spends its time.
+///
+/// simd-json first indexes every structural byte and scalar start, then walks that index to
+/// build its tape. [`ParseTrace::stage1_indexes`] and [`ParseTrace::tape_len`] give the
+/// sizes simd-json would see for the same input, next to the work done here.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseTrace {
+    /// The single-byte tokens, `{ } [ ] : ,`.
+    pub structural: usize,
+    /// The structural tokens that went through the lexer, rather than the single-byte fast path.
+    pub structural_lexed: usize,
+    /// String values.
+    pub strings: usize,
+    /// Numbers, in any base.
+    pub numbers: usize,
+    /// `true`, `false` and `null`.
+    pub literals: usize,
+    /// Object keys, quoted or not.
+    pub keys: usize,
+    /// Strings and keys with escapes, which are decoded rather than borrowed.
+    pub escaped: usize,
+    pub objects: usize,
+    pub arrays: usize,
+    /// The deepest nesting of objects and arrays.
+    pub max_depth: usize,
+}
+
+impl ParseTrace {
+    /// The entries simd-json would find in its structural index, one per token.
+    pub fn stage1_indexes(&self) -> usize {
+        self.structural + self.strings + self.numbers + self.literals + self.keys
+    }
+
+    /// The 64-bit words of the tape simd-json would build, counting the root.
+    pub fn tape_len(&self) -> usize {
+        2 + 2 * (self.objects + self.arrays)
+            + self.strings
+            + self.keys
+            + self.literals
+            + 2 * self.numbers
+    }
+
+    pub(crate) fn leaf(&mut self, leaf: &LeafValue, escaped: bool) {
+        match leaf {
+            LeafValue::String => {
+                self.strings += 1;
+                self.escaped += escaped as usize;
+            }
+            LeafValue::Number | LeafValue::RadixInt(_) | LeafValue::BigInt => self.numbers += 1,
+            LeafValue::Bool(_) | LeafValue::Null => self.literals += 1,
+        }
+    }
+
+    pub(crate) fn key(&mut self, escaped: bool) {
+        self.keys += 1;
+        self.escaped += escaped as usize;
+    }
+
+    pub(crate) fn open(&mut self, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+    }
+}
+
+impl Arena<'_> {
+    /// What the lexer and parser have done in this arena, since it was created.
+    pub fn parse_trace(&self) -> ParseTrace {
+        self.trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arena, ParseTrace};
+
+    #[test]
+    fn parse_trace() {
+        let mut arena = Arena::new(r#"{"a\n": [1, 2, true], "b": {"c": "d"}}"#);
+        crate::parse(&mut arena).unwrap();
+
+        let trace = arena.parse_trace();
+        assert_eq!(
+            trace,
+            ParseTrace {
+                structural: 12,
+                structural_lexed: 2,
+                strings: 1,
+                numbers: 2,
+                literals: 1,
+                keys: 3,
+                escaped: 1,
+                objects: 2,
+                arrays: 1,
+                max_depth: 2,
+            }
+        );
+        assert_eq!(trace.stage1_indexes(), 19);
+        assert_eq!(trace.tape_len(), 2 + 6 + 1 + 3 + 1 + 4);
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Writes JSON text for the binary formats, tracking containers with an explicit stack.
+pub(crate) struct JsonWriter {
+    pub(crate) out: String,
+    stack: Vec<Frame>,
+    done: bool,
+}
+
+struct Frame {
+    object: bool,
+    /// items left in an array, or entries left in an object.
+    /// `None` for indefinite length containers.
+    remaining: Option<u64>,
+    first: bool,
+    expect_key: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Position {
+    Key,
+    Value,
+}
+
+impl JsonWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            out: String::new(),
+            stack: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Whether the top level value is complete.
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Write any separators needed before the next item.
+    pub(crate) fn item(&mut self) -> Position {
+        let Some(frame) = self.stack.last_mut() else {
+            return Position::Value;
+        };
+        if frame.object && !frame.expect_key {
+            self.out.push(':');
+            return Position::Value;
+        }
+        if !core::mem::take(&mut frame.first) {
+            self.out.push(',');
+        }
+        if frame.object {
+            Position::Key
+        } else {
+            Position::Value
+        }
+    }
+
+    /// Mark the current item as complete, closing any containers that are now full.
+    pub(crate) fn complete(&mut self) {
+        loop {
+            let Some(frame) = self.stack.last_mut() else {
+                self.done = true;
+                return;
+            };
+            if frame.object {
+                frame.expect_key = !frame.expect_key;
+                if !frame.expect_key {
+                    // just completed a key
+                    return;
+                }
+            }
+            match &mut frame.remaining {
+                Some(n) => {
+                    *n -= 1;
+                    if *n > 0 {
+                        return;
+                    }
+                }
+                None => return,
+            }
+            self.close();
+        }
+    }
+
+    fn close(&mut self) {
+        let frame = self.stack.pop().unwrap();
+        self.out.push(if frame.object { '}' } else { ']' });
+    }
+
+    pub(crate) fn begin(&mut self, object: bool, len: Option<u64>) {
+        self.out.push(if object { '{' } else { '[' });
+        self.stack.push(Frame {
+            object,
+            remaining: len,
+            first: true,
+            expect_key: true,
+        });
+        if len == Some(0) {
+            self.close();
+            self.complete();
+        }
+    }
+
+    /// End an indefinite length container. Returns false if not allowed here.
+    #[cfg(feature = "cbor")]
+    pub(crate) fn end(&mut self) -> bool {
+        match self.stack.last() {
+            Some(frame) if frame.remaining.is_none() && (!frame.object || frame.expect_key) => {
+                self.close();
+                self.complete();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn string(&mut self, s: &str) {
+        self.out.push('"');
+        crate::ser::escape(s, &mut self.out);
+        self.out.push('"');
+        self.complete();
+    }
+
+    /// Write a number or literal. Quoted if in key position.
+    pub(crate) fn scalar(&mut self, position: &Position, s: core::fmt::Arguments<'_>) {
+        use core::fmt::Write;
+
+        if *position == Position::Key {
+            self.out.push('"');
+        }
+        let _ = self.out.write_fmt(s);
+        if *position == Position::Key {
+            self.out.push('"');
+        }
+        self.complete();
+    }
+
+    pub(crate) fn float(&mut self, position: &Position, f: f64) {
+        if f.is_finite() {
+            self.scalar(position, format_args!("{f:?}"));
+        } else {
+            self.scalar(position, format_args!("null"));
+        }
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.out.push('"');
+        crate::base64::encode_url(bytes, &mut self.out);
+        self.out.push('"');
+        self.complete();
+    }
+}
@@ -0,0 +1,379 @@
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+
+use crate::fmt::FmtValue;
+use crate::{Arena, Error, Object, Value, ValueKind};
+
+/// An edited copy of a shared document.
+///
+/// The parsed base document is held behind an [`Arc`] and never modified,
+/// so any number of variants can be derived from it cheaply.
+/// Each variant only owns the values it replaces.
+pub struct Variant<'s> {
+    base: Arc<Arena<'s>>,
+    root: Value,
+    patches: Vec<Patch<'s>>,
+}
+
+/// [`Variant::set`] could not replace a value.
+#[derive(Debug)]
+pub enum VariantError {
+    /// The path has no parent to hold the value: a missing key or index above it, a leaf,
+    /// or an array index out of bounds.
+    Path,
+    /// The replacement is not valid JSON.
+    Json(Error),
+}
+
+impl fmt::Display for VariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariantError::Path => f.write_str("path has no parent in the document"),
+            VariantError::Json(error) => write!(f, "replacement: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for VariantError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            VariantError::Json(error) => Some(error),
+            VariantError::Path => None,
+        }
+    }
+}
+
+struct Patch<'s> {
+    path: Vec<String>,
+    arena: Arena<'s>,
+    value: Value,
+}
+
+impl<'s> Variant<'s> {
+    pub fn new(base: Arc<Arena<'s>>, root: Value) -> Self {
+        Self {
+            base,
+            root,
+            patches: Vec::new(),
+        }
+    }
+
+    pub fn base(&self) -> &Arc<Arena<'s>> {
+        &self.base
+    }
+
+    /// Replace the value at `path` with the JSON document in `src`.
+    ///
+    /// Path segments are object keys, or decimal indices for arrays.
+    /// Any earlier replacement at or below `path` is discarded.
+    ///
+    /// The parent of `path` must already be in the variant. A new key can be added to an
+    /// object, but an array index must be in bounds.
+    pub fn set(&mut self, path: &[&str], src: &'s str) -> Result<(), VariantError> {
+        if let Some((last, parent)) = path.split_last() {
+            let (_, parent) = self.get(parent).ok_or(VariantError::Path)?;
+            let fits = match parent.kind() {
+                ValueKind::Object(_) => true,
+                ValueKind::Array(array) => {
+                    crate::flatten::array_index(last).is_some_and(|i| i < array.len())
+                }
+                ValueKind::Leaf(_) => false,
+            };
+            if !fits {
+                return Err(VariantError::Path);
+            }
+        }
+        let mut arena = Arena::new(src);
+        let value = crate::parse(&mut arena).map_err(VariantError::Json)?;
+
+        self.patches.retain(|patch| !starts_with(&patch.path, path));
+        self.patches.push(Patch {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            arena,
+            value,
+        });
+        Ok(())
+    }
+
+    /// Get the value at `path`, taking replacements into account.
+    ///
+    /// Replacements made below `path` are not visible through the returned
+    /// value. Use [`to_json`](Self::to_json) for the fully merged document.
+    pub fn get(&self, path: &[&str]) -> Option<(&Arena<'s>, &Value)> {
+        // the deepest patch that covers this path wins.
+        let patch = self
+            .patches
+            .iter()
+            .filter(|patch| starts_with(path, &patch.path))
+            .max_by_key(|patch| patch.path.len());
+
+        let (arena, mut value, rest) = match patch {
+            Some(patch) => (&patch.arena, &patch.value, &path[patch.path.len()..]),
+            None => (&*self.base, &self.root, path),
+        };
+
+        for segment in rest {
+            value = child(arena, value, segment)?;
+        }
+        Some((arena, value))
+    }
+
+    /// Serialize the merged document as compact JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    /// Serialize the merged document as compact JSON, appending to `out`.
+    pub fn write_json(&self, out: &mut String) {
+        self.merged().write_json(out);
+    }
+
+    fn merged(&self) -> Merged<'_, 's> {
+        Merged::new(&self.base, &self.root, self.patches.iter().collect(), 0)
+    }
+}
+
+impl Debug for Variant<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.merged().fmt(f)
+    }
+}
+
+fn child<'a>(arena: &'a Arena<'_>, value: &Value, segment: &str) -> Option<&'a Value> {
+    match value.kind() {
+        ValueKind::Leaf(_) => None,
+        ValueKind::Object(object) => arena.get(&object, segment),
+        ValueKind::Array(array) => {
+            let index = crate::flatten::array_index(segment)?;
+            arena.array_values(&array).get(index)
+        }
+    }
+}
+
+fn starts_with(path: &[impl AsRef<str>], prefix: &[impl AsRef<str>]) -> bool {
+    path.len() >= prefix.len()
+        && core::iter::zip(path, prefix).all(|(a, b)| a.as_ref() == b.as_ref())
+}
+
+/// A value of the merged document.
+struct Merged<'a, 's> {
+    arena: &'a Arena<'s>,
+    value: &'a Value,
+    /// the patches strictly below this value
+    patches: Vec<&'a Patch<'s>>,
+    depth: usize,
+}
+
+impl<'a, 's> Merged<'a, 's> {
+    /// `patches` must all be at or below the value at `depth`.
+    fn new(
+        arena: &'a Arena<'s>,
+        value: &'a Value,
+        mut patches: Vec<&'a Patch<'s>>,
+        depth: usize,
+    ) -> Self {
+        let (arena, value) = match patches.iter().position(|p| p.path.len() == depth) {
+            Some(i) => {
+                let patch = patches.swap_remove(i);
+                (&patch.arena, &patch.value)
+            }
+            None => (arena, value),
+        };
+        Self {
+            arena,
+            value,
+            patches,
+            depth,
+        }
+    }
+
+    fn child(&self, segment: impl Fn(&str) -> bool, value: &'a Value) -> Self {
+        let patches = self
+            .patches
+            .iter()
+            .filter(|p| segment(&p.path[self.depth]))
+            .copied()
+            .collect();
+        Self::new(self.arena, value, patches, self.depth + 1)
+    }
+
+    /// The keys that only exist in the patches, each with its value.
+    fn added(&self, object: &Object) -> impl Iterator<Item = (&'a str, &'a Value)> + '_ {
+        let keys = self.arena.keys_of(object);
+        self.patches.iter().filter_map(move |patch| {
+            let k = &*patch.path[self.depth];
+            (patch.path.len() == self.depth + 1 && !keys.iter().any(|key| &self.arena[key] == k))
+                .then_some((k, &patch.value))
+        })
+    }
+
+    fn write_json(&self, out: &mut String) {
+        match self.value.kind() {
+            ValueKind::Object(object) if !self.patches.is_empty() => {
+                out.push('{');
+                let entries = self.arena.entries(&object).chain(self.added(&object));
+                for (i, (k, v)) in entries.enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push('"');
+                    crate::ser::escape(k, out);
+                    out.push_str("\":");
+                    self.child(|s| s == k, v).write_json(out);
+                }
+                out.push('}');
+            }
+            ValueKind::Array(array) if !self.patches.is_empty() => {
+                out.push('[');
+                for (i, v) in self.arena.array_iter(&array).enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.child(|s| crate::flatten::array_index(s) == Some(i), v)
+                        .write_json(out);
+                }
+                out.push(']');
+            }
+            _ => self.arena.write_json(self.value, out),
+        }
+    }
+}
+
+impl Debug for Merged<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.patches.is_empty() {
+            return FmtValue {
+                arena: self.arena,
+                value: self.value,
+            }
+            .fmt(f);
+        }
+
+        match self.value.kind() {
+            ValueKind::Leaf(_) => FmtValue {
+                arena: self.arena,
+                value: self.value,
+            }
+            .fmt(f),
+            ValueKind::Object(object) => {
+                let mut f = f.debug_map();
+
+                let arena = self.arena;
+                let keys = arena.keys_of(&object);
+                let values =
+                    &arena.values[object.values().start as usize..object.values().end as usize];
+                for (k, v) in core::iter::zip(keys, values) {
+                    let k = &arena[k];
+                    f.entry(&k, &self.child(|s| s == k, v));
+                }
+
+                for (k, v) in self.added(&object) {
+                    f.entry(&k, &self.child(|s| s == k, v));
+                }
+
+                f.finish()
+            }
+            ValueKind::Array(array) => {
+                let mut f = f.debug_list();
+
+                let values =
+                    &self.arena.values[array.values().start as usize..array.values().end as usize];
+                for (i, v) in values.iter().enumerate() {
+                    f.entry(&self.child(|s| crate::flatten::array_index(s) == Some(i), v));
+                }
+
+                f.finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use crate::{Arena, Variant, VariantError};
+
+    #[test]
+    fn variants() {
+        let mut arena = Arena::new(r#"{"replicas": 1, "image": {"name": "app", "tag": "v1"}}"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let base = Arc::new(arena);
+
+        let mut a = Variant::new(base.clone(), root);
+        a.set(&["replicas"], "3").unwrap();
+        a.set(&["image", "tag"], r#""v2""#).unwrap();
+        a.set(&["debug"], "true").unwrap();
+
+        let mut b = Variant::new(base.clone(), root);
+        b.set(&["image"], r#"{"name": "canary"}"#).unwrap();
+        b.set(&["image", "tag"], r#""v3""#).unwrap();
+
+        let (arena, value) = a.get(&["image", "tag"]).unwrap();
+        assert_eq!(
+            std::format!("{:?}", crate::fmt::FmtValue { arena, value }),
+            r#""v2""#
+        );
+        let (arena, value) = b.get(&["image", "name"]).unwrap();
+        assert_eq!(
+            std::format!("{:?}", crate::fmt::FmtValue { arena, value }),
+            r#""canary""#
+        );
+        assert!(b.get(&["replicas", "nested"]).is_none());
+
+        assert_eq!(
+            std::format!("{a:?}"),
+            r#"{"replicas": 3, "image": {"name": "app", "tag": "v2"}, "debug": true}"#
+        );
+        assert_eq!(
+            a.to_json(),
+            r#"{"replicas":3,"image":{"name":"app","tag":"v2"},"debug":true}"#
+        );
+        assert_eq!(
+            std::format!("{b:?}"),
+            r#"{"replicas": 1, "image": {"name": "canary", "tag": "v3"}}"#
+        );
+    }
+
+    #[test]
+    fn to_json() {
+        let mut arena = Arena::new(r#"{"a\n": [1, {"b": 2}], "c": null}"#);
+        let root = crate::parse(&mut arena).unwrap();
+        let mut variant = Variant::new(Arc::new(arena), root);
+        assert_eq!(variant.to_json(), r#"{"a\n":[1,{"b":2}],"c":null}"#);
+
+        variant.set(&["a\n", "1", "b"], "[3]").unwrap();
+        variant.set(&["a\n", "1", "d"], r#""e""#).unwrap();
+        variant.set(&["c"], "{}").unwrap();
+        variant.set(&["c", "f"], "4").unwrap();
+        assert_eq!(
+            variant.to_json(),
+            r#"{"a\n":[1,{"b":[3],"d":"e"}],"c":{"f":4}}"#
+        );
+
+        // nowhere to put these in the merged document
+        for path in [
+            &["x", "y"][..],
+            &["a\n", "2"],
+            &["c", "f", "g"],
+            &["a\n", "0", "b"],
+        ] {
+            assert!(
+                matches!(variant.set(path, "5"), Err(VariantError::Path)),
+                "{path:?}"
+            );
+        }
+        assert!(matches!(
+            variant.set(&["c"], "[1,"),
+            Err(VariantError::Json(_))
+        ));
+        assert_eq!(
+            variant.to_json(),
+            r#"{"a\n":[1,{"b":[3],"d":"e"}],"c":{"f":4}}"#
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+use crate::{CacheError, Document};
+
+/// A file that is parsed again whenever it changes, from [`watch`].
+///
+/// Stops watching when dropped.
+pub struct Watch {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    current: Mutex<Arc<Document>>,
+    stop: AtomicBool,
+}
+
+/// Parse the file at `path`, and parse it again in a background thread whenever it changes.
+///
+/// The file is checked every `interval`, by its modification time and length. If it no longer
+/// reads or parses, `on_error` is called and the last good document is kept.
+pub fn watch(
+    path: impl Into<PathBuf>,
+    interval: Duration,
+    mut on_error: impl FnMut(CacheError) + Send + 'static,
+) -> Result<Watch, CacheError> {
+    let path = path.into();
+    let mut version = version(&path);
+    let shared = Arc::new(Shared {
+        current: Mutex::new(Arc::new(load(&path)?)),
+        stop: AtomicBool::new(false),
+    });
+
+    let thread = std::thread::spawn({
+        let shared = shared.clone();
+        move || {
+            while !shared.stop.load(Ordering::Relaxed) {
+                std::thread::park_timeout(interval);
+                let new = self::version(&path);
+                if new == version {
+                    continue;
+                }
+                version = new;
+                match load(&path) {
+                    Ok(doc) => *shared.current.lock().unwrap() = Arc::new(doc),
+                    Err(e) => on_error(e),
+                }
+            }
+        }
+    });
+
+    Ok(Watch {
+        shared,
+        thread: Some(thread),
+    })
+}
+
+impl Watch {
+    /// The latest document that parsed.
+    pub fn current(&self) -> Arc<Document> {
+        self.shared.current.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// What identifies a version of the file, if it can be read.
+fn version(path: &Path) -> Option<(Option<SystemTime>, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+fn load(path: &Path) -> Result<Document, CacheError> {
+    let src = std::fs::read_to_string(path).map_err(CacheError::Io)?;
+    Document::parse(src).map_err(CacheError::Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::sync::mpsc;
+
+    use crate::CacheError;
+
+    #[test]
+    fn watch() {
+        let dir = std::env::temp_dir().join(std::format!("sonny-jim-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        std::fs::write(&path, r#"{"replicas": 1}"#).unwrap();
+
+        let (errors, errored) = mpsc::channel();
+        let watch = super::watch(&path, Duration::from_millis(5), move |e| {
+            errors.send(e).unwrap();
+        })
+        .unwrap();
+        let json = || {
+            let doc = watch.current();
+            doc.arena().to_json(doc.root())
+        };
+        assert_eq!(json(), r#"{"replicas":1}"#);
+
+        std::fs::write(&path, r#"{"replicas": 10}"#).unwrap();
+        for _ in 0..400 {
+            if json() != r#"{"replicas":1}"# {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(json(), r#"{"replicas":10}"#);
+
+        // a broken file is reported, and the last good document is kept
+        std::fs::write(&path, r#"{"replicas": "#).unwrap();
+        let error = errored.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert!(matches!(error, CacheError::Json(_)));
+        assert_eq!(json(), r#"{"replicas":10}"#);
+
+        drop(watch);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use axum_core::body::Body;
+use axum_core::extract::rejection::BytesRejection;
+use axum_core::extract::{FromRequest, Request};
+use axum_core::response::{IntoResponse, Response};
+use bytes::{Bytes, BytesMut};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use http_body::Frame;
+
+use crate::pool::POOL;
+use crate::ser::Serializer;
+use crate::{Document, Error};
+
+/// An axum extractor that parses the request body into a [`Document`].
+///
+/// The request must have a JSON `Content-Type`.
+/// The body size is limited by axum's `DefaultBodyLimit`.
+///
+/// The document is parsed with buffers from this thread's [`ArenaPool`](crate::ArenaPool),
+/// which go back to the pool once it has been written out as a response.
+#[derive(Debug)]
+pub struct SonnyJson(pub Document);
+
+impl<S: Send + Sync> FromRequest<S> for SonnyJson {
+    type Rejection = SonnyJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !json_content_type(req.headers()) {
+            return Err(SonnyJsonRejection::MissingJsonContentType);
+        }
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(SonnyJsonRejection::Body)?;
+        // a uniquely owned body converts without a copy, so it is validated in place.
+        let body = String::from_utf8(Vec::from(body))
+            .map_err(|e| SonnyJsonRejection::InvalidUtf8(e.utf8_error()))?;
+
+        match POOL.with(|pool| pool.parse_document(body)) {
+            Ok(doc) => Ok(SonnyJson(doc)),
+            Err(e) => Err(SonnyJsonRejection::Parse(e)),
+        }
+    }
+}
+
+impl IntoResponse for SonnyJson {
+    fn into_response(self) -> Response {
+        self.0.into_response()
+    }
+}
+
+/// Responds with the document serialized as compact JSON.
+///
+/// The body is serialized in chunks as it is polled, rather than into one `String` upfront.
+/// Once it is all written, the document's buffers go back to this thread's
+/// [`ArenaPool`](crate::ArenaPool).
+impl IntoResponse for Document {
+    fn into_response(self) -> Response {
+        let body = DocumentBody {
+            doc: Some(self),
+            serializer: Serializer::new(),
+            chunk: String::new(),
+            buf: BytesMut::new(),
+        };
+        let mut res = Body::new(body).into_response();
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        res
+    }
+}
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+struct DocumentBody {
+    /// `None` once the whole document has been written.
+    doc: Option<Document>,
+    serializer: Serializer,
+    /// Each chunk is serialized into here, and then copied into `buf`.
+    chunk: String,
+    /// Frames are split off of this, so its allocation is reclaimed once they are dropped.
+    buf: BytesMut,
+}
+
+impl http_body::Body for DocumentBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        let Some(doc) = &this.doc else {
+            return Poll::Ready(None);
+        };
+
+        this.chunk.clear();
+        let done = this
+            .serializer
+            .write(doc.arena(), doc.root(), &mut this.chunk, CHUNK_SIZE);
+        this.buf.reserve(this.chunk.len());
+        this.buf.extend_from_slice(this.chunk.as_bytes());
+        let data = this.buf.split().freeze();
+
+        if done {
+            if let Some(doc) = this.doc.take() {
+                POOL.with(|pool| pool.recycle(doc));
+            }
+        }
+        Poll::Ready(Some(Ok(Frame::data(data))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.doc.is_none()
+    }
+}
+
+fn json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE) else {
+        return false;
+    };
+    let Ok(content_type) = content_type.to_str() else {
+        return false;
+    };
+
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    let Some((ty, subtype)) = essence.split_once('/') else {
+        return false;
+    };
+
+    ty.eq_ignore_ascii_case("application")
+        && (subtype.eq_ignore_ascii_case("json")
+            || subtype.len() > 5 && subtype[subtype.len() - 5..].eq_ignore_ascii_case("+json"))
+}
+
+/// Rejection for the [`SonnyJson`] extractor.
+#[derive(Debug)]
+pub enum SonnyJsonRejection {
+    /// The request did not have a JSON `Content-Type` header.
+    MissingJsonContentType,
+    /// The body could not be read, or was too large.
+    Body(BytesRejection),
+    /// The body was not UTF-8.
+    InvalidUtf8(core::str::Utf8Error),
+    /// The body was not valid JSON.
+    Parse(Error),
+}
+
+impl fmt::Display for SonnyJsonRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SonnyJsonRejection::MissingJsonContentType => {
+                f.write_str("Expected request with `Content-Type: application/json`")
+            }
+            SonnyJsonRejection::Body(e) => e.fmt(f),
+            SonnyJsonRejection::InvalidUtf8(e) => {
+                write!(f, "Request body didn't contain valid UTF-8: {e}")
+            }
+            SonnyJsonRejection::Parse(e) => {
+                write!(f, "Failed to parse the request body as JSON: {e}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SonnyJsonRejection {}
+
+impl IntoResponse for SonnyJsonRejection {
+    fn into_response(self) -> Response {
+        let status = match self {
+            SonnyJsonRejection::MissingJsonContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            SonnyJsonRejection::Body(e) => return e.into_response(),
+            SonnyJsonRejection::InvalidUtf8(_) | SonnyJsonRejection::Parse(_) => {
+                StatusCode::BAD_REQUEST
+            }
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use axum_core::body::Body;
+    use axum_core::extract::FromRequest;
+    use axum_core::response::{IntoResponse, Response};
+    use bytes::Bytes;
+    use http::{Request, StatusCode};
+
+    use super::{SonnyJson, SonnyJsonRejection, CHUNK_SIZE};
+    use crate::Document;
+
+    fn request(content_type: &str, body: impl Into<Body>) -> Request<Body> {
+        Request::builder()
+            .header("content-type", content_type)
+            .body(body.into())
+            .unwrap()
+    }
+
+    fn chunks(res: Response) -> Vec<Bytes> {
+        let mut body = res.into_body();
+        let mut chunks = Vec::new();
+        while let Some(frame) = pollster::block_on(core::future::poll_fn(|cx| {
+            http_body::Body::poll_frame(core::pin::Pin::new(&mut body), cx)
+        })) {
+            chunks.push(frame.unwrap().into_data().unwrap());
+        }
+        chunks
+    }
+
+    #[test]
+    fn extract() {
+        let req = request("application/json; charset=utf-8", r#"{"hello": "world"}"#);
+        let SonnyJson(doc) = pollster::block_on(SonnyJson::from_request(req, &())).unwrap();
+        assert_eq!(std::format!("{doc:?}"), r#"{"hello": "world"}"#);
+
+        // responding gives the buffers back to the pool, for the next request.
+        assert_eq!(
+            chunks(doc.into_response()).concat(),
+            br#"{"hello":"world"}"#
+        );
+        let before = crate::pooled_stats();
+        let req = request("application/merge-patch+json", "[]");
+        pollster::block_on(SonnyJson::from_request(req, &())).unwrap();
+        assert_eq!(crate::pooled_stats().hits - before.hits, 1);
+    }
+
+    #[test]
+    fn reject() {
+        let req = request("text/plain", "{}");
+        let err = pollster::block_on(SonnyJson::from_request(req, &())).unwrap_err();
+        assert!(matches!(err, SonnyJsonRejection::MissingJsonContentType));
+        assert_eq!(
+            err.into_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let req = request("application/json", r#"{"hello": }"#);
+        let err = pollster::block_on(SonnyJson::from_request(req, &())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse the request body as JSON: unexpected token at bytes 10..11"
+        );
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let req = request("application/json", &b"\"\xff\""[..]);
+        let err = pollster::block_on(SonnyJson::from_request(req, &())).unwrap_err();
+        assert!(matches!(err, SonnyJsonRejection::InvalidUtf8(_)));
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn respond() {
+        let data = std::format!("[{}]", ["\"abcdefghijklmnopqrstuvwxyz\""; 1000].join(", "));
+        let doc = Document::parse(data.as_str()).unwrap();
+        let res = doc.into_response();
+        assert_eq!(res.headers()["content-type"], "application/json");
+
+        let chunks = chunks(res);
+
+        let expected = data.replace(", ", ",");
+        assert_eq!(chunks.len(), expected.len().div_ceil(CHUNK_SIZE));
+        assert_eq!(chunks.concat(), expected.as_bytes());
+    }
+}
@@ -0,0 +1,517 @@
+//! Reading YAML into an arena, with the YAML span of every value.
+//!
+//! The YAML is transcoded to JSON text for the arena, so everything that works on a
+//! parsed JSON document works the same on a YAML one. [`YamlDocument::yaml_span`]
+//! maps a value back to where it came from, for error messages.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use yaml_rust2::parser::{Event, Parser, Tag};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+use crate::{Arena, Error, Value};
+
+/// A YAML stream parsed by [`parse_yaml`].
+pub struct YamlDocument {
+    arena: Arena<'static>,
+    roots: Vec<Value>,
+    /// Where each value starts in the JSON text, and its span in the YAML, ordered by the former.
+    spans: Vec<(u32, Range<u32>)>,
+}
+
+impl YamlDocument {
+    /// The arena source is the input transcoded to JSON text, so value spans point into that.
+    pub fn arena(&self) -> &Arena<'static> {
+        &self.arena
+    }
+
+    /// The top-level values, one for each document in the stream.
+    pub fn roots(&self) -> &[Value] {
+        &self.roots
+    }
+
+    /// The span of `value` in the YAML input.
+    ///
+    /// A value read through an alias has the span of the anchored node.
+    pub fn yaml_span(&self, value: &Value) -> Option<Range<u32>> {
+        let i = self
+            .spans
+            .binary_search_by_key(&self.arena.span(value).start, |(start, _)| *start)
+            .ok()?;
+        Some(self.spans[i].1.clone())
+    }
+}
+
+impl fmt::Debug for YamlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("YamlDocument")
+            .field("roots", &self.roots)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub enum YamlError {
+    /// The input is not valid YAML.
+    Syntax {
+        offset: u32,
+        message: String,
+    },
+    /// A mapping key that is not a scalar, such as a sequence or an alias.
+    ComplexKey {
+        span: Range<u32>,
+    },
+    /// A float that JSON cannot hold, like `.inf` or `.nan`.
+    NotFinite {
+        span: Range<u32>,
+    },
+    /// An alias inside the node it refers to.
+    RecursiveAlias {
+        span: Range<u32>,
+    },
+    Json(Error),
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YamlError::Syntax { offset, message } => write!(f, "{message} at byte {offset}"),
+            YamlError::ComplexKey { span } => {
+                write!(f, "expected a scalar mapping key at bytes {span:?}")
+            }
+            YamlError::NotFinite { span } => {
+                write!(f, "float at bytes {span:?} cannot be represented in JSON")
+            }
+            YamlError::RecursiveAlias { span } => {
+                write!(
+                    f,
+                    "alias at bytes {span:?} refers to a node that contains it"
+                )
+            }
+            YamlError::Json(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for YamlError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            YamlError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a YAML stream into an arena, one root for each document.
+///
+/// Plain scalars are typed by the YAML 1.2 core schema, so `~` is `null`, `0x1f` is `31`
+/// and `yes` is a string. Aliases are expanded, and mapping keys must be scalars.
+/// Tags are ignored, other than `!!str`.
+pub fn parse_yaml(src: &str) -> Result<YamlDocument, YamlError> {
+    let mut transcoder = Transcoder {
+        src,
+        chars: (!src.is_ascii()).then(|| {
+            src.char_indices()
+                .map(|(i, _)| i as u32)
+                .chain([src.len() as u32])
+                .collect()
+        }),
+        out: String::new(),
+        spans: Vec::new(),
+        stack: Vec::new(),
+        anchors: Vec::new(),
+    };
+    let mut parser = Parser::new_from_str(src);
+    loop {
+        let (event, marker) = parser
+            .next_token()
+            .map_err(|e| transcoder.syntax(e.marker(), e.info()))?;
+        if event == Event::StreamEnd {
+            break;
+        }
+        transcoder.event(&mut parser, event, marker)?;
+    }
+
+    let Transcoder { out, spans, .. } = transcoder;
+    let mut arena = Arena::new_shared(out.into());
+    let roots = crate::parse_all(&mut arena).map_err(YamlError::Json)?;
+    Ok(YamlDocument {
+        arena,
+        roots,
+        spans,
+    })
+}
+
+struct Transcoder<'a> {
+    src: &'a str,
+    /// The byte offset of each char, since markers count chars. `None` if the source is ASCII.
+    chars: Option<Vec<u32>>,
+    out: String,
+    spans: Vec<(u32, Range<u32>)>,
+    stack: Vec<Frame>,
+    /// The anchored nodes, by anchor id.
+    anchors: Vec<Option<Anchor>>,
+}
+
+struct Frame {
+    mapping: bool,
+    /// Whether the next node is a mapping key.
+    key: bool,
+    first: bool,
+    /// The index of the container in `spans`.
+    entry: usize,
+    anchor: usize,
+    out: usize,
+}
+
+/// Where an anchored node was written, to copy for its aliases.
+struct Anchor {
+    text: Range<usize>,
+    spans: Range<usize>,
+}
+
+enum Scalar<'e> {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(&'e str),
+}
+
+impl Transcoder<'_> {
+    fn offset(&self, marker: &Marker) -> u32 {
+        match &self.chars {
+            Some(chars) => chars[marker.index().min(chars.len() - 1)],
+            None => marker.index().min(self.src.len()) as u32,
+        }
+    }
+
+    fn syntax(&self, marker: &Marker, message: &str) -> YamlError {
+        YamlError::Syntax {
+            offset: self.offset(marker),
+            message: message.into(),
+        }
+    }
+
+    fn event(
+        &mut self,
+        parser: &mut Parser<core::str::Chars<'_>>,
+        event: Event,
+        marker: Marker,
+    ) -> Result<(), YamlError> {
+        let start = self.offset(&marker);
+        match event {
+            Event::DocumentEnd => self.out.push('\n'),
+            Event::Scalar(value, style, anchor, tag) => {
+                let next = match parser.peek() {
+                    Ok((_, next)) => self.offset(next),
+                    Err(_) => self.src.len() as u32,
+                };
+                let span = start..self.scalar_end(start, style, &value, next);
+                let key = self.separator();
+                let (text, entries) = (self.out.len(), self.spans.len());
+                if key {
+                    self.out.push('"');
+                    crate::ser::escape(&value, &mut self.out);
+                    self.out.push('"');
+                    self.anchor(anchor, text, entries);
+                    self.complete(span.clone());
+                    return Ok(());
+                }
+
+                self.spans.push((text as u32, span.clone()));
+                match resolve(&value, style, tag.as_ref()) {
+                    Some(Scalar::Null) => self.out.push_str("null"),
+                    Some(Scalar::Bool(b)) => self.out.push_str(if b { "true" } else { "false" }),
+                    Some(Scalar::Number(n)) => self.out.push_str(&n),
+                    Some(Scalar::String(s)) => {
+                        self.out.push('"');
+                        crate::ser::escape(s, &mut self.out);
+                        self.out.push('"');
+                    }
+                    None => return Err(YamlError::NotFinite { span }),
+                }
+                self.anchor(anchor, text, entries);
+                self.complete(span);
+            }
+            Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => {
+                let mapping = matches!(event, Event::MappingStart(..));
+                if self.separator() {
+                    return Err(YamlError::ComplexKey {
+                        span: start..start + 1,
+                    });
+                }
+                let (out, entry) = (self.out.len(), self.spans.len());
+                self.spans.push((out as u32, start..start));
+                self.out.push(if mapping { '{' } else { '[' });
+                self.stack.push(Frame {
+                    mapping,
+                    key: mapping,
+                    first: true,
+                    entry,
+                    anchor,
+                    out,
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let frame = self.stack.pop().unwrap();
+                self.out.push(if frame.mapping { '}' } else { ']' });
+
+                let span = &mut self.spans[frame.entry].1;
+                // flow containers end at their closing bracket, block ones at their last node.
+                if matches!(
+                    self.src.as_bytes().get(span.start as usize),
+                    Some(b'[' | b'{')
+                ) {
+                    span.end = start + 1;
+                }
+                let span = span.clone();
+                self.anchor(frame.anchor, frame.out, frame.entry);
+                self.complete(span);
+            }
+            Event::Alias(id) => {
+                let name = self.src[start as usize + 1..]
+                    .find(|c: char| c.is_whitespace() || ",[]{}".contains(c))
+                    .unwrap_or(self.src.len() - start as usize - 1);
+                let span = start..start + 1 + name as u32;
+                if self.separator() {
+                    return Err(YamlError::ComplexKey { span });
+                }
+
+                // an anchored node is only complete once it has ended.
+                let Some(anchor) = self.anchors.get(id).and_then(Option::as_ref) else {
+                    return Err(YamlError::RecursiveAlias { span });
+                };
+                let shift = self.out.len() - anchor.text.start;
+                for i in anchor.spans.clone() {
+                    let (json, yaml) = self.spans[i].clone();
+                    self.spans.push((json + shift as u32, yaml));
+                }
+                self.out.extend_from_within(anchor.text.clone());
+                self.complete(span);
+            }
+            Event::Nothing | Event::StreamStart | Event::StreamEnd | Event::DocumentStart => {}
+        }
+        Ok(())
+    }
+
+    /// Write the separator before the next node. Returns whether it is a mapping key.
+    fn separator(&mut self) -> bool {
+        let Some(frame) = self.stack.last_mut() else {
+            return false;
+        };
+        if frame.mapping && !frame.key {
+            self.out.push(':');
+            return false;
+        }
+        if !core::mem::take(&mut frame.first) {
+            self.out.push(',');
+        }
+        frame.mapping
+    }
+
+    /// Mark the current node, at `span`, as complete.
+    fn complete(&mut self, span: Range<u32>) {
+        if let Some(frame) = self.stack.last_mut() {
+            if frame.mapping {
+                frame.key = !frame.key;
+            }
+            // a block mapping only starts once its first key has been read.
+            let container = &mut self.spans[frame.entry].1;
+            container.start = container.start.min(span.start);
+            container.end = span.end;
+        }
+    }
+
+    fn anchor(&mut self, anchor: usize, text: usize, entries: usize) {
+        if anchor == 0 {
+            return;
+        }
+        if self.anchors.len() <= anchor {
+            self.anchors.resize_with(anchor + 1, || None);
+        }
+        self.anchors[anchor] = Some(Anchor {
+            text: text..self.out.len(),
+            spans: entries..self.spans.len(),
+        });
+    }
+
+    /// Where a scalar starting at `start` ends, given that the next event starts at `next`.
+    fn scalar_end(&self, start: u32, style: TScalarStyle, value: &str, next: u32) -> u32 {
+        let rest = &self.src[start as usize..];
+        let len = match style {
+            TScalarStyle::Plain if rest.starts_with(value) => Some(value.len()),
+            TScalarStyle::SingleQuoted => closing(rest, b'\'', |bytes, i| {
+                // a quote is escaped by doubling it
+                (bytes[i..].starts_with(b"''")).then_some(i + 2)
+            }),
+            TScalarStyle::DoubleQuoted => {
+                closing(rest, b'"', |bytes, i| (bytes[i] == b'\\').then_some(i + 2))
+            }
+            _ => None,
+        };
+        match len {
+            Some(len) => start + len as u32,
+            None => {
+                let text = &self.src[start as usize..(next as usize).max(start as usize)];
+                start + text.trim_end().len() as u32
+            }
+        }
+    }
+}
+
+/// The length of the quoted scalar at the start of `rest`, up to its closing `quote`.
+///
+/// `escape` gives where to continue from, if the byte at `i` starts an escape.
+fn closing(rest: &str, quote: u8, escape: impl Fn(&[u8], usize) -> Option<usize>) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        if let Some(next) = escape(bytes, i) {
+            i = next;
+        } else if bytes[i] == quote {
+            return Some(i + 1);
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// The JSON value of a scalar, or `None` for a float that JSON cannot hold.
+fn resolve<'e>(value: &'e str, style: TScalarStyle, tag: Option<&Tag>) -> Option<Scalar<'e>> {
+    let is_str = tag.is_some_and(|tag| tag.handle == "tag:yaml.org,2002:" && tag.suffix == "str");
+    if style != TScalarStyle::Plain || is_str {
+        return Some(Scalar::String(value));
+    }
+
+    let scalar = match value {
+        "" | "~" | "null" | "Null" | "NULL" => Scalar::Null,
+        "true" | "True" | "TRUE" => Scalar::Bool(true),
+        "false" | "False" | "FALSE" => Scalar::Bool(false),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" | "-.inf" | "-.Inf" | "-.INF"
+        | ".nan" | ".NaN" | ".NAN" => return None,
+        _ => number(value).map_or(Scalar::String(value), Scalar::Number),
+    };
+    Some(scalar)
+}
+
+/// A YAML 1.2 core schema number as JSON text.
+fn number(value: &str) -> Option<String> {
+    let radix = [("0x", 16), ("0o", 8)]
+        .into_iter()
+        .find_map(|(prefix, radix)| Some((value.strip_prefix(prefix)?, radix)));
+    if let Some((digits, radix)) = radix {
+        if digits.starts_with(['+', '-']) {
+            return None;
+        }
+        let n = u128::from_str_radix(digits, radix).ok()?;
+        return Some(alloc::format!("{n}"));
+    }
+
+    let unsigned = value.strip_prefix('+').unwrap_or(value);
+    let digits = unsigned.strip_prefix('-').unwrap_or(unsigned);
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        || !digits.contains(|c: char| c.is_ascii_digit())
+        || !digits
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+    {
+        return None;
+    }
+    let f = unsigned.parse::<f64>().ok()?;
+    if crate::scan_number(unsigned.as_bytes()).is_ok_and(|(len, _)| len == unsigned.len()) {
+        Some(unsigned.into())
+    } else if digits.bytes().all(|b| b.is_ascii_digit()) {
+        // leading zeros, like `007`
+        let neg = unsigned.len() > digits.len();
+        let digits = digits.trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+        Some(if neg {
+            alloc::format!("-{digits}")
+        } else {
+            digits.into()
+        })
+    } else if f.is_finite() {
+        Some(alloc::format!("{f:?}"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_yaml, YamlError};
+    use crate::refs::Refs;
+    use crate::ValueKind;
+
+    #[test]
+    fn yaml() {
+        let src = "\
+# a deployment
+kind: Deployment
+metadata:
+  name: 'web''s'
+  labels: {app: web, tier: \"front\"}
+spec:
+  replicas: 0x3
+  ports: [80, +443, 1.5e3]
+  env: &env
+    - name: DEBUG
+      value: ~
+  sidecar:
+    env: *env
+  script: |
+    echo hi
+  enabled: yes
+---
+- true
+- 007
+";
+        let doc = parse_yaml(src).unwrap();
+        let arena = doc.arena();
+        let [first, second] = doc.roots() else {
+            panic!("expected two documents")
+        };
+        assert_eq!(
+            arena.to_json(first),
+            r#"{"kind":"Deployment","metadata":{"name":"web's","labels":{"app":"web","tier":"front"}},"spec":{"replicas":3,"ports":[80,443,1.5e3],"env":[{"name":"DEBUG","value":null}],"sidecar":{"env":[{"name":"DEBUG","value":null}]},"script":"echo hi\n","enabled":"yes"}}"#
+        );
+        assert_eq!(arena.to_json(second), "[true,7]");
+
+        let span = |path: &str| {
+            let value = Refs::new(arena, first).pointer(path).unwrap();
+            let span = doc.yaml_span(value).unwrap();
+            &src[span.start as usize..span.end as usize]
+        };
+        assert_eq!(span(""), &src[15..src.find("---").unwrap() - 1]);
+        assert_eq!(span("/kind"), "Deployment");
+        assert_eq!(span("/metadata/name"), "'web''s'");
+        assert_eq!(span("/metadata/labels"), "{app: web, tier: \"front\"}");
+        assert_eq!(span("/metadata/labels/tier"), "\"front\"");
+        assert_eq!(span("/spec/ports"), "[80, +443, 1.5e3]");
+        assert_eq!(span("/spec/ports/1"), "+443");
+        assert_eq!(span("/spec/env/0/value"), "~");
+        assert_eq!(span("/spec/sidecar/env/0/name"), "DEBUG");
+        assert_eq!(span("/spec/script"), "echo hi");
+
+        // spans are in bytes, not chars
+        let doc = parse_yaml("é: [ü]").unwrap();
+        let ValueKind::Object(object) = &doc.roots()[0].kind() else {
+            panic!("expected an object")
+        };
+        let (_, value) = doc.arena().entries(object).next().unwrap();
+        assert_eq!(doc.yaml_span(value), Some(4..8));
+
+        assert!(matches!(parse_yaml("a: [1"), Err(YamlError::Syntax { .. })));
+        assert!(matches!(
+            parse_yaml("? [1]\n: 2"),
+            Err(YamlError::ComplexKey { span }) if span == (2..3)
+        ));
+        assert!(matches!(
+            parse_yaml("x: .inf"),
+            Err(YamlError::NotFinite { span }) if span == (3..7)
+        ));
+    }
+}